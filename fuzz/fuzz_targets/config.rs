@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tictactoe::config::Config;
+
+fuzz_target!(|source: &str| {
+    if let Ok(config) = Config::parse(source) {
+        let _ = config.preset_names();
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tictactoe::db::GameRecord;
+
+fuzz_target!(|line: &str| {
+    if let Some(record) = GameRecord::parse(line) {
+        let _ = record.board();
+        let _ = record.human_moves().count();
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tictactoe::input::parse_move;
+
+fuzz_target!(|data: (u8, u8, &str)| {
+    let (width, height, input) = data;
+    if let Err(e) = parse_move(input, width as usize, height as usize) {
+        let _ = e.to_string();
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tictactoe::{Board, Cell};
+
+fuzz_target!(|data: (u8, &str)| {
+    let (dim, position) = data;
+    if let Ok(board) = Board::from_string(position, dim as usize, Cell::X) {
+        let _ = board.to_string();
+        let _ = board.best_moves(Cell::O);
+    }
+    if let Ok(board) = Board::from_notation(position) {
+        let notation = board.to_notation();
+        assert_eq!(Board::from_notation(&notation).unwrap().to_notation(), notation);
+    }
+});
@@ -0,0 +1,368 @@
+//! Move by move evaluation of a game, for display and for export as CSV, the
+//! [mistakes] which changed its outcome with best play, and [score maps](ScoreMap) of
+//! single positions.
+
+use std::fmt::Write;
+
+use crate::board::{Board, Cell, Coord};
+use crate::engine::{Engine, Minimax};
+use crate::minimax::FULL_SEARCH;
+use crate::stats::Quality;
+use crate::tablebase::Value;
+
+/// Background colors of the terminal score map from the worst to the best move, as indices
+/// into the 256 color palette
+const TERMINAL_COLORS: [u8; 5] = [124, 130, 136, 64, 28];
+
+/// Header line of the CSV export, matching [`MoveAnalysis::to_csv`]
+pub const CSV_HEADER: &str = "game,ply,player,x,y,quality,score,best_score,best_moves,fork_moves";
+
+/// The engine's evaluation of a move
+#[derive(Debug, PartialEq, Clone)]
+pub struct MoveAnalysis {
+    /// Number of the move, starting at 1
+    pub ply: usize,
+    pub player: Cell,
+    /// Zero based coordinates
+    pub x: usize,
+    pub y: usize,
+    pub quality: Quality,
+    /// Heuristic score of the move
+    pub score: usize,
+    /// The highest heuristic score of all moves in the position
+    pub best_score: usize,
+    /// The moves the engine considers best in the position
+    pub best_moves: Vec<Coord>,
+    /// The moves creating a double threat in the position, see [`Board::fork_moves`]
+    pub fork_moves: Vec<Coord>,
+}
+
+impl MoveAnalysis {
+    /// The analysis as a CSV record. Coordinates are one based like the user input, the
+    /// best moves and the fork moves are separated by spaces.
+    pub fn to_csv(&self, game: usize) -> String {
+        let join = |moves: &[Coord]| {
+            moves
+                .iter()
+                .map(|mv| format!("{}:{}", mv.col + 1, mv.row + 1))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            game,
+            self.ply,
+            self.player,
+            self.x + 1,
+            self.y + 1,
+            self.quality.name(),
+            self.score,
+            self.best_score,
+            join(&self.best_moves),
+            join(&self.fork_moves)
+        )
+    }
+}
+
+/// Evaluate every move played on the board
+pub fn analyze(board: &Board) -> Vec<MoveAnalysis> {
+    let mut replay = board.clone();
+    while replay.undo().is_some() {}
+    let width = board.width();
+    board
+        .history()
+        .iter()
+        .enumerate()
+        .map(|(idx, &mv)| {
+            let Coord { row: y, col: x } = mv;
+            let player = board.get_cell(mv);
+            let scores = replay.scores(player);
+            let best_score = (0..scores.len())
+                .filter(|&i| replay.is_playable((i % width, i / width)))
+                .map(|i| scores[i])
+                .max()
+                .unwrap_or(0);
+            let analysis = MoveAnalysis {
+                ply: idx + 1,
+                player,
+                x,
+                y,
+                quality: Quality::of(&replay, mv, player),
+                score: scores[x + y * width],
+                best_score,
+                best_moves: replay.best_moves(player),
+                fork_moves: replay.fork_moves(player),
+            };
+            replay.set_cell(mv, player).unwrap();
+            analysis
+        })
+        .collect()
+}
+
+/// The engine's score of every move in a position, laid out like the board
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScoreMap {
+    width: usize,
+    cells: Vec<Cell>,
+    scores: Vec<Option<i64>>,
+}
+
+impl ScoreMap {
+    /// Score the moves of `side` with the engine, see [`Engine::evaluate_moves`]
+    pub fn new(engine: &mut dyn Engine, board: &Board, side: Cell) -> ScoreMap {
+        let (width, height) = (board.width(), board.height());
+        let mut scores = vec![None; width * height];
+        for (mv, score) in engine.evaluate_moves(board, side) {
+            scores[mv.col + mv.row * width] = Some(score);
+        }
+        ScoreMap {
+            width,
+            cells: (0..width * height)
+                .map(|i| board.get_cell((i % width, i / width)))
+                .collect(),
+            scores,
+        }
+    }
+
+    /// The score of a move, `None` for taken cells
+    pub fn score(&self, x: usize, y: usize) -> Option<i64> {
+        self.scores[x + y * self.width]
+    }
+
+    // the rank of a score among the distinct scores between 0 and `levels`, so proven
+    // wins don't squeeze the other scores together
+    fn level(&self, score: i64, levels: usize) -> usize {
+        let mut distinct: Vec<i64> = self.scores.iter().flatten().copied().collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let below = distinct.iter().filter(|&&other| other < score).count();
+        match distinct.len() {
+            1 => levels,
+            len => below * levels / (len - 1),
+        }
+    }
+
+    /// Render the map with the pieces on the taken cells and the scores, formatted by
+    /// `label`, on the blank ones, on backgrounds from red to green if `color` is set
+    pub fn render(&self, color: bool, label: impl Fn(i64) -> String) -> String {
+        let columns = self.width;
+        let cells: Vec<String> = self
+            .scores
+            .iter()
+            .zip(&self.cells)
+            .map(|(score, cell)| match (score, cell) {
+                (Some(score), _) => label(*score),
+                (None, Cell::Blank) => String::new(),
+                (None, piece) => piece.to_string(),
+            })
+            .collect();
+        let width = cells
+            .iter()
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or(1);
+        let sep = format!("+{}", "-".repeat(width + 2)).repeat(columns) + "+";
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", sep);
+        for (i, cell) in cells.iter().enumerate() {
+            let text = format!(" {:^width$} ", cell);
+            match self.scores[i] {
+                Some(score) if color => {
+                    let bg = TERMINAL_COLORS[self.level(score, TERMINAL_COLORS.len() - 1)];
+                    let _ = write!(out, "|\x1b[97;48;5;{}m{}\x1b[0m", bg, text);
+                }
+                _ => {
+                    let _ = write!(out, "|{}", text);
+                }
+            }
+            if i % columns == columns - 1 {
+                let _ = writeln!(out, "|");
+                let _ = writeln!(out, "{}", sep);
+            }
+        }
+        out
+    }
+}
+
+/// A move after which the outcome with best play was worse for its player than before
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mistake {
+    /// Number of the move, starting at 1
+    pub ply: usize,
+    pub player: Cell,
+    /// Zero based coordinates
+    pub x: usize,
+    pub y: usize,
+    /// The value of the position for the player before the move
+    pub before: Value,
+    /// The value for the player after the move
+    pub after: Value,
+    /// A move which would have kept the value
+    pub better: Coord,
+}
+
+/// The moves of the game which turned a win into a draw or loss, or a draw into a loss,
+/// proven by the minimax engine, with the tablebases if `tablebases` is set, see
+/// [`EngineConfig::tablebases`](crate::EngineConfig::tablebases). Positions with more than
+/// [`FULL_SEARCH`] blank cells are not judged, unless a forced win is found in them.
+pub fn mistakes(board: &Board, tablebases: bool) -> Vec<Mistake> {
+    let mut engine = Minimax::default();
+    if tablebases {
+        engine = engine.with_tablebases();
+    }
+    let mut replay = board.clone();
+    while replay.undo().is_some() {}
+    let mut mistakes = Vec::new();
+    for (idx, &mv) in board.history().iter().enumerate() {
+        let player = board.get_cell(mv);
+        let after = value_of(&mut engine, &replay, mv, player);
+        let before = match replay.legal_moves().count() {
+            blanks if blanks > FULL_SEARCH => None,
+            _ => engine.proven_value(&replay, player),
+        };
+        if let (Some(before), Some(after)) = (before, after) {
+            if rank(after) < rank(before) {
+                let better = replay
+                    .legal_moves()
+                    .find(|&mv| value_of(&mut engine, &replay, mv, player) == Some(before))
+                    .expect("the value of a position is that of its best move");
+                mistakes.push(Mistake {
+                    ply: idx + 1,
+                    player,
+                    x: mv.col,
+                    y: mv.row,
+                    before,
+                    after,
+                    better,
+                });
+            }
+        }
+        replay.set_cell(mv, player).unwrap();
+    }
+    mistakes
+}
+
+// The value of a move for the player making it
+fn value_of(engine: &mut Minimax, board: &Board, mv: Coord, player: Cell) -> Option<Value> {
+    let mut board = board.clone();
+    board.set_cell(mv, player).unwrap();
+    if let Some(winner) = board.winner() {
+        return Some(if winner == player {
+            Value::Win
+        } else {
+            Value::Loss
+        });
+    }
+    match board.legal_moves().count() {
+        0 => Some(Value::Draw),
+        blanks if blanks > FULL_SEARCH => None,
+        _ => match engine.proven_value(&board, player.opponent())? {
+            Value::Win => Some(Value::Loss),
+            Value::Draw => Some(Value::Draw),
+            Value::Loss => Some(Value::Win),
+        },
+    }
+}
+
+fn rank(value: Value) -> u8 {
+    match value {
+        Value::Loss => 0,
+        Value::Draw => 1,
+        Value::Win => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn analysis() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        let moves = analyze(&board);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(
+            moves[0],
+            MoveAnalysis {
+                ply: 1,
+                player: Cell::X,
+                x: 0,
+                y: 0,
+                quality: Quality::Good,
+                score: 4,
+                best_score: 5,
+                best_moves: vec![(1, 1).into()],
+                fork_moves: vec![],
+            }
+        );
+        assert_eq!(moves[1].to_csv(7), "7,2,O,2,2,best,4,4,2:2,");
+        assert_eq!(CSV_HEADER.split(',').count(), 10);
+    }
+
+    #[test]
+    fn score_map() {
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        let mut engine = Minimax::default();
+        let map = ScoreMap::new(&mut engine, &board, Cell::X);
+        assert_eq!(map.score(0, 0), None);
+        let win = map.score(2, 0).unwrap();
+        assert!((0..3)
+            .flat_map(|y| (0..3).map(move |x| (x, y)))
+            .filter_map(|(x, y)| map.score(x, y))
+            .all(|score| score <= win));
+        assert_eq!(map.level(win, 4), 4);
+        let rendered = map.render(false, |score| (score == win).to_string());
+        assert_eq!(
+            rendered.lines().nth(1).unwrap(),
+            "|   X   |   X   | true  |"
+        );
+        assert_eq!(rendered.lines().count(), 7);
+        assert!(map
+            .render(true, |s| s.to_string())
+            .contains("\x1b[97;48;5;28m"));
+    }
+
+    #[test]
+    fn score_map_within_the_think_time() {
+        // with a think time the search deepens until the time is up
+        let board = Board::build(6, Cell::X).unwrap();
+        let limit = Duration::from_millis(100);
+        let mut engine = Minimax::default();
+        engine.think_time = Some(limit);
+        let start = Instant::now();
+        let map = ScoreMap::new(&mut engine, &board, Cell::X);
+        assert!(start.elapsed() >= limit);
+        assert!(start.elapsed().as_secs() < 1);
+        assert!((0..6).all(|y| (0..6).all(|x| map.score(x, y).is_some())));
+    }
+
+    #[test]
+    fn game_mistakes() {
+        // answering the center with an edge loses, the other moves keep the win
+        let board = Board::from_moves(&[(1, 1), (1, 0), (0, 1), (2, 1)], Cell::X);
+        let found = mistakes(&board, false);
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(
+            (
+                found[0].ply,
+                found[0].player,
+                found[0].before,
+                found[0].after
+            ),
+            (2, Cell::O, Value::Draw, Value::Loss)
+        );
+        let mut kept = Board::build(3, Cell::X).unwrap();
+        kept.set_cell((1, 1), Cell::X).unwrap();
+        kept.set_cell(found[0].better, Cell::O).unwrap();
+        assert_eq!(
+            crate::solver::forced_outcome(&kept, Cell::X),
+            Some(crate::board::GameOver::Tie)
+        );
+        // a game without mistakes
+        assert!(mistakes(&Board::build(3, Cell::X).unwrap(), false).is_empty());
+    }
+}
@@ -1,16 +1,28 @@
+use std::collections::HashSet;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use regex::Regex;
+use crate::engine::{Engine, EngineConfig, EngineKind};
+use crate::input::{self, InputError, ParseError};
+use crate::rng::Rng;
+use crate::rules::{Gravity, Misere, Ruleset, Standard};
+use crate::t;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Cell {
     X,
     O,
     Blank,
+    /// A cell neither player can use, see [`Board::with_blocked`]
+    Blocked,
 }
 
 impl Cell {
-    fn opponent(&self) -> Cell {
+    pub fn opponent(&self) -> Cell {
         match self {
             Cell::X => Cell::O,
             Cell::O => Cell::X,
@@ -25,272 +37,1810 @@ impl fmt::Display for Cell {
             Cell::X => "X",
             Cell::O => "O",
             Cell::Blank => " ",
+            Cell::Blocked => "#",
         };
         let _ = write!(f, "{}", s);
         Ok(())
     }
 }
 
+/// The supported board dimensions
+pub const DIM_RANGE: RangeInclusive<usize> = 2..=30;
+
+// How often a random start is drawn before giving up on finding a fair one
+const RANDOM_START_DRAWS: usize = 100;
+
+/// Weights of the heuristic [cell scores](Board::scores)
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct EvalWeights {
+    /// Score of every blank cell
+    pub blank: usize,
+    /// Score for every line through the cell the player can still complete
+    pub line: usize,
+    /// Score for every piece the player already has on those lines
+    pub progress: usize,
+}
+
+impl Default for EvalWeights {
+    fn default() -> EvalWeights {
+        EvalWeights {
+            blank: 1,
+            line: 1,
+            progress: 1,
+        }
+    }
+}
+
+/// The cells of one of the lines which win when filled, counted by what is on them. The
+/// board keeps the counts of its lines up to date with every move.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LineCount {
+    pub x: usize,
+    pub o: usize,
+    pub blanks: usize,
+}
+
+impl LineCount {
+    /// The number of cells taken by the player
+    pub fn pieces(&self, cell: Cell) -> usize {
+        match cell {
+            Cell::X => self.x,
+            Cell::O => self.o,
+            Cell::Blank => self.blanks,
+            Cell::Blocked => 0,
+        }
+    }
+
+    // Counts the cell taken by the player, or given back with `taken` unset
+    fn update(&mut self, cell: Cell, taken: bool) {
+        let pieces = match cell {
+            Cell::X => &mut self.x,
+            _ => &mut self.o,
+        };
+        if taken {
+            *pieces += 1;
+            self.blanks -= 1;
+        } else {
+            *pieces -= 1;
+            self.blanks += 1;
+        }
+    }
+}
+
+/// Maps the coordinates of a cell to those after rotating or mirroring a board with the
+/// given highest column and row
+pub(crate) type Transform = fn(usize, usize, usize, usize) -> (usize, usize);
+
+/// The eight ways to rotate and mirror a board, the first leaving it as it is. The last four
+/// swap rows and columns, so only the first four apply to boards which aren't square.
+pub(crate) const SYMMETRIES: [Transform; 8] = [
+    |x, y, _, _| (x, y),
+    |x, y, last_x, _| (last_x - x, y),
+    |x, y, _, last_y| (x, last_y - y),
+    |x, y, last_x, last_y| (last_x - x, last_y - y),
+    |x, y, _, _| (y, x),
+    |x, y, _, last_y| (last_y - y, x),
+    |x, y, last_x, _| (y, last_x - x),
+    |x, y, last_x, last_y| (last_y - y, last_x - x),
+];
+
+/// The cells of a game with its move history and the settings it is played with
+///
+/// With the `serde` feature a board is saved as its game settings, the position before the
+/// first move and the moves made since, see [`Board::history`]. Loading it checks that the
+/// players and the pieces of the position fit together and plays the moves again, failing
+/// if one of them is illegal, out of turn or made after the game ended. Positions played
+/// by rules other than the standard ones, [gravity](Board::with_gravity) and
+/// [misère](Misere) can't be loaded, and the moves [taken back](Board::undo) aren't kept.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SavedBoard", try_from = "SavedBoard")
+)]
 pub struct Board {
-    dim: usize,
+    width: usize,
+    height: usize,
+    // the number of pieces in a row which wins
+    win_length: usize,
+    // whether lines continue across the edges on the opposite side
+    wrap: bool,
+    // where moves can be made, who has won and when the game is over
+    rules: Arc<dyn Ruleset>,
     cells: Vec<Cell>,
     win_lines: Vec<Vec<usize>>,
+    // the counts of the win lines, in the same order
+    line_counts: Vec<LineCount>,
+    // the indices of the win lines through each cell
+    cell_lines: Vec<Vec<usize>>,
     human_uses: Cell,
     moves: usize,
+    // Zobrist hash of the position in each of its symmetric orientations, the first four
+    // only if the board isn't square
+    hashes: [u64; 8],
+    history: Vec<Coord>,
+    move_times: Vec<Option<Duration>>,
+    // the moves taken back, the last one first, with their pieces and times, until the
+    // next move is made
+    undone: Vec<(Coord, Cell, Option<Duration>)>,
+    pie_rule: bool,
+    // whether the sides were swapped after the first move
+    swapped: bool,
+    // the player and the number of their pieces placed before the first move
+    handicap: Option<(Cell, usize)>,
+    // the player making the first move, unless there is a handicap
+    first: Cell,
+    // the number of blocked cells
+    blocked: usize,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameOver {
     HumanWon,
     ComputerWon,
     Tie,
+    /// The computer gave up a game it has proven lost
+    Resigned,
+}
+
+impl GameOver {
+    /// Whether the human won, also by the computer resigning
+    pub fn human_won(self) -> bool {
+        matches!(self, GameOver::HumanWon | GameOver::Resigned)
+    }
 }
 
 impl fmt::Display for GameOver {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            GameOver::HumanWon => write!(f, "You won!"),
-            GameOver::ComputerWon => write!(f, "Computer won!"),
-            GameOver::Tie => write!(f, "It's a tie!"),
+            GameOver::HumanWon => write!(f, "{}", t!("human-won")),
+            GameOver::ComputerWon => write!(f, "{}", t!("computer-won")),
+            GameOver::Tie => write!(f, "{}", t!("tie")),
+            GameOver::Resigned => write!(f, "{}", t!("resigned")),
         }
     }
 }
 
+/// How a game ended, with the winner and the line deciding it, see [`Board::game_result`]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameResult {
+    /// The result as seen by the human
+    pub over: GameOver,
+    /// The player who won, `None` for a tie
+    pub winner: Option<Cell>,
+    /// The cells of the completed line which decided the game, in order along the line.
+    /// It is empty for a tie or when the [rules](Ruleset) decided the game otherwise.
+    pub line: Vec<Coord>,
+}
+
+/// The zero based coordinates of a cell: the row from the top and the column from the
+/// left
+///
+/// As text it is the one based column and row `x y`, the way moves are entered. It converts
+/// from and to `(x, y)` pairs, column first.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coord {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.col + 1, self.row + 1)
+    }
+}
+
+/// Parses `x y` on a board of the largest supported size, see [`input::parse_move`]
+impl FromStr for Coord {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let max = *DIM_RANGE.end();
+        input::parse_move(s, max, max).map(Coord::from)
+    }
+}
+
+impl From<(usize, usize)> for Coord {
+    fn from((x, y): (usize, usize)) -> Self {
+        Coord { row: y, col: x }
+    }
+}
+
+impl From<Coord> for (usize, usize) {
+    fn from(coord: Coord) -> Self {
+        (coord.col, coord.row)
+    }
+}
+
+/// Compares with an `(x, y)` pair, column first, e.g. a move written out in a test
+impl PartialEq<(usize, usize)> for Coord {
+    fn eq(&self, &(x, y): &(usize, usize)) -> bool {
+        self.col == x && self.row == y
+    }
+}
+
+/// A move: the piece of a player put on a cell
+///
+/// As text it is the piece followed by the coordinates, e.g. `X 2 3`.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub coord: Coord,
+    pub mark: Cell,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.mark, self.coord)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (mark, coord) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let mark = match mark {
+            "X" | "x" => Cell::X,
+            "O" | "o" => Cell::O,
+            _ => {
+                return Err(ParseError::Malformed {
+                    input: s.to_string(),
+                    suggestion: None,
+                })
+            }
+        };
+        Ok(Move {
+            coord: coord.parse()?,
+            mark,
+        })
+    }
+}
+
+/// Why a move can't be made, see [`Board::apply_move`]
+#[derive(Debug, PartialEq, Copy, Clone, thiserror::Error)]
+pub enum MoveError {
+    /// The coordinates are off the board
+    #[error("{}", t!("move-out-of-bounds", cell = .0))]
+    OutOfBounds(Coord),
+    /// The cell has a piece on it
+    #[error("{}", t!("cell-taken"))]
+    CellTaken,
+    /// The cell is [blocked](Board::with_blocked)
+    #[error("{}", t!("cell-blocked"))]
+    CellBlocked,
+    /// The [rules](Ruleset::is_legal) don't allow a move on the blank cell, e.g. with
+    /// gravity above a blank cell
+    #[error("{}", t!("cell-not-supported"))]
+    NotPlayable,
+    /// The move is for the other player than the one to move
+    #[error("{}", t!("move-not-your-turn", symbol = to_move))]
+    NotYourTurn { to_move: Cell },
+    /// A player has won or the board is full
+    #[error("{}", t!("game-already-over"))]
+    GameAlreadyOver,
+    /// The sides can't be [swapped](Board::swap_sides) now
+    #[error("{}", t!("swap-not-allowed"))]
+    SwapNotAllowed,
+    /// In [ultimate tic-tac-toe](crate::ultimate) the cell isn't on a small board the
+    /// player may play on
+    #[error("{}", t!("ultimate-wrong-board"))]
+    WrongBoard,
+    /// In [Three Men's Morris](crate::morris) the move doesn't place a piece while the
+    /// player has some left, or doesn't slide one of theirs to an adjacent blank cell
+    #[error("{}", t!("morris-illegal-move"))]
+    IllegalSlide,
+    /// The current line of the [variation tree](crate::variation::VariationTree) is
+    /// shorter than the number of moves to go back to
+    #[error("{}", t!("no-such-move"))]
+    NoSuchMove,
+}
+
+/// Why a board or a position can't be created, see [`Board::build_rectangle`] and
+/// [`Board::from_notation`], or one of the boards of the variants
+#[derive(Debug, PartialEq, Copy, Clone, thiserror::Error)]
+pub enum BoardError {
+    /// The number of columns or rows is outside [`DIM_RANGE`], or the range of the variant
+    #[error("{}", t!("board-invalid-dimension", got = got, min = min, max = max))]
+    InvalidDimension { got: usize, min: usize, max: usize },
+    /// The win length is below 2 or longer than the shorter side
+    #[error("{}", t!("board-invalid-win-length", got = got, min = min, max = max))]
+    InvalidWinLength { got: usize, min: usize, max: usize },
+    /// The number of players of a [game of several players](crate::multiplayer) is outside
+    /// [`PLAYER_RANGE`](crate::multiplayer::PLAYER_RANGE)
+    #[error("{}", t!("board-invalid-players", got = got, min = min, max = max))]
+    InvalidPlayers { got: usize, min: usize, max: usize },
+    /// [Fading](crate::fading) pieces disappear before a line can be completed, or the pieces
+    /// of both players could fill the board
+    #[error("{}", t!("board-invalid-lifetime", got = got, min = min, max = max))]
+    InvalidLifetime { got: usize, min: usize, max: usize },
+    /// A player is to play a blank or blocked cell instead of X or O
+    #[error("{}", t!("board-invalid-player"))]
+    InvalidPlayer,
+    /// The [notation](Board::from_notation) contains a character other than the pieces,
+    /// `-`, `#`, `/` and whitespace
+    #[error("{}", t!("position-invalid-char", char = .0))]
+    InvalidChar(char),
+    /// The rows of the notation have different lengths
+    #[error("{}", t!("position-not-rectangular"))]
+    NotRectangular,
+    /// The notation without rows has no square number of cells
+    #[error("{}", t!("position-not-square"))]
+    NotSquare,
+    /// The notation has another number of cells than the board
+    #[error("{}", t!("position-wrong-size", cells = cells, width = width, height = height))]
+    WrongSize {
+        cells: usize,
+        width: usize,
+        height: usize,
+    },
+    /// One player has more than one piece more than the other, which can't happen when
+    /// they take turns
+    #[error("{}", t!("position-implausible", x = x, o = o))]
+    ImplausibleCounts { x: usize, o: usize },
+    /// O has more pieces than X, where [X moves first](Board::from_position)
+    #[error("{}", t!("position-illegal", x = x, o = o))]
+    NotXFirst { x: usize, o: usize },
+    /// A player has won or the board is full, where a game is to be played from the position
+    #[error("{}", t!("position-game-over"))]
+    GameOver,
+    /// The position can't arise under the [rules](Ruleset::check_position), e.g. a piece
+    /// floats above a blank cell with gravity
+    #[error("{}", t!("position-floating"))]
+    Floating,
+    /// A [handicap](Board::from_handicap) position has pieces of both players
+    #[error("{}", t!("handicap-both-players"))]
+    HandicapBothPlayers,
+    /// [Handicap](Board::with_handicap) pieces are to be placed on a board with pieces
+    #[error("{}", t!("handicap-not-empty"))]
+    HandicapNotEmpty,
+    /// The handicap pieces don't fit without completing a line or filling the board
+    #[error("{}", t!("invalid-handicap", count = count))]
+    InvalidHandicap { count: usize },
+    /// A [random start](Board::with_random_start) is to be played on a board with pieces
+    #[error("{}", t!("random-start-not-empty"))]
+    RandomStartNotEmpty,
+    /// A random start of other than 1 or 2 plies
+    #[error("{}", t!("random-start-plies", plies = plies))]
+    InvalidRandomStart { plies: usize },
+    /// Every random start drawn let the player to move force a win right away
+    #[error("{}", t!("random-start-unfair"))]
+    NoFairStart,
+    /// The cell to [block](Board::with_blocked) has a piece on it
+    #[error("{}", t!("blocked-taken", cell = .0))]
+    BlockedTaken(Coord),
+    /// Blocking the cells would leave no blank cell
+    #[error("{}", t!("invalid-blocked"))]
+    NoBlankCell,
+    /// Both players have completed a line, which can't happen as the game ends with the
+    /// first one
+    #[error("{}", t!("position-both-won"))]
+    BothWon,
+}
+
 impl Board {
-    /// Create a new board with the given number of rows and columns
-    pub fn build(dim: usize, human_uses: Cell) -> Result<Board, &'static str> {
-        assert!(human_uses != Cell::Blank);
-        if !(2..=30).contains(&dim) {
-            return Err("Invalid board dimension, must be between 2 and 30");
+    /// Create a new board with the given number of rows and columns, won by filling a row,
+    /// column or diagonal
+    pub fn build(dim: usize, human_uses: Cell) -> Result<Board, BoardError> {
+        Board::build_with_win_length(dim, dim, human_uses)
+    }
+
+    /// A 3x3 board after the given moves, alternating from X, with the human playing
+    /// `human_uses`
+    ///
+    /// Panics if a move is off the board or on a taken cell.
+    #[cfg(test)]
+    pub(crate) fn from_moves(moves: &[(usize, usize)], human_uses: Cell) -> Board {
+        let mut board = Board::build(3, human_uses).unwrap();
+        let mut cell = Cell::X;
+        for &(x, y) in moves {
+            board.set_cell((x, y), cell).unwrap();
+            cell = cell.opponent();
         }
+        board
+    }
+
+    /// Create a new board with the given number of rows and columns, won by the given
+    /// number of pieces in a row, column or diagonal, e.g. 5 in a row on a 10x10 board
+    ///
+    /// Returns an error if the win length is below 2 or longer than the rows.
+    pub fn build_with_win_length(
+        dim: usize,
+        win_length: usize,
+        human_uses: Cell,
+    ) -> Result<Board, BoardError> {
+        Board::build_rectangle(dim, dim, win_length, human_uses)
+    }
+
+    /// Create a new board with the given number of columns and rows, won by the given
+    /// number of pieces in a row, column or diagonal, e.g. 4 in a row on a 7x6 board
+    ///
+    /// Returns an error if a side is outside [`DIM_RANGE`], the win length is below 2 or
+    /// longer than the shorter side, or the human doesn't play X or O.
+    pub fn build_rectangle(
+        width: usize,
+        height: usize,
+        win_length: usize,
+        human_uses: Cell,
+    ) -> Result<Board, BoardError> {
+        if !matches!(human_uses, Cell::X | Cell::O) {
+            return Err(BoardError::InvalidPlayer);
+        }
+        for got in [width, height] {
+            if !DIM_RANGE.contains(&got) {
+                return Err(BoardError::InvalidDimension {
+                    got,
+                    min: *DIM_RANGE.start(),
+                    max: *DIM_RANGE.end(),
+                });
+            }
+        }
+        if !(2..=width.min(height)).contains(&win_length) {
+            return Err(BoardError::InvalidWinLength {
+                got: win_length,
+                min: 2,
+                max: width.min(height),
+            });
+        }
+        let win_lines = Board::segments(width, height, win_length, false);
+        let cell_lines = Board::cell_lines(&win_lines, width * height);
+        let empty = LineCount {
+            x: 0,
+            o: 0,
+            blanks: win_length,
+        };
         Ok(Board {
-            dim,
-            cells: vec![Cell::Blank; dim * dim],
-            win_lines: Board::win_lines(dim),
+            width,
+            height,
+            win_length,
+            wrap: false,
+            rules: Arc::new(Standard),
+            cells: vec![Cell::Blank; width * height],
+            line_counts: vec![empty; win_lines.len()],
+            win_lines,
+            cell_lines,
             human_uses,
             moves: 0,
+            hashes: [0; 8],
+            history: Vec::new(),
+            move_times: Vec::new(),
+            undone: Vec::new(),
+            pie_rule: false,
+            swapped: false,
+            handicap: None,
+            first: Cell::X,
+            blocked: 0,
         })
     }
 
-    /// Create a board from a string containing 'X', 'O' and '-' in lines. Empty lines are ignored.
+    /// Create a board from a string containing 'X', 'O' and '-' in lines. Whitespace and
+    /// `/` between the rows are ignored.
+    ///
+    /// Returns an error if the string contains other characters, doesn't describe
+    /// `dim * dim` cells or isn't a position of a game, see [`Board::from_notation`]. The
+    /// position has no move history.
+    #[cfg(test)]
+    pub(crate) fn from_string(s: &str, dim: usize, human_uses: Cell) -> Result<Board, BoardError> {
+        Board::from_string_rectangle(s, dim, dim, human_uses)
+    }
+
+    /// Like [`Board::from_string`] for a board with the given number of columns and rows,
+    /// won by filling the shorter side
     #[cfg(test)]
-    fn from_string(s: &str, dim: usize, human_uses: Cell) -> Result<Board, &'static str> {
-        let s = s.trim().replace(['\r', '\n', ' '], "");
-        let mut moves = 0;
-        let cells = s
-            .chars()
+    pub(crate) fn from_string_rectangle(
+        s: &str,
+        width: usize,
+        height: usize,
+        human_uses: Cell,
+    ) -> Result<Board, BoardError> {
+        let mut board = Board::parse_notation(s, width, height, human_uses)?;
+        board.check_turns()?;
+        Ok(board)
+    }
+
+    // The cells of a position string, row by row
+    fn parse_cells(s: &str) -> Result<Vec<Cell>, BoardError> {
+        s.chars()
+            .filter(|&c| !c.is_whitespace() && c != '/')
             .map(|c| match c {
-                '-' => Cell::Blank,
-                'X' => {
-                    moves += 1;
-                    Cell::X
+                '-' => Ok(Cell::Blank),
+                'X' => Ok(Cell::X),
+                'O' => Ok(Cell::O),
+                '#' => Ok(Cell::Blocked),
+                _ => Err(BoardError::InvalidChar(c)),
+            })
+            .collect()
+    }
+
+    // The position of the notation on a board of the given size, which all the ways of
+    // creating a board from a string share. Only one player may have completed a line, but
+    // the pieces aren't counted, so that handicap positions pass.
+    fn parse_notation(
+        s: &str,
+        width: usize,
+        height: usize,
+        human_uses: Cell,
+    ) -> Result<Board, BoardError> {
+        let mut board = Board::build_rectangle(width, height, width.min(height), human_uses)?;
+        let cells = Board::parse_cells(s)?;
+        if cells.len() != width * height {
+            return Err(BoardError::WrongSize {
+                cells: cells.len(),
+                width,
+                height,
+            });
+        }
+        board.fill(&cells);
+        board.check_one_winner()?;
+        Ok(board)
+    }
+
+    // Checks that at most one player has completed a line
+    fn check_one_winner(&self) -> Result<(), BoardError> {
+        let completed = |cell| {
+            self.line_counts
+                .iter()
+                .any(|count| count.pieces(cell) == self.win_length)
+        };
+        if completed(Cell::X) && completed(Cell::O) {
+            return Err(BoardError::BothWon);
+        }
+        Ok(())
+    }
+
+    // Checks that the players took turns, letting O move first if it has one piece more
+    fn check_turns(&mut self) -> Result<(), BoardError> {
+        let count = |cell| self.cells.iter().filter(|&&c| c == cell).count();
+        let (x, o) = (count(Cell::X), count(Cell::O));
+        match x as isize - o as isize {
+            0 | 1 => {}
+            -1 => self.first = Cell::O,
+            _ => return Err(BoardError::ImplausibleCounts { x, o }),
+        }
+        Ok(())
+    }
+
+    /// Create a board from its notation as produced by [`Board::to_notation`]: the rows of
+    /// `X`, `O`, `-` for blank and `#` for blocked cells separated by `/`, e.g.
+    /// `X--/-O-/---`. Whitespace is ignored. Without separators the board is square, with
+    /// the dimension taken from the number of cells. The board is won by filling the
+    /// shorter side, and the player with fewer pieces is to move, X if both have as many.
+    ///
+    /// Returns an error if the rows differ in length, the size isn't supported, other
+    /// characters are used, one player has more than one piece more than the other or both
+    /// have completed a line. Finished games are accepted. The position has no move
+    /// history, and the human plays X.
+    pub fn from_notation(s: &str) -> Result<Board, BoardError> {
+        let (width, height) = Board::position_size(s)?;
+        let mut board = Board::parse_notation(s, width, height, Cell::X)?;
+        board.check_turns()?;
+        Ok(board)
+    }
+
+    /// The position in the notation read by [`Board::from_notation`]: the rows separated by
+    /// `/`, with `-` for blank and `#` for blocked cells, e.g. `X--/-O-/---`
+    pub fn to_notation(&self) -> String {
+        let rows: Vec<String> = self
+            .cells
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Cell::Blank => '-',
+                        Cell::X => 'X',
+                        Cell::O => 'O',
+                        Cell::Blocked => '#',
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("/")
+    }
+
+    /// Create a board from a position string as produced by [`Board::to_notation`],
+    /// see [`Board::from_notation`], for the human playing the given piece.
+    ///
+    /// Returns an error if the position can't arise in a game where X moves first, or if
+    /// the game is already over.
+    pub fn from_position(s: &str, human_uses: Cell) -> Result<Board, BoardError> {
+        let mut board = Board::from_notation(s)?;
+        board.human_uses = human_uses;
+        let count = |cell| board.cells.iter().filter(|&&c| c == cell).count();
+        let (x, o) = (count(Cell::X), count(Cell::O));
+        if o > x {
+            return Err(BoardError::NotXFirst { x, o });
+        }
+        if board.winner().is_some() || board.legal_moves().next().is_none() {
+            return Err(BoardError::GameOver);
+        }
+        Ok(board)
+    }
+
+    /// Create a board from a position string like [`Board::from_position`] with the pieces
+    /// of only one player on it, placed as a handicap before the first move. The other
+    /// player moves first.
+    ///
+    /// Returns an error if the position has pieces of both players, or if the game is
+    /// already over.
+    pub fn from_handicap(s: &str, human_uses: Cell) -> Result<Board, BoardError> {
+        let (width, height) = Board::position_size(s)?;
+        let mut board = Board::parse_notation(s, width, height, human_uses)?;
+        let count = |cell| board.cells.iter().filter(|&&c| c == cell).count();
+        board.handicap = match (count(Cell::X), count(Cell::O)) {
+            (0, 0) => None,
+            (x, 0) => Some((Cell::X, x)),
+            (0, o) => Some((Cell::O, o)),
+            _ => return Err(BoardError::HandicapBothPlayers),
+        };
+        if board.winner().is_some() || board.legal_moves().next().is_none() {
+            return Err(BoardError::GameOver);
+        }
+        Ok(board)
+    }
+
+    // The width and height of a position string, from its rows separated by `/` or else
+    // from the number of cells of a square board
+    fn position_size(s: &str) -> Result<(usize, usize), BoardError> {
+        let rows: Vec<usize> = s
+            .split('/')
+            .map(|row| row.chars().filter(|c| !c.is_whitespace()).count())
+            .collect();
+        let (width, height) = if rows.len() > 1 {
+            if rows.iter().any(|&row| row != rows[0]) {
+                return Err(BoardError::NotRectangular);
+            }
+            (rows[0], rows.len())
+        } else {
+            let dim = (1..=*DIM_RANGE.end())
+                .find(|dim| dim * dim >= rows[0])
+                .filter(|dim| dim * dim == rows[0])
+                .ok_or(BoardError::NotSquare)?;
+            (dim, dim)
+        };
+        Ok((width, height))
+    }
+
+    /// The player to move: X moves first unless [another
+    /// player](Board::set_first_player) was chosen, or with a
+    /// [handicap](Board::with_handicap) the other player, and the players take turns from
+    /// then on
+    pub fn to_move(&self) -> Cell {
+        let (first, placed) = match self.handicap {
+            Some((cell, count)) => (cell.opponent(), count),
+            None => (self.first, 0),
+        };
+        let moved = |cell| {
+            let pieces = self.cells.iter().filter(|&&c| c == cell).count();
+            if cell == first {
+                pieces
+            } else {
+                pieces - placed
+            }
+        };
+        if moved(first) > moved(first.opponent()) {
+            first.opponent()
+        } else {
+            first
+        }
+    }
+
+    /// Let the given player make the first move, e.g. O when the computer playing O
+    /// begins, which decides the [player to move](Board::to_move) from then on. X moves
+    /// first by default. A [handicap](Board::with_handicap) lets the other player begin
+    /// instead.
+    pub fn set_first_player(&mut self, cell: Cell) {
+        assert!(matches!(cell, Cell::X | Cell::O));
+        self.first = cell;
+    }
+
+    /// The player making the first move, see [`Board::set_first_player`]
+    pub fn first_player(&self) -> Cell {
+        self.first
+    }
+
+    /// The position of the empty board with the given number of pieces of the player placed
+    /// before the first move, on the cells on the most lines, nearest the center first, but
+    /// never completing a line. The other player moves first.
+    ///
+    /// Returns an error if the pieces aren't X or O, the board isn't empty or the pieces
+    /// don't fit.
+    pub fn with_handicap(&self, cell: Cell, count: usize) -> Result<Board, BoardError> {
+        if !matches!(cell, Cell::X | Cell::O) {
+            return Err(BoardError::InvalidPlayer);
+        }
+        if self.cells.iter().any(|&c| matches!(c, Cell::X | Cell::O)) {
+            return Err(BoardError::HandicapNotEmpty);
+        }
+        let mut board = self.clone();
+        let center = |idx: usize| {
+            let dx = (2 * (idx % self.width)).abs_diff(self.width - 1);
+            let dy = (2 * (idx / self.width)).abs_diff(self.height - 1);
+            dx * dx + dy * dy
+        };
+        for _ in 0..count {
+            let idx = (0..board.cells.len())
+                .filter(|&idx| board.playable(idx))
+                .filter(|&idx| {
+                    board.cell_lines[idx].iter().all(|&line| {
+                        board.line_counts[line].pieces(cell) + 1 < board.win_lines[line].len()
+                    })
+                })
+                .max_by_key(|&idx| {
+                    (
+                        board.cell_lines[idx].len(),
+                        std::cmp::Reverse(center(idx)),
+                        std::cmp::Reverse(idx),
+                    )
+                })
+                .ok_or(BoardError::InvalidHandicap { count })?;
+            board.put(idx, cell);
+            board.moves += 1;
+        }
+        if board.legal_moves().next().is_none() {
+            return Err(BoardError::InvalidHandicap { count });
+        }
+        board.handicap = (count > 0).then_some((cell, count));
+        Ok(board)
+    }
+
+    /// The position of the empty board with the first one or two plies, X first, played at
+    /// random as the start of the game. With two plies both players have a piece. The pieces
+    /// have no move history, so the game, and the engine, start from them. Starts after
+    /// which the player to move can already force a win are drawn again.
+    ///
+    /// Returns an error if the board isn't empty, more plies are asked for, or no fair start
+    /// is found.
+    pub fn with_random_start(&self, plies: usize, rng: &mut Rng) -> Result<Board, BoardError> {
+        if self.cells.iter().any(|&c| matches!(c, Cell::X | Cell::O)) {
+            return Err(BoardError::RandomStartNotEmpty);
+        }
+        if !(1..=2).contains(&plies) {
+            return Err(BoardError::InvalidRandomStart { plies });
+        }
+        for _ in 0..RANDOM_START_DRAWS {
+            let mut board = self.clone();
+            let mut cell = Cell::X;
+            for _ in 0..plies {
+                let moves: Vec<Coord> = board.legal_moves().collect();
+                let mv = moves[rng.below(moves.len())];
+                board.put(board.index(mv), cell);
+                board.moves += 1;
+                cell = cell.opponent();
+            }
+            if board.winner().is_none()
+                && board.legal_moves().next().is_some()
+                && !board.can_force_win(cell)
+            {
+                return Ok(board);
+            }
+        }
+        Err(BoardError::NoFairStart)
+    }
+
+    /// The player and the number of their pieces placed before the first move, see
+    /// [`Board::with_handicap`]
+    pub fn handicap(&self) -> Option<(Cell, usize)> {
+        self.handicap
+    }
+
+    /// The position on a board of the same size won by the given number of pieces in a row,
+    /// see [`Board::build_rectangle`]. The position has no move history.
+    pub fn with_win_length(&self, win_length: usize) -> Result<Board, BoardError> {
+        let mut board =
+            Board::build_rectangle(self.width, self.height, win_length, self.human_uses)?
+                .with_wrap(self.wrap);
+        board.fill(&self.cells);
+        board.handicap = self.handicap;
+        board.first = self.first;
+        Ok(board)
+    }
+
+    /// The position with the lines continuing across the edges on the opposite side, as if
+    /// the board was wrapped around a torus, or with the lines ending at the edges again.
+    /// Every cell then lies on the same number of lines, corners are no better than others.
+    pub fn with_wrap(&self, wrap: bool) -> Board {
+        let mut board = self.clone();
+        board.wrap = wrap;
+        board.rebuild_lines();
+        board
+    }
+
+    /// Whether the lines wrap around the edges, see [`Board::with_wrap`]
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    /// The position with the cells at the given zero based coordinates blocked: neither
+    /// player can use them, and the lines through them can't be completed. Rotated or
+    /// mirrored positions no longer count as the same.
+    ///
+    /// Returns an error if one of the cells has a piece on it or no blank cell is left.
+    pub fn with_blocked(&self, cells: &[Coord]) -> Result<Board, BoardError> {
+        let mut board = self.clone();
+        for &coord in cells {
+            match board.get_cell(coord) {
+                Cell::Blank => {
+                    board.cells[self.index(coord)] = Cell::Blocked;
+                    board.blocked += 1;
                 }
-                'O' => {
-                    moves += 1;
-                    Cell::O
+                Cell::Blocked => {}
+                _ => return Err(BoardError::BlockedTaken(coord)),
+            }
+        }
+        if !board.cells.contains(&Cell::Blank) {
+            return Err(BoardError::NoBlankCell);
+        }
+        board.rebuild_lines();
+        Ok(board)
+    }
+
+    /// The position with the given number of blank cells chosen at random blocked, see
+    /// [`Board::with_blocked`]
+    pub fn with_random_blocked(&self, count: usize, rng: &mut Rng) -> Result<Board, BoardError> {
+        let mut blanks: Vec<Coord> = (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == Cell::Blank)
+            .map(|idx| self.coord(idx))
+            .collect();
+        if count >= blanks.len() {
+            return Err(BoardError::NoBlankCell);
+        }
+        let cells: Vec<Coord> = (0..count)
+            .map(|_| blanks.swap_remove(rng.below(blanks.len())))
+            .collect();
+        self.with_blocked(&cells)
+    }
+
+    /// The blocked cells, row by row, see [`Board::with_blocked`]
+    pub fn blocked_cells(&self) -> Vec<Coord> {
+        (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == Cell::Blocked)
+            .map(|idx| self.coord(idx))
+            .collect()
+    }
+
+    /// Whether any cell is blocked, see [`Board::with_blocked`]
+    pub fn has_blocked(&self) -> bool {
+        self.blocked > 0
+    }
+
+    // Computes the lines for the size, win length and wrapping again, leaving out those
+    // through blocked cells, and counts the pieces on them
+    fn rebuild_lines(&mut self) {
+        let cells = &self.cells;
+        self.win_lines = Board::segments(self.width, self.height, self.win_length, self.wrap)
+            .into_iter()
+            .filter(|line| line.iter().all(|&idx| cells[idx] != Cell::Blocked))
+            .collect();
+        self.cell_lines = Board::cell_lines(&self.win_lines, self.cells.len());
+        self.line_counts = self
+            .win_lines
+            .iter()
+            .map(|line| {
+                let mut count = LineCount {
+                    x: 0,
+                    o: 0,
+                    blanks: line.len(),
+                };
+                for &idx in line {
+                    if self.cells[idx] != Cell::Blank {
+                        count.update(self.cells[idx], true);
+                    }
                 }
-                _ => panic!("Invalid character in board string"),
+                count
             })
             .collect();
+    }
 
-        Ok(Board {
-            dim,
-            cells,
-            win_lines: Board::win_lines(dim),
-            human_uses,
-            moves,
-        })
+    // Puts the cells on the empty board, blocking cells first so that their lines are gone
+    // before the pieces are counted
+    fn fill(&mut self, cells: &[Cell]) {
+        for (idx, &cell) in cells.iter().enumerate() {
+            if cell == Cell::Blocked {
+                self.cells[idx] = Cell::Blocked;
+                self.blocked += 1;
+            }
+        }
+        if self.blocked > 0 {
+            self.rebuild_lines();
+        }
+        for (idx, &cell) in cells.iter().enumerate() {
+            if matches!(cell, Cell::X | Cell::O) {
+                self.put(idx, cell);
+                self.moves += 1;
+            }
+        }
+    }
+
+    /// The position played by the given rules, see [`Ruleset`]. Returns an error if the
+    /// position can't arise under them.
+    pub fn with_rules(&self, rules: impl Ruleset + 'static) -> Result<Board, BoardError> {
+        rules.check_position(self)?;
+        let mut board = self.clone();
+        board.rules = Arc::new(rules);
+        board.rehash();
+        Ok(board)
+    }
+
+    /// The rules the position is played by, see [`Board::with_rules`]
+    pub fn rules(&self) -> &dyn Ruleset {
+        self.rules.as_ref()
+    }
+
+    /// Whether the position is played by the rules of the standard game, which the opening
+    /// book and the tablebases assume
+    pub fn has_standard_rules(&self) -> bool {
+        self.rules.name() == Standard.name()
     }
 
-    /// Get the list of winning lines
-    fn win_lines(dim: usize) -> Vec<Vec<usize>> {
+    /// The position with pieces falling to the lowest blank cell of the column they are
+    /// dropped into, as in Connect Four, or placed on any blank cell again. Returns an error
+    /// if a piece of the position has a blank cell below it.
+    pub fn with_gravity(&self, gravity: bool) -> Result<Board, BoardError> {
+        if gravity {
+            self.with_rules(Gravity)
+        } else {
+            self.with_rules(Standard)
+        }
+    }
+
+    /// Whether pieces fall down their column, see [`Board::with_gravity`]
+    pub fn has_gravity(&self) -> bool {
+        self.rules.name() == Gravity.name()
+    }
+
+    /// Whether completing a line loses, see [`Misere`]
+    pub fn has_misere(&self) -> bool {
+        self.rules.name() == Misere.name()
+    }
+
+    /// Whether a move can be made on the cell: it is blank and [legal](Ruleset::is_legal)
+    /// under the rules, e.g. with [gravity](Board::with_gravity) on the bottom row or above
+    /// a piece
+    pub fn is_playable(&self, coord: impl Into<Coord>) -> bool {
+        self.playable(self.index(coord.into()))
+    }
+
+    /// The cell a piece dropped into the column lands on, `None` if the column is full
+    pub fn drop_row(&self, x: usize) -> Option<usize> {
+        (0..self.height)
+            .rev()
+            .find(|&y| self.get_cell((x, y)) == Cell::Blank)
+    }
+
+    // Whether a move can be made on the cell with the given index
+    fn playable(&self, idx: usize) -> bool {
+        self.cells[idx] == Cell::Blank && self.rules.is_legal(self, self.coord(idx))
+    }
+
+    /// The lines which win when filled, as cell indexes `x + y * width`
+    pub(crate) fn lines(&self) -> &[Vec<usize>] {
+        &self.win_lines
+    }
+
+    /// Get the list of winning lines: every segment of `win_length` cells of the columns,
+    /// the rows, the diagonals from the top left and those from the top right. With `wrap`
+    /// the segments continue across the edges, one starting at every cell in each direction
+    /// unless it covers the same cells as another, e.g. the rows when they are that long.
+    pub(crate) fn segments(
+        width: usize,
+        height: usize,
+        win_length: usize,
+        wrap: bool,
+    ) -> Vec<Vec<usize>> {
+        if wrap {
+            let mut seen = HashSet::new();
+            let mut win_lines = Vec::new();
+            // down, right, down right and up right, with the steps up taken modulo the height
+            for (dx, dy) in [(0, 1), (1, 0), (1, 1), (1, height - 1)] {
+                for y in 0..height {
+                    for x in 0..width {
+                        let line: Vec<usize> = (0..win_length)
+                            .map(|i| (x + i * dx) % width + (y + i * dy) % height * width)
+                            .collect();
+                        let mut cells = line.clone();
+                        cells.sort();
+                        if seen.insert(cells) {
+                            win_lines.push(line);
+                        }
+                    }
+                }
+            }
+            return win_lines;
+        }
+        let (columns, rows) = (0..=width - win_length, 0..=height - win_length);
         let mut win_lines = Vec::new();
-        for x in 0..dim {
-            let mut line = Vec::new();
-            for y in 0..dim {
-                line.push(x + y * dim);
+        for x in 0..width {
+            for y in rows.clone() {
+                win_lines.push((0..win_length).map(|i| x + (y + i) * width).collect());
             }
-            win_lines.push(line);
         }
-        for y in 0..dim {
-            let mut line = Vec::new();
-            for x in 0..dim {
-                line.push(x + y * dim);
+        for y in 0..height {
+            for x in columns.clone() {
+                win_lines.push((0..win_length).map(|i| x + i + y * width).collect());
             }
-            win_lines.push(line);
         }
-        let mut line = Vec::new();
-        for x in 0..dim {
-            line.push(x + x * dim);
+        for y in rows.clone() {
+            for x in columns.clone() {
+                win_lines.push((0..win_length).map(|i| x + i + (y + i) * width).collect());
+            }
         }
-        win_lines.push(line);
-        let mut line = Vec::new();
-        for x in 0..dim {
-            line.push(x + (dim - 1 - x) * dim);
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let line = (0..win_length).map(|i| x + i + (y + win_length - 1 - i) * width);
+                win_lines.push(line.collect());
+            }
         }
-        win_lines.push(line);
         win_lines
     }
 
-    /// Set the cell at the given coordinates and maintain the 'moves' count.
+    // The indexes of the lines through each cell
+    fn cell_lines(win_lines: &[Vec<usize>], cells: usize) -> Vec<Vec<usize>> {
+        let mut cell_lines = vec![Vec::new(); cells];
+        for (line_idx, line) in win_lines.iter().enumerate() {
+            for &idx in line {
+                cell_lines[idx].push(line_idx);
+            }
+        }
+        cell_lines
+    }
+
+    /// Set the cell at the given coordinates and maintain the 'moves' count and history.
     ///
-    /// Returns an error if the cell is already occupied
-    fn set_cell(&mut self, x: usize, y: usize, cell: Cell) -> Result<(), &'static str> {
-        assert!(x < self.dim);
-        assert!(y < self.dim);
-        if self.get_cell(x, y) != Cell::Blank {
-            return Err("Cell already taken");
+    /// Returns an error if the coordinates are off the board or the cell can't be played
+    pub(crate) fn set_cell(
+        &mut self,
+        coord: impl Into<Coord>,
+        cell: Cell,
+    ) -> Result<(), MoveError> {
+        let coord = coord.into();
+        if coord.col >= self.width || coord.row >= self.height {
+            return Err(MoveError::OutOfBounds(coord));
+        }
+        if self.get_cell(coord) == Cell::Blocked {
+            return Err(MoveError::CellBlocked);
+        }
+        if self.get_cell(coord) != Cell::Blank {
+            return Err(MoveError::CellTaken);
         };
-        self.cells[x + y * self.dim] = cell;
+        if !self.is_playable(coord) {
+            return Err(MoveError::NotPlayable);
+        }
+        self.put(self.index(coord), cell);
         self.moves += 1;
+        self.history.push(coord);
+        self.move_times.push(None);
+        self.undone.clear();
         Ok(())
     }
 
-    /// Get the cell at the given coordinates.
-    fn get_cell(&self, x: usize, y: usize) -> Cell {
-        assert!(x < self.dim);
-        assert!(y < self.dim);
-        self.cells[x + y * self.dim]
-    }
-
-    /// Accept input from the user and make a move
-    pub fn user_move(&mut self) -> Option<GameOver> {
-        let mut x: usize;
-        let mut y: usize;
-        loop {
-            (x, y) = self.accept_input();
-            if let Err(e) = self.set_cell(x, y, self.human_uses) {
-                println!("{}", e);
-                continue;
+    /// Create a position by playing up to the given number of random moves, X first, on an
+    /// empty board. Stops early when the game is over.
+    pub fn random(
+        dim: usize,
+        human_uses: Cell,
+        plies: usize,
+        rng: &mut Rng,
+    ) -> Result<Board, BoardError> {
+        let mut board = Board::build(dim, human_uses)?;
+        let mut cell = Cell::X;
+        while board.history.len() < plies && board.result().is_none() {
+            let moves: Vec<Coord> = board.legal_moves().collect();
+            board
+                .set_cell(moves[rng.below(moves.len())], cell)
+                .expect("the move is legal");
+            cell = cell.opponent();
+        }
+        Ok(board)
+    }
+
+    // Places the piece on the blank cell, keeping the hashes and line counts up to date
+    fn put(&mut self, idx: usize, cell: Cell) {
+        self.cells[idx] = cell;
+        self.toggle_hash(idx, cell);
+        for &line in &self.cell_lines[idx] {
+            self.line_counts[line].update(cell, true);
+        }
+    }
+
+    // Removes the piece from the cell, keeping the hashes and line counts up to date
+    fn clear(&mut self, idx: usize) {
+        let cell = self.cells[idx];
+        self.cells[idx] = Cell::Blank;
+        self.toggle_hash(idx, cell);
+        for &line in &self.cell_lines[idx] {
+            self.line_counts[line].update(cell, false);
+        }
+    }
+
+    // Computes the hashes from the pieces on the board again, e.g. after the rules changed
+    // how many of them are kept
+    fn rehash(&mut self) {
+        self.hashes = [0; 8];
+        for idx in 0..self.cells.len() {
+            if matches!(self.cells[idx], Cell::X | Cell::O) {
+                self.toggle_hash(idx, self.cells[idx]);
+            }
+        }
+    }
+
+    // Adds the piece on the cell to the hashes, or removes it
+    fn toggle_hash(&mut self, idx: usize, cell: Cell) {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let (last_x, last_y) = (self.width - 1, self.height - 1);
+        let symmetries = self.symmetries();
+        for (hash, transform) in self.hashes[..symmetries].iter_mut().zip(SYMMETRIES) {
+            let (x, y) = transform(x, y, last_x, last_y);
+            // a fixed random number for every cell and piece
+            let key = 2 * (x + y * self.width) + usize::from(cell == Cell::O);
+            *hash ^= Rng::new(key as u64).next_u64();
+        }
+    }
+
+    /// Zobrist hash of the pieces on the board. Positions with the same pieces have the
+    /// same hash, regardless of the order of the moves.
+    pub fn hash(&self) -> u64 {
+        self.hashes[0]
+    }
+
+    /// Zobrist hash which is also the same for positions that are rotated or mirrored
+    /// versions of each other
+    pub fn canonical_hash(&self) -> u64 {
+        self.hashes[..self.symmetries()]
+            .iter()
+            .copied()
+            .min()
+            .unwrap()
+    }
+
+    /// The position rotated or mirrored into the orientation all its symmetric versions
+    /// share, the one whose [`hash`](Board::hash) is the [canonical
+    /// hash](Board::canonical_hash). The position has no move history.
+    pub fn canonical_form(&self) -> Board {
+        let (_, transform) = self.hashes[..self.symmetries()]
+            .iter()
+            .zip(SYMMETRIES)
+            .min_by_key(|&(hash, _)| *hash)
+            .unwrap();
+        let mut board = self.clone();
+        for idx in 0..self.cells.len() {
+            if matches!(board.cells[idx], Cell::X | Cell::O) {
+                board.clear(idx);
+            }
+        }
+        board.history.clear();
+        board.move_times.clear();
+        board.undone.clear();
+        let (last_x, last_y) = (self.width - 1, self.height - 1);
+        for (idx, &cell) in self.cells.iter().enumerate() {
+            if matches!(cell, Cell::X | Cell::O) {
+                let (x, y) = transform(idx % self.width, idx / self.width, last_x, last_y);
+                board.put(x + y * self.width, cell);
             }
-            break;
         }
-        self.check_game_over(x, y, self.human_uses)
+        board
+    }
+
+    /// Whether rotating or mirroring the position can give the same position again, e.g.
+    /// for the empty board
+    pub fn is_symmetric(&self) -> bool {
+        self.hashes[1..self.symmetries()].contains(&self.hashes[0])
+    }
+
+    // The number of the ways to rotate and mirror the board, see [`SYMMETRIES`]. With rules
+    // like gravity only mirroring the columns keeps the bottom row where it is.
+    fn symmetries(&self) -> usize {
+        if self.blocked > 0 {
+            1
+        } else if !self.rules.is_rotatable() {
+            2
+        } else if self.is_square() {
+            SYMMETRIES.len()
+        } else {
+            4
+        }
+    }
+
+    /// Take back the last move and return its coordinates, if any. It can be made again
+    /// with [`Board::redo`]. Taking back the first move also takes back a
+    /// [swap](Board::swap_sides) after it.
+    pub fn undo(&mut self) -> Option<Coord> {
+        if self.history.len() == 1 {
+            self.undo_swap();
+        }
+        let coord = self.history.pop()?;
+        let time = self.move_times.pop().flatten();
+        let cell = self.get_cell(coord);
+        self.clear(self.index(coord));
+        self.moves -= 1;
+        self.undone.push((coord, cell, time));
+        Some(coord)
+    }
+
+    /// Make the move taken back last with [`Board::undo`] again and return its coordinates,
+    /// if there is one. Making another move instead forgets the moves taken back.
+    pub fn redo(&mut self) -> Option<Coord> {
+        let (coord, cell, time) = self.undone.pop()?;
+        self.put(self.index(coord), cell);
+        self.moves += 1;
+        self.history.push(coord);
+        self.move_times.push(time);
+        Some(coord)
+    }
+
+    /// Coordinates of the moves played so far, in order
+    pub fn history(&self) -> &[Coord] {
+        &self.history
+    }
+
+    /// Time taken for each move in the history, if it was measured
+    pub fn move_times(&self) -> &[Option<Duration>] {
+        &self.move_times
+    }
+
+    /// Record the time taken for the last move, e.g. by the human to enter it. Moves of the
+    /// computer are timed when they are made.
+    pub fn set_last_move_time(&mut self, elapsed: Duration) {
+        if let Some(time) = self.move_times.last_mut() {
+            *time = Some(elapsed);
+        }
+    }
+
+    /// The number of columns
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The number of pieces of both players on the board, also those placed before the
+    /// first move of the [history](Board::history)
+    pub fn piece_count(&self) -> usize {
+        self.moves
+    }
+
+    /// Whether the board has as many rows as columns
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
+    }
+
+    /// The number of pieces in a row which wins, the shorter side unless the board was
+    /// built with a win length of its own
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// The cell type used by the human player
+    pub fn human_uses(&self) -> Cell {
+        self.human_uses
+    }
+
+    /// Let the second player swap sides after the first move instead of moving, taking over
+    /// the first move, so that the first player has no reason to open with the strongest
+    /// move: the pie rule
+    pub fn set_pie_rule(&mut self, pie_rule: bool) {
+        self.pie_rule = pie_rule;
+    }
+
+    /// Whether the pie rule is on, see [`Board::set_pie_rule`]
+    pub fn pie_rule(&self) -> bool {
+        self.pie_rule
+    }
+
+    /// Whether the player to move may swap sides now: the pie rule is on and only the first
+    /// move has been made
+    pub fn can_swap(&self) -> bool {
+        self.pie_rule && self.history.len() == 1 && !self.swapped
+    }
+
+    /// Swap sides under the pie rule: the human takes over the piece of the first move and
+    /// the computer plays the other one, or the other way round. The player who swapped has
+    /// their move with it, so the other player is to move next.
+    ///
+    /// Returns an error if swapping isn't [allowed](Board::can_swap).
+    pub fn swap_sides(&mut self) -> Result<(), MoveError> {
+        if !self.can_swap() {
+            return Err(MoveError::SwapNotAllowed);
+        }
+        self.human_uses = self.human_uses.opponent();
+        self.swapped = true;
+        Ok(())
     }
 
+    /// Take back the [swap](Board::swap_sides) right after the first move, so that the
+    /// player who swapped can choose again, returning whether there was one
+    pub fn undo_swap(&mut self) -> bool {
+        if !self.swapped || self.history.len() != 1 {
+            return false;
+        }
+        self.human_uses = self.human_uses.opponent();
+        self.swapped = false;
+        true
+    }
+
+    /// Whether the sides were swapped after the first move, see [`Board::swap_sides`]
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// The cells a move can be made on, row by row: the blank cells the
+    /// [rules](Ruleset::is_legal) allow, e.g. with [gravity](Board::with_gravity) the
+    /// lowest blank cell of every column
+    pub fn legal_moves(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.cells.len())
+            .filter(|&idx| self.playable(idx))
+            .map(|idx| self.coord(idx))
+    }
+
+    /// The player who has won, usually by completing a line, found by checking every line of
+    /// the board, see [`Ruleset::winner`]. Unlike [`Board::result`] it doesn't look at the
+    /// last move.
+    pub fn winner(&self) -> Option<Cell> {
+        self.rules.winner(self)
+    }
+
+    /// Whether every cell is taken or blocked
+    pub fn is_full(&self) -> bool {
+        self.moves + self.blocked == self.cells.len()
+    }
+
+    /// The counts of the pieces on every line which wins when filled: the segments of the
+    /// [win length](Board::win_length) of the columns, the rows, the diagonals from the top
+    /// left and those from the top right. They are kept up to date with every move, so
+    /// evaluations don't have to scan the lines.
+    pub fn line_counts(&self) -> &[LineCount] {
+        &self.line_counts
+    }
+
+    /// The counts of the lines through the cell at the given coordinates, see
+    /// [`Board::line_counts`]
+    pub fn line_counts_at(&self, coord: impl Into<Coord>) -> impl Iterator<Item = &LineCount> {
+        let coord = coord.into();
+        assert!(coord.col < self.width);
+        assert!(coord.row < self.height);
+        self.cell_lines[self.index(coord)]
+            .iter()
+            .map(|&line| &self.line_counts[line])
+    }
+
+    /// The coordinates and contents of every cell, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (Coord, Cell)> + '_ {
+        self.contents(0..self.cells.len()).into_iter()
+    }
+
+    /// The cells of every row, top to bottom, each from left to right
+    pub fn rows(&self) -> impl Iterator<Item = Vec<(Coord, Cell)>> + '_ {
+        let width = self.width;
+        (0..self.height).map(move |y| self.contents((0..width).map(|x| x + y * width)))
+    }
+
+    /// The cells of every column, left to right, each from top to bottom
+    pub fn cols(&self) -> impl Iterator<Item = Vec<(Coord, Cell)>> + '_ {
+        let (width, height) = (self.width, self.height);
+        (0..width).map(move |x| self.contents((0..height).map(|y| x + y * width)))
+    }
+
+    /// The cells of every diagonal long enough to hold the [win
+    /// length](Board::win_length), from edge to edge: first those from the top left to the
+    /// bottom right, then those from the top right to the bottom left, each from the top
+    pub fn diagonals(&self) -> impl Iterator<Item = Vec<(Coord, Cell)>> + '_ {
+        let (width, height) = (self.width, self.height);
+        // the diagonals start on the top row or on the side they come from
+        let down_right = (0..width)
+            .map(|x| (x, 0))
+            .chain((1..height).map(|y| (0, y)))
+            .map(move |(x, y)| {
+                let len = (width - x).min(height - y);
+                self.contents((0..len).map(|i| x + i + (y + i) * width))
+            });
+        let down_left = (0..width)
+            .map(|x| (x, 0))
+            .chain((1..height).map(move |y| (width - 1, y)))
+            .map(move |(x, y)| {
+                let len = (x + 1).min(height - y);
+                self.contents((0..len).map(|i| x - i + (y + i) * width))
+            });
+        down_right
+            .chain(down_left)
+            .filter(|diagonal| diagonal.len() >= self.win_length)
+    }
+
+    /// The cells of every line which wins when filled, in the order of
+    /// [`Board::line_counts`], each in order along the line. With [wrap](Board::with_wrap)
+    /// lines continue across the edges, and lines through blocked cells are left out.
+    pub fn win_lines(&self) -> impl Iterator<Item = Vec<(Coord, Cell)>> + '_ {
+        self.win_lines
+            .iter()
+            .map(|line| self.contents(line.iter().copied()))
+    }
+
+    // The coordinates and contents of the cells with the given indices
+    fn contents(&self, cells: impl IntoIterator<Item = usize>) -> Vec<(Coord, Cell)> {
+        cells
+            .into_iter()
+            .map(|idx| (self.coord(idx), self.cells[idx]))
+            .collect()
+    }
+
+    /// Get the cell at the given coordinates.
+    pub fn get_cell(&self, coord: impl Into<Coord>) -> Cell {
+        let coord = coord.into();
+        assert!(coord.col < self.width);
+        assert!(coord.row < self.height);
+        self.cells[self.index(coord)]
+    }
+
+    /// Make the move, which must be one of the player to move, and return the result if it
+    /// ends the game. Prompting for moves and showing the board is up to the caller.
+    ///
+    /// Returns an error if the cell is off the board or no move can be made on it, if it
+    /// isn't the player's turn, or if the game is already over.
+    pub fn apply_move(&mut self, mv: Move) -> Result<Option<GameOver>, MoveError> {
+        self.check_move(mv)?;
+        self.set_cell(mv.coord, mv.mark)
+            .expect("the cell was checked to be playable");
+        Ok(self.check_game_over(mv.coord))
+    }
+
+    /// Check that the move can be made, see [`Board::apply_move`], without making it
+    pub fn check_move(&self, mv: Move) -> Result<(), MoveError> {
+        let Move { coord, mark } = mv;
+        if coord.col >= self.width || coord.row >= self.height {
+            return Err(MoveError::OutOfBounds(coord));
+        }
+        if self.winner().is_some() || self.rules.is_terminal(self) {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        if mark != self.to_move() {
+            return Err(MoveError::NotYourTurn {
+                to_move: self.to_move(),
+            });
+        }
+        match self.get_cell(coord) {
+            Cell::Blocked => Err(MoveError::CellBlocked),
+            Cell::X | Cell::O => Err(MoveError::CellTaken),
+            Cell::Blank if !self.is_playable(coord) => Err(MoveError::NotPlayable),
+            Cell::Blank => Ok(()),
+        }
+    }
+
+    /// Make a move for the human player at the given coordinates
+    ///
+    /// Returns an error if the coordinates are off the board or the cell can't be played
+    pub fn human_move(&mut self, coord: impl Into<Coord>) -> Result<Option<GameOver>, MoveError> {
+        let coord = coord.into();
+        self.set_cell(coord, self.human_uses)?;
+        Ok(self.check_game_over(coord))
+    }
+
+    /// Make a move for the computer with the default engine and its
+    /// [default settings](EngineConfig::default)
     pub fn computer_move(&mut self) -> Option<GameOver> {
+        let mut engine = EngineKind::default().build(&EngineConfig::default());
+        self.computer_move_with(engine.as_mut())
+    }
+
+    /// Make a move for the computer chosen by the given engine, the
+    /// [first move](Engine::pie_opening) with the pie rule accordingly
+    ///
+    /// Panics if the engine chooses a cell no move can be made on
+    pub fn computer_move_with(&mut self, engine: &mut dyn Engine) -> Option<GameOver> {
+        let start = Instant::now();
         let comp_uses = self.human_uses.opponent();
-        let (x, y) = self.best_move(comp_uses);
-        self.set_cell(x, y, comp_uses).unwrap();
-        self.check_game_over(x, y, comp_uses)
+        let coord = if self.pie_rule && self.history.is_empty() {
+            engine.pie_opening(self, comp_uses)
+        } else {
+            engine.choose(self, comp_uses)
+        };
+        assert!(
+            coord.col < self.width && coord.row < self.height && self.is_playable(coord),
+            "the engine chose {coord}, which is not a playable cell"
+        );
+        self.set_cell(coord, comp_uses).unwrap();
+        self.set_last_move_time(start.elapsed());
+        self.check_game_over(coord)
     }
 
-    /// Find the best next move.
+    /// Heuristic score of every cell for the given player, indexed by `x + y * width`,
+    /// weighted by the default [`EvalWeights`]
+    pub fn scores(&self, cell: Cell) -> Vec<usize> {
+        self.scores_with(cell, EvalWeights::default())
+    }
+
+    /// Heuristic score of every cell for the given player, weighted by the given weights
     //
     // Fills a field by row / column / diagonal with a sum of:
-    // - if cell empty: 1
-    //   - if line does not contain opponent piece: dim - empty on line
-    fn best_move(&mut self, cell: Cell) -> (usize, usize) {
+    // - if cell empty: blank
+    //   - if line does not contain opponent piece: line + progress * pieces on line
+    pub fn scores_with(&self, cell: Cell, weights: EvalWeights) -> Vec<usize> {
         let opponent = cell.opponent();
-        let mut wins: Vec<usize> = self
-            .cells
-            .iter()
-            .map(|c| if *c == Cell::Blank { 1 } else { 0 })
+        let mut wins: Vec<usize> = (0..self.cells.len())
+            .map(|idx| if self.playable(idx) { weights.blank } else { 0 })
             .collect();
-        'outer: for win_line in self.win_lines.iter() {
-            let mut blanks: Vec<usize> = Vec::new();
-            for idx in win_line {
-                let c = self.cells[*idx];
-                if c == opponent {
-                    continue 'outer;
-                }
-                if c == Cell::Blank {
-                    blanks.push(*idx);
-                }
+        for (win_line, count) in self.win_lines.iter().zip(&self.line_counts) {
+            if count.pieces(opponent) > 0 {
+                continue;
             }
-            if blanks.len() == 1 {
-                // win in 1 move, no need to continue
-                return (blanks[0] % self.dim, blanks[0] / self.dim);
+            let score = weights.line + weights.progress * count.pieces(cell);
+            for &idx in win_line {
+                if self.playable(idx) {
+                    wins[idx] += score;
+                }
             }
-            let moves = self.dim + 1 - blanks.len();
-            for idx in blanks {
-                wins[idx] += moves;
+        }
+        wins
+    }
+
+    /// Find the best next move, rating the cells by the cell scores of the weights.
+    pub(crate) fn best_move(&self, cell: Cell, weights: EvalWeights) -> Coord {
+        // win in 1 move, otherwise prevent losing in 1 move
+        for player in [cell, cell.opponent()] {
+            if let Some(idx) = self.completing_cells(player).first() {
+                return self.coord(*idx);
             }
         }
-        // check for 1 move lose
-        'outer: for win_line in self.win_lines.iter() {
-            let mut blank = 0;
-            let mut count = 0;
-            for idx in win_line {
-                let c = self.cells[*idx];
-                if c == cell {
-                    continue 'outer;
-                }
-                if c == Cell::Blank {
-                    if count > 0 {
-                        continue 'outer;
-                    }
-                    blank = *idx;
-                    count += 1;
+        // create a double threat, otherwise keep the opponent from creating one, otherwise
+        // determine move from wins calculation
+        let wins = self.scores_with(cell, weights);
+        let candidates = [self.fork_cells(cell), self.fork_blocks(cell)]
+            .into_iter()
+            .find(|cells| !cells.is_empty())
+            .unwrap_or_else(|| (0..wins.len()).filter(|&idx| self.playable(idx)).collect());
+        let max = candidates.into_iter().max_by_key(|&idx| wins[idx]).unwrap();
+        self.coord(max)
+    }
+
+    // the index into the cells of the cell at the coordinates
+    fn index(&self, coord: Coord) -> usize {
+        coord.col + coord.row * self.width
+    }
+
+    // the coordinates of the cell at the index into the cells
+    fn coord(&self, idx: usize) -> Coord {
+        Coord {
+            row: idx / self.width,
+            col: idx % self.width,
+        }
+    }
+
+    /// All moves the engine considers best: the winning moves if there are any, otherwise
+    /// the moves preventing an immediate loss, otherwise the [moves creating a double
+    /// threat](Board::fork_moves), otherwise those keeping the opponent from creating one,
+    /// otherwise the moves with the highest score.
+    ///
+    /// The move chosen by the engine is always one of them.
+    pub fn best_moves(&self, cell: Cell) -> Vec<Coord> {
+        self.best_moves_with(cell, EvalWeights::default())
+    }
+
+    /// The [best moves](Board::best_moves), the scores weighted by the given weights
+    pub fn best_moves_with(&self, cell: Cell, weights: EvalWeights) -> Vec<Coord> {
+        let mut best = self.completing_cells(cell);
+        if best.is_empty() {
+            best = self.completing_cells(cell.opponent());
+        }
+        if best.is_empty() {
+            best = self.fork_cells(cell);
+        }
+        if best.is_empty() {
+            best = self.fork_blocks(cell);
+        }
+        if best.is_empty() {
+            let wins = self.scores_with(cell, weights);
+            let max = wins.iter().max().copied().unwrap_or(0);
+            best = (0..wins.len())
+                .filter(|&idx| wins[idx] == max && self.playable(idx))
+                .collect();
+        }
+        best.sort();
+        best.dedup();
+        best.into_iter().map(|idx| self.coord(idx)).collect()
+    }
+
+    /// The moves which immediately win the game for the given player
+    pub fn winning_moves(&self, cell: Cell) -> Vec<Coord> {
+        let mut cells = self.completing_cells(cell);
+        cells.sort();
+        cells.dedup();
+        cells.into_iter().map(|idx| self.coord(idx)).collect()
+    }
+
+    /// Whether the given player, when it is their turn, can force a win with their next two
+    /// moves: they can win right away, or they can threaten two lines at once without the
+    /// opponent having a winning move left
+    pub fn can_force_win(&self, cell: Cell) -> bool {
+        if !self.completing_cells(cell).is_empty() {
+            return true;
+        }
+        let opponent_wins = self.completing_cells(cell.opponent());
+        self.fork_cells(cell)
+            .into_iter()
+            .any(|idx| opponent_wins.iter().all(|&win| win == idx))
+    }
+
+    /// The moves after which the given player threatens to complete two lines at once, so
+    /// that the opponent can block only one of them: a double threat, or fork
+    pub fn fork_moves(&self, cell: Cell) -> Vec<Coord> {
+        self.fork_cells(cell)
+            .into_iter()
+            .map(|idx| self.coord(idx))
+            .collect()
+    }
+
+    // Blank cells which turn more than one line into a threat for the given player
+    fn fork_cells(&self, cell: Cell) -> Vec<usize> {
+        // number of lines each blank cell would turn into a threat
+        let mut threats = vec![0; self.cells.len()];
+        for (line, count) in self.win_lines.iter().zip(&self.line_counts) {
+            if count.pieces(cell.opponent()) > 0 || count.blanks != 2 {
+                continue;
+            }
+            for &idx in line {
+                if self.cells[idx] == Cell::Blank {
+                    threats[idx] += 1;
                 }
             }
-            if count == 1 {
-                return (blank % self.dim, blank / self.dim);
+        }
+        (0..self.cells.len())
+            .filter(|&idx| threats[idx] > 1 && self.playable(idx))
+            .collect()
+    }
+
+    // Blank cells keeping the opponent of the given player from creating a double threat:
+    // the cell where they could create one, or, if there are several, the cells threatening
+    // a line whose blocking reply doesn't create one
+    fn fork_blocks(&self, cell: Cell) -> Vec<usize> {
+        let forks = self.fork_cells(cell.opponent());
+        if forks.len() < 2 {
+            return forks;
+        }
+        let mut forcing = Vec::new();
+        for (line, count) in self.win_lines.iter().zip(&self.line_counts) {
+            if count.pieces(cell.opponent()) > 0 || count.blanks != 2 {
+                continue;
+            }
+            let blanks: Vec<usize> = line
+                .iter()
+                .copied()
+                .filter(|&idx| self.cells[idx] == Cell::Blank)
+                .collect();
+            for (threat, reply) in [(blanks[0], blanks[1]), (blanks[1], blanks[0])] {
+                if self.playable(threat) && !forks.contains(&reply) {
+                    forcing.push(threat);
+                }
             }
         }
-        // determine move from wins calculation
-        let max = wins
+        if forcing.is_empty() {
+            forks
+        } else {
+            forcing
+        }
+    }
+
+    // Playable cells which complete a line for the given player
+    fn completing_cells(&self, cell: Cell) -> Vec<usize> {
+        self.win_lines
             .iter()
-            .enumerate()
-            .max_by_key(|(_idx, &val)| val)
-            .unwrap()
-            .0;
-        (max % self.dim, max / self.dim)
+            .zip(&self.line_counts)
+            .filter(|(_, count)| count.blanks == 1 && count.pieces(cell) == self.win_length - 1)
+            .filter_map(|(line, _)| {
+                line.iter()
+                    .copied()
+                    .find(|&idx| self.cells[idx] == Cell::Blank)
+            })
+            .filter(|&idx| self.playable(idx))
+            .collect()
     }
 
-    /// Accept input from the user and validate it. On error, print an error message and loop.
-    fn accept_input(&mut self) -> (usize, usize) {
-        loop {
-            println!("Enter x and y separated by a space: ");
-            let mut input = String::new();
-            if let Err(e) = std::io::stdin().read_line(&mut input) {
-                println!("Failed to read line: {}", e);
-                continue;
-            }
-            let re = Regex::new(r"^(\d+) (\d+)").unwrap();
-            let cap = re.captures(&input);
-            if cap.is_none() {
-                println!("Invalid input: {}", input);
-                continue;
-            }
-            let cap = cap.unwrap();
-            let row: usize = cap[1].parse().unwrap();
-            let col: usize = cap[2].parse().unwrap();
-            if row < 1 || col < 1 || row > self.dim || col > self.dim {
-                println!("Invalid coordinates");
-                continue;
+    /// Parse a line of user input into zero based coordinates. With
+    /// [gravity](Board::with_gravity) a single number selects the column to drop a piece
+    /// into.
+    pub fn parse_input(&self, input: &str) -> Result<Coord, InputError> {
+        if let (true, Ok(x)) = (self.has_gravity(), input.trim().parse::<usize>()) {
+            if !(1..=self.width).contains(&x) {
+                return Err(ParseError::OutOfRange {
+                    axis: 'x',
+                    value: x,
+                    dim: self.width,
+                }
+                .into());
             }
-            return (row - 1, col - 1);
+            let y = self
+                .drop_row(x - 1)
+                .ok_or_else(|| InputError::Invalid(t!("column-full").to_string()))?;
+            return Ok((x - 1, y).into());
         }
+        Ok(input::parse_move(input, self.width, self.height)?.into())
     }
 
     /// Check if the game is over and return the state:
     /// HumanWon, ComputerWon, Tie or None
     ///
-    /// The game is over if one player has occupied cells in a full line (row, column or diagonal).
+    /// The game is over if one player has occupied the cells of a line (the win length of a row,
+    /// column or diagonal).
     /// If all cells are occupied, it's a tie.
     ///
-    /// To reduce the complexity of the calculation, the function receives coordinates and player of the last move,
-    /// as only the last move can lead to a win.
-    fn check_game_over(&self, x: usize, y: usize, cell: Cell) -> Option<GameOver> {
-        let idx = x + y * self.dim;
-        let win_lines = self.win_lines.iter().filter(|v| v.contains(&idx));
-        'outer: for win_line in win_lines {
-            for idx in win_line {
-                if self.cells[*idx] != cell {
-                    continue 'outer;
-                }
-            }
-            return self.won(cell);
+    /// To reduce the complexity of the calculation, the function receives coordinates of the last move,
+    /// as only the last move can lead to a win. What counts as a win or a tie is up to the
+    /// [rules](Ruleset).
+    fn check_game_over(&self, coord: Coord) -> Option<GameOver> {
+        if let Some(winner) = self.rules.winner_after(self, coord) {
+            return self.won(winner);
         }
-        if self.moves == self.dim * self.dim {
+        if self.rules.is_terminal(self) {
             Some(GameOver::Tie)
         } else {
             None
         }
     }
 
+    /// The state of the game: HumanWon, ComputerWon, Tie or None while it goes on. After a
+    /// move only the lines through it can have changed the result; a position without
+    /// moves, e.g. [from its notation](Board::from_notation), is judged as a whole.
+    pub fn result(&self) -> Option<GameOver> {
+        match self.history.last() {
+            Some(&coord) => self.check_game_over(coord),
+            None => {
+                if let Some(winner) = self.rules.winner(self) {
+                    return self.won(winner);
+                }
+                self.rules.is_terminal(self).then_some(GameOver::Tie)
+            }
+        }
+    }
+
+    /// The result of the game like [`Board::result`], together with the winner and the
+    /// line deciding the game, e.g. to highlight it
+    pub fn game_result(&self) -> Option<GameResult> {
+        let over = self.result()?;
+        let winner = match over {
+            GameOver::HumanWon | GameOver::Resigned => Some(self.human_uses),
+            GameOver::ComputerWon => Some(self.human_uses.opponent()),
+            GameOver::Tie => None,
+        };
+        let line = match (winner, self.history.last()) {
+            (None, _) => None,
+            (Some(_), Some(&last)) => {
+                let idx = self.index(last);
+                let cell = self.cells[idx];
+                self.cell_lines[idx]
+                    .iter()
+                    .copied()
+                    .find(|&line| self.line_counts[line].pieces(cell) == self.win_length)
+            }
+            (Some(_), None) => self
+                .line_counts
+                .iter()
+                .position(|count| count.x == self.win_length || count.o == self.win_length),
+        };
+        let line = line.map_or_else(Vec::new, |line| {
+            self.win_lines[line]
+                .iter()
+                .map(|&idx| self.coord(idx))
+                .collect()
+        });
+        Some(GameResult { over, winner, line })
+    }
+
+    /// Whether the computer has won or can't be stopped from winning anymore: it threatens
+    /// to complete more than one line and the human has no winning move of their own
+    pub fn computer_forces_win(&self) -> bool {
+        let computer = self.human_uses.opponent();
+        match self.result() {
+            Some(over) => over == GameOver::ComputerWon,
+            None => {
+                self.winning_moves(self.human_uses).is_empty()
+                    && self.winning_moves(computer).len() > 1
+            }
+        }
+    }
+
     // Translates the winning cell type (X or O) into the game over state
     fn won(&self, c: Cell) -> Option<GameOver> {
         if c == self.human_uses {
@@ -303,11 +1853,11 @@ impl Board {
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let sep = "+---".repeat(self.dim) + "+";
+        let sep = "+---".repeat(self.width) + "+";
         let _ = writeln!(f, "{}", sep);
-        for y in 0..self.dim {
-            for x in 0..self.dim {
-                let _ = write!(f, "| {} ", self.get_cell(x, y));
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let _ = write!(f, "| {} ", self.get_cell((x, y)));
             }
             let _ = writeln!(f, "|");
             let _ = writeln!(f, "{}", sep);
@@ -316,6 +1866,137 @@ impl fmt::Display for Board {
     }
 }
 
+// How a board is saved, see [`Board`]
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedBoard {
+    width: usize,
+    height: usize,
+    win_length: usize,
+    wrap: bool,
+    rules: String,
+    human_uses: Cell,
+    first: Cell,
+    handicap: Option<(Cell, usize)>,
+    swapped: bool,
+    pie_rule: bool,
+    // the cells before the first move of the history, row by row
+    start: Vec<Cell>,
+    moves: Vec<Move>,
+    move_times: Vec<Option<Duration>>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for SavedBoard {
+    fn from(board: Board) -> Self {
+        let mut start = board.cells.clone();
+        let moves = board
+            .history
+            .iter()
+            .map(|&coord| {
+                let mark = std::mem::replace(&mut start[board.index(coord)], Cell::Blank);
+                Move { coord, mark }
+            })
+            .collect();
+        SavedBoard {
+            width: board.width,
+            height: board.height,
+            win_length: board.win_length,
+            wrap: board.wrap,
+            rules: board.rules.name().to_string(),
+            human_uses: board.human_uses,
+            first: board.first,
+            handicap: board.handicap,
+            swapped: board.swapped,
+            pie_rule: board.pie_rule,
+            start,
+            moves,
+            move_times: board.move_times,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Board {
+    // Sets the player making the first move and the handicap pieces placed before it,
+    // checking that they belong to X or O and that the pieces on the board can arise from
+    // them
+    fn start_with(
+        &mut self,
+        first: Cell,
+        handicap: Option<(Cell, usize)>,
+    ) -> Result<(), BoardError> {
+        let player = |cell| matches!(cell, Cell::X | Cell::O);
+        if !player(first) || handicap.is_some_and(|(cell, _)| !player(cell)) {
+            return Err(BoardError::InvalidPlayer);
+        }
+        let (begins, placed) = match handicap {
+            Some((cell, count)) => (cell.opponent(), count),
+            None => (first, 0),
+        };
+        let count = |cell| self.cells.iter().filter(|&&c| c == cell).count();
+        let (x, o) = (count(Cell::X), count(Cell::O));
+        let moved = count(begins);
+        let answered = count(begins.opponent())
+            .checked_sub(placed)
+            .ok_or(BoardError::InvalidHandicap { count: placed })?;
+        if moved != answered && moved != answered + 1 {
+            return Err(BoardError::ImplausibleCounts { x, o });
+        }
+        self.first = first;
+        self.handicap = handicap;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedBoard> for Board {
+    type Error = String;
+
+    fn try_from(saved: SavedBoard) -> Result<Self, Self::Error> {
+        let mut board = Board::build_rectangle(
+            saved.width,
+            saved.height,
+            saved.win_length,
+            saved.human_uses,
+        )
+        .map_err(|e| e.to_string())?
+        .with_wrap(saved.wrap);
+        if saved.start.len() != board.cells.len() {
+            let error = BoardError::WrongSize {
+                cells: saved.start.len(),
+                width: board.width,
+                height: board.height,
+            };
+            return Err(error.to_string());
+        }
+        board.fill(&saved.start);
+        board.check_one_winner().map_err(|e| e.to_string())?;
+        board = match saved.rules.as_str() {
+            "standard" => board,
+            "gravity" => board.with_rules(Gravity).map_err(|e| e.to_string())?,
+            "misere" => board.with_rules(Misere).map_err(|e| e.to_string())?,
+            name => return Err(t!("saved-rules-unknown", name = name)),
+        };
+        board
+            .start_with(saved.first, saved.handicap)
+            .map_err(|e| e.to_string())?;
+        for mv in saved.moves {
+            if !matches!(mv.mark, Cell::X | Cell::O) {
+                return Err(t!("saved-move-mark").to_string());
+            }
+            board.apply_move(mv).map_err(|e| e.to_string())?;
+        }
+        if saved.move_times.len() != board.history.len() {
+            return Err(t!("saved-move-times").to_string());
+        }
+        board.move_times = saved.move_times;
+        board.swapped = saved.swapped;
+        board.pie_rule = saved.pie_rule;
+        Ok(board)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -331,7 +2012,86 @@ mod tests {
             Cell::X,
         )
         .unwrap();
-        assert_eq!(board.check_game_over(0, 0, Cell::X).unwrap(), GameOver::Tie);
+        assert_eq!(board.check_game_over((0, 0).into()).unwrap(), GameOver::Tie);
+    }
+
+    #[test]
+    fn position_strings() {
+        let mut board = Board::build(3, Cell::O).unwrap();
+        board.set_cell((0, 0), Cell::X).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        board.set_cell((2, 1), Cell::X).unwrap();
+        let position = board.to_notation();
+        assert_eq!(position, "X--/-OX/---");
+        let pasted = Board::from_position(&position, Cell::O).unwrap();
+        assert_eq!(pasted.width(), 3);
+        assert_eq!(pasted.cells, board.cells);
+        assert_eq!(pasted.to_move(), Cell::O);
+        assert!(pasted.history().is_empty());
+        assert_eq!(
+            Board::from_position("X---", Cell::X).unwrap().cells,
+            Board::from_string("X-\n--", 2, Cell::X).unwrap().cells
+        );
+        for position in [
+            "X--/-O-/--",
+            "XX-/---/---",
+            "XXX/OO-/---",
+            "XOX/XOO/OXX",
+            "XX--",
+        ] {
+            assert!(
+                Board::from_position(position, Cell::X).is_err(),
+                "{}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn notations() {
+        for board in random_positions() {
+            let notation = board.to_notation();
+            let parsed = Board::from_notation(&notation).unwrap();
+            assert_eq!(parsed.to_notation(), notation);
+            assert_eq!(parsed.hash(), board.hash());
+            assert_eq!(parsed.to_move(), board.to_move());
+        }
+        let o_first = Board::from_notation("-O-/---/---").unwrap();
+        assert_eq!(o_first.to_move(), Cell::X);
+        assert!(Board::from_notation("XXX/OO-/---").is_ok());
+        assert_eq!(
+            Board::from_notation("#-\n-X").unwrap().to_notation(),
+            "#-/-X"
+        );
+        for (notation, error) in [
+            ("XO-/X?-/---", BoardError::InvalidChar('?')),
+            ("X--/--", BoardError::NotRectangular),
+            ("XX--X", BoardError::NotSquare),
+            ("XXX/---/---", BoardError::ImplausibleCounts { x: 3, o: 0 }),
+            ("XXX/OOO/---", BoardError::BothWon),
+        ] {
+            assert_eq!(Board::from_notation(notation).err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn invalid_position() {
+        for (position, dim) in [("XO-\nX?-\n---", 3), ("XO-X", 3), ("", 3), ("XO-X", 0)] {
+            assert!(Board::from_string(position, dim, Cell::X).is_err());
+        }
+        // the older parsers check positions like the notation
+        assert_eq!(
+            Board::from_string("XX-/X--/---", 3, Cell::X).unwrap_err(),
+            BoardError::ImplausibleCounts { x: 3, o: 0 }
+        );
+        assert_eq!(
+            Board::from_position("XXX/OOO/X--", Cell::X).unwrap_err(),
+            BoardError::BothWon
+        );
+        assert_eq!(
+            Board::from_handicap("XXX/---/---", Cell::O).unwrap_err(),
+            BoardError::GameOver
+        );
     }
 
     #[test]
@@ -341,7 +2101,7 @@ mod tests {
                 "row 1", // name
                 "
                 XXX
-                OXX
+                OXO
                 XOO", // board
                 (2, 0),  // last move for X
             ),
@@ -372,7 +2132,7 @@ mod tests {
             (
                 "dia 1",
                 "
-                XXO
+                XOO
                 OXX
                 XOX",
                 (0, 0),
@@ -389,7 +2149,7 @@ mod tests {
         for (name, board, (x, y)) in tests {
             let board = Board::from_string(board, 3, Cell::X).unwrap();
             assert_eq!(
-                board.check_game_over(x, y, Cell::X).unwrap(),
+                board.check_game_over((x, y).into()).unwrap(),
                 GameOver::HumanWon,
                 "test case {} failed",
                 name
@@ -407,7 +2167,7 @@ mod tests {
 ---
 ---
 ---",
-                (1, 1),
+                Coord { row: 1, col: 1 },
             ),
             (
                 "avoid loss",
@@ -416,7 +2176,7 @@ mod tests {
 X--
 XO-
 ---",
-                (0, 2),
+                Coord { row: 2, col: 0 },
             ),
             (
                 "win over avoid loss",
@@ -425,20 +2185,97 @@ XO-
 X--
 XO-
 -O-",
-                (1, 0),
+                Coord { row: 0, col: 1 },
             ),
         ];
-        for (name, board, (x, y)) in tests {
-            let mut board = Board::from_string(board, 3, Cell::X).unwrap();
+        for (name, board, best) in tests {
+            let board = Board::from_string(board, 3, Cell::X).unwrap();
             assert_eq!(
-                board.best_move(Cell::O),
-                (x, y),
+                board.best_move(Cell::O, EvalWeights::default()),
+                best,
                 "test case '{}' failed",
                 name
             );
         }
     }
 
+    #[test]
+    fn best_move_is_among_best_moves() {
+        let tests = [
+            (
+                "
+---
+---
+---",
+                vec![(1, 1)],
+            ),
+            (
+                "
+X--
+XO-
+---",
+                vec![(0, 2)],
+            ),
+            (
+                "
+X--
+XO-
+-O-",
+                vec![(1, 0)],
+            ),
+        ];
+        for (board, best) in tests {
+            let board = Board::from_string(board, 3, Cell::X).unwrap();
+            assert_eq!(board.best_moves(Cell::O), best);
+            let chosen = board.best_move(Cell::O, EvalWeights::default());
+            assert!(best.contains(&chosen.into()));
+        }
+        // on an empty 4x4 board all cells on the diagonals are equally good
+        let board = Board::build(4, Cell::X).unwrap();
+        assert_eq!(
+            board.best_moves(Cell::O),
+            [
+                (0, 0),
+                (3, 0),
+                (1, 1),
+                (2, 1),
+                (1, 2),
+                (2, 2),
+                (0, 3),
+                (3, 3)
+            ]
+        );
+        assert!(board
+            .best_moves(Cell::O)
+            .contains(&board.best_move(Cell::O, EvalWeights::default())));
+    }
+
+    #[test]
+    fn eval_weights() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        assert_eq!(board.scores(Cell::X), vec![4, 3, 4, 3, 5, 3, 4, 3, 4]);
+        let blanks = EvalWeights {
+            blank: 2,
+            line: 0,
+            progress: 0,
+        };
+        assert_eq!(board.scores_with(Cell::X, blanks), vec![2; 9]);
+        assert_eq!(board.best_moves_with(Cell::X, blanks).len(), 9);
+        assert_eq!(board.best_moves(Cell::X), [(1, 1)]);
+        // only the pieces on the lines count
+        let pieces = EvalWeights {
+            blank: 0,
+            line: 0,
+            progress: 3,
+        };
+        board.set_cell((0, 0), Cell::X).unwrap();
+        assert_eq!(
+            board.scores_with(Cell::X, pieces),
+            vec![0, 3, 3, 3, 3, 0, 3, 0, 3]
+        );
+        assert_eq!(board.scores_with(Cell::O, pieces)[4], 0);
+    }
+
     #[test]
     fn game_is_not_over() {
         let board = Board::from_string(
@@ -450,6 +2287,725 @@ XO-
             Cell::X,
         )
         .unwrap();
-        assert!(board.check_game_over(0, 2, Cell::X).is_none());
+        assert!(board.check_game_over((0, 2).into()).is_none());
+    }
+
+    #[test]
+    fn forced_win() {
+        let tests = [
+            ("two threats", "OO-\nO-X\n-X-", true),
+            ("one threat", "OO-\nX--\n-X-", false),
+            ("human wins first", "OO-\nO-X\nX-X", false),
+        ];
+        for (name, board, expected) in tests {
+            let board = Board::from_string(board, 3, Cell::X).unwrap();
+            assert_eq!(board.computer_forces_win(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn force_win() {
+        let tests = [
+            ("win in one", "XX-\n-O-\nO--", true),
+            ("no fork", "X--\n-O-\n---", false),
+            ("fork", "X--\n-O-\n--X", true),
+            ("fork blocking a threat", "X-O\n-O-\n--X", true),
+            ("fork ignoring a threat", "X--\nOO-\n--X", false),
+        ];
+        for (name, board, expected) in tests {
+            let board = Board::from_string(board, 3, Cell::X).unwrap();
+            assert_eq!(board.can_force_win(Cell::X), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn fork_moves() {
+        let board = Board::from_string("XX--/-O-X/-OOX/----", 4, Cell::X).unwrap();
+        assert_eq!(board.fork_moves(Cell::X), [(3, 0)]);
+        assert!(board.fork_moves(Cell::O).is_empty());
+        // create the double threat, or take its cell away from the opponent
+        assert_eq!(
+            board.best_move(Cell::X, EvalWeights::default()),
+            Coord { row: 0, col: 3 }
+        );
+        assert_eq!(board.best_moves(Cell::X), [(3, 0)]);
+        assert_eq!(board.best_moves(Cell::O), [(3, 0)]);
+    }
+
+    #[test]
+    fn win_length() {
+        assert!(Board::build_with_win_length(5, 1, Cell::X).is_err());
+        assert!(Board::build_with_win_length(5, 6, Cell::X).is_err());
+        // every segment of three cells of the 5 rows, 5 columns and 9 + 9 diagonals
+        assert_eq!(Board::segments(5, 5, 3, false).len(), 48);
+        assert_eq!(
+            Board::segments(3, 3, 3, false),
+            [
+                [0, 3, 6],
+                [1, 4, 7],
+                [2, 5, 8],
+                [0, 1, 2],
+                [3, 4, 5],
+                [6, 7, 8],
+                [0, 4, 8],
+                [6, 4, 2]
+            ]
+        );
+        let mut board = Board::build_with_win_length(5, 3, Cell::X).unwrap();
+        assert_eq!(board.win_length(), 3);
+        for (x, y) in [(3, 1), (2, 2)] {
+            assert_eq!(board.human_move((x, y)), Ok(None));
+        }
+        assert_eq!(board.winning_moves(Cell::X), [(4, 0), (1, 3)]);
+        assert_eq!(board.human_move((1, 3)), Ok(Some(GameOver::HumanWon)));
+        assert_eq!(board.winner(), Some(Cell::X));
+        // the position on the board where the whole line wins
+        let whole = Board::from_string("XXX--/-----/OO---/-----/-----", 5, Cell::X).unwrap();
+        assert_eq!(whole.winner(), None);
+        assert_eq!(whole.with_win_length(3).unwrap().winner(), Some(Cell::X));
+    }
+
+    #[test]
+    fn wrap() {
+        // the 3 rows, 3 columns and 3 + 3 diagonals, the broken ones included
+        assert_eq!(Board::segments(3, 3, 3, true).len(), 12);
+        assert_eq!(Board::segments(4, 4, 3, true).len(), 64);
+        // every pair of cells
+        assert_eq!(Board::segments(2, 2, 2, true).len(), 6);
+        let board = Board::from_string("-X-/--X/-OO", 3, Cell::X)
+            .unwrap()
+            .with_wrap(true);
+        assert!(board.wraps());
+        // the diagonal from the top continues at the left edge
+        assert_eq!(board.winning_moves(Cell::X), [(0, 2)]);
+        assert_eq!(
+            crate::threats::forced_win(&board, Cell::X, 1),
+            Some(vec![(0, 2).into()])
+        );
+        let unwrapped = board.with_wrap(false);
+        assert!(unwrapped.winning_moves(Cell::X).is_empty());
+        assert!(board.with_win_length(2).unwrap().wraps());
+    }
+
+    #[test]
+    fn blocked() {
+        let mut board = Board::build(3, Cell::X)
+            .unwrap()
+            .with_blocked(&[(1, 1).into()])
+            .unwrap();
+        // the middle row, the middle column and both diagonals are gone
+        assert_eq!(board.lines().len(), 4);
+        assert_eq!(board.to_string().lines().nth(3), Some("|   | # |   |"));
+        assert_eq!(board.to_notation(), "---/-#-/---");
+        assert_eq!(board.human_move((1, 1)), Err(MoveError::CellBlocked));
+        assert_eq!(board.legal_moves().count(), 8);
+        for (x, y) in [(0, 0), (1, 0), (2, 2)] {
+            board.set_cell((x, y), board.to_move()).unwrap();
+        }
+        // the diagonal between X's corners can't be completed
+        assert!(board.winning_moves(Cell::X).is_empty());
+        assert!(board.with_blocked(&[(0, 0).into()]).is_err());
+        let pasted = Board::from_position("X--/-#-/--O", Cell::X).unwrap();
+        assert_eq!(pasted.blocked_cells(), [(1, 1)]);
+        assert_eq!(pasted.lines().len(), 4);
+        let mut rng = Rng::new(1);
+        let random = Board::build(4, Cell::X)
+            .unwrap()
+            .with_random_blocked(3, &mut rng)
+            .unwrap();
+        assert_eq!(random.blocked_cells().len(), 3);
+        assert_eq!(random.canonical_hash(), random.hash());
+        assert!(Board::build(2, Cell::X)
+            .unwrap()
+            .with_random_blocked(4, &mut rng)
+            .is_err());
+    }
+
+    #[test]
+    fn apply_move() {
+        let mut board = Board::from_string("X#-/-O-/---", 3, Cell::O).unwrap();
+        let mv = |x, y, mark| Move {
+            coord: Coord { row: y, col: x },
+            mark,
+        };
+        assert_eq!(
+            board.apply_move(mv(3, 0, Cell::X)),
+            Err(MoveError::OutOfBounds((3, 0).into()))
+        );
+        assert_eq!(
+            board.apply_move(mv(2, 2, Cell::O)),
+            Err(MoveError::NotYourTurn { to_move: Cell::X })
+        );
+        assert_eq!(
+            board.apply_move(mv(0, 0, Cell::X)),
+            Err(MoveError::CellTaken)
+        );
+        assert_eq!(
+            board.apply_move(mv(1, 0, Cell::X)),
+            Err(MoveError::CellBlocked)
+        );
+        assert_eq!(board.apply_move(mv(0, 1, Cell::X)), Ok(None));
+        assert_eq!(board.apply_move(mv(2, 2, Cell::O)), Ok(None));
+        // X, the computer, completes the left column
+        assert_eq!(
+            board.apply_move(mv(0, 2, Cell::X)),
+            Ok(Some(GameOver::ComputerWon))
+        );
+        assert_eq!(board.history(), [(0, 1), (2, 2), (0, 2)]);
+        assert_eq!(
+            board.apply_move(mv(2, 0, Cell::O)),
+            Err(MoveError::GameAlreadyOver)
+        );
+        let mut board = Board::build(3, Cell::X)
+            .unwrap()
+            .with_gravity(true)
+            .unwrap();
+        assert_eq!(
+            board.apply_move(mv(0, 0, Cell::X)),
+            Err(MoveError::NotPlayable)
+        );
+        assert_eq!(board.apply_move(mv(0, 2, Cell::X)), Ok(None));
+    }
+
+    #[test]
+    fn coord_and_move_text() {
+        let coord = Coord { row: 2, col: 0 };
+        assert_eq!(coord.to_string(), "1 3");
+        assert_eq!("1 3".parse(), Ok(coord));
+        assert_eq!(<(usize, usize)>::from(coord), (0, 2));
+        assert!("0 3".parse::<Coord>().is_err());
+        let mv = Move {
+            coord,
+            mark: Cell::O,
+        };
+        assert_eq!(mv.to_string(), "O 1 3");
+        assert_eq!(" o 1 3 ".parse(), Ok(mv));
+        assert!("1 3".parse::<Move>().is_err());
+        assert!("X".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn random_start() {
+        let empty = Board::build(3, Cell::X).unwrap();
+        let mut rng = Rng::new(5);
+        for plies in [1, 2] {
+            let board = empty.with_random_start(plies, &mut rng).unwrap();
+            assert!(board.history().is_empty());
+            let pieces = |cell| board.cells.iter().filter(|&&c| c == cell).count();
+            assert_eq!((pieces(Cell::X), pieces(Cell::O)), (1, plies - 1));
+            assert_eq!(board.to_move(), [Cell::O, Cell::X][plies - 1]);
+            assert_eq!(
+                board.with_random_start(1, &mut rng).unwrap_err(),
+                BoardError::RandomStartNotEmpty
+            );
+        }
+        assert_eq!(
+            empty.with_random_start(3, &mut rng).unwrap_err(),
+            BoardError::InvalidRandomStart { plies: 3 }
+        );
+        // the first player wins right away with any start of two in a row
+        let board = Board::build_with_win_length(3, 2, Cell::X).unwrap();
+        assert_eq!(
+            board.with_random_start(2, &mut rng).unwrap_err(),
+            BoardError::NoFairStart
+        );
+    }
+
+    #[test]
+    fn handicap() {
+        let empty = Board::build(3, Cell::X).unwrap();
+        let mut board = empty.with_handicap(Cell::X, 2).unwrap();
+        assert_eq!(board.to_notation(), "X--/-X-/---");
+        assert_eq!(board.handicap(), Some((Cell::X, 2)));
+        assert!(board.history().is_empty());
+        assert_eq!(board.to_move(), Cell::O);
+        board.set_cell((2, 2), Cell::O).unwrap();
+        assert_eq!(board.to_move(), Cell::X);
+        assert_eq!(
+            board.with_handicap(Cell::X, 1).unwrap_err(),
+            BoardError::HandicapNotEmpty
+        );
+        // the third piece leaves the diagonal open
+        let board = empty.with_handicap(Cell::X, 3).unwrap();
+        assert_eq!(board.to_notation(), "X-X/-X-/---");
+        assert!(board.winner().is_none());
+        assert_eq!(
+            empty.with_handicap(Cell::X, 9).unwrap_err(),
+            BoardError::InvalidHandicap { count: 9 }
+        );
+        let board = Board::from_handicap("O-O/---/---", Cell::X).unwrap();
+        assert_eq!(board.handicap(), Some((Cell::O, 2)));
+        assert_eq!(board.to_move(), Cell::X);
+        assert_eq!(
+            Board::from_handicap("XO-/---/---", Cell::X).unwrap_err(),
+            BoardError::HandicapBothPlayers
+        );
+    }
+
+    #[test]
+    fn pie_rule() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        assert!(!board.can_swap());
+        assert_eq!(board.swap_sides(), Err(MoveError::SwapNotAllowed));
+        board.undo();
+        board.set_pie_rule(true);
+        assert!(!board.can_swap());
+        board.set_cell((1, 1), Cell::O).unwrap();
+        assert!(board.can_swap());
+        board.swap_sides().unwrap();
+        assert!(board.swapped());
+        assert_eq!(board.human_uses(), Cell::O);
+        assert_eq!(board.to_move(), Cell::X);
+        // only once
+        assert_eq!(board.swap_sides(), Err(MoveError::SwapNotAllowed));
+    }
+
+    #[test]
+    fn gravity() {
+        let mut board = Board::build_rectangle(7, 6, 4, Cell::X)
+            .unwrap()
+            .with_gravity(true)
+            .unwrap();
+        assert_eq!(board.legal_moves().count(), 7);
+        assert!(board.legal_moves().all(|coord| coord.row == 5));
+        assert_eq!(board.human_move((3, 0)), Err(MoveError::NotPlayable));
+        assert_eq!(board.parse_input("4").unwrap(), (3, 5));
+        assert!(board.parse_input("8").is_err());
+        for (x, y) in [(3, 5), (3, 4), (2, 5), (4, 5), (1, 5), (2, 4)] {
+            board.set_cell((x, y), board.to_move()).unwrap();
+        }
+        // the piece lands on the one in the column
+        assert_eq!(board.parse_input("4").unwrap(), (3, 3));
+        // O blocked X's three in a row on the bottom at one end
+        assert_eq!(board.winning_moves(Cell::X), [(0, 5)]);
+        assert!(board.fork_moves(Cell::X).is_empty());
+        assert!(board
+            .best_moves(Cell::O)
+            .iter()
+            .all(|&mv| board.is_playable(mv)));
+        let floating = Board::from_string_rectangle("---/-X-", 3, 2, Cell::X).unwrap();
+        assert!(floating.with_gravity(true).is_ok());
+        let floating = Board::from_string_rectangle("-X-/---", 3, 2, Cell::X).unwrap();
+        assert_eq!(
+            floating.with_gravity(true).unwrap_err(),
+            BoardError::Floating
+        );
+    }
+
+    #[test]
+    fn hashes() {
+        let a = Board::from_string("X--/-O-/--X", 3, Cell::X).unwrap();
+        let mut b = Board::build(3, Cell::X).unwrap();
+        for (x, y, cell) in [(2, 2, Cell::X), (1, 1, Cell::O), (0, 0, Cell::X)] {
+            b.set_cell((x, y), cell).unwrap();
+        }
+        assert_eq!(a.hash(), b.hash());
+        let rotated = Board::from_string("--X/-O-/X--", 3, Cell::X).unwrap();
+        assert_ne!(a.hash(), rotated.hash());
+        assert_eq!(a.canonical_hash(), rotated.canonical_hash());
+        let other = Board::from_string("X--/-O-/-X-", 3, Cell::X).unwrap();
+        assert_ne!(a.canonical_hash(), other.canonical_hash());
+        b.undo();
+        b.undo();
+        b.undo();
+        assert_eq!(b.hash(), Board::build(3, Cell::X).unwrap().hash());
+        assert!(b.is_symmetric() && a.is_symmetric() && !other.is_symmetric());
+        for board in random_positions() {
+            let rebuilt = Board::from_string(&board.to_notation(), board.width(), Cell::X);
+            assert_eq!(rebuilt.unwrap().hash(), board.hash());
+        }
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let a = Board::from_string("X--/-O-/---", 3, Cell::X).unwrap();
+        let canonical = a.canonical_form();
+        assert_eq!(canonical.hash(), a.canonical_hash());
+        for corner in ["--X/-O-/---", "---/-O-/X--", "---/-O-/--X"] {
+            let board = Board::from_string(corner, 3, Cell::X).unwrap();
+            assert_eq!(
+                board.canonical_form().to_notation(),
+                canonical.to_notation()
+            );
+        }
+        for board in random_positions() {
+            let canonical = board.canonical_form();
+            assert_eq!(canonical.hash(), board.canonical_hash());
+            assert_eq!(canonical.canonical_hash(), board.canonical_hash());
+            assert_eq!(canonical.to_move(), board.to_move());
+            assert!(canonical.history().is_empty());
+        }
+    }
+
+    #[test]
+    fn rectangular_board() {
+        let mut board = Board::build_rectangle(7, 6, 4, Cell::X).unwrap();
+        assert!(!board.is_square());
+        assert_eq!(Board::segments(7, 6, 4, false).len(), 69);
+        assert_eq!(
+            Board::build_rectangle(7, 6, 7, Cell::X).unwrap_err(),
+            BoardError::InvalidWinLength {
+                got: 7,
+                min: 2,
+                max: 6
+            }
+        );
+        let error = Board::build_rectangle(31, 6, 4, Cell::X).unwrap_err();
+        assert_eq!(
+            error,
+            BoardError::InvalidDimension {
+                got: 31,
+                min: 2,
+                max: 30
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            t!("board-invalid-dimension", got = 31, min = 2, max = 30)
+        );
+        assert_eq!(
+            board.set_cell((7, 0), Cell::X),
+            Err(MoveError::OutOfBounds((7, 0).into()))
+        );
+        assert_eq!(board.to_string().lines().count(), 13);
+        assert_eq!(board.legal_moves().count(), 42);
+        assert_eq!(board.parse_input("7 6").unwrap(), (6, 5));
+        assert!(board.parse_input("6 7").is_err());
+        for x in 3..6 {
+            assert_eq!(board.human_move((x, 5)), Ok(None));
+        }
+        assert_eq!(board.human_move((6, 5)), Ok(Some(GameOver::HumanWon)));
+        // mirrored positions share the canonical hash, transposing doesn't apply
+        let position = board.to_notation();
+        assert_eq!(position, "-------/-------/-------/-------/-------/---XXXX");
+        let mut mirrored = Board::build_rectangle(7, 6, 4, Cell::X).unwrap();
+        for x in 0..4 {
+            mirrored.human_move((x, 5)).unwrap();
+        }
+        assert_eq!(
+            mirrored.to_notation(),
+            "-------/-------/-------/-------/-------/XXXX---"
+        );
+        assert_eq!(mirrored.canonical_hash(), board.canonical_hash());
+        assert_eq!(mirrored.canonical_form().hash(), board.canonical_hash());
+        assert!(Board::build_rectangle(7, 6, 4, Cell::X)
+            .unwrap()
+            .is_symmetric());
+        let pasted =
+            Board::from_position("X------/-------/-------/-------/-------/-------", Cell::O)
+                .unwrap();
+        assert_eq!(
+            (pasted.width(), pasted.height(), pasted.win_length()),
+            (7, 6, 6)
+        );
+        assert!(Board::from_position("X--/--", Cell::O).is_err());
+    }
+
+    #[test]
+    fn undo_restores_position() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        board.computer_move();
+        assert_eq!(board.history().len(), 2);
+        assert_eq!(board.undo(), Some((1, 1).into()));
+        assert_eq!(board.get_cell((1, 1)), Cell::Blank);
+        assert_eq!(board.undo(), Some((0, 0).into()));
+        assert_eq!(board.undo(), None);
+        assert_eq!(board.moves, 0);
+        assert!(board.cells.iter().all(|c| *c == Cell::Blank));
+    }
+
+    #[test]
+    fn redo_makes_undone_moves_again() {
+        let mut board = Board::from_string("---/---/---", 3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        board.set_last_move_time(Duration::from_secs(2));
+        board.set_cell((1, 1), Cell::O).unwrap();
+        let hash = board.hash();
+        board.undo();
+        board.undo();
+        assert_eq!(board.redo(), Some((0, 0).into()));
+        assert_eq!(board.redo(), Some((1, 1).into()));
+        assert_eq!(board.redo(), None);
+        assert_eq!(board.get_cell((1, 1)), Cell::O);
+        assert_eq!(board.history(), [(0, 0), (1, 1)]);
+        assert_eq!(board.move_times()[0], Some(Duration::from_secs(2)));
+        assert_eq!(board.hash(), hash);
+        // another move forgets the moves taken back
+        board.undo();
+        board.set_cell((2, 2), Cell::O).unwrap();
+        assert_eq!(board.redo(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saved_and_loaded() {
+        let mut board = Board::from_position("#--/-X-/---", Cell::O)
+            .unwrap()
+            .with_gravity(false)
+            .unwrap();
+        board.human_move((2, 2)).unwrap();
+        board.set_last_move_time(Duration::from_millis(1500));
+        board.set_cell((0, 1), Cell::X).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.to_notation(), "#--/XX-/--O");
+        assert_eq!(loaded.history(), board.history());
+        assert_eq!(loaded.move_times(), board.move_times());
+        assert_eq!(loaded.hash(), board.hash());
+        assert_eq!(loaded.line_counts(), board.line_counts());
+        assert_eq!(loaded.to_move(), Cell::O);
+        // loading plays the moves again, which must be legal
+        let taken = json.replace(r#"{"row":1,"col":0}"#, r#"{"row":1,"col":1}"#);
+        assert!(serde_json::from_str::<Board>(&taken).is_err());
+        assert_eq!(serde_json::to_string(&GameOver::Tie).unwrap(), r#""Tie""#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn untrusted_saves() {
+        let load = |board: &Board, from: &str, to: &str| {
+            let json = serde_json::to_string(board).unwrap();
+            assert!(json.contains(from), "{}", json);
+            serde_json::from_str::<Board>(&json.replace(from, to))
+        };
+        let empty = Board::build(3, Cell::X).unwrap();
+        assert!(load(&empty, r#""first":"X""#, r#""first":"Blocked""#).is_err());
+        assert!(load(&empty, r#""first":"X""#, r#""first":"O""#).is_ok());
+        assert!(load(&empty, r#""handicap":null"#, r#""handicap":["X",5]"#).is_err());
+        assert!(load(&empty, r#""handicap":null"#, r#""handicap":["Blank",0]"#).is_err());
+        let handicap = empty.with_handicap(Cell::X, 2).unwrap();
+        assert!(load(&handicap, r#""handicap":["X",2]"#, r#""handicap":["X",3]"#).is_err());
+        assert!(load(&handicap, r#""handicap":["X",2]"#, r#""handicap":["O",2]"#).is_err());
+        // the moves are played in turn and stop at the end of the game
+        let mut board = Board::build(3, Cell::X).unwrap();
+        for (x, y, cell) in [(0, 0, Cell::X), (1, 0, Cell::O), (0, 1, Cell::X)] {
+            board.set_cell((x, y), cell).unwrap();
+        }
+        assert!(load(&board, r#""mark":"O""#, r#""mark":"X""#).is_err());
+        board.set_cell((1, 1), Cell::O).unwrap();
+        board.set_cell((0, 2), Cell::X).unwrap();
+        let won = load(&board, "", "").unwrap();
+        assert_eq!(won.result(), Some(GameOver::HumanWon));
+        board.set_cell((2, 2), Cell::O).unwrap();
+        assert!(load(&board, "", "").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn malformed_saves() {
+        let json = serde_json::to_string(&Board::build(3, Cell::X).unwrap()).unwrap();
+        for (from, to) in [
+            (r#""human_uses":"X""#, r#""human_uses":"Blank""#),
+            (r#""human_uses":"X""#, r#""human_uses":"Blocked""#),
+            (r#""width":3"#, r#""width":0"#),
+            (r#""win_length":3"#, r#""win_length":1"#),
+            (r#""rules":"standard""#, r#""rules":"wild""#),
+            (r#""move_times":[]"#, r#""move_times":[null]"#),
+        ] {
+            let malformed = json.replace(from, to);
+            assert_ne!(malformed, json);
+            assert!(serde_json::from_str::<Board>(&malformed).is_err(), "{}", to);
+        }
+        assert!(serde_json::from_str::<Board>("{}").is_err());
+        assert!(serde_json::from_str::<Board>(&json[1..]).is_err());
+    }
+
+    #[test]
+    fn winning_lines() {
+        let mut board = Board::from_string("XO-/-XO/---", 3, Cell::O).unwrap();
+        assert_eq!(board.game_result(), None);
+        board.set_cell((2, 2), Cell::X).unwrap();
+        let coords = |cells: [(usize, usize); 3]| cells.map(Coord::from).to_vec();
+        assert_eq!(
+            board.game_result(),
+            Some(GameResult {
+                over: GameOver::ComputerWon,
+                winner: Some(Cell::X),
+                line: coords([(0, 0), (1, 1), (2, 2)]),
+            })
+        );
+        // the line continues across the edge
+        let mut board = Board::from_string("----/----/-O-X/-O-X", 4, Cell::X)
+            .unwrap()
+            .with_win_length(3)
+            .unwrap()
+            .with_wrap(true);
+        board.set_cell((1, 0), Cell::O).unwrap();
+        assert_eq!(
+            board.game_result().map(|result| result.line),
+            Some(coords([(1, 2), (1, 3), (1, 0)]))
+        );
+        let mut tie = Board::from_string("XOX/XOO/OX-", 3, Cell::X).unwrap();
+        tie.set_cell((2, 2), Cell::X).unwrap();
+        assert_eq!(
+            tie.game_result(),
+            Some(GameResult {
+                over: GameOver::Tie,
+                winner: None,
+                line: Vec::new(),
+            })
+        );
+        // positions loaded without moves are judged as a whole
+        let loaded = Board::from_notation("OOO/XX-/XX-").unwrap();
+        assert_eq!(
+            loaded.game_result(),
+            Some(GameResult {
+                over: GameOver::ComputerWon,
+                winner: Some(Cell::O),
+                line: coords([(0, 0), (1, 0), (2, 0)]),
+            })
+        );
+        let full = Board::from_notation("XOX/XOO/OXX").unwrap();
+        assert_eq!(full.result(), Some(GameOver::Tie));
+        assert_eq!(
+            Board::from_notation("XO-/---/---").unwrap().game_result(),
+            None
+        );
+    }
+
+    #[test]
+    fn cell_iterators() {
+        let board = Board::from_string_rectangle("XO--/-X-O/---#", 4, 3, Cell::X)
+            .unwrap()
+            .with_win_length(3)
+            .unwrap();
+        let coords = |line: &[(Coord, Cell)]| -> Vec<(usize, usize)> {
+            line.iter().map(|&(coord, _)| coord.into()).collect()
+        };
+        let pieces = |line: &[(Coord, Cell)]| -> String {
+            line.iter().map(|&(_, cell)| cell.to_string()).collect()
+        };
+        assert_eq!(board.cells().count(), 12);
+        assert_eq!(
+            board.cells().nth(5),
+            Some((Coord { row: 1, col: 1 }, Cell::X))
+        );
+        let rows: Vec<_> = board.rows().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(coords(&rows[1]), [(0, 1), (1, 1), (2, 1), (3, 1)]);
+        let cols: Vec<_> = board.cols().collect();
+        assert_eq!(cols.len(), 4);
+        assert_eq!(coords(&cols[3]), [(3, 0), (3, 1), (3, 2)]);
+        let diagonals: Vec<_> = board.diagonals().map(|d| coords(&d)).collect();
+        assert_eq!(
+            diagonals,
+            [
+                vec![(0, 0), (1, 1), (2, 2)],
+                vec![(1, 0), (2, 1), (3, 2)],
+                vec![(2, 0), (1, 1), (0, 2)],
+                vec![(3, 0), (2, 1), (1, 2)],
+            ]
+        );
+        // the lines through the blocked cell are left out
+        assert_eq!(board.win_lines().count(), board.line_counts().len());
+        for (line, count) in board.win_lines().zip(board.line_counts()) {
+            assert_eq!(pieces(&line).matches('X').count(), count.x);
+            assert_eq!(pieces(&line).matches('O').count(), count.o);
+            assert!(!coords(&line).contains(&(3, 2)));
+        }
+    }
+
+    // Random positions of all sizes up to 6x6, including finished games
+    fn random_positions() -> Vec<Board> {
+        let mut rng = Rng::new(7);
+        let mut positions = Vec::new();
+        for dim in 2..=6 {
+            for _ in 0..50 {
+                let plies = rng.below(dim * dim + 1);
+                positions.push(Board::random(dim, Cell::X, plies, &mut rng).unwrap());
+            }
+        }
+        positions
+    }
+
+    #[test]
+    fn engine_plays_blank_cells() {
+        for board in random_positions() {
+            if board.result().is_some() {
+                continue;
+            }
+            let mut after = board.clone();
+            after.computer_move();
+            let &mv = after.history().last().unwrap();
+            assert_eq!(board.get_cell(mv), Cell::Blank, "{}", board);
+            assert!(board
+                .best_moves(Cell::O)
+                .iter()
+                .all(|&mv| board.get_cell(mv) == Cell::Blank));
+        }
+    }
+
+    #[test]
+    fn result_agrees_with_winner() {
+        for board in random_positions() {
+            if board.history().is_empty() {
+                continue;
+            }
+            let expected = match board.winner() {
+                Some(cell) => board.won(cell),
+                None if board.legal_moves().next().is_none() => Some(GameOver::Tie),
+                None => None,
+            };
+            assert_eq!(board.result(), expected, "{}", board);
+        }
+    }
+
+    #[test]
+    fn legal_moves_follow_the_rules() {
+        for board in random_positions() {
+            let gravity = board.with_gravity(true);
+            for board in [Some(board), gravity.ok()].into_iter().flatten() {
+                let legal: Vec<Coord> = board.legal_moves().collect();
+                for (coord, cell) in board.cells() {
+                    let playable = cell == Cell::Blank && board.is_playable((coord.col, coord.row));
+                    assert_eq!(legal.contains(&coord), playable);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn make_and_undo_is_identity() {
+        for board in random_positions() {
+            for mv in board.legal_moves() {
+                let mut after = board.clone();
+                after.set_cell(mv, Cell::O).unwrap();
+                assert_eq!(after.legal_moves().count(), board.legal_moves().count() - 1);
+                assert_eq!(after.undo(), Some(mv));
+                assert_eq!(after.cells, board.cells);
+                assert_eq!(after.history, board.history);
+                assert_eq!(after.moves, board.moves);
+                assert_eq!(after.hashes, board.hashes);
+                assert_eq!(after.line_counts, board.line_counts);
+            }
+        }
+    }
+
+    #[test]
+    fn line_counts_match_the_cells() {
+        // the counts of every line found by looking at its cells
+        let scanned = |board: &Board| -> Vec<LineCount> {
+            let count = |line: &Vec<usize>, cell| {
+                line.iter().filter(|&&idx| board.cells[idx] == cell).count()
+            };
+            board
+                .win_lines
+                .iter()
+                .map(|line| LineCount {
+                    x: count(line, Cell::X),
+                    o: count(line, Cell::O),
+                    blanks: count(line, Cell::Blank),
+                })
+                .collect()
+        };
+        for board in random_positions() {
+            assert_eq!(board.line_counts(), scanned(&board), "{}", board);
+            let canonical = board.canonical_form();
+            assert_eq!(canonical.line_counts(), scanned(&canonical), "{}", board);
+            let parsed = Board::from_string(&board.to_notation(), board.width(), Cell::X).unwrap();
+            assert_eq!(parsed.line_counts(), board.line_counts());
+        }
     }
 }
@@ -0,0 +1,231 @@
+//! Opening book for the 3x3 and 4x4 boards: the move to play in every position after up to
+//! three moves on the 3x3 board and up to two moves on the 4x4 board, X moving first.
+//!
+//! The moves were precomputed with a complete minimax search, so they keep the best
+//! outcome. Among equally good moves the one the heuristic rates highest was taken. Each
+//! position is listed in one of its eight symmetric orientations.
+
+use crate::board::{Board, Cell, Coord, SYMMETRIES};
+
+/// Positions in the format of [`Board::to_notation`] and the move to play, one based
+const BOOK: &str = "
+---/---/--- 2:2
+X--/---/--- 2:2
+-X-/---/--- 2:2
+---/-X-/--- 1:1
+XO-/---/--- 2:2
+X-O/---/--- 1:2
+X--/-O-/--- 3:1
+X--/--O/--- 2:2
+X--/---/--O 3:1
+OX-/---/--- 2:2
+-X-/O--/--- 2:2
+-X-/-O-/--- 1:1
+-X-/---/O-- 1:1
+-X-/---/-O- 1:1
+O--/-X-/--- 3:1
+-O-/-X-/--- 1:3
+XOX/---/--- 2:2
+XO-/X--/--- 1:3
+XO-/-X-/--- 3:3
+XO-/--X/--- 2:2
+XO-/---/X-- 1:2
+XO-/---/-X- 1:3
+XO-/---/--X 2:2
+XXO/---/--- 3:2
+X-O/X--/--- 1:3
+X-O/-X-/--- 3:3
+X-O/--X/--- 2:2
+X-O/---/X-- 1:2
+X-O/---/-X- 3:3
+X-O/---/--X 2:2
+XX-/-O-/--- 3:1
+X-X/-O-/--- 2:1
+X--/-OX/--- 2:3
+X--/-O-/--X 2:1
+XX-/--O/--- 3:1
+X--/X-O/--- 1:3
+X--/-XO/--- 3:3
+X--/--O/X-- 1:2
+X--/--O/-X- 2:2
+XX-/---/--O 3:1
+X--/-X-/--O 3:1
+X--/--X/--O 1:3
+OX-/X--/--- 2:2
+OX-/-X-/--- 2:3
+OX-/--X/--- 1:3
+OX-/---/-X- 2:2
+-X-/OX-/--- 2:3
+-X-/O-X/--- 3:3
+-X-/O--/-X- 2:2
+-X-/XO-/--- 3:1
+-X-/-O-/-X- 1:1
+-X-/-X-/O-- 2:3
+-X-/--X/O-- 1:1
+-X-/-X-/-O- 1:3
+----/----/----/---- 1:1
+X---/----/----/---- 3:2
+-X--/----/----/---- 3:2
+----/-X--/----/---- 4:1
+XO--/----/----/---- 3:3
+X-O-/----/----/---- 2:2
+X--O/----/----/---- 2:2
+X---/-O--/----/---- 4:1
+X---/--O-/----/---- 4:4
+X---/---O/----/---- 3:3
+X---/----/--O-/---- 4:1
+X---/----/---O/---- 2:2
+X---/----/----/---O 2:1
+OX--/----/----/---- 2:3
+-XO-/----/----/---- 2:2
+-X-O/----/----/---- 2:2
+-X--/O---/----/---- 4:1
+-X--/-O--/----/---- 4:1
+-X--/--O-/----/---- 1:1
+-X--/---O/----/---- 1:1
+-X--/----/O---/---- 4:1
+-X--/----/-O--/---- 1:1
+-X--/----/--O-/---- 4:1
+-X--/----/---O/---- 1:1
+-X--/----/----/O--- 2:2
+-X--/----/----/-O-- 1:1
+-X--/----/----/--O- 1:1
+-X--/----/----/---O 2:3
+O---/-X--/----/---- 3:2
+-O--/-X--/----/---- 3:2
+--O-/-X--/----/---- 2:3
+---O/-X--/----/---- 3:3
+----/-XO-/----/---- 1:1
+----/-X-O/----/---- 1:1
+----/-X--/--O-/---- 2:1
+----/-X--/---O/---- 1:1
+----/-X--/----/---O 3:2
+";
+
+/// The book move for the given player, if the position is in the book and it's their turn
+pub fn lookup(board: &Board, side: Cell) -> Option<Coord> {
+    moves(board, side).first().copied()
+}
+
+/// The book moves for the given player, row by row. A position which is symmetric has a
+/// book move in each of its orientations, e.g. every corner of the empty 4x4 board. Only
+/// square boards won by filling a whole line, with X moving first, are in the book.
+pub fn moves(board: &Board, side: Cell) -> Vec<Coord> {
+    let dim = board.width();
+    if side != board.to_move()
+        || board.first_player() != Cell::X
+        || board.result().is_some()
+        || !board.is_square()
+        || board.win_length() != dim
+        || board.wraps()
+        || !board.has_standard_rules()
+        || board.handicap().is_some()
+        || board.has_blocked()
+    {
+        return Vec::new();
+    }
+    let last = dim - 1;
+    let mut moves: Vec<Coord> = SYMMETRIES
+        .iter()
+        .filter_map(|transform| {
+            // the position with every piece moved to its transformed cell
+            let mut cells = vec![Cell::Blank; dim * dim];
+            for y in 0..dim {
+                for x in 0..dim {
+                    let (tx, ty) = transform(x, y, last, last);
+                    cells[tx + ty * dim] = board.get_cell((x, y));
+                }
+            }
+            let position: Vec<String> = cells
+                .chunks(dim)
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| match cell {
+                            Cell::Blank => '-',
+                            Cell::X => 'X',
+                            Cell::O => 'O',
+                            Cell::Blocked => '#',
+                        })
+                        .collect()
+                })
+                .collect();
+            let mv = entry(&position.join("/"))?;
+            board
+                .legal_moves()
+                .find(|&Coord { row: y, col: x }| transform(x, y, last, last) == mv)
+        })
+        .collect();
+    moves.sort();
+    moves.dedup();
+    moves
+}
+
+// The zero based move listed for the position, as `(x, y)`
+fn entry(position: &str) -> Option<(usize, usize)> {
+    BOOK.lines().find_map(|line| {
+        let (pos, mv) = line.split_once(' ')?;
+        if pos != position {
+            return None;
+        }
+        let (x, y) = mv.split_once(':')?;
+        Some((x.parse::<usize>().ok()? - 1, y.parse::<usize>().ok()? - 1))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver;
+
+    #[test]
+    fn book_moves_keep_the_outcome() {
+        // the 3x3 positions, the 4x4 ones are too large for the solver
+        for line in BOOK.lines().skip(1) {
+            let (position, _) = line.split_once(' ').unwrap();
+            let Ok(board) = Board::from_string(position, 3, Cell::X) else {
+                continue;
+            };
+            let side = board.to_move();
+            let mv = lookup(&board, side).unwrap();
+            assert!(
+                solver::optimal_moves(&board, side).unwrap().contains(&mv),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn symmetric_positions() {
+        // a corner is answered with the center in every orientation
+        for corner in [(0, 0), (2, 0), (0, 2), (2, 2)] {
+            let mut board = Board::build(3, Cell::X).unwrap();
+            board.set_cell((corner.0, corner.1), Cell::X).unwrap();
+            assert_eq!(lookup(&board, Cell::O), Some((1, 1).into()));
+        }
+        let board = Board::from_string("X--/---/--O", 3, Cell::X).unwrap();
+        let mirrored = Board::from_string("--X/---/O--", 3, Cell::X).unwrap();
+        let mv = lookup(&board, Cell::X).unwrap();
+        assert_eq!(
+            lookup(&mirrored, Cell::X),
+            Some(Coord {
+                col: 2 - mv.col,
+                ..mv
+            })
+        );
+        // not the player's turn, or too late for the book
+        assert_eq!(lookup(&board, Cell::O), None);
+        let late = Board::from_string("XO-/-X-/--O", 3, Cell::X).unwrap();
+        assert_eq!(lookup(&late, Cell::X), None);
+        assert_eq!(lookup(&Board::build(5, Cell::X).unwrap(), Cell::X), None);
+        // the book assumes X moved first
+        let mut o_first = Board::build(3, Cell::X).unwrap();
+        o_first.set_first_player(Cell::O);
+        o_first.set_cell((0, 0), Cell::O).unwrap();
+        assert_eq!(lookup(&o_first, Cell::X), None);
+        assert_eq!(
+            moves(&Board::build(4, Cell::X).unwrap(), Cell::X),
+            [(0, 0), (3, 0), (0, 3), (3, 3)]
+        );
+    }
+}
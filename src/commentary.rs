@@ -0,0 +1,306 @@
+//! Short remarks on the moves of a game, derived from the threats on the board, and
+//! explanations of the computer's moves for its opponent.
+
+use crate::board::{Board, Cell, Coord};
+use crate::t;
+
+/// A line of the board which wins when filled
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Line {
+    Row(usize),
+    Column(usize),
+    // from the top left to the bottom right
+    Diagonal,
+    // from the top right to the bottom left
+    AntiDiagonal,
+}
+
+impl Line {
+    // The lines passing through the cell, the diagonals only on square boards
+    fn through(board: &Board, coord: Coord) -> Vec<Line> {
+        let Coord { row: y, col: x } = coord;
+        let mut lines = vec![Line::Row(y), Line::Column(x)];
+        if board.is_square() && x == y {
+            lines.push(Line::Diagonal);
+        }
+        if board.is_square() && x + y == board.width() - 1 {
+            lines.push(Line::AntiDiagonal);
+        }
+        lines
+    }
+
+    fn cells(&self, board: &Board) -> Vec<Coord> {
+        let (width, height) = (board.width(), board.height());
+        match *self {
+            Line::Row(row) => (0..width).map(|col| Coord { row, col }).collect(),
+            Line::Column(col) => (0..height).map(|row| Coord { row, col }).collect(),
+            Line::Diagonal => (0..width).map(|i| Coord { row: i, col: i }).collect(),
+            Line::AntiDiagonal => (0..width)
+                .map(|i| Coord {
+                    row: i,
+                    col: width - 1 - i,
+                })
+                .collect(),
+        }
+    }
+
+    // Whether all cells of the line but the given one are taken by the player
+    fn completed_by(&self, board: &Board, cell: Cell, coord: Coord) -> bool {
+        self.cells(board)
+            .into_iter()
+            .all(|other| other == coord || board.get_cell(other) == cell)
+    }
+
+    // Number of cells of the line taken by the player
+    fn pieces(&self, board: &Board, cell: Cell) -> usize {
+        self.cells(board)
+            .into_iter()
+            .filter(|&coord| board.get_cell(coord) == cell)
+            .count()
+    }
+
+    fn name(&self, board: &Board) -> String {
+        let (width, height) = (board.width(), board.height());
+        match *self {
+            Line::Row(0) => t!("line-top-row").to_string(),
+            Line::Row(1) if height == 3 => t!("line-middle-row").to_string(),
+            Line::Row(y) if y == height - 1 => t!("line-bottom-row").to_string(),
+            Line::Row(y) => t!("line-row", row = y + 1),
+            Line::Column(0) => t!("line-left-column").to_string(),
+            Line::Column(1) if width == 3 => t!("line-middle-column").to_string(),
+            Line::Column(x) if x == width - 1 => t!("line-right-column").to_string(),
+            Line::Column(x) => t!("line-column", column = x + 1),
+            Line::Diagonal => t!("line-diagonal").to_string(),
+            Line::AntiDiagonal => t!("line-anti-diagonal").to_string(),
+        }
+    }
+}
+
+/// A remark on the last move played on the board, or `None` if there is nothing worth
+/// mentioning
+pub fn comment(board: &Board) -> Option<String> {
+    let &mv = board.history().last()?;
+    let player = board.get_cell(mv);
+    let opponent = player.opponent();
+    let mut before = board.clone();
+    before.undo();
+
+    let line_through = |cell: Cell, board: &Board| {
+        Line::through(board, mv)
+            .into_iter()
+            .find(|line| line.completed_by(board, cell, mv))
+    };
+    if let Some(line) = line_through(player, board) {
+        return Some(t!("comment-wins", player = player, line = line.name(board)));
+    }
+    if let Some(&win) = before.winning_moves(player).first() {
+        let line = Line::through(board, win)
+            .into_iter()
+            .find(|line| line.completed_by(&before, player, win))?;
+        return Some(t!(
+            "comment-missed-win",
+            player = player,
+            line = line.name(board)
+        ));
+    }
+    let threats = board.winning_moves(player);
+    if threats.len() > 1 {
+        return Some(t!("comment-double-threat", player = player));
+    }
+    if let Some(line) = line_through(opponent, &before) {
+        return Some(t!(
+            "comment-blocks",
+            player = player,
+            line = line.name(board)
+        ));
+    }
+    if let Some(&threat) = threats.first() {
+        let line = Line::through(board, threat)
+            .into_iter()
+            .find(|line| line.completed_by(board, player, threat))?;
+        return Some(t!(
+            "comment-threat",
+            player = player,
+            line = line.name(board)
+        ));
+    }
+    if board.history().len() <= 2 {
+        let (width, height) = (board.width(), board.height());
+        let Coord { row: y, col: x } = mv;
+        if width % 2 == 1 && height % 2 == 1 && x == width / 2 && y == height / 2 {
+            return Some(t!("comment-center", player = player));
+        }
+        if (x == 0 || x == width - 1) && (y == 0 || y == height - 1) {
+            return Some(t!("comment-corner", player = player));
+        }
+    }
+    None
+}
+
+/// Why the last move on the board was played, addressing its player's opponent as "you",
+/// e.g. "blocks your 2 in a row on the top row" or "creates a double threat"; `None` if no
+/// move was played. The reasons come from the lines through the cell: the win, the threats
+/// blocked and made, and otherwise the lines the move extends or spoils.
+pub fn explain(board: &Board) -> Option<String> {
+    let &mv = board.history().last()?;
+    let player = board.get_cell(mv);
+    let opponent = player.opponent();
+    let mut before = board.clone();
+    before.undo();
+    let lines = Line::through(board, mv);
+    // the line the cell completes for either player
+    let completed = |cell: Cell| {
+        lines
+            .iter()
+            .find(|line| line.completed_by(&before, cell, mv))
+    };
+    if let Some(line) = completed(player) {
+        return Some(t!("explain-wins", line = line.name(board)));
+    }
+
+    let mut reasons = Vec::new();
+    if let Some(line) = completed(opponent) {
+        let count = line.cells(board).len() - 1;
+        reasons.push(t!("explain-blocks", count = count, line = line.name(board)));
+    }
+    // the lines the player can complete with the next move
+    let threats: Vec<Line> = board
+        .winning_moves(player)
+        .into_iter()
+        .filter_map(|threat| {
+            Line::through(board, threat)
+                .into_iter()
+                .find(|line| line.completed_by(board, player, threat))
+        })
+        .collect();
+    match threats[..] {
+        [] => {}
+        [line] => reasons.push(t!("explain-threat", line = line.name(board))),
+        [first, second, ..] => reasons.push(t!(
+            "explain-double-threat",
+            first = first.name(board),
+            second = second.name(board)
+        )),
+    }
+    if reasons.is_empty() {
+        // the line without the opponent's pieces the player has the most pieces on
+        let own = lines
+            .iter()
+            .filter(|line| line.pieces(board, opponent) == 0)
+            .max_by_key(|line| line.pieces(board, player));
+        let spoiled = lines
+            .iter()
+            .filter(|line| line.pieces(board, opponent) > 0 && line.pieces(&before, player) == 0)
+            .count();
+        match own {
+            Some(line) if line.pieces(board, player) > 1 => reasons.push(t!(
+                "explain-extends",
+                line = line.name(board),
+                count = line.pieces(board, player)
+            )),
+            _ => {}
+        }
+        if spoiled > 0 {
+            reasons.push(t!("explain-spoils", count = spoiled));
+        }
+        if reasons.is_empty() {
+            let open = lines
+                .iter()
+                .filter(|line| line.pieces(board, opponent) == 0)
+                .count();
+            reasons.push(match open {
+                0 => t!("explain-no-lines").to_string(),
+                1 => t!("explain-open-line").to_string(),
+                _ => t!("explain-open-lines", count = open),
+            });
+        }
+    }
+    let mut reasons = reasons.into_iter();
+    let first = reasons.next()?;
+    Some(reasons.fold(first, |all, reason| {
+        t!("explain-and", first = all, second = reason)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments() {
+        // moves played and the expected remark on the last one
+        type Case = (&'static [(usize, usize)], Option<&'static str>);
+        let tests: [Case; 8] = [
+            (&[(1, 1)], Some("X takes the center")),
+            (&[(1, 1), (0, 0)], Some("O takes a corner")),
+            (
+                &[(1, 1), (1, 0), (0, 1)],
+                Some("X threatens to complete the middle row"),
+            ),
+            (
+                &[(1, 1), (1, 0), (0, 1), (2, 1)],
+                Some("O blocks the middle row"),
+            ),
+            (
+                &[(0, 0), (1, 0), (2, 2), (1, 1), (0, 2)],
+                Some("X creates a double threat"),
+            ),
+            (
+                &[(0, 0), (1, 0), (2, 2), (1, 1), (0, 2), (1, 2)],
+                Some("O completes the middle column and wins"),
+            ),
+            (
+                &[(0, 0), (1, 1), (1, 0), (2, 2), (0, 2)],
+                Some("X misses the win on the top row"),
+            ),
+            (&[(0, 0), (1, 1), (2, 1)], None),
+        ];
+        for (moves, expected) in tests {
+            assert_eq!(
+                comment(&Board::from_moves(moves, Cell::X)).as_deref(),
+                expected,
+                "moves {:?}",
+                moves
+            );
+        }
+    }
+
+    #[test]
+    fn explanations() {
+        // moves played and the expected explanation of the last one
+        type Case = (&'static [(usize, usize)], &'static str);
+        let tests: [Case; 7] = [
+            (&[(1, 1)], "takes a cell on 4 lines still open"),
+            (&[(1, 1), (0, 1)], "spoils 1 of your open lines"),
+            (
+                &[(1, 1), (1, 0), (0, 1)],
+                "threatens to complete the middle row",
+            ),
+            (
+                &[(1, 1), (1, 0), (0, 1), (2, 1)],
+                "blocks your 2 in a row on the middle row",
+            ),
+            (
+                &[(0, 0), (1, 1), (2, 2), (0, 2), (2, 0), (1, 0), (2, 1)],
+                "completes the right column and wins",
+            ),
+            (
+                &[(0, 0), (1, 0), (2, 2), (1, 1), (0, 2)],
+                "creates a double threat on the left column and the bottom row, you can only block one",
+            ),
+            (
+                &[(1, 1), (0, 0), (0, 1), (1, 0), (2, 0)],
+                "blocks your 2 in a row on the top row and creates a double threat on the middle row and the diagonal from the top right, you can only block one",
+            ),
+        ];
+        for (moves, expected) in tests {
+            assert_eq!(
+                explain(&Board::from_moves(moves, Cell::X)).as_deref(),
+                Some(expected),
+                "moves {:?}",
+                moves
+            );
+        }
+        assert_eq!(explain(&Board::from_moves(&[], Cell::X)), None);
+    }
+}
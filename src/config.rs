@@ -0,0 +1,373 @@
+//! User settings read from a config file.
+//!
+//! The file uses the same `key = value` syntax as the message catalogs. By default it is
+//! read from `$XDG_CONFIG_HOME/tictactoe/config` (or `~/.config/tictactoe/config`); a
+//! missing default file is the same as an empty one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::i18n;
+use crate::t;
+
+/// Error returned when the config file can't be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read
+    Io { path: PathBuf, error: io::Error },
+    /// The given line is not of the form `key = value`
+    Syntax { path: PathBuf, line: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, error } => write!(
+                f,
+                "{}",
+                t!("config-read-failed", path = path.display(), error = error)
+            ),
+            ConfigError::Syntax { path, line } => write!(
+                f,
+                "{}",
+                t!("config-syntax", path = path.display(), line = line)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A command of the explore mode which can be bound to a key
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Action {
+    Variations,
+    MainLine,
+    Copy,
+    CopyGame,
+    Qr,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// All actions, in the order they are listed in the help
+    pub const ALL: [Action; 7] = [
+        Action::Variations,
+        Action::MainLine,
+        Action::Copy,
+        Action::CopyGame,
+        Action::Qr,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// The name of the action in the config file, where it is bound as `key.<name>`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Variations => "variations",
+            Action::MainLine => "main-line",
+            Action::Copy => "copy",
+            Action::CopyGame => "copy-game",
+            Action::Qr => "qr",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn default_key(&self) -> &'static str {
+        match self {
+            Action::Variations => "v",
+            Action::MainLine => "g",
+            Action::Copy => "c",
+            Action::CopyGame => "r",
+            Action::Qr => "k",
+            Action::Help => "?",
+            Action::Quit => "q",
+        }
+    }
+}
+
+/// Error returned when the key bindings in the config file can't be used
+#[derive(Debug, PartialEq)]
+pub enum KeymapError {
+    /// The key contains digits or whitespace and would be mistaken for a move
+    Invalid { action: Action, key: String },
+    /// Two actions are bound to the same key
+    Conflict {
+        key: String,
+        actions: (Action, Action),
+    },
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeymapError::Invalid { action, key } => write!(
+                f,
+                "{}",
+                t!("key-invalid", action = action.name(), key = key)
+            ),
+            KeymapError::Conflict { key, actions } => write!(
+                f,
+                "{}",
+                t!(
+                    "key-conflict",
+                    key = key,
+                    first = actions.0.name(),
+                    second = actions.1.name()
+                )
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// The keys bound to the actions of the explore mode
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    // indexed like Action::ALL
+    keys: Vec<String>,
+}
+
+impl Keymap {
+    /// The key bound to the action
+    pub fn key(&self, action: Action) -> &str {
+        let idx = Action::ALL.iter().position(|&a| a == action).unwrap();
+        &self.keys[idx]
+    }
+
+    /// The action bound to the input, if any. Keys are matched ignoring case.
+    pub fn action(&self, input: &str) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .zip(&self.keys)
+            .find(|(_, key)| key.eq_ignore_ascii_case(input))
+            .map(|(action, _)| action)
+    }
+}
+
+/// Presets shipped with the game, in config file syntax. A preset of the same name in the
+/// config file replaces the built-in one.
+const BUILTIN_PRESETS: &str = "
+preset.casual.dimension = 3
+preset.standard.dimension = 4
+preset.expert.dimension = 5
+preset.expert.computer-begins = true
+";
+
+/// Settings from the config file
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    /// The config file used when none is given explicitly
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(dir.join("tictactoe").join("config"))
+    }
+
+    /// Load the given config file, or the default one if `None`
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Config::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(error) => return Err(ConfigError::Io { path, error }),
+        };
+        Config::parse(&source).map_err(|line| ConfigError::Syntax { path, line })
+    }
+
+    /// Parse the contents of a config file. Returns the number of the first invalid line
+    /// on error.
+    pub fn parse(source: &str) -> Result<Config, usize> {
+        let values = i18n::parse_messages(source)?
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Ok(Config { values })
+    }
+
+    /// Get the value of a setting
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Names of all presets, built-in and from the config file
+    pub fn preset_names(&self) -> Vec<String> {
+        let builtin = Config::parse(BUILTIN_PRESETS).unwrap();
+        let mut names: Vec<String> = self
+            .values
+            .keys()
+            .chain(builtin.values.keys())
+            .filter_map(|key| key.strip_prefix("preset.")?.split_once('.'))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The settings of the named preset, configured as `preset.<name>.<setting> = value`
+    /// in the config file or built in
+    pub fn preset(&self, name: &str) -> Option<Config> {
+        let prefix = format!("preset.{}.", name);
+        let settings = |config: &Config| {
+            let values: HashMap<String, String> = config
+                .values
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.strip_prefix(&prefix)?.to_string(), value.clone()))
+                })
+                .collect();
+            (!values.is_empty()).then_some(Config { values })
+        };
+        settings(self).or_else(|| settings(&Config::parse(BUILTIN_PRESETS).unwrap()))
+    }
+
+    /// All settings and their values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// The key bindings of the explore mode, configured as `key.<action> = <key>`. Actions
+    /// which are not configured keep their default key.
+    ///
+    /// Returns an error if a key could be mistaken for a move or two actions share a key.
+    pub fn keymap(&self) -> Result<Keymap, KeymapError> {
+        let mut keys: Vec<String> = Vec::new();
+        for action in Action::ALL {
+            let key = self
+                .get(&format!("key.{}", action.name()))
+                .unwrap_or(action.default_key());
+            if key.is_empty() || key.chars().any(|c| c.is_ascii_digit() || c.is_whitespace()) {
+                return Err(KeymapError::Invalid {
+                    action,
+                    key: key.to_string(),
+                });
+            }
+            if let Some(idx) = keys.iter().position(|k| k.eq_ignore_ascii_case(key)) {
+                return Err(KeymapError::Conflict {
+                    key: key.to_string(),
+                    actions: (Action::ALL[idx], action),
+                });
+            }
+            keys.push(key.to_string());
+        }
+        Ok(Keymap { keys })
+    }
+
+    /// Render the template configured as `template.<name>`, or the given default if there
+    /// is none, replacing its `{placeholders}`
+    pub fn render(&self, name: &str, default: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let template = self.get(&format!("template.{}", name)).unwrap_or(default);
+        i18n::fill(template, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates() {
+        let config = Config::parse(
+            "
+            # branding for the stream
+            name = Alice
+            template.prompt = {name}, place your {symbol} (move {move}):
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.get("name"), Some("Alice"));
+        assert_eq!(
+            config.render(
+                "prompt",
+                "default",
+                &[("name", &"Alice"), ("symbol", &"X"), ("move", &3)]
+            ),
+            "Alice, place your X (move 3):"
+        );
+        assert_eq!(config.render("turn", "{name}", &[("name", &"Bob")]), "Bob");
+    }
+
+    #[test]
+    fn presets() {
+        let config = Config::parse(
+            "
+            preset.expert.dimension = 6
+            preset.kids.dimension = 3
+            preset.kids.computer-begins = false
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            config.preset_names(),
+            ["casual", "expert", "kids", "standard"]
+        );
+        let expert = config.preset("expert").unwrap();
+        assert_eq!(expert.get("dimension"), Some("6"));
+        // the config file replaces the whole built-in preset
+        assert_eq!(expert.get("computer-begins"), None);
+        assert_eq!(config.preset("casual").unwrap().get("dimension"), Some("3"));
+        assert_eq!(config.preset("kids").unwrap().iter().count(), 2);
+        assert!(config.preset("unknown").is_none());
+    }
+
+    #[test]
+    fn keymap() {
+        let keys = Config::default().keymap().unwrap();
+        assert_eq!(keys.key(Action::Quit), "q");
+        assert_eq!(keys.action("V"), Some(Action::Variations));
+        assert_eq!(keys.action("x"), None);
+        let config = Config::parse("key.quit = x\nkey.help = h").unwrap();
+        let keys = config.keymap().unwrap();
+        assert_eq!(keys.action("x"), Some(Action::Quit));
+        assert_eq!(keys.action("q"), None);
+        assert_eq!(keys.key(Action::Help), "h");
+        assert_eq!(
+            Config::parse("key.quit = G").unwrap().keymap().unwrap_err(),
+            KeymapError::Conflict {
+                key: "G".to_string(),
+                actions: (Action::MainLine, Action::Quit)
+            }
+        );
+        assert_eq!(
+            Config::parse("key.help = b3")
+                .unwrap()
+                .keymap()
+                .unwrap_err()
+                .to_string(),
+            "the key 'b3' for 'help' could be mistaken for a move"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Config::parse("name = Alice\ntemplate.prompt").unwrap_err(),
+            2
+        );
+        let missing = Path::new("/nonexistent/tictactoe/config");
+        assert!(matches!(
+            Config::load(Some(missing)),
+            Err(ConfigError::Io { .. })
+        ));
+    }
+}
@@ -0,0 +1,323 @@
+//! 3D tic-tac-toe on a cube of stacked layers, 4x4x4 by default.
+//!
+//! A line of the full side length wins: rows, columns and pillars, the diagonals of every
+//! face and cross section, and the four space diagonals from corner to opposite corner.
+//!
+//! Unlike the square boards the moves are `(x, y, z)` triples rather than a
+//! [`Coord`](crate::board::Coord), which has no layer.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::board::{BoardError, Cell, MoveError};
+use crate::input::{self, ParseError};
+
+/// The supported numbers of cells along each side
+pub const DIM_RANGE: RangeInclusive<usize> = 2..=8;
+
+/// The default number of cells along each side
+pub const DEFAULT_DIM: usize = 4;
+
+/// A 3D board. X always moves first.
+#[derive(Debug, Clone)]
+pub struct Cube {
+    dim: usize,
+    // the cells by x + dim * (y + dim * z)
+    cells: Vec<Cell>,
+    win_lines: Vec<Vec<usize>>,
+    history: Vec<(usize, usize, usize)>,
+}
+
+impl Cube {
+    /// Create an empty cube with the given number of cells along each side
+    pub fn build(dim: usize) -> Result<Cube, BoardError> {
+        if !DIM_RANGE.contains(&dim) {
+            return Err(BoardError::InvalidDimension {
+                got: dim,
+                min: *DIM_RANGE.start(),
+                max: *DIM_RANGE.end(),
+            });
+        }
+        Ok(Cube {
+            dim,
+            cells: vec![Cell::Blank; dim * dim * dim],
+            win_lines: win_lines(dim),
+            history: Vec::new(),
+        })
+    }
+
+    /// The number of cells along each side
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// The piece at the given zero based coordinates, z being the layer
+    pub fn get_cell(&self, x: usize, y: usize, z: usize) -> Cell {
+        self.cells[self.index(x, y, z)]
+    }
+
+    /// The lines winning the game, as cell indexes `x + dim * (y + dim * z)`
+    pub fn win_lines(&self) -> &[Vec<usize>] {
+        &self.win_lines
+    }
+
+    /// The player to move
+    pub fn to_move(&self) -> Cell {
+        if self.history.len().is_multiple_of(2) {
+            Cell::X
+        } else {
+            Cell::O
+        }
+    }
+
+    /// The moves made so far
+    pub fn history(&self) -> &[(usize, usize, usize)] {
+        &self.history
+    }
+
+    /// Place the piece of the player to move at the given zero based coordinates
+    ///
+    /// Returns an error if the cell is taken or the game is over.
+    pub fn play(&mut self, x: usize, y: usize, z: usize) -> Result<(), MoveError> {
+        assert!(x < self.dim && y < self.dim && z < self.dim);
+        let idx = self.index(x, y, z);
+        if self.cells[idx] != Cell::Blank {
+            return Err(MoveError::CellTaken);
+        }
+        if self.winner().is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        self.cells[idx] = self.to_move();
+        self.history.push((x, y, z));
+        Ok(())
+    }
+
+    /// Take back the last move, returning its coordinates
+    pub fn undo(&mut self) -> Option<(usize, usize, usize)> {
+        let (x, y, z) = self.history.pop()?;
+        let idx = self.index(x, y, z);
+        self.cells[idx] = Cell::Blank;
+        Some((x, y, z))
+    }
+
+    /// The player who completed a line
+    pub fn winner(&self) -> Option<Cell> {
+        self.win_lines.iter().find_map(|line| {
+            let first = self.cells[line[0]];
+            (first != Cell::Blank && line.iter().all(|&idx| self.cells[idx] == first))
+                .then_some(first)
+        })
+    }
+
+    /// Whether the game is over, by a win or because the cube is full
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || !self.cells.contains(&Cell::Blank)
+    }
+
+    /// Parse a line of user input of the form `x y z` into zero based coordinates
+    pub fn parse_input(&self, input: &str) -> Result<(usize, usize, usize), ParseError> {
+        input::parse_move_3d(input, self.dim)
+    }
+
+    /// Choose a move for the player to move: complete a line, block the opponent's line,
+    /// or else take the cell on the most promising open lines
+    ///
+    /// Returns `None` if the game is over.
+    pub fn best_move(&self) -> Option<(usize, usize, usize)> {
+        if self.is_over() {
+            return None;
+        }
+        let player = self.to_move();
+        let blanks: Vec<usize> = (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == Cell::Blank)
+            .collect();
+        let completes = |p: Cell| {
+            blanks.iter().copied().find(|&idx| {
+                self.win_lines.iter().any(|line| {
+                    line.contains(&idx) && line.iter().all(|&i| i == idx || self.cells[i] == p)
+                })
+            })
+        };
+        let idx = completes(player)
+            .or_else(|| completes(player.opponent()))
+            .or_else(|| {
+                blanks
+                    .iter()
+                    .copied()
+                    .max_by_key(|&idx| self.score(idx, player))
+            })?;
+        Some(self.coords(idx))
+    }
+
+    // the value of taking a blank cell: lines the player can still complete count by their
+    // pieces, lines only the opponent can complete a bit less
+    fn score(&self, idx: usize, player: Cell) -> usize {
+        self.win_lines
+            .iter()
+            .filter(|line| line.contains(&idx))
+            .map(|line| {
+                let mine = line.iter().filter(|&&i| self.cells[i] == player).count();
+                let theirs = line
+                    .iter()
+                    .filter(|&&i| self.cells[i] == player.opponent())
+                    .count();
+                match (mine, theirs) {
+                    (n, 0) => 1 + 4 * n * n,
+                    (0, n) => 3 * n * n,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + self.dim * (y + self.dim * z)
+    }
+
+    fn coords(&self, idx: usize) -> (usize, usize, usize) {
+        (
+            idx % self.dim,
+            idx / self.dim % self.dim,
+            idx / (self.dim * self.dim),
+        )
+    }
+}
+
+/// All lines of `dim` cells through a cube with `dim` cells along each side, as cell indexes
+/// `x + dim * (y + dim * z)`. There are `((dim + 2)³ - dim³) / 2` of them, 76 on a 4x4x4
+/// cube.
+pub fn win_lines(dim: usize) -> Vec<Vec<usize>> {
+    let n = dim as isize;
+    let mut lines = Vec::new();
+    // the 13 directions up to reversal: the first non-zero step is positive
+    for dz in -1..=1isize {
+        for dy in -1..=1isize {
+            for dx in -1..=1isize {
+                if [dx, dy, dz].into_iter().find(|&d| d != 0) != Some(1) {
+                    continue;
+                }
+                // along every axis with a step the line runs from one face to the opposite
+                // one, along the others it may lie anywhere
+                let starts = |d: isize| match d {
+                    0 => (0..n).collect(),
+                    1 => vec![0],
+                    _ => vec![n - 1],
+                };
+                for &z in &starts(dz) {
+                    for &y in &starts(dy) {
+                        for &x in &starts(dx) {
+                            lines.push(
+                                (0..n)
+                                    .map(|i| {
+                                        let (x, y, z) = (x + i * dx, y + i * dy, z + i * dz);
+                                        (x + n * (y + n * z)) as usize
+                                    })
+                                    .collect(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Draws the layers side by side, the lowest layer z = 1 on the left
+impl fmt::Display for Cube {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sep = "+---".repeat(self.dim) + "+";
+        let layers: Vec<String> = (1..=self.dim)
+            .map(|z| format!("{:<width$}", format!("z = {}", z), width = sep.len()))
+            .collect();
+        writeln!(f, "{}", layers.join("  ").trim_end())?;
+        let seps = vec![sep.as_str(); self.dim].join("  ");
+        writeln!(f, "{}", seps)?;
+        for y in 0..self.dim {
+            let rows: Vec<String> = (0..self.dim)
+                .map(|z| {
+                    let cells: String = (0..self.dim)
+                        .map(|x| format!("| {} ", self.get_cell(x, y, z)))
+                        .collect();
+                    cells + "|"
+                })
+                .collect();
+            writeln!(f, "{}", rows.join("  "))?;
+            writeln!(f, "{}", seps)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert!(Cube::build(8).is_ok());
+        assert_eq!(
+            Cube::build(9).unwrap_err(),
+            BoardError::InvalidDimension {
+                got: 9,
+                min: 2,
+                max: 8
+            }
+        );
+    }
+
+    #[test]
+    fn line_counts() {
+        for (dim, count) in [(2, 28), (3, 49), (4, 76), (5, 109)] {
+            let lines = win_lines(dim);
+            assert_eq!(lines.len(), count, "dim {}", dim);
+            assert!(lines.iter().all(|line| line.len() == dim));
+        }
+        // the space diagonal from corner to corner
+        assert!(win_lines(4).contains(&vec![0, 21, 42, 63]));
+        // a pillar through the layers
+        assert!(win_lines(4).contains(&vec![5, 21, 37, 53]));
+    }
+
+    #[test]
+    fn pillar_wins() {
+        let mut cube = Cube::build(4).unwrap();
+        for (x, y, z) in [
+            (1, 1, 0),
+            (0, 0, 0),
+            (1, 1, 1),
+            (0, 0, 1),
+            (1, 1, 2),
+            (0, 0, 2),
+        ] {
+            cube.play(x, y, z).unwrap();
+        }
+        assert_eq!(cube.winner(), None);
+        // X completes the pillar before O can
+        assert_eq!(cube.best_move(), Some((1, 1, 3)));
+        cube.play(1, 1, 3).unwrap();
+        assert_eq!(cube.winner(), Some(Cell::X));
+        assert_eq!(cube.play(3, 3, 3), Err(MoveError::GameAlreadyOver));
+        cube.undo();
+        cube.play(3, 3, 3).unwrap();
+        // O completes its own pillar rather than blocking
+        assert_eq!(cube.best_move(), Some((0, 0, 3)));
+    }
+
+    #[test]
+    fn render() {
+        let mut cube = Cube::build(2).unwrap();
+        cube.play(1, 0, 1).unwrap();
+        assert_eq!(
+            cube.to_string(),
+            "\
+z = 1      z = 2
++---+---+  +---+---+
+|   |   |  |   | X |
++---+---+  +---+---+
+|   |   |  |   |   |
++---+---+  +---+---+
+"
+        );
+    }
+}
@@ -0,0 +1,670 @@
+//! Database of finished games, used for the long term statistics.
+//!
+//! Games are appended to a text file, one line per game, holding `key=value` fields
+//! separated by spaces:
+//!
+//! ```text
+//! time=1760000000 dim=3 human=X first=X result=computer moves=1:1,2:2,1:2,1:3,3:3,3:1 accuracy=1/3
+//! ```
+//!
+//! Coordinates are one based like the user input. Unknown fields are ignored so that
+//! newer versions can add fields without breaking older ones.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::{Board, BoardError, Cell, Coord, GameOver, DIM_RANGE};
+use crate::pns;
+use crate::rules::Misere;
+use crate::solver;
+use crate::stats::Accuracy;
+use crate::t;
+
+/// The directory the game keeps its data in: `$XDG_DATA_HOME/tictactoe` (or
+/// `~/.local/share/tictactoe`)
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(dir.join("tictactoe"))
+}
+
+/// The directory the game keeps data in which can be recomputed:
+/// `$XDG_CACHE_HOME/tictactoe` (or `~/.cache/tictactoe`)
+pub fn cache_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(dir.join("tictactoe"))
+}
+
+/// Reason a line of the database doesn't describe a game which could have been played.
+/// Plies count from 1.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RecordError {
+    /// The line can't be parsed
+    Malformed,
+    /// The move is on a cell which is already taken
+    Occupied { ply: usize },
+    /// The move was made after the game had already ended
+    AfterEnd { ply: usize },
+    /// The game ended with a different result than the recorded one
+    WrongResult {
+        recorded: GameOver,
+        actual: GameOver,
+    },
+    /// The game ended early with a result which wasn't decided yet
+    Unfinished { recorded: GameOver },
+    /// The accuracy refers to more moves than the human made
+    BadAccuracy,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Malformed => write!(f, "{}", t!("record-malformed")),
+            RecordError::Occupied { ply } => write!(f, "{}", t!("record-occupied", ply = ply)),
+            RecordError::AfterEnd { ply } => write!(f, "{}", t!("record-after-end", ply = ply)),
+            RecordError::WrongResult { recorded, actual } => write!(
+                f,
+                "{}",
+                t!(
+                    "record-wrong-result",
+                    recorded = result_name(*recorded),
+                    actual = result_name(*actual)
+                )
+            ),
+            RecordError::Unfinished { recorded } => {
+                write!(
+                    f,
+                    "{}",
+                    t!("record-unfinished", recorded = result_name(*recorded))
+                )
+            }
+            RecordError::BadAccuracy => write!(f, "{}", t!("record-bad-accuracy")),
+        }
+    }
+}
+
+// The outcome with best play of a game ended early. Positions too large for the solver
+// are proven by a proof-number search.
+fn forced_outcome(board: &Board, to_move: Cell) -> Option<GameOver> {
+    let outcome = match solver::forced_outcome(board, to_move) {
+        Some(outcome) => outcome,
+        None => {
+            let human = board.human_uses();
+            let wins = |attacker| Some(pns::prove(board, to_move, attacker, pns::MAX_NODES)?.wins);
+            if wins(human)? {
+                GameOver::HumanWon
+            } else if wins(human.opponent())? {
+                GameOver::ComputerWon
+            } else {
+                GameOver::Tie
+            }
+        }
+    };
+    Some(outcome)
+}
+
+// The value of the `result` field
+fn result_name(result: GameOver) -> &'static str {
+    match result {
+        GameOver::HumanWon => "human",
+        GameOver::ComputerWon => "computer",
+        GameOver::Tie => "tie",
+        GameOver::Resigned => "resigned",
+    }
+}
+
+/// A finished game as stored in the database
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameRecord {
+    /// Seconds since the Unix epoch when the game ended
+    pub time: u64,
+    /// The number of columns and rows of the board
+    pub width: usize,
+    pub height: usize,
+    /// The number of pieces in a row which won, see [`Board::win_length`]
+    pub win_length: usize,
+    /// Whether the lines wrapped around the edges, see [`Board::with_wrap`]
+    pub wrap: bool,
+    /// Whether pieces fell down their column, see [`Board::with_gravity`]
+    pub gravity: bool,
+    /// Whether completing a line lost, see [`Misere`]
+    pub misere: bool,
+    /// Whether the sides were swapped after the first move, see [`Board::swap_sides`]
+    pub swapped: bool,
+    /// Zero based coordinates of the blocked cells, see [`Board::with_blocked`]
+    pub blocked: Vec<Coord>,
+    pub human_uses: Cell,
+    /// The symbol which moved first
+    pub first: Cell,
+    pub result: GameOver,
+    /// Zero based coordinates of all moves
+    pub moves: Vec<Coord>,
+    pub accuracy: Option<Accuracy>,
+}
+
+impl GameRecord {
+    /// Create the record of a game which ended now
+    pub fn new(board: &Board, result: GameOver, accuracy: Option<Accuracy>) -> GameRecord {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        GameRecord {
+            time,
+            width: board.width(),
+            height: board.height(),
+            win_length: board.win_length(),
+            wrap: board.wraps(),
+            gravity: board.has_gravity(),
+            misere: board.has_misere(),
+            swapped: board.swapped(),
+            blocked: board.blocked_cells(),
+            human_uses: board.human_uses(),
+            first: match board.history().first() {
+                Some(&first) => board.get_cell(first),
+                None => board.human_uses(),
+            },
+            result,
+            moves: board.history().to_vec(),
+            accuracy,
+        }
+    }
+
+    /// Replay the game on a new board. Returns `None` if the moves don't fit on the board.
+    pub fn board(&self) -> Option<Board> {
+        let mut board = self.new_board().ok()?;
+        let mut cell = self.first;
+        for &mv in &self.moves {
+            if mv.col >= self.width || mv.row >= self.height {
+                return None;
+            }
+            board.set_cell(mv, cell).ok()?;
+            cell = cell.opponent();
+        }
+        Some(board)
+    }
+
+    /// Replay the game from the start and check that every move was legal, that the
+    /// recorded result is the actual one and that the accuracy fits the human's moves.
+    ///
+    /// Games ended early because the outcome was decided are accepted if the result is the
+    /// outcome with best play; a resigned game if the human can force a win.
+    pub fn verify(&self) -> Result<(), RecordError> {
+        let mut board = self.new_board().map_err(|_| RecordError::Malformed)?;
+        let mut cell = self.first;
+        for (idx, &mv) in self.moves.iter().enumerate() {
+            if board.result().is_some() {
+                return Err(RecordError::AfterEnd { ply: idx + 1 });
+            }
+            if mv.col >= self.width || mv.row >= self.height || board.get_cell(mv) != Cell::Blank {
+                return Err(RecordError::Occupied { ply: idx + 1 });
+            }
+            board
+                .set_cell(mv, cell)
+                .map_err(|_| RecordError::Malformed)?;
+            cell = cell.opponent();
+        }
+        match board.result() {
+            Some(actual) if actual != self.result => {
+                return Err(RecordError::WrongResult {
+                    recorded: self.result,
+                    actual,
+                })
+            }
+            Some(_) => {}
+            None => {
+                // the computer only resigns games the human wins with best play
+                let expected = match self.result {
+                    GameOver::Resigned => GameOver::HumanWon,
+                    result => result,
+                };
+                if forced_outcome(&board, cell) != Some(expected) {
+                    return Err(RecordError::Unfinished {
+                        recorded: self.result,
+                    });
+                }
+            }
+        }
+        if let Some(a) = self.accuracy {
+            if a.matched > a.moves || a.moves > self.human_moves().count() {
+                return Err(RecordError::BadAccuracy);
+            }
+        }
+        Ok(())
+    }
+
+    // The empty board the game was played on
+    fn new_board(&self) -> Result<Board, BoardError> {
+        Board::build_rectangle(self.width, self.height, self.win_length, self.human_uses)
+            .and_then(|board| board.with_wrap(self.wrap).with_gravity(self.gravity))
+            .and_then(|board| {
+                if self.misere {
+                    board.with_rules(Misere)
+                } else {
+                    Ok(board)
+                }
+            })
+            .and_then(|board| match self.blocked.as_slice() {
+                [] => Ok(board),
+                blocked => board.with_blocked(blocked),
+            })
+    }
+
+    /// Whether the game was played on the square board of the given dimension
+    pub fn is_dim(&self, dim: usize) -> bool {
+        self.width == dim && self.height == dim
+    }
+
+    /// The size of the board, e.g. `3` for the 3x3 board and `7x6` for a board with 7
+    /// columns and 6 rows
+    pub fn size(&self) -> String {
+        if self.width == self.height {
+            self.width.to_string()
+        } else {
+            format!("{}x{}", self.width, self.height)
+        }
+    }
+
+    /// Parse and verify a line of the database
+    pub fn parse_verified(line: &str) -> Result<GameRecord, RecordError> {
+        let record = GameRecord::parse(line).ok_or(RecordError::Malformed)?;
+        record.verify()?;
+        Ok(record)
+    }
+
+    /// The moves made by the human, without the first move if the human took it over by
+    /// swapping sides
+    pub fn human_moves(&self) -> impl Iterator<Item = Coord> + '_ {
+        let skip = usize::from(self.first != self.human_uses);
+        let swapped = usize::from(self.swapped && skip == 0);
+        self.moves
+            .iter()
+            .copied()
+            .skip(skip)
+            .step_by(2)
+            .skip(swapped)
+    }
+
+    /// The record as a line of the database
+    pub fn to_line(&self) -> String {
+        let cells = |cells: &[Coord]| {
+            cells
+                .iter()
+                .map(|cell| format!("{}:{}", cell.col + 1, cell.row + 1))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let mut line = format!(
+            "time={} dim={} human={} first={} result={} moves={}",
+            self.time,
+            self.size(),
+            self.human_uses,
+            self.first,
+            result_name(self.result),
+            cells(&self.moves)
+        );
+        if let Some(a) = self.accuracy {
+            line += &format!(" accuracy={}/{}", a.matched, a.moves);
+        }
+        if self.win_length != self.width.min(self.height) {
+            line += &format!(" k={}", self.win_length);
+        }
+        if self.wrap {
+            line += " wrap=true";
+        }
+        if self.gravity {
+            line += " gravity=true";
+        }
+        if self.misere {
+            line += " misere=true";
+        }
+        if self.swapped {
+            line += " swap=true";
+        }
+        if !self.blocked.is_empty() {
+            line += &format!(" blocked={}", cells(&self.blocked));
+        }
+        line
+    }
+
+    /// Parse a line of the database. Returns `None` if the line is malformed or describes
+    /// moves which don't fit on the board.
+    pub fn parse(line: &str) -> Option<GameRecord> {
+        let mut record = GameRecord {
+            time: 0,
+            width: 0,
+            height: 0,
+            win_length: 0,
+            wrap: false,
+            gravity: false,
+            misere: false,
+            swapped: false,
+            blocked: Vec::new(),
+            human_uses: Cell::Blank,
+            first: Cell::Blank,
+            result: GameOver::Tie,
+            moves: Vec::new(),
+            accuracy: None,
+        };
+        let mut result = None;
+        // one based `x:y` separated by commas
+        let cells = |value: &str| {
+            value
+                .split(',')
+                .filter(|mv| !mv.is_empty())
+                .map(|mv| {
+                    let (x, y) = mv.split_once(':')?;
+                    let (x, y): (usize, usize) = (x.parse().ok()?, y.parse().ok()?);
+                    Some(Coord {
+                        row: y.checked_sub(1)?,
+                        col: x.checked_sub(1)?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        for field in line.split_whitespace() {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "time" => record.time = value.parse().ok()?,
+                "dim" => {
+                    (record.width, record.height) = match value.split_once('x') {
+                        Some((width, height)) => (width.parse().ok()?, height.parse().ok()?),
+                        None => (value.parse().ok()?, value.parse().ok()?),
+                    }
+                }
+                "k" => record.win_length = value.parse().ok()?,
+                "wrap" => record.wrap = value.parse().ok()?,
+                "gravity" => record.gravity = value.parse().ok()?,
+                "misere" => record.misere = value.parse().ok()?,
+                "swap" => record.swapped = value.parse().ok()?,
+                "human" => {
+                    record.human_uses = match value {
+                        "X" => Cell::X,
+                        "O" => Cell::O,
+                        _ => return None,
+                    }
+                }
+                "first" => {
+                    record.first = match value {
+                        "X" => Cell::X,
+                        "O" => Cell::O,
+                        _ => return None,
+                    }
+                }
+                "result" => {
+                    result = Some(match value {
+                        "human" => GameOver::HumanWon,
+                        "computer" => GameOver::ComputerWon,
+                        "tie" => GameOver::Tie,
+                        "resigned" => GameOver::Resigned,
+                        _ => return None,
+                    })
+                }
+                "moves" => record.moves = cells(value)?,
+                "blocked" => record.blocked = cells(value)?,
+                "accuracy" => {
+                    let (matched, moves) = value.split_once('/')?;
+                    record.accuracy = Some(Accuracy {
+                        matched: matched.parse().ok()?,
+                        moves: moves.parse().ok()?,
+                    })
+                }
+                _ => {}
+            }
+        }
+        record.result = result?;
+        // recorded before the field was added, when the human always began
+        if record.first == Cell::Blank {
+            record.first = record.human_uses;
+        }
+        // only recorded when less than the shorter side won
+        if record.win_length == 0 {
+            record.win_length = record.width.min(record.height);
+        }
+        let on_board = record
+            .moves
+            .iter()
+            .chain(&record.blocked)
+            .all(|cell| cell.col < record.width && cell.row < record.height);
+        (DIM_RANGE.contains(&record.width)
+            && DIM_RANGE.contains(&record.height)
+            && (2..=record.width.min(record.height)).contains(&record.win_length)
+            && on_board
+            && record.human_uses != Cell::Blank)
+            .then_some(record)
+    }
+}
+
+/// The file holding the recorded games
+#[derive(Debug, Clone)]
+pub struct StatsDb {
+    path: PathBuf,
+}
+
+impl StatsDb {
+    /// Open the database in `$XDG_DATA_HOME/tictactoe/games` (or
+    /// `~/.local/share/tictactoe/games`)
+    pub fn open_default() -> Option<StatsDb> {
+        Some(StatsDb::open(data_dir()?.join("games")))
+    }
+
+    /// Open the database in the given file. The file is created when the first game is added.
+    pub fn open(path: impl Into<PathBuf>) -> StatsDb {
+        StatsDb { path: path.into() }
+    }
+
+    /// The file the games are stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add a game
+    pub fn append(&self, record: &GameRecord) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record.to_line())
+    }
+
+    /// Read all games. Lines which can't be parsed are skipped.
+    pub fn load(&self) -> io::Result<Vec<GameRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(content.lines().filter_map(GameRecord::parse).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_round_trip() {
+        let mut board = Board::build(3, Cell::O).unwrap();
+        board.computer_move();
+        board.human_move((0, 0)).unwrap();
+        let record = GameRecord::new(
+            &board,
+            GameOver::ComputerWon,
+            Some(Accuracy {
+                matched: 0,
+                moves: 1,
+            }),
+        );
+        let line = record.to_line();
+        assert!(line.ends_with(" dim=3 human=O first=X result=computer moves=2:2,1:1 accuracy=0/1"));
+        assert_eq!(GameRecord::parse(&line), Some(record.clone()));
+        assert_eq!(record.human_moves().collect::<Vec<_>>(), [(0, 0)]);
+        assert_eq!(record.board().unwrap().history(), board.history());
+        assert_eq!(record.board().unwrap().get_cell((1, 1)), Cell::X);
+        assert!(
+            GameRecord::parse("time=1 dim=2 human=X result=tie moves= new=field")
+                .unwrap()
+                .moves
+                .is_empty()
+        );
+        let replayed = GameRecord::parse("time=1 dim=3 human=X result=tie moves=1:1,1:1");
+        assert!(replayed.unwrap().board().is_none());
+        // three in a row on the 5x5 board
+        let line = "time=1 dim=5 human=X first=X result=human moves=1:1,5:5,2:1,5:4,3:1 k=3";
+        let record = GameRecord::parse(line).unwrap();
+        assert_eq!(record.win_length, 3);
+        assert_eq!(record.to_line(), line);
+        assert!(record.verify().is_ok());
+        // the diagonal continues across the edge
+        let line = "time=1 dim=3 human=X first=X result=human moves=2:1,1:1,3:2,1:2,1:3 wrap=true";
+        let record = GameRecord::parse(line).unwrap();
+        assert!(record.wrap);
+        assert_eq!(record.to_line(), line);
+        assert!(record.verify().is_ok());
+        assert_eq!(record.board().unwrap().winner(), Some(Cell::X));
+        let unwrapped = GameRecord::parse(&line.replace(" wrap=true", "")).unwrap();
+        assert_eq!(unwrapped.board().unwrap().winner(), None);
+        let line = "time=1 dim=7x6 human=X first=X result=tie moves=7:6 k=4";
+        let record = GameRecord::parse(line).unwrap();
+        assert_eq!(
+            (record.width, record.height, record.size()),
+            (7, 6, "7x6".to_string())
+        );
+        assert_eq!(record.to_line(), line);
+        assert_eq!(record.board().unwrap().get_cell((6, 5)), Cell::X);
+        let line = "time=1 dim=3 human=X first=X result=tie moves=1:1 blocked=2:2,3:1";
+        let record = GameRecord::parse(line).unwrap();
+        assert_eq!(record.blocked, [(1, 1), (2, 0)]);
+        assert_eq!(record.to_line(), line);
+        assert_eq!(record.board().unwrap().get_cell((1, 1)), Cell::Blocked);
+        assert!(GameRecord::parse(&line.replace("moves=1:1", "moves=2:2"))
+            .unwrap()
+            .board()
+            .is_none());
+        for line in [
+            "",
+            "garbage",
+            "time=1 dim=3 human=X moves=1:1",
+            "dim=3 human=Z result=tie",
+            "time=1 dim=3 human=X result=tie moves=4:1",
+            "time=1 dim=3 human=X result=tie moves=0:1",
+            "time=1 dim=99999999999 human=X result=tie moves=",
+            "time=1 dim=3 human=X result=tie moves=1:1:1",
+            "time=1 dim=3 human=X result=tie moves= k=4",
+            "time=1 dim=3 human=X result=tie moves= blocked=4:1",
+        ] {
+            assert_eq!(GameRecord::parse(line), None);
+        }
+    }
+
+    #[test]
+    fn verification() {
+        let verify = |line: &str| GameRecord::parse_verified(line).map(|_| ());
+        let game = "time=1 dim=3 human=X first=X";
+        assert_eq!(
+            verify(&format!(
+                "{game} result=human moves=1:1,2:1,1:2,2:2,1:3 accuracy=2/3"
+            )),
+            Ok(())
+        );
+        // ended early, as the human wins with the next move
+        assert_eq!(
+            verify(&format!(
+                "{game} result=human moves=1:1,2:2,3:3,1:3,3:1,2:1"
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&format!(
+                "{game} result=computer moves=1:1,2:2,3:3,1:3,3:1,2:1"
+            )),
+            Err(RecordError::Unfinished {
+                recorded: GameOver::ComputerWon
+            })
+        );
+        assert_eq!(verify(&format!("{game} result=tie moves=")), Ok(()));
+        // resigned only when the human wins with best play, also on boards too large for
+        // the solver
+        assert_eq!(
+            verify(&format!(
+                "{game} result=resigned moves=1:1,2:2,3:3,1:3,3:1,2:1"
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&format!("{game} result=resigned moves=")),
+            Err(RecordError::Unfinished {
+                recorded: GameOver::Resigned
+            })
+        );
+        let large = "time=1 dim=5 human=X first=X moves=1:4,3:1,2:4,1:3,4:4,4:3,5:4,2:5";
+        assert_eq!(verify(&format!("{large} result=resigned")), Ok(()));
+        assert!(verify(&format!("{large} result=tie")).is_err());
+        assert_eq!(
+            verify(&format!("{game} result=human moves=")),
+            Err(RecordError::Unfinished {
+                recorded: GameOver::HumanWon
+            })
+        );
+        assert_eq!(
+            verify(&format!("{game} result=human moves=1:1,2:1,1:1")),
+            Err(RecordError::Occupied { ply: 3 })
+        );
+        assert_eq!(
+            verify(&format!(
+                "{game} result=human moves=1:1,2:1,1:2,2:2,1:3,3:3"
+            )),
+            Err(RecordError::AfterEnd { ply: 6 })
+        );
+        assert_eq!(
+            verify(&format!("{game} result=computer moves=1:1,2:1,1:2,2:2,1:3")),
+            Err(RecordError::WrongResult {
+                recorded: GameOver::ComputerWon,
+                actual: GameOver::HumanWon
+            })
+        );
+        assert_eq!(
+            verify(&format!(
+                "{game} result=human moves=1:1,2:1,1:2,2:2,1:3 accuracy=4/4"
+            )),
+            Err(RecordError::BadAccuracy)
+        );
+        assert_eq!(
+            verify(&format!(
+                "{game} result=human moves=1:1,2:1,1:2,2:2,1:3 accuracy=3/2"
+            )),
+            Err(RecordError::BadAccuracy)
+        );
+        // completing the column loses by the misère rules
+        assert_eq!(
+            verify(&format!(
+                "{game} result=computer moves=1:1,2:1,1:2,2:2,1:3 misere=true"
+            )),
+            Ok(())
+        );
+        assert_eq!(verify("garbage"), Err(RecordError::Malformed));
+    }
+
+    #[test]
+    fn append_and_load() {
+        let path = std::env::temp_dir().join(format!("tictactoe-db-test-{}", std::process::id()));
+        let db = StatsDb::open(&path);
+        assert_eq!(db.load().unwrap(), []);
+        let board = Board::build(3, Cell::X).unwrap();
+        let record = GameRecord::new(&board, GameOver::Tie, None);
+        db.append(&record).unwrap();
+        db.append(&record).unwrap();
+        assert_eq!(db.load().unwrap(), [record.clone(), record]);
+        fs::remove_file(path).unwrap();
+    }
+}
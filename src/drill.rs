@@ -0,0 +1,180 @@
+//! Opening drills on the 3x3 board: positions after the first one or two moves, to be
+//! answered with a move that keeps the best outcome.
+//!
+//! The progress on every position is kept in the profile and the positions are scheduled
+//! like flash cards: each correct answer moves a position up one level, a wrong one back to
+//! the first, and positions on lower levels come up more often.
+
+use crate::board::{Board, Cell, Coord};
+use crate::profile::Profile;
+use crate::rng::Rng;
+use crate::solver;
+
+/// Number of positions presented in one drill session
+pub const ROUNDS: usize = 10;
+
+/// Highest level a position can reach
+pub const MAX_LEVEL: usize = 4;
+
+/// The drilled positions, one of each up to symmetry, given by the moves leading to them
+pub const POSITIONS: [&[(usize, usize)]; 15] = [
+    &[(1, 1)],
+    &[(0, 0)],
+    &[(1, 0)],
+    &[(1, 1), (0, 0)],
+    &[(1, 1), (1, 0)],
+    &[(0, 0), (1, 1)],
+    &[(0, 0), (1, 0)],
+    &[(0, 0), (2, 0)],
+    &[(0, 0), (2, 1)],
+    &[(0, 0), (2, 2)],
+    &[(1, 0), (0, 0)],
+    &[(1, 0), (0, 1)],
+    &[(1, 0), (0, 2)],
+    &[(1, 0), (1, 1)],
+    &[(1, 0), (1, 2)],
+];
+
+/// The board after the given moves, with the human playing the side to move
+pub fn position(moves: &[(usize, usize)]) -> Board {
+    let to_move = if moves.len() % 2 == 1 {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    let mut board = Board::build(3, to_move).unwrap();
+    let mut cell = Cell::X;
+    for &(x, y) in moves {
+        board.set_cell((x, y), cell).unwrap();
+        cell = cell.opponent();
+    }
+    board
+}
+
+/// The moves which keep the best outcome for the side to move in the position
+pub fn answers(board: &Board) -> Vec<Coord> {
+    solver::optimal_moves(board, board.human_uses()).unwrap_or_default()
+}
+
+/// The progress on one position
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct Progress {
+    pub level: usize,
+    pub solved: usize,
+    pub attempts: usize,
+}
+
+impl Progress {
+    // The profile key of the position, e.g. `drill.2:2,1:1`
+    fn key(moves: &[(usize, usize)]) -> String {
+        let moves: Vec<String> = moves
+            .iter()
+            .map(|(x, y)| format!("{}:{}", x + 1, y + 1))
+            .collect();
+        format!("drill.{}", moves.join(","))
+    }
+
+    /// Load the progress on the position from the profile. Positions never drilled start
+    /// on the first level.
+    pub fn load(profile: &Profile, moves: &[(usize, usize)]) -> Progress {
+        let values: Vec<usize> = profile
+            .get(&Progress::key(moves))
+            .unwrap_or_default()
+            .split_whitespace()
+            .map_while(|v| v.parse().ok())
+            .collect();
+        match values[..] {
+            [level, solved, attempts] => Progress {
+                level: level.min(MAX_LEVEL),
+                solved: solved.min(attempts),
+                attempts,
+            },
+            _ => Progress::default(),
+        }
+    }
+
+    /// Store the progress on the position in the profile
+    pub fn store(&self, profile: &mut Profile, moves: &[(usize, usize)]) {
+        profile.set(
+            &Progress::key(moves),
+            format!("{} {} {}", self.level, self.solved, self.attempts),
+        );
+    }
+
+    /// Record an answer
+    pub fn record(&mut self, correct: bool) {
+        self.attempts = self.attempts.saturating_add(1);
+        if correct {
+            self.solved = self.solved.saturating_add(1);
+            self.level = (self.level + 1).min(MAX_LEVEL);
+        } else {
+            self.level = 0;
+        }
+    }
+
+    /// Share of correct answers in percent, if the position was drilled
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.solved as f64 * 100.0 / self.attempts as f64)
+    }
+}
+
+/// Choose the index of the next position to drill. A position comes up half as often for
+/// every level it has reached.
+pub fn pick(progress: &[Progress], rng: &mut Rng) -> usize {
+    let weight = |p: &Progress| 1 << (MAX_LEVEL - p.level.min(MAX_LEVEL));
+    let total: usize = progress.iter().map(weight).sum();
+    let mut n = rng.below(total);
+    for (idx, p) in progress.iter().enumerate() {
+        if n < weight(p) {
+            return idx;
+        }
+        n -= weight(p);
+    }
+    progress.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_have_answers() {
+        for moves in POSITIONS {
+            let board = position(moves);
+            assert_eq!(board.history(), moves);
+            assert!(!answers(&board).is_empty());
+        }
+        assert_eq!(
+            answers(&position(&[(0, 0), (1, 1)])).len(),
+            7,
+            "every move holds the draw"
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let path = std::env::temp_dir().join(format!("tictactoe-drill-{}", std::process::id()));
+        let mut profile = Profile::load(&path).unwrap();
+        let moves = POSITIONS[4];
+        let mut progress = Progress::load(&profile, moves);
+        assert_eq!(progress, Progress::default());
+        progress.record(true);
+        progress.record(true);
+        progress.record(false);
+        progress.record(true);
+        progress.store(&mut profile, moves);
+        assert_eq!(profile.get("drill.2:2,2:1"), Some("1 3 4"));
+        assert_eq!(Progress::load(&profile, moves).success_rate(), Some(75.0));
+        profile.set("drill.2:2,2:1", "garbage");
+        assert_eq!(Progress::load(&profile, moves), Progress::default());
+    }
+
+    #[test]
+    fn weak_positions_come_up_more_often() {
+        let mut progress = [Progress::default(); 2];
+        progress[1].level = MAX_LEVEL;
+        let mut rng = Rng::new(1);
+        let weak = (0..1000).filter(|_| pick(&progress, &mut rng) == 0).count();
+        assert!(weak > 900, "{}", weak);
+    }
+}
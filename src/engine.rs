@@ -0,0 +1,1233 @@
+//! The engines choosing the computer's moves.
+//!
+//! An engine implements [`Engine`] and is passed to [`Board::computer_move_with`]. The
+//! engines shipped with the game, and programs speaking the protocol of [`external`], are
+//! selected by an [`EngineKind`].
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Cell, Coord, EvalWeights, GameOver};
+use crate::book;
+use crate::experience::Experience;
+use crate::external::External;
+use crate::minimax::{self, SearchStats, TranspositionTable};
+use crate::rng::Rng;
+use crate::solver;
+use crate::tablebase::{Tablebase, Value};
+use crate::threats;
+
+/// Chooses moves for one side
+pub trait Engine {
+    /// The move for `side` in the position on the board. It must be on a blank cell, and
+    /// the board has at least one.
+    ///
+    /// Engines which search should stop in time when they have a
+    /// [think time](EngineConfig::think_time).
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord;
+
+    /// The moves for `side` with the engine's score for each, best first, e.g. to show
+    /// alternatives or hints. Scores are only comparable within one call; how they relate
+    /// to the move from [`Engine::choose`] is up to the engine.
+    ///
+    /// Engines which don't rate moves return only the move they choose, scored 0.
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        vec![(self.choose(board, side), 0)]
+    }
+
+    /// The score of each position for the player to move, the score of the best of its
+    /// [moves](Engine::evaluate_moves), or `None` where the game is over. Meant for scoring
+    /// many positions at once, e.g. for statistics or datasets.
+    ///
+    /// By default the positions are evaluated one after the other. The heuristic and
+    /// minimax engines evaluate them on as many threads as there are CPU cores, each with
+    /// a fresh engine of the same settings.
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        boards.iter().map(|board| best_score(self, board)).collect()
+    }
+
+    /// What the engine searched to find the move it chose last, `None` if it didn't search
+    /// for it, e.g. because it doesn't search at all
+    fn last_search(&self) -> Option<SearchStats> {
+        None
+    }
+
+    /// Start thinking about the position in the background while `side`, the opponent,
+    /// considers their move. The next move the engine chooses or evaluates stops it.
+    ///
+    /// Engines which don't search ahead ignore it.
+    fn ponder(&mut self, _board: &Board, _side: Cell) {}
+
+    /// The value of the position for `side`, who is to move, if the engine can prove it,
+    /// e.g. to resign a lost game or offer a draw.
+    ///
+    /// By default positions close enough to the end for the [`solver`] are proven.
+    fn proven_value(&mut self, board: &Board, side: Cell) -> Option<Value> {
+        let winner = match solver::forced_outcome(board, side)? {
+            GameOver::Tie => return Some(Value::Draw),
+            GameOver::ComputerWon => board.human_uses().opponent(),
+            GameOver::HumanWon | GameOver::Resigned => board.human_uses(),
+        };
+        Some(if winner == side {
+            Value::Win
+        } else {
+            Value::Loss
+        })
+    }
+
+    /// Whether `side`, who is to move after the opponent's first move on the board, should
+    /// swap sides under the [pie rule](Board::set_pie_rule)
+    ///
+    /// By default it swaps when the first move is one of the best the engine would have
+    /// chosen itself.
+    fn swap(&mut self, board: &Board, side: Cell) -> bool {
+        let Some(&first) = board.history().first() else {
+            return false;
+        };
+        let mut before = board.clone();
+        before.undo();
+        let ranked = self.evaluate_moves(&before, side.opponent());
+        let best = ranked.first().map(|&(_, score)| score);
+        ranked
+            .iter()
+            .any(|&(mv, score)| mv == first && Some(score) == best)
+    }
+
+    /// The first move for `side` on the empty board under the [pie rule](Board::set_pie_rule),
+    /// which the opponent may take over
+    ///
+    /// By default it is the best of the moves not rated as high as the best ones, so that
+    /// the [default](Engine::swap) opponent doesn't swap, or the best move if all are rated
+    /// the same.
+    fn pie_opening(&mut self, board: &Board, side: Cell) -> Coord {
+        let ranked = self.evaluate_moves(board, side);
+        let best = ranked[0].1;
+        ranked
+            .iter()
+            .find(|&&(_, score)| score < best)
+            .unwrap_or(&ranked[0])
+            .0
+    }
+
+    /// Why the engine couldn't play as asked since the last call, e.g. an [`External`]
+    /// program which failed and was replaced, for the caller to report. The library doesn't
+    /// print it.
+    ///
+    /// Engines which can't fail return `None`.
+    fn take_error(&mut self) -> Option<io::Error> {
+        None
+    }
+
+    /// Store what the engine has learned where a new engine of its kind finds it, e.g. at
+    /// the end of a game.
+    ///
+    /// Engines which don't learn do nothing.
+    fn persist(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The engines shipped with the game and external ones
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum EngineKind {
+    /// See [`Heuristic`]
+    #[default]
+    Heuristic,
+    /// See [`Minimax`]
+    Minimax,
+    /// The program at the path, see [`External`]
+    External(PathBuf),
+}
+
+impl EngineKind {
+    /// A new engine of this kind with the settings of the config. External engines play
+    /// their own openings and choose their moves themselves.
+    pub fn build(&self, config: &EngineConfig) -> Box<dyn Engine> {
+        let rng = config.seed.map(Rng::new);
+        let engine: Box<dyn Engine> = match self {
+            EngineKind::Heuristic => Box::new(Heuristic {
+                rng: rng.clone(),
+                personality: config.personality,
+                eval_weights: config.eval_weights,
+                think_time: config.think_time,
+            }),
+            EngineKind::Minimax => {
+                let mut minimax = Minimax::default();
+                minimax.rng = rng.clone();
+                minimax.think_time = config.think_time;
+                minimax.contempt = config.contempt;
+                if config.cache {
+                    minimax.cache = Some(TranspositionTable::default_path);
+                }
+                if config.tablebases {
+                    minimax.tablebase_path = Some(Tablebase::default_path);
+                }
+                Box::new(minimax)
+            }
+            EngineKind::External(path) => return Box::new(External::new(path.clone())),
+        };
+        if config.book {
+            Box::new(Book::new(engine, rng))
+        } else {
+            engine
+        }
+    }
+}
+
+/// The settings an engine is [built](EngineKind::build) with
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct EngineConfig {
+    /// Whether the engine plays the moves of the opening [`book`] while the position is in
+    /// it. On by default.
+    pub book: bool,
+    /// Seed of the random choice among equally good moves. Without a seed the engine always
+    /// takes the first one.
+    pub seed: Option<u64>,
+    /// How the heuristic engine rates the cells
+    pub personality: Personality,
+    /// The weights of the heuristic engine's [cell scores](Board::scores_with)
+    pub eval_weights: EvalWeights,
+    /// How much worse than a balanced position the minimax engine rates a draw for itself,
+    /// see [`minimax`]. Negative values make it seek draws.
+    pub contempt: i64,
+    /// The time the engine may spend on a move, `None` for no limit. The heuristic engine
+    /// decides in a single pass and always finishes well within any limit; searching
+    /// engines stop when the time is up and play the best move found so far.
+    pub think_time: Option<Duration>,
+    /// Whether the minimax engine starts from the transposition table kept from earlier
+    /// games, see [`Engine::persist`]
+    pub cache: bool,
+    /// Whether the minimax engine looks moves up in the tablebases generated into their
+    /// [default path](Tablebase::default_path)
+    pub tablebases: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> EngineConfig {
+        EngineConfig {
+            book: true,
+            seed: None,
+            personality: Personality::Balanced,
+            eval_weights: EvalWeights::default(),
+            contempt: 0,
+            think_time: None,
+            cache: false,
+            tablebases: false,
+        }
+    }
+}
+
+// The score of the best move for the player to move, `None` if the game is over
+fn best_score(engine: &mut (impl Engine + ?Sized), board: &Board) -> Option<i64> {
+    if board.winner().is_some() || board.legal_moves().next().is_none() {
+        return None;
+    }
+    let moves = engine.evaluate_moves(board, board.to_move());
+    moves.first().map(|&(_, score)| score)
+}
+
+// Evaluates the positions in order on as many threads as there are CPU cores, each with an
+// engine of its own
+fn evaluate_parallel<E: Engine>(
+    boards: &[Board],
+    engine: impl Fn() -> E + Sync,
+) -> Vec<Option<i64>> {
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    let chunk = boards.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = boards
+            .chunks(chunk)
+            .map(|chunk| {
+                let engine = &engine;
+                scope.spawn(move || {
+                    let mut engine = engine();
+                    chunk
+                        .iter()
+                        .map(|board| best_score(&mut engine, board))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("the evaluation doesn't panic"))
+            .collect()
+    })
+}
+
+// One of the moves, chosen randomly if there is a generator, otherwise the first
+fn pick(moves: &[Coord], rng: &mut Option<Rng>) -> Coord {
+    match rng {
+        Some(rng) => moves[rng.below(moves.len())],
+        None => moves[0],
+    }
+}
+
+/// How the heuristic engine weighs the cells once there is no line to win or block
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum Personality {
+    /// Rates cells by the lines it can still complete itself, see [`Board::scores`]
+    #[default]
+    Balanced,
+    /// Mostly builds its own lines, spoiling the opponent's only on the side
+    Aggressive,
+    /// Mostly spoils the opponent's lines
+    Defensive,
+    /// Prefers the cells near the center
+    Central,
+    /// Adds a random amount to every rating, so its moves are hard to predict
+    Erratic,
+}
+
+impl Personality {
+    /// The rating of every cell for the given player, row by row, from the cell scores of
+    /// the weights; 0 for occupied cells. The erratic personality draws from the generator.
+    pub fn ratings(
+        self,
+        board: &Board,
+        side: Cell,
+        weights: EvalWeights,
+        rng: &mut Rng,
+    ) -> Vec<i64> {
+        // weights of the own lines, the opponent's lines, the closeness to the center and
+        // the random amount
+        let (own, other, center, noise) = match self {
+            Personality::Balanced => (1, 0, 0, 0),
+            Personality::Aggressive => (3, 1, 0, 0),
+            Personality::Defensive => (1, 3, 0, 0),
+            Personality::Central => (1, 1, 2, 0),
+            Personality::Erratic => (1, 1, 0, 3),
+        };
+        let (width, height) = (board.width(), board.height());
+        let own_scores = board.scores_with(side, weights);
+        let other_scores = board.scores_with(side.opponent(), weights);
+        (0..width * height)
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                if !board.is_playable((x, y)) {
+                    return 0;
+                }
+                // in half cells, so that both middle cells of an even board are central
+                let distance = (2 * x)
+                    .abs_diff(width - 1)
+                    .max((2 * y).abs_diff(height - 1));
+                let mut rating = own * own_scores[idx] as i64
+                    + other * other_scores[idx] as i64
+                    + center * (width.max(height) - distance / 2) as i64;
+                if noise > 0 {
+                    rating += noise * rng.below(width.max(height)) as i64;
+                }
+                rating
+            })
+            .collect()
+    }
+}
+
+/// Wins or blocks a line if it can, otherwise rates every cell by the lines it helps to
+/// complete and decides in a single pass. Where completing a line doesn't win, e.g. by the
+/// [misère](crate::rules::Misere) rules, it searches like [`Minimax`] instead.
+#[derive(Debug, Clone, Default)]
+pub struct Heuristic {
+    /// Chooses among the [best moves](Board::best_moves) if set, otherwise the first of
+    /// the highest rated cells is played
+    pub rng: Option<Rng>,
+    /// How the cells are rated
+    pub personality: Personality,
+    /// The weights of the [cell scores](Board::scores_with)
+    pub eval_weights: EvalWeights,
+    /// The time the search may take where completing a line doesn't win
+    pub think_time: Option<Duration>,
+}
+
+impl Heuristic {
+    // The ratings of the personality, drawing from a generator seeded by the position when
+    // there is none
+    fn ratings(&mut self, board: &Board, side: Cell) -> Vec<i64> {
+        let mut fallback = Rng::new(board.hash());
+        let rng = self.rng.as_mut().unwrap_or(&mut fallback);
+        self.personality
+            .ratings(board, side, self.eval_weights, rng)
+    }
+}
+
+impl Engine for Heuristic {
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        if self.personality != Personality::Balanced || !board.rules().lines_win() {
+            let ranked = self.evaluate_moves(board, side);
+            let best: Vec<_> = ranked
+                .iter()
+                .filter(|&&(_, score)| score == ranked[0].1)
+                .map(|&(mv, _)| mv)
+                .collect();
+            return pick(&best, &mut self.rng);
+        }
+        match self.rng {
+            Some(_) => pick(
+                &board.best_moves_with(side, self.eval_weights),
+                &mut self.rng,
+            ),
+            None => board.best_move(side, self.eval_weights),
+        }
+    }
+
+    /// The ratings of the personality, with winning moves and then blocking moves above
+    /// all others
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        if !board.rules().lines_win() {
+            let deadline = self.think_time.map(|limit| Instant::now() + limit);
+            return minimax::evaluate_moves(
+                board,
+                side,
+                0,
+                deadline,
+                &mut TranspositionTable::default(),
+            );
+        }
+        let scores = self.ratings(board, side);
+        let max = scores.iter().copied().max().unwrap_or(0);
+        let wins = board.winning_moves(side);
+        let blocks = board.winning_moves(side.opponent());
+        let mut moves: Vec<_> = board
+            .legal_moves()
+            .map(|mv| {
+                let score = if wins.contains(&mv) {
+                    max + 2
+                } else if blocks.contains(&mv) {
+                    max + 1
+                } else {
+                    scores[mv.col + mv.row * board.width()]
+                };
+                (mv, score)
+            })
+            .collect();
+        moves.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        moves
+    }
+
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        evaluate_parallel(boards, || self.clone())
+    }
+}
+
+/// Searches the moves of both players ahead, see [`minimax`]. The positions searched are
+/// kept for the following moves.
+///
+/// With tablebases, when a [`Tablebase`] for the board size has been generated into its
+/// default path, the moves are looked up in it instead. On boards too large to search to the end, a forced
+/// win by a sequence of [threats] is played if there is one.
+///
+/// While pondering, the positions are searched in a thread of their own, which has the
+/// transposition table until the engine moves again.
+///
+/// With a cache, the table of the board size is loaded on the first move and
+/// [persisted](Engine::persist) for the next engine.
+#[derive(Debug, Default)]
+pub struct Minimax {
+    /// Chooses among equally good moves if set
+    pub rng: Option<Rng>,
+    /// The time the search may take, `None` for no limit
+    pub think_time: Option<Duration>,
+    /// How much worse than 0 a draw scores for the engine, see [`minimax`]
+    pub contempt: i64,
+    table: TranspositionTable,
+    // where the table is kept for each board size, and the size it was loaded for
+    cache: Option<fn(usize) -> Option<PathBuf>>,
+    cached_dim: Option<usize>,
+    // where the tablebase for each board size is looked for, none by default
+    tablebase_path: Option<fn(usize) -> Option<PathBuf>>,
+    // the tablebase for each board size played on, if there is one
+    tablebases: HashMap<usize, Option<Tablebase>>,
+    last_search: Option<SearchStats>,
+    pondering: Option<Pondering>,
+}
+
+// A search running in the background, which hands the table back when it's done
+#[derive(Debug)]
+struct Pondering {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<TranspositionTable>,
+}
+
+impl Minimax {
+    /// The engine with the tablebases generated into their
+    /// [default path](Tablebase::default_path), see [`EngineConfig::tablebases`]
+    pub fn with_tablebases(mut self) -> Minimax {
+        self.tablebase_path = Some(Tablebase::default_path);
+        self
+    }
+
+    // The tablebase for the board size, if there are tablebases and one has been generated,
+    // and the board is square and won by filling a whole line ending at the edges, by the
+    // standard rules
+    fn tablebase(&mut self, board: &Board) -> Option<&Tablebase> {
+        let dim = board.width();
+        let path = self.tablebase_path?;
+        if !board.is_square()
+            || board.win_length() != dim
+            || board.wraps()
+            || !board.has_standard_rules()
+        {
+            return None;
+        }
+        self.tablebases
+            .entry(dim)
+            .or_insert_with(|| Tablebase::load(path(dim)?).ok())
+            .as_ref()
+    }
+
+    // Loads the table kept for the board size, unless it's loaded already. A missing or
+    // broken file is the same as an empty table. Tables are only kept for square boards won
+    // by filling a whole line ending at the edges, played by the standard rules and without
+    // blocked cells.
+    fn load_table(&mut self, board: &Board) {
+        let Some(path) = self.cache else {
+            return;
+        };
+        let dim = Some(board.width()).filter(|&dim| {
+            board.is_square()
+                && board.win_length() == dim
+                && !board.wraps()
+                && board.has_standard_rules()
+                && !board.has_blocked()
+        });
+        if self.cached_dim != dim {
+            self.table = dim
+                .and_then(path)
+                .and_then(|path| TranspositionTable::load(path).ok())
+                .unwrap_or_default();
+            self.cached_dim = dim;
+        }
+    }
+
+    // Stops the search in the background and takes the table back
+    fn stop_pondering(&mut self) {
+        if let Some(pondering) = self.pondering.take() {
+            pondering.stop.store(true, Ordering::Relaxed);
+            self.table = pondering.thread.join().unwrap_or_default();
+        }
+    }
+}
+
+impl Drop for Minimax {
+    fn drop(&mut self) {
+        self.stop_pondering();
+    }
+}
+
+impl Engine for Minimax {
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        self.stop_pondering();
+        self.load_table(board);
+        if let Some(tablebase) = self.tablebase(board) {
+            let moves = tablebase.best_moves(board, side);
+            if !moves.is_empty() {
+                self.last_search = None;
+                return pick(&moves, &mut self.rng);
+            }
+        }
+        if board.rules().lines_win() && board.legal_moves().count() > minimax::FULL_SEARCH {
+            if let Some(line) = threats::forced_win(board, side, threats::MAX_THREATS) {
+                self.last_search = None;
+                return line[0];
+            }
+        }
+        let deadline = self.think_time.map(|limit| Instant::now() + limit);
+        let (mv, stats) = minimax::best_move_with_stats(
+            board,
+            side,
+            self.contempt,
+            deadline,
+            &mut self.table,
+            self.rng.as_mut(),
+        );
+        self.last_search = Some(stats);
+        mv
+    }
+
+    /// The search scores, see [`minimax::evaluate_moves`]
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        self.stop_pondering();
+        self.load_table(board);
+        let deadline = self.think_time.map(|limit| Instant::now() + limit);
+        minimax::evaluate_moves(board, side, self.contempt, deadline, &mut self.table)
+    }
+
+    /// The scores of engines without the positions searched before, which stay with the
+    /// thread that searched them
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        self.stop_pondering();
+        let (think_time, contempt) = (self.think_time, self.contempt);
+        evaluate_parallel(boards, || {
+            let mut minimax = Minimax::default();
+            minimax.think_time = think_time;
+            minimax.contempt = contempt;
+            minimax
+        })
+    }
+
+    fn last_search(&self) -> Option<SearchStats> {
+        self.last_search.clone()
+    }
+
+    fn ponder(&mut self, board: &Board, side: Cell) {
+        self.stop_pondering();
+        // nothing to search for if the moves are looked up or the game is over
+        if self.tablebase(board).is_some() || board.result().is_some() {
+            return;
+        }
+        self.load_table(board);
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut table = std::mem::take(&mut self.table);
+        let board = board.clone();
+        let contempt = self.contempt;
+        let thread = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                minimax::ponder(&board, side, contempt, &mut table, &stop);
+                table
+            }
+        });
+        self.pondering = Some(Pondering { stop, thread });
+    }
+
+    /// Looked up in the tablebase, otherwise searched: a win or loss found by the search
+    /// is proven, a draw once the search reached the end of the game
+    fn proven_value(&mut self, board: &Board, side: Cell) -> Option<Value> {
+        self.stop_pondering();
+        self.load_table(board);
+        if let Some(value) = self
+            .tablebase(board)
+            .and_then(|tablebase| tablebase.value(board))
+        {
+            return Some(value);
+        }
+        let deadline = self.think_time.map(|limit| Instant::now() + limit);
+        let (_, stats) = minimax::best_move_with_stats(
+            board,
+            side,
+            self.contempt,
+            deadline,
+            &mut self.table,
+            None,
+        );
+        if stats.score > minimax::WIN_SCORE {
+            Some(Value::Win)
+        } else if stats.score < -minimax::WIN_SCORE {
+            Some(Value::Loss)
+        } else if stats.depth >= board.legal_moves().count() {
+            Some(Value::Draw)
+        } else {
+            None
+        }
+    }
+
+    /// Saves the transposition table of the board size played last if there is a cache
+    fn persist(&mut self) -> io::Result<()> {
+        self.stop_pondering();
+        let (Some(path), Some(dim)) = (self.cache, self.cached_dim) else {
+            return Ok(());
+        };
+        match path(dim) {
+            Some(path) => self.table.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rates above this make [`Blunder`] also miss wins in one move
+pub const MISS_WINS_RATE: f64 = 0.9;
+
+/// Plays a worse move than the wrapped engine now and then on purpose, e.g. for playing
+/// with children. A move which wins at once is still played unless the rate is above
+/// [`MISS_WINS_RATE`].
+pub struct Blunder {
+    pub engine: Box<dyn Engine>,
+    /// Probability of a worse move, from 0 to 1
+    pub rate: f64,
+    pub rng: Rng,
+}
+
+impl Engine for Blunder {
+    /// A random one of the moves the wrapped engine rates below its best, with the
+    /// probability of the rate, otherwise the move of the wrapped engine
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        let wins = !board.winning_moves(side).is_empty();
+        if !self.rng.chance(self.rate) || (wins && self.rate <= MISS_WINS_RATE) {
+            return self.engine.choose(board, side);
+        }
+        let moves = self.engine.evaluate_moves(board, side);
+        let best = moves.first().map_or(0, |&(_, score)| score);
+        let worse: Vec<_> = moves
+            .into_iter()
+            .filter(|&(_, score)| score < best)
+            .map(|(mv, _)| mv)
+            .collect();
+        match worse.is_empty() {
+            true => self.engine.choose(board, side),
+            false => worse[self.rng.below(worse.len())],
+        }
+    }
+
+    /// The scores of the wrapped engine
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        self.engine.evaluate_moves(board, side)
+    }
+
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        self.engine.evaluate_batch(boards)
+    }
+
+    fn last_search(&self) -> Option<SearchStats> {
+        self.engine.last_search()
+    }
+
+    fn ponder(&mut self, board: &Board, side: Cell) {
+        self.engine.ponder(board, side);
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.engine.take_error()
+    }
+
+    fn persist(&mut self) -> io::Result<()> {
+        self.engine.persist()
+    }
+
+    fn proven_value(&mut self, board: &Board, side: Cell) -> Option<Value> {
+        self.engine.proven_value(board, side)
+    }
+}
+
+/// Plays the moves of the opening [`book`], and the moves of the wrapped engine once the
+/// position isn't in the book
+pub struct Book {
+    pub engine: Box<dyn Engine>,
+    /// Chooses among the book moves of a symmetric position if set
+    pub rng: Option<Rng>,
+    // whether the last move chosen came from the book
+    from_book: bool,
+}
+
+impl Book {
+    /// Play the moves of the book, otherwise those of the given engine
+    pub fn new(engine: Box<dyn Engine>, rng: Option<Rng>) -> Book {
+        Book {
+            engine,
+            rng,
+            from_book: false,
+        }
+    }
+}
+
+impl Engine for Book {
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        let moves = book::moves(board, side);
+        self.from_book = !moves.is_empty();
+        if moves.is_empty() {
+            return self.engine.choose(board, side);
+        }
+        pick(&moves, &mut self.rng)
+    }
+
+    /// The scores of the wrapped engine
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        self.engine.evaluate_moves(board, side)
+    }
+
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        self.engine.evaluate_batch(boards)
+    }
+
+    /// The search of the wrapped engine, unless the move came from the book
+    fn last_search(&self) -> Option<SearchStats> {
+        match self.from_book {
+            true => None,
+            false => self.engine.last_search(),
+        }
+    }
+
+    fn ponder(&mut self, board: &Board, side: Cell) {
+        self.engine.ponder(board, side);
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.engine.take_error()
+    }
+
+    fn persist(&mut self) -> io::Result<()> {
+        self.engine.persist()
+    }
+
+    fn proven_value(&mut self, board: &Board, side: Cell) -> Option<Value> {
+        self.engine.proven_value(board, side)
+    }
+}
+
+/// Of the moves the wrapped engine scores as high as the one it chose, plays the one which
+/// did best in past games, see [`Experience`]
+pub struct Learned {
+    pub engine: Box<dyn Engine>,
+    pub experience: Experience,
+}
+
+impl Learned {
+    // How well the move did for `side` in past games
+    fn success(&self, board: &Board, side: Cell, mv: Coord) -> f64 {
+        let mut after = board.clone();
+        after.set_cell(mv, side).unwrap();
+        self.experience.outcomes(&after).success()
+    }
+}
+
+impl Engine for Learned {
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        let chosen = self.engine.choose(board, side);
+        if self.experience.is_empty() {
+            return chosen;
+        }
+        let moves = self.engine.evaluate_moves(board, side);
+        let Some(&(_, score)) = moves.iter().find(|&&(mv, _)| mv == chosen) else {
+            return chosen;
+        };
+        // the chosen move wins ties, so without experience nothing changes
+        moves
+            .into_iter()
+            .filter(|&(mv, other)| other == score && mv != chosen)
+            .map(|(mv, _)| mv)
+            .fold(chosen, |best, mv| {
+                if self.success(board, side, mv) > self.success(board, side, best) {
+                    mv
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// The scores of the wrapped engine, moves with equal scores ordered by how well they
+    /// did in past games
+    fn evaluate_moves(&mut self, board: &Board, side: Cell) -> Vec<(Coord, i64)> {
+        let mut moves: Vec<_> = self
+            .engine
+            .evaluate_moves(board, side)
+            .into_iter()
+            .map(|(mv, score)| (mv, score, self.success(board, side, mv)))
+            .collect();
+        moves.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+        moves
+            .into_iter()
+            .map(|(mv, score, _)| (mv, score))
+            .collect()
+    }
+
+    /// The scores of the wrapped engine, which experience doesn't change
+    fn evaluate_batch(&mut self, boards: &[Board]) -> Vec<Option<i64>> {
+        self.engine.evaluate_batch(boards)
+    }
+
+    fn last_search(&self) -> Option<SearchStats> {
+        self.engine.last_search()
+    }
+
+    fn ponder(&mut self, board: &Board, side: Cell) {
+        self.engine.ponder(board, side);
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.engine.take_error()
+    }
+
+    fn persist(&mut self) -> io::Result<()> {
+        self.engine.persist()
+    }
+
+    fn proven_value(&mut self, board: &Board, side: Cell) -> Option<Value> {
+        self.engine.proven_value(board, side)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the first blank cell
+    struct FirstBlank;
+
+    impl Engine for FirstBlank {
+        fn choose(&mut self, board: &Board, _side: Cell) -> Coord {
+            board.legal_moves().next().unwrap()
+        }
+    }
+
+    // The moves of a game of the engine against itself
+    fn game(kind: &EngineKind, seed: Option<u64>) -> Vec<Coord> {
+        let mut engine = kind.build(&EngineConfig {
+            seed,
+            ..EngineConfig::default()
+        });
+        let mut board = Board::build(3, Cell::X).unwrap();
+        let mut side = Cell::O;
+        while board.result().is_none() {
+            let mv = engine.choose(&board, side);
+            board.set_cell(mv, side).unwrap();
+            side = side.opponent();
+        }
+        board.history().to_vec()
+    }
+
+    #[test]
+    fn seeded_games_differ_and_repeat() {
+        for kind in [EngineKind::Heuristic, EngineKind::Minimax] {
+            assert_eq!(game(&kind, None), game(&kind, None));
+            assert_eq!(game(&kind, Some(7)), game(&kind, Some(7)));
+            let games: Vec<_> = (0..5).map(|seed| game(&kind, Some(seed))).collect();
+            assert!(games.iter().any(|g| *g != games[0]), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn ranked_moves() {
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        for kind in [EngineKind::Heuristic, EngineKind::Minimax] {
+            let mut engine = kind.build(&EngineConfig {
+                book: false,
+                ..EngineConfig::default()
+            });
+            let ranked = engine.evaluate_moves(&board, Cell::X);
+            assert_eq!(ranked.len(), 5);
+            // the win, then the block
+            assert_eq!(ranked[0].0, (2, 0));
+            assert_eq!(ranked[1].0, (2, 1));
+            assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+            assert_eq!(ranked[0].0, engine.choose(&board, Cell::X));
+        }
+        assert_eq!(
+            FirstBlank.evaluate_moves(&board, Cell::X),
+            [((2, 0).into(), 0)]
+        );
+    }
+
+    #[test]
+    fn handicap() {
+        let mut board = Board::build(3, Cell::X)
+            .unwrap()
+            .with_handicap(Cell::O, 2)
+            .unwrap();
+        // X moves first and must block the diagonal
+        for kind in [EngineKind::Heuristic, EngineKind::Minimax] {
+            let mut engine = kind.build(&EngineConfig::default());
+            assert_eq!(engine.choose(&board, board.to_move()), (2, 2), "{:?}", kind);
+        }
+        board.set_cell((2, 2), Cell::X).unwrap();
+        // O is two pieces ahead and wins
+        let mut engine = Minimax::default();
+        assert_eq!(engine.proven_value(&board, Cell::O), Some(Value::Win));
+    }
+
+    #[test]
+    fn pie_rule() {
+        let mut engine = Heuristic::default();
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.set_pie_rule(true);
+        // the opening leaves no reason to swap
+        let opening = engine.pie_opening(&board, Cell::O);
+        assert_ne!(opening, (1, 1));
+        board.set_cell(opening, Cell::O).unwrap();
+        assert!(!engine.swap(&board, Cell::X));
+        // the best opening is worth taking over
+        board.undo();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        assert!(engine.swap(&board, Cell::X));
+    }
+
+    #[test]
+    fn pondering() {
+        let mut engine = Minimax::default();
+        let mut board = Board::build(5, Cell::X).unwrap();
+        board.set_cell((2, 2), Cell::O).unwrap();
+        engine.ponder(&board, Cell::X);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        board.set_cell((1, 1), Cell::X).unwrap();
+        let mv = engine.choose(&board, Cell::O);
+        assert_eq!(board.get_cell(mv), Cell::Blank);
+        assert!(engine.pondering.is_none());
+        assert!(!engine.table.is_empty());
+        // dropped while pondering
+        engine.ponder(&board, Cell::X);
+    }
+
+    #[test]
+    fn proven_values() {
+        let mut heuristic = Heuristic::default();
+        let mut minimax = Minimax::default();
+        let mut board = Board::build(3, Cell::X).unwrap();
+        assert_eq!(heuristic.proven_value(&board, Cell::X), Some(Value::Draw));
+        assert_eq!(minimax.proven_value(&board, Cell::X), Some(Value::Draw));
+        // answering the center with an edge loses
+        board.set_cell((1, 1), Cell::X).unwrap();
+        board.set_cell((1, 0), Cell::O).unwrap();
+        assert_eq!(heuristic.proven_value(&board, Cell::X), Some(Value::Win));
+        assert_eq!(minimax.proven_value(&board, Cell::X), Some(Value::Win));
+        let mv = solver::optimal_moves(&board, Cell::X).unwrap()[0];
+        board.set_cell(mv, Cell::X).unwrap();
+        assert_eq!(heuristic.proven_value(&board, Cell::O), Some(Value::Loss));
+        assert_eq!(minimax.proven_value(&board, Cell::O), Some(Value::Loss));
+        // too far from the end for the solver
+        let board = Board::build(5, Cell::X).unwrap();
+        assert_eq!(heuristic.proven_value(&board, Cell::X), None);
+    }
+
+    #[test]
+    fn persisted_table() {
+        let path = |dim| {
+            let name = format!("tictactoe-engine-test-{}-{}", std::process::id(), dim);
+            Some(std::env::temp_dir().join(name))
+        };
+        let cached = || {
+            let mut engine = Minimax::default();
+            engine.cache = Some(path);
+            engine
+        };
+        // no book and no tablebase for the 5x5 board
+        let board = Board::from_string("XOX--/OXO--/-OX--/X----/-O---", 5, Cell::X).unwrap();
+        let mut engine = cached();
+        let mv = engine.choose(&board, Cell::X);
+        let searched = engine.last_search().unwrap();
+        engine.persist().unwrap();
+        let mut engine = cached();
+        assert_eq!(engine.choose(&board, Cell::X), mv);
+        assert!(engine.last_search().unwrap().nodes < searched.nodes);
+        std::fs::remove_file(path(5).unwrap()).unwrap();
+        // nothing to keep without a cache
+        let mut engine = Minimax::default();
+        engine.choose(&board, Cell::X);
+        engine.persist().unwrap();
+    }
+
+    #[test]
+    fn injected_tablebases() {
+        let path = |dim| {
+            let name = format!("tictactoe-tablebase-test-{}-{}", std::process::id(), dim);
+            Some(std::env::temp_dir().join(name))
+        };
+        Tablebase::generate(3)
+            .unwrap()
+            .save(path(3).unwrap())
+            .unwrap();
+        let board = Board::build(3, Cell::X).unwrap();
+        // none by default, so tests don't see the tablebases installed
+        let mut engine = Minimax::default();
+        assert!(engine.tablebase(&board).is_none());
+        engine.tablebase_path = Some(path);
+        assert_eq!(
+            engine.tablebase(&board).map(Tablebase::positions),
+            Some(5478)
+        );
+        // looked up instead of searched
+        engine.choose(&board, Cell::X);
+        assert_eq!(engine.last_search(), None);
+        std::fs::remove_file(path(3).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn blunders() {
+        let blunder = |rate| Blunder {
+            engine: EngineKind::Heuristic.build(&EngineConfig {
+                book: false,
+                ..EngineConfig::default()
+            }),
+            rate,
+            rng: Rng::new(3),
+        };
+        // X takes the center, then answers O's corner with the opposite corner
+        let board = Board::from_string("---/-X-/O--", 3, Cell::X).unwrap();
+        let best = EngineKind::Heuristic
+            .build(&EngineConfig {
+                book: false,
+                ..EngineConfig::default()
+            })
+            .choose(&board, Cell::X);
+        let mut never = blunder(0.0);
+        assert!((0..20).all(|_| never.choose(&board, Cell::X) == best));
+        let mut always = blunder(1.0);
+        let moves: Vec<_> = (0..20).map(|_| always.choose(&board, Cell::X)).collect();
+        assert!(moves.iter().all(|&mv| mv != best));
+        assert!(moves.iter().any(|&mv| mv != moves[0]));
+        // a win in one is only missed at the highest rates
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        let mut often = blunder(MISS_WINS_RATE);
+        assert!((0..20).all(|_| often.choose(&board, Cell::X) == (2, 0)));
+        assert!((0..20).any(|_| always.choose(&board, Cell::X) != (2, 0)));
+    }
+
+    #[test]
+    fn personalities() {
+        let all = [
+            Personality::Balanced,
+            Personality::Aggressive,
+            Personality::Defensive,
+            Personality::Central,
+            Personality::Erratic,
+        ];
+        let heuristic = |personality, seed: Option<u64>| Heuristic {
+            rng: seed.map(Rng::new),
+            personality,
+            ..Heuristic::default()
+        };
+        // wins and blocks come first for every personality
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        assert!(all
+            .iter()
+            .all(|&p| heuristic(p, Some(0)).choose(&board, Cell::X) == (2, 0)));
+        let board = Board::from_string("XX-/O--/---", 3, Cell::X).unwrap();
+        assert!(all
+            .iter()
+            .all(|&p| heuristic(p, Some(0)).choose(&board, Cell::O) == (2, 0)));
+        // X extends its lines, or stands in O's way
+        let board = Board::from_string("XX---/-----/-----/O----/O----", 5, Cell::X).unwrap();
+        assert_eq!(
+            heuristic(Personality::Aggressive, None).choose(&board, Cell::X),
+            (1, 1)
+        );
+        assert_eq!(
+            heuristic(Personality::Defensive, None).choose(&board, Cell::X),
+            (2, 2)
+        );
+        let empty = Board::build(5, Cell::X).unwrap();
+        let moves: Vec<_> = (0..5)
+            .map(|seed| heuristic(Personality::Erratic, Some(seed)).choose(&empty, Cell::X))
+            .collect();
+        assert!(moves.iter().any(|&mv| mv != moves[0]));
+    }
+
+    #[test]
+    fn search_stats() {
+        let mut engine = EngineKind::Minimax.build(&EngineConfig::default());
+        engine.choose(&Board::build(3, Cell::X).unwrap(), Cell::X);
+        assert_eq!(engine.last_search(), None);
+        // no book and no tablebase for the 5x5 board
+        let mut board = Board::build(5, Cell::X).unwrap();
+        board.set_cell((2, 2), Cell::X).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        engine.choose(&board, Cell::X);
+        let stats = engine.last_search().unwrap();
+        assert_eq!(stats.depth, minimax::depth(23));
+        assert!(stats.nodes > stats.cutoffs && stats.cutoffs > 0);
+        assert_eq!(
+            EngineKind::Heuristic
+                .build(&EngineConfig {
+                    book: false,
+                    ..EngineConfig::default()
+                })
+                .last_search(),
+            None
+        );
+    }
+
+    #[test]
+    fn built_with_the_config() {
+        // the contempt reaches the search: the draws score it below 0
+        let board = Board::from_moves(&[(0, 0)], Cell::X);
+        let mut engine = EngineKind::Minimax.build(&EngineConfig {
+            book: false,
+            contempt: 5,
+            ..EngineConfig::default()
+        });
+        assert_eq!(
+            engine.evaluate_moves(&board, Cell::O)[0],
+            ((1, 1).into(), -5)
+        );
+        // the weights reach the heuristic: with only blank cells scoring, all are equal
+        let mut engine = EngineKind::Heuristic.build(&EngineConfig {
+            book: false,
+            eval_weights: EvalWeights {
+                blank: 1,
+                line: 0,
+                progress: 0,
+            },
+            ..EngineConfig::default()
+        });
+        let board = Board::build(3, Cell::X).unwrap();
+        let moves = engine.evaluate_moves(&board, Cell::X);
+        assert!(moves.iter().all(|&(_, score)| score == 1));
+    }
+
+    #[test]
+    fn custom_engine() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((1, 1)).unwrap();
+        board.computer_move_with(&mut FirstBlank);
+        assert_eq!(board.get_cell((0, 0)), Cell::O);
+        board.human_move((2, 2)).unwrap();
+        board.computer_move_with(
+            EngineKind::Heuristic
+                .build(&EngineConfig::default())
+                .as_mut(),
+        );
+        assert_eq!(board.history().len(), 4);
+    }
+
+    #[test]
+    fn learned() {
+        // every first move draws, but X lost a game after the one it would choose
+        let board = Board::build(3, Cell::X).unwrap();
+        let first = Minimax::default().choose(&board, Cell::X);
+        let mut lost = board.clone();
+        lost.set_cell(first, Cell::X).unwrap();
+        let mut experience = Experience::default();
+        experience.record(&lost, Some(Cell::O));
+        let mut engine = Learned {
+            engine: Box::new(Minimax::default()),
+            experience,
+        };
+        let answer = engine.choose(&board, Cell::X);
+        assert_ne!(answer, first);
+        assert_eq!(engine.evaluate_moves(&board, Cell::X)[0].0, answer);
+        // only moves as good as the chosen one are considered
+        let mut won = Board::build(3, Cell::X).unwrap();
+        for (x, y, cell) in [(0, 0, Cell::X), (0, 1, Cell::O), (1, 0, Cell::X)] {
+            won.set_cell((x, y), cell).unwrap();
+        }
+        won.set_cell((1, 1), Cell::O).unwrap();
+        engine.experience.record(&won, Some(Cell::O));
+        won.undo();
+        assert_eq!(engine.choose(&won, Cell::O), (2, 0));
+        // without experience the wrapped engine's move
+        engine.experience = Experience::default();
+        assert_eq!(engine.choose(&board, Cell::X), first);
+    }
+
+    #[test]
+    fn batch_evaluation() {
+        let mut rng = Rng::new(5);
+        let boards: Vec<Board> = (0..40)
+            .map(|_| {
+                let plies = rng.below(10);
+                Board::random(3, Cell::X, plies, &mut rng).unwrap()
+            })
+            .collect();
+        let engines: [Box<dyn Fn() -> Box<dyn Engine>>; 3] = [
+            Box::new(|| Box::new(Heuristic::default())),
+            Box::new(|| Box::new(Minimax::default())),
+            Box::new(|| Box::new(FirstBlank)),
+        ];
+        for engine in engines {
+            let scores = engine().evaluate_batch(&boards);
+            // the same scores as one position after the other
+            let mut sequential = engine();
+            for (board, score) in boards.iter().zip(&scores) {
+                assert_eq!(*score, best_score(sequential.as_mut(), board), "{}", board);
+            }
+        }
+        let scores = Minimax::default().evaluate_batch(&boards);
+        assert!(scores.contains(&None));
+        assert!(scores
+            .iter()
+            .flatten()
+            .any(|&score| score > minimax::WIN_SCORE));
+    }
+}
@@ -0,0 +1,207 @@
+//! How the games went on from the positions reached before, kept across sessions, so the
+//! [`Learned`](crate::engine::Learned) engine can prefer the moves which did well.
+//!
+//! Every position counts the games it was reached in and the points the player who moved
+//! into it scored: 2 for a win and 1 for a draw. Rotated and mirrored positions share their
+//! counts.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::board::{Board, Cell};
+use crate::db;
+use crate::t;
+
+// Start of an experience file
+const MAGIC: &[u8] = b"TTTE";
+
+// Bytes of a stored position: hash, games and points
+const ENTRY_SIZE: usize = 16;
+
+/// The results of the games a position was reached in, for the player who moved into it
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Outcomes {
+    pub games: u32,
+    /// 2 for every win, 1 for every draw
+    pub points: u32,
+}
+
+impl Outcomes {
+    /// The share of the points scored between 0 and 1, counting one draw more than was
+    /// played so that positions seen rarely stay close to 1/2
+    pub fn success(&self) -> f64 {
+        (f64::from(self.points) + 1.0) / (2.0 * f64::from(self.games) + 2.0)
+    }
+}
+
+/// The outcomes of the positions of past games on boards of one size
+#[derive(Debug, Clone, Default)]
+pub struct Experience {
+    entries: HashMap<u64, Outcomes>,
+}
+
+impl Experience {
+    /// Where the experience for the board size is kept: `experience-<dim>` in the [data
+    /// directory](db::data_dir), or `experience-<width>x<height>` for boards which aren't
+    /// square
+    pub fn default_path(width: usize, height: usize) -> Option<PathBuf> {
+        let size = if width == height {
+            width.to_string()
+        } else {
+            format!("{}x{}", width, height)
+        };
+        Some(db::data_dir()?.join(format!("experience-{}", size)))
+    }
+
+    /// Read the experience written by [`Experience::save`]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Experience> {
+        let content = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, t!("experience-invalid"));
+        let entries = content.strip_prefix(MAGIC).ok_or_else(invalid)?;
+        if !entries.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(invalid());
+        }
+        let entries = entries
+            .chunks_exact(ENTRY_SIZE)
+            .map(|bytes| {
+                let (key, rest) = bytes.split_at(8);
+                let (games, points) = rest.split_at(4);
+                let outcomes = Outcomes {
+                    games: u32::from_le_bytes(games.try_into().unwrap()),
+                    points: u32::from_le_bytes(points.try_into().unwrap()),
+                };
+                (u64::from_le_bytes(key.try_into().unwrap()), outcomes)
+            })
+            .collect();
+        Ok(Experience { entries })
+    }
+
+    /// Write the experience to the file, creating its directory if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = MAGIC.to_vec();
+        content.reserve(self.entries.len() * ENTRY_SIZE);
+        for (key, outcomes) in &self.entries {
+            content.extend_from_slice(&key.to_le_bytes());
+            content.extend_from_slice(&outcomes.games.to_le_bytes());
+            content.extend_from_slice(&outcomes.points.to_le_bytes());
+        }
+        fs::write(path, content)
+    }
+
+    /// Number of positions known
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no position is known
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Count the finished game for every position in it, `winner` being `None` for a draw
+    pub fn record(&mut self, board: &Board, winner: Option<Cell>) {
+        let mut replay = board.clone();
+        while let Some(&last) = replay.history().last() {
+            let mover = replay.get_cell(last);
+            let outcomes = self.entries.entry(replay.canonical_hash()).or_default();
+            outcomes.games = outcomes.games.saturating_add(1);
+            outcomes.points = outcomes.points.saturating_add(match winner {
+                Some(winner) if winner == mover => 2,
+                Some(_) => 0,
+                None => 1,
+            });
+            replay.undo();
+        }
+    }
+
+    /// The outcomes of the position on the board for the player who moved into it
+    pub fn outcomes(&self, board: &Board) -> Outcomes {
+        self.entries
+            .get(&board.canonical_hash())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let mut experience = Experience::default();
+        // X wins in the top row
+        let won = Board::from_moves(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)], Cell::X);
+        experience.record(&won, won.winner());
+        experience.record(&won, won.winner());
+        let corner = Board::from_moves(&[(0, 0)], Cell::X);
+        assert_eq!(
+            experience.outcomes(&corner),
+            Outcomes {
+                games: 2,
+                points: 4
+            }
+        );
+        // the mirrored opening shares the counts
+        assert_eq!(
+            experience
+                .outcomes(&Board::from_moves(&[(2, 2)], Cell::X))
+                .games,
+            2
+        );
+        let answer = Board::from_moves(&[(0, 0), (0, 1)], Cell::X);
+        assert_eq!(experience.outcomes(&answer).points, 0);
+        assert!(experience.outcomes(&answer).success() < 0.5);
+        assert_eq!(
+            experience
+                .outcomes(&Board::from_moves(&[(1, 1)], Cell::X))
+                .success(),
+            0.5
+        );
+        // a resigned game is lost without its final position
+        experience.record(&answer, Some(Cell::O));
+        assert_eq!(
+            experience.outcomes(&answer),
+            Outcomes {
+                games: 3,
+                points: 2
+            }
+        );
+        assert_eq!(experience.len(), 5);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut experience = Experience::default();
+        let draw = Board::from_moves(
+            &[
+                (1, 1),
+                (0, 0),
+                (2, 2),
+                (2, 0),
+                (1, 0),
+                (1, 2),
+                (0, 1),
+                (2, 1),
+                (0, 2),
+            ],
+            Cell::X,
+        );
+        experience.record(&draw, None);
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-experience-{}", std::process::id()));
+        experience.save(&path).unwrap();
+        let loaded = Experience::load(&path).unwrap();
+        assert_eq!(loaded.len(), 9);
+        assert_eq!(loaded.outcomes(&draw), experience.outcomes(&draw));
+        fs::write(&path, b"TTTE123").unwrap();
+        assert!(Experience::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+//! Engines running as programs of their own, so bots can be written in any language with
+//! the game as referee and user interface.
+//!
+//! The program is started with the first move it has to choose and gets one request per
+//! line on its stdin: the position in the format of [`Board::to_notation`] and the side
+//! to move, e.g. `X--/-O-/--- O`. It answers each with a line holding its move, column and
+//! row counting from 1 like the user input, e.g. `3 1`. What it writes to stderr is passed
+//! through. When the engine is dropped, e.g. at the end of the game, the program is
+//! stopped.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::board::{Board, Cell, Coord};
+use crate::engine::{Engine, Heuristic};
+use crate::input;
+use crate::t;
+
+/// Asks a program for its moves. If the program can't be started, or answers with anything
+/// but a move on a blank cell the rules allow, the [`Heuristic`] engine plays the rest of
+/// the game and the error is kept for [`Engine::take_error`], so the caller can warn.
+pub struct External {
+    path: PathBuf,
+    process: Option<Process>,
+    failed: bool,
+    // why the program failed, until it is taken
+    error: Option<io::Error>,
+    fallback: Heuristic,
+}
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl External {
+    pub fn new(path: PathBuf) -> External {
+        External {
+            path,
+            process: None,
+            failed: false,
+            error: None,
+            fallback: Heuristic::default(),
+        }
+    }
+
+    /// Whether the program failed and the fallback plays instead
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    fn request(&mut self, board: &Board, side: Cell) -> io::Result<Coord> {
+        if self.process.is_none() {
+            let mut child = Command::new(&self.path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let stdin = child.stdin.take().expect("stdin is piped");
+            let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+            self.process = Some(Process {
+                child,
+                stdin,
+                stdout,
+            });
+        }
+        let process = self.process.as_mut().expect("the process was started");
+        writeln!(process.stdin, "{} {}", board.to_notation(), side)?;
+        process.stdin.flush()?;
+        let mut line = String::new();
+        if process.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                t!("external-no-answer"),
+            ));
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                t!("external-invalid-move", answer = line.trim()),
+            )
+        };
+        let mv = input::parse_move(&line, board.width(), board.height())
+            .map(Coord::from)
+            .map_err(|_| invalid())?;
+        if !board.is_playable(mv) {
+            return Err(invalid());
+        }
+        Ok(mv)
+    }
+}
+
+impl Engine for External {
+    /// The move the program answers with
+    fn choose(&mut self, board: &Board, side: Cell) -> Coord {
+        if !self.failed {
+            match self.request(board, side) {
+                Ok(mv) => return mv,
+                Err(e) => {
+                    let message = t!("external-failed", path = self.path.display(), error = e);
+                    self.error = Some(io::Error::new(e.kind(), message));
+                    self.failed = true;
+                    self.process = None;
+                }
+            }
+        }
+        self.fallback.choose(board, side)
+    }
+
+    /// Why the program failed, with its path, once after it did
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // the program waits for the next request
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    // A shell script at a fresh path in the temporary directory
+    fn script(name: &str, body: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-{}-{}.sh", name, std::process::id()));
+        fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn plays_the_programs_moves() {
+        // plays the first blank cell
+        let path = script(
+            "first-blank",
+            r#"while read position side; do
+  row=${position%%/*}
+  cells=$(echo "$position" | tr -d /)
+  before=${cells%%-*}
+  echo "$(( ${#before} % ${#row} + 1 )) $(( ${#before} / ${#row} + 1 ))"
+done
+"#,
+        );
+        let mut engine = External::new(path.clone());
+        let mut board = Board::build(3, Cell::X).unwrap();
+        let mut side = Cell::X;
+        while board.winner().is_none() && board.legal_moves().next().is_some() {
+            let expected = board.legal_moves().next().unwrap();
+            assert_eq!(engine.choose(&board, side), expected);
+            board.set_cell(expected, side).unwrap();
+            side = side.opponent();
+        }
+        assert!(!engine.failed());
+        assert!(engine.take_error().is_none());
+        drop(engine);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn falls_back() {
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        let path = script("taken", "read line\necho 1 1\n");
+        let mut engine = External::new(path.clone());
+        assert_eq!(engine.choose(&board, Cell::X), (2, 0));
+        assert!(engine.failed());
+        let error = engine.take_error().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains(&*path.to_string_lossy()));
+        assert!(engine.take_error().is_none());
+        fs::remove_file(path).unwrap();
+        let mut missing = External::new(PathBuf::from("/nonexistent/tictactoe-bot"));
+        assert_eq!(missing.choose(&board, Cell::O), (2, 1));
+        assert!(missing.failed());
+        assert_eq!(
+            missing.take_error().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+        // a blank cell which gravity doesn't let a piece stay on
+        let board = Board::from_string("---/---/-X-", 3, Cell::X)
+            .unwrap()
+            .with_gravity(true)
+            .unwrap();
+        let path = script("floating", "read line\necho 2 1\n");
+        let mut floating = External::new(path.clone());
+        assert_eq!(floating.choose(&board, Cell::O), (1, 1));
+        assert!(floating.failed());
+        fs::remove_file(path).unwrap();
+    }
+}
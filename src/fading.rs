@@ -0,0 +1,345 @@
+//! Tic-tac-toe with fading pieces: a piece disappears after a number of its owner's later
+//! moves, by default as many as it takes to win. Each player never has more pieces than
+//! that on the board, so the board never fills up and a game can't end in a tie by it.
+
+use std::fmt;
+
+use crate::board::{Board, BoardError, Cell, Coord, MoveError, DIM_RANGE};
+use crate::input::{self, ParseError};
+use crate::variant::Variant;
+
+/// The search depth used by the computer player, see
+/// [`best_move`](crate::variant::best_move)
+pub const SEARCH_DEPTH: usize = 6;
+
+/// The number of moves after which a game nobody has won is a tie, so that it ends
+pub const MOVE_LIMIT: usize = 100;
+
+// a move made, with the cell of the piece which faded with it and that piece's move number
+#[derive(Debug, Copy, Clone)]
+struct Played {
+    mv: Coord,
+    faded: Option<(usize, usize)>,
+}
+
+/// A board on which pieces fade. X always moves first.
+#[derive(Debug, Clone)]
+pub struct FadingBoard {
+    width: usize,
+    height: usize,
+    lifetime: usize,
+    // the cells by x + y * width
+    cells: Vec<Cell>,
+    // the move number each piece was placed with, by cell
+    placed: Vec<usize>,
+    win_lines: Vec<Vec<usize>>,
+    history: Vec<Played>,
+}
+
+impl FadingBoard {
+    /// Create an empty board with the given number of columns and rows, won by the given
+    /// number of pieces in a row, on which a piece disappears after `lifetime` of its
+    /// owner's later moves
+    ///
+    /// Returns an error if the size or the win length is out of range, if the pieces fade
+    /// before a line can be completed, or if the pieces of both players could fill the board.
+    pub fn build(
+        width: usize,
+        height: usize,
+        win_length: usize,
+        lifetime: usize,
+    ) -> Result<FadingBoard, BoardError> {
+        for got in [width, height] {
+            if !DIM_RANGE.contains(&got) {
+                return Err(BoardError::InvalidDimension {
+                    got,
+                    min: *DIM_RANGE.start(),
+                    max: *DIM_RANGE.end(),
+                });
+            }
+        }
+        if !(2..=width.min(height)).contains(&win_length) {
+            return Err(BoardError::InvalidWinLength {
+                got: win_length,
+                min: 2,
+                max: width.min(height),
+            });
+        }
+        // the pieces of both players leave at least one cell blank
+        let max = (width * height - 1) / 2;
+        if !(win_length..=max).contains(&lifetime) {
+            return Err(BoardError::InvalidLifetime {
+                got: lifetime,
+                min: win_length,
+                max,
+            });
+        }
+        Ok(FadingBoard {
+            width,
+            height,
+            lifetime,
+            cells: vec![Cell::Blank; width * height],
+            placed: vec![0; width * height],
+            win_lines: Board::segments(width, height, win_length, false),
+            history: Vec::new(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The number of its owner's later moves after which a piece disappears
+    pub fn lifetime(&self) -> usize {
+        self.lifetime
+    }
+
+    /// The piece at the given coordinates
+    pub fn get_cell(&self, coord: impl Into<Coord>) -> Cell {
+        self.cells[self.index(coord.into())]
+    }
+
+    /// Whether the piece on the cell at the given coordinates disappears with its owner's
+    /// next move. `false` for blank cells.
+    pub fn fades_next(&self, coord: impl Into<Coord>) -> bool {
+        self.fades(self.index(coord.into()))
+    }
+
+    // whether the piece on the cell at the index disappears with its owner's next move
+    fn fades(&self, idx: usize) -> bool {
+        let cell = self.cells[idx];
+        // the move number of the owner's next move
+        let next = match cell {
+            Cell::Blank => return false,
+            c if c == self.to_move() => self.history.len(),
+            _ => self.history.len() + 1,
+        };
+        next - self.placed[idx] == 2 * self.lifetime
+    }
+
+    /// The player to move
+    pub fn to_move(&self) -> Cell {
+        if self.history.len().is_multiple_of(2) {
+            Cell::X
+        } else {
+            Cell::O
+        }
+    }
+
+    /// The moves made so far
+    pub fn history(&self) -> Vec<Coord> {
+        self.history.iter().map(|played| played.mv).collect()
+    }
+
+    /// The blank cells, row by row. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<Coord> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.blanks()
+            .map(|idx| Coord {
+                row: idx / self.width,
+                col: idx % self.width,
+            })
+            .collect()
+    }
+
+    /// Put the piece of the player to move on the blank cell at the given coordinates.
+    /// Their piece placed `lifetime` of their moves ago disappears with it, before the lines
+    /// are checked.
+    ///
+    /// Returns an error if the cell is off the board or taken, or the game is over.
+    pub fn play(&mut self, coord: impl Into<Coord>) -> Result<(), MoveError> {
+        let coord = coord.into();
+        if coord.col >= self.width || coord.row >= self.height {
+            return Err(MoveError::OutOfBounds(coord));
+        }
+        if self.get_cell(coord) != Cell::Blank {
+            return Err(MoveError::CellTaken);
+        }
+        if self.is_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let faded =
+            (0..self.cells.len()).find(|&idx| self.fades(idx) && self.cells[idx] == self.to_move());
+        let faded = faded.map(|idx| {
+            self.cells[idx] = Cell::Blank;
+            (idx, self.placed[idx])
+        });
+        let idx = self.index(coord);
+        self.cells[idx] = self.to_move();
+        self.placed[idx] = self.history.len();
+        self.history.push(Played { mv: coord, faded });
+        Ok(())
+    }
+
+    /// Take back the last move, bringing back the piece which faded with it, and return its
+    /// coordinates
+    pub fn undo(&mut self) -> Option<Coord> {
+        let Played { mv, faded } = self.history.pop()?;
+        let idx = self.index(mv);
+        self.cells[idx] = Cell::Blank;
+        if let Some((idx, placed)) = faded {
+            self.cells[idx] = self.to_move();
+            self.placed[idx] = placed;
+        }
+        Some(mv)
+    }
+
+    /// The player with a full line of pieces still on the board
+    pub fn winner(&self) -> Option<Cell> {
+        self.win_lines.iter().find_map(|line| {
+            let first = self.cells[line[0]];
+            (first != Cell::Blank && line.iter().all(|&idx| self.cells[idx] == first))
+                .then_some(first)
+        })
+    }
+
+    /// Whether the game is over, by a win or because the move limit is reached
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || self.history.len() >= MOVE_LIMIT
+    }
+
+    /// Parse a line of user input into coordinates
+    pub fn parse_input(&self, input: &str) -> Result<Coord, ParseError> {
+        input::parse_move(input, self.width, self.height).map(Coord::from)
+    }
+
+    /// Draw the board, the pieces which disappear with their owner's next move dimmed if
+    /// `color` is set and in lower case otherwise
+    pub fn render(&self, color: bool) -> String {
+        let sep = "+---".repeat(self.width) + "+\n";
+        let mut out = sep.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.get_cell((x, y)).to_string();
+                out += &match self.fades_next((x, y)) {
+                    true if color => format!("| \x1b[2m{}\x1b[0m ", cell),
+                    true => format!("| {} ", cell.to_lowercase()),
+                    false => format!("| {} ", cell),
+                };
+            }
+            out += "|\n";
+            out += &sep;
+        }
+        out
+    }
+
+    // the index into the cells of the cell at the coordinates
+    fn index(&self, coord: Coord) -> usize {
+        coord.col + coord.row * self.width
+    }
+
+    fn blanks(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.cells.len()).filter(|&idx| self.cells[idx] == Cell::Blank)
+    }
+}
+
+impl Variant for FadingBoard {
+    type Move = Coord;
+
+    fn legal_moves(&self) -> Vec<Coord> {
+        FadingBoard::legal_moves(self)
+    }
+
+    fn play(&mut self, mv: Coord) -> Result<(), MoveError> {
+        FadingBoard::play(self, mv)
+    }
+
+    fn undo(&mut self) -> Option<Coord> {
+        FadingBoard::undo(self)
+    }
+
+    fn winner(&self) -> Option<Cell> {
+        FadingBoard::winner(self)
+    }
+
+    // pieces on lines the opponent has no piece on, counted by their square, pieces about
+    // to fade counting for nothing
+    fn evaluate(&self) -> i64 {
+        let player = self.to_move();
+        let lasting = |idx: usize, p| self.cells[idx] == p && !self.fades(idx);
+        self.win_lines
+            .iter()
+            .map(|line| {
+                let count = |p| line.iter().filter(|&&idx| lasting(idx, p)).count() as i64;
+                match (count(player), count(player.opponent())) {
+                    (n, 0) => n * n,
+                    (0, n) => -n * n,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for FadingBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant;
+
+    fn play_all(board: &mut FadingBoard, moves: &[(usize, usize)]) {
+        for &mv in moves {
+            board.play(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn pieces_fade() {
+        let mut board = FadingBoard::build(3, 3, 3, 3).unwrap();
+        assert!(FadingBoard::build(3, 3, 3, 2).is_err());
+        assert_eq!(
+            FadingBoard::build(3, 3, 3, 5).unwrap_err(),
+            BoardError::InvalidLifetime {
+                got: 5,
+                min: 3,
+                max: 4
+            }
+        );
+        play_all(&mut board, &[(0, 0), (1, 1), (2, 0), (1, 0), (0, 2)]);
+        // X's first piece goes with X's next move
+        assert!(board.fades_next((0, 0)));
+        assert!(!board.fades_next((2, 0)));
+        assert!(!board.fades_next((1, 1)));
+        board.play((2, 2)).unwrap();
+        assert!(board.fades_next((1, 1)));
+        board.play((0, 1)).unwrap();
+        assert_eq!(board.get_cell((0, 0)), Cell::Blank);
+        // the column would be complete without the fading
+        assert_eq!(board.winner(), None);
+        assert_eq!(board.undo(), Some((0, 1).into()));
+        assert_eq!(board.get_cell((0, 0)), Cell::X);
+        assert!(board.fades_next((0, 0)));
+    }
+
+    #[test]
+    fn engine_wins() {
+        let mut board = FadingBoard::build(3, 3, 3, 3).unwrap();
+        play_all(&mut board, &[(1, 1), (0, 1), (0, 0), (2, 1)]);
+        // X completes the diagonal, its first piece stays until then
+        assert_eq!(variant::best_move(&board, 2), Some((2, 2).into()));
+        board.play((2, 2)).unwrap();
+        assert_eq!(board.winner(), Some(Cell::X));
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn render() {
+        let mut board = FadingBoard::build(3, 3, 3, 3).unwrap();
+        play_all(&mut board, &[(0, 0), (1, 1), (2, 0), (1, 0), (0, 2)]);
+        let plain = board.render(false);
+        assert_eq!(plain.lines().nth(1), Some("| x | O | X |"));
+        assert!(board.render(true).contains("| \x1b[2mX\x1b[0m |"));
+        assert_eq!(board.to_string(), plain);
+    }
+}
@@ -0,0 +1,370 @@
+//! A game between two [players](Player) on a [`Board`].
+//!
+//! A [`Game`] keeps the board, whose turn it is and the result, so that a program only
+//! calls [`Game::play_turn`] until the game is over and shows what happened in between, the
+//! way the terminal game of the binary does.
+
+use std::time::Instant;
+
+use crate::board::{Board, Cell, Coord, GameOver, GameResult, Move, MoveError};
+use crate::player::{Choice, Player};
+
+/// Where a game stands
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GameState {
+    /// The player in the seat, playing the piece, is to move
+    ToMove { seat: usize, side: Cell },
+    /// The game is over with the result
+    Over(GameOver),
+}
+
+/// What a player did on their turn
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Turn {
+    /// The player made the move
+    Move(Move),
+    /// The player swapped sides under the [pie rule](Board::set_pie_rule) instead of
+    /// moving, so the other player is to move
+    Swap,
+    /// The player took back their last move and the opponent's reply, see
+    /// [`Game::undo_turn`]
+    Undo,
+}
+
+/// Two players taking turns on a board until the game is over
+///
+/// The players sit in two seats: the first one plays the [human's piece](Board::human_uses)
+/// of the board, the second one the other piece. The moves and their times are kept in the
+/// [history](Board::history) of the board.
+pub struct Game<'a> {
+    board: Board,
+    players: [Box<dyn Player + 'a>; 2],
+    result: Option<GameOver>,
+}
+
+impl<'a> Game<'a> {
+    /// A game from the position on the board, which may already be over
+    pub fn new(board: Board, players: [Box<dyn Player + 'a>; 2]) -> Self {
+        let result = board.result();
+        Game {
+            board,
+            players,
+            result,
+        }
+    }
+
+    /// The board with the position of the game
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The board with the final position, e.g. to analyze the game
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    /// The seat of the player to move, 0 for the one playing the human's piece
+    pub fn seat(&self) -> usize {
+        usize::from(self.board.to_move() != self.board.human_uses())
+    }
+
+    /// The player in the seat, together with the board, e.g. to ask the player's engine
+    /// about the position
+    pub fn player_mut(&mut self, seat: usize) -> (&mut (dyn Player + 'a), &Board) {
+        (self.players[seat].as_mut(), &self.board)
+    }
+
+    /// Where the game stands: who is to move, or the result once it is over
+    pub fn state(&self) -> GameState {
+        match self.result {
+            Some(over) => GameState::Over(over),
+            None => GameState::ToMove {
+                seat: self.seat(),
+                side: self.board.to_move(),
+            },
+        }
+    }
+
+    /// The result once the game is over
+    pub fn result(&self) -> Option<GameOver> {
+        self.result
+    }
+
+    /// The result once the game is over with the winner and the winning line, see
+    /// [`Board::game_result`]. A resigned game has no line.
+    pub fn game_result(&self) -> Option<GameResult> {
+        match self.result? {
+            GameOver::Resigned => Some(GameResult {
+                over: GameOver::Resigned,
+                winner: Some(self.board.human_uses()),
+                line: Vec::new(),
+            }),
+            _ => self.board.game_result(),
+        }
+    }
+
+    /// Let the player to move give up, which ends the game. It is
+    /// [resigned](GameOver::Resigned) when the computer's seat, the second one, gives up,
+    /// otherwise the computer won.
+    ///
+    /// Returns an error if the game is already over.
+    pub fn resign(&mut self) -> Result<GameOver, MoveError> {
+        if self.result.is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let over = match self.seat() {
+            0 => GameOver::ComputerWon,
+            _ => GameOver::Resigned,
+        };
+        self.result = Some(over);
+        Ok(over)
+    }
+
+    /// Let the player to move swap sides or make their move, timing it
+    ///
+    /// Returns an error if the game is over or the player chose a move the board
+    /// [rejects](Board::apply_move), leaving the game as it was.
+    pub fn play_turn(&mut self) -> Result<Turn, MoveError> {
+        if self.result.is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let seat = self.seat();
+        if self.board.can_swap() && self.players[seat].swap(&self.board) {
+            self.board.swap_sides().unwrap();
+            return Ok(Turn::Swap);
+        }
+        let start = Instant::now();
+        let mv = match self.players[seat].choose(&self.board) {
+            Choice::Move(mv) => mv,
+            Choice::Undo => {
+                self.undo_turn();
+                return Ok(Turn::Undo);
+            }
+        };
+        self.result = self.board.apply_move(mv)?;
+        self.board.set_last_move_time(start.elapsed());
+        Ok(Turn::Move(mv))
+    }
+
+    /// Let the players take turns until the game is over and return the result
+    ///
+    /// Returns an error if a player chose a move the board rejects.
+    pub fn play(&mut self) -> Result<GameOver, MoveError> {
+        loop {
+            if let Some(over) = self.result {
+                return Ok(over);
+            }
+            self.play_turn()?;
+        }
+    }
+
+    /// Let the player waiting for the move of the player to move think about the position
+    pub fn ponder(&mut self) {
+        let seat = self.seat();
+        self.players[1 - seat].ponder(&self.board);
+    }
+
+    /// Take back the last move, returning its cell
+    pub fn undo(&mut self) -> Option<Coord> {
+        let last = self.board.undo()?;
+        self.result = None;
+        Some(last)
+    }
+
+    /// Make the move taken back last again, returning its cell, see [`Board::redo`]
+    pub fn redo(&mut self) -> Option<Coord> {
+        let last = self.board.redo()?;
+        self.result = self.board.result();
+        Some(last)
+    }
+
+    /// Take back the last move of the player to move and the opponent's reply, so that it
+    /// is their turn again, returning whether they had a move to take back. If the player
+    /// swapped sides under the pie rule instead of moving, the swap is taken back with the
+    /// reply, and they can choose again.
+    pub fn undo_turn(&mut self) -> bool {
+        if self.board.history().len() < 2 {
+            return false;
+        }
+        self.undo();
+        if !self.board.undo_swap() {
+            self.undo();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineConfig, EngineKind};
+    use crate::player::{ComputerPlayer, ScriptedPlayer};
+
+    #[test]
+    fn turns_until_over() {
+        let board = Board::build(3, Cell::X).unwrap();
+        let mut game = Game::new(
+            board,
+            [
+                Box::new(ScriptedPlayer::new(
+                    [(0, 0), (1, 1), (2, 2)].map(Coord::from),
+                )),
+                Box::new(ScriptedPlayer::new([(1, 0), (2, 0)].map(Coord::from))),
+            ],
+        );
+        assert_eq!(
+            game.state(),
+            GameState::ToMove {
+                seat: 0,
+                side: Cell::X
+            }
+        );
+        assert_eq!(
+            game.play_turn(),
+            Ok(Turn::Move(Move {
+                coord: Coord { row: 0, col: 0 },
+                mark: Cell::X
+            }))
+        );
+        assert_eq!(
+            game.state(),
+            GameState::ToMove {
+                seat: 1,
+                side: Cell::O
+            }
+        );
+        assert_eq!(game.play(), Ok(GameOver::HumanWon));
+        assert_eq!(game.state(), GameState::Over(GameOver::HumanWon));
+        let result = game.game_result().unwrap();
+        assert_eq!(result.winner, Some(Cell::X));
+        assert_eq!(result.line, [(0, 0), (1, 1), (2, 2)].map(Coord::from));
+        assert_eq!(game.play_turn(), Err(MoveError::GameAlreadyOver));
+        assert_eq!(game.board().move_times().len(), 5);
+        assert_eq!(game.undo(), Some(Coord { row: 2, col: 2 }));
+        assert_eq!(game.result(), None);
+    }
+
+    #[test]
+    fn resigned_game() {
+        let board = Board::build(3, Cell::X).unwrap();
+        let mut game = Game::new(
+            board,
+            [
+                Box::new(ScriptedPlayer::new([(1, 1)].map(Coord::from))),
+                Box::new(ScriptedPlayer::new([])),
+            ],
+        );
+        game.play_turn().unwrap();
+        assert_eq!(game.resign(), Ok(GameOver::Resigned));
+        assert_eq!(game.state(), GameState::Over(GameOver::Resigned));
+        assert_eq!(
+            game.game_result(),
+            Some(GameResult {
+                over: GameOver::Resigned,
+                winner: Some(Cell::X),
+                line: Vec::new(),
+            })
+        );
+        assert_eq!(game.resign(), Err(MoveError::GameAlreadyOver));
+        // a finished position ends the game before it begins
+        let board = Board::from_notation("XXX/OO-/---").unwrap();
+        let game = Game::new(
+            board,
+            [
+                Box::new(ScriptedPlayer::new([])),
+                Box::new(ScriptedPlayer::new([])),
+            ],
+        );
+        assert_eq!(game.result(), Some(GameOver::HumanWon));
+        assert_eq!(
+            game.game_result().map(|result| result.line),
+            Some([(0, 0), (1, 0), (2, 0)].map(Coord::from).to_vec())
+        );
+    }
+
+    #[test]
+    fn rejected_move() {
+        let board = Board::build(3, Cell::X).unwrap();
+        let mut game = Game::new(
+            board,
+            [
+                Box::new(ScriptedPlayer::new([(1, 1)].map(Coord::from))),
+                Box::new(ScriptedPlayer::new([(1, 1)].map(Coord::from))),
+            ],
+        );
+        game.play_turn().unwrap();
+        assert_eq!(game.play_turn(), Err(MoveError::CellTaken));
+        assert_eq!(game.board().history(), [(1, 1)]);
+        assert_eq!(game.seat(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo_turns() {
+        let board = Board::build(3, Cell::X).unwrap();
+        let mut game = Game::new(
+            board,
+            [
+                Box::new(ScriptedPlayer::new([(0, 0), (1, 1)].map(Coord::from))),
+                Box::new(ScriptedPlayer::new([(2, 2)].map(Coord::from))),
+            ],
+        );
+        assert!(!game.undo_turn());
+        game.play_turn().unwrap();
+        game.play_turn().unwrap();
+        game.play_turn().unwrap();
+        assert!(game.undo_turn());
+        assert_eq!(game.board().history(), [(0, 0)]);
+        assert_eq!(game.seat(), 1);
+        assert_eq!(game.redo(), Some(Coord { row: 2, col: 2 }));
+        assert_eq!(game.redo(), Some(Coord { row: 1, col: 1 }));
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.board().history(), [(0, 0), (2, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn computer_swaps() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.set_pie_rule(true);
+        let engine = EngineKind::Minimax.build(&EngineConfig {
+            book: false,
+            ..EngineConfig::default()
+        });
+        let mut game = Game::new(
+            board,
+            [
+                // the center, which the engine rates best
+                Box::new(ScriptedPlayer::new([(1, 1), (0, 0)].map(Coord::from))),
+                Box::new(ComputerPlayer::new(engine)),
+            ],
+        );
+        game.play_turn().unwrap();
+        assert_eq!(game.play_turn(), Ok(Turn::Swap));
+        // the computer plays X now, and the scripted player O is to move
+        assert_eq!(game.board().human_uses(), Cell::O);
+        assert_eq!(
+            game.state(),
+            GameState::ToMove {
+                seat: 0,
+                side: Cell::O
+            }
+        );
+        // taking back the computer's turn takes back the reply and the swap
+        game.play_turn().unwrap();
+        assert!(game.undo_turn());
+        assert_eq!(game.board().history(), [(1, 1)]);
+        assert!(!game.board().swapped() && game.board().can_swap());
+        assert_eq!(game.board().human_uses(), Cell::X);
+        assert_eq!(
+            game.state(),
+            GameState::ToMove {
+                seat: 1,
+                side: Cell::O
+            }
+        );
+        // taking back the first move after a swap leaves the empty board unswapped
+        game.board.swap_sides().unwrap();
+        game.undo();
+        assert!(!game.board().swapped());
+        assert_eq!(game.board().human_uses(), Cell::X);
+    }
+}
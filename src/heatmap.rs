@@ -0,0 +1,192 @@
+//! Heatmaps showing how often the human played on each cell across the recorded games.
+
+use std::fmt::Write;
+
+use crate::db::GameRecord;
+
+/// Background colors of the terminal heatmap from rare to frequent, as indices into the
+/// 256 color palette
+const TERMINAL_COLORS: [u8; 6] = [236, 52, 88, 124, 160, 196];
+
+/// Size of a cell in the SVG heatmap in pixels
+const SVG_CELL: usize = 40;
+
+/// Number of times each cell of a board was played
+#[derive(Debug, PartialEq, Clone)]
+pub struct Heatmap {
+    dim: usize,
+    counts: Vec<usize>,
+}
+
+impl Heatmap {
+    /// The cells the human opened with in the games on boards of the given dimension
+    pub fn first_moves(games: &[GameRecord], dim: usize) -> Heatmap {
+        Heatmap::of(games.iter().filter(|game| game.is_dim(dim)), dim)
+    }
+
+    /// The cells the human opened with in the games they won on boards of the given
+    /// dimension
+    pub fn winning_first_moves(games: &[GameRecord], dim: usize) -> Heatmap {
+        let won = games
+            .iter()
+            .filter(|game| game.is_dim(dim) && game.result.human_won());
+        Heatmap::of(won, dim)
+    }
+
+    fn of<'a>(games: impl Iterator<Item = &'a GameRecord>, dim: usize) -> Heatmap {
+        let mut counts = vec![0; dim * dim];
+        for first in games.filter_map(|game| game.human_moves().next()) {
+            counts[first.col + first.row * dim] += 1;
+        }
+        Heatmap { dim, counts }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// How often the given cell was played
+    pub fn count(&self, x: usize, y: usize) -> usize {
+        self.counts[x + y * self.dim]
+    }
+
+    /// Number of games counted
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    // the intensity of a cell between 0 and `levels`
+    fn level(&self, count: usize, levels: usize) -> usize {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            0
+        } else {
+            (count * levels).div_ceil(max)
+        }
+    }
+
+    /// Render the heatmap laid out like the board, with the counts on colored backgrounds
+    /// if `color` is set
+    pub fn render(&self, color: bool) -> String {
+        let width = self
+            .counts
+            .iter()
+            .max()
+            .map_or(1, |max| max.to_string().len());
+        let sep = format!("+{}", "-".repeat(width + 2)).repeat(self.dim) + "+";
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", sep);
+        for row in self.counts.chunks(self.dim) {
+            for &count in row {
+                let cell = format!(" {:>width$} ", count);
+                if color {
+                    let bg = TERMINAL_COLORS[self.level(count, TERMINAL_COLORS.len() - 1)];
+                    let _ = write!(out, "|\x1b[97;48;5;{}m{}\x1b[0m", bg, cell);
+                } else {
+                    let _ = write!(out, "|{}", cell);
+                }
+            }
+            let _ = writeln!(out, "|");
+            let _ = writeln!(out, "{}", sep);
+        }
+        out
+    }
+}
+
+/// Render heatmaps side by side as an SVG image, each with a title above it
+pub fn to_svg(maps: &[(&str, &Heatmap)]) -> String {
+    let size = maps.iter().map(|(_, map)| map.dim).max().unwrap_or(0) * SVG_CELL;
+    let (width, height) = (maps.len() * (size + SVG_CELL), size + 2 * SVG_CELL);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" text-anchor="middle">"#
+    );
+    for (idx, (title, map)) in maps.iter().enumerate() {
+        let left = idx * (size + SVG_CELL) + SVG_CELL / 2;
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{}" y="{}" font-size="16">{}</text>"#,
+            left + map.dim * SVG_CELL / 2,
+            SVG_CELL * 3 / 4,
+            escape(title)
+        );
+        for (i, &count) in map.counts.iter().enumerate() {
+            let (x, y) = (
+                left + i % map.dim * SVG_CELL,
+                SVG_CELL + i / map.dim * SVG_CELL,
+            );
+            // from white to red
+            let shade = 255 - map.level(count, 255);
+            let _ = writeln!(
+                svg,
+                r#"  <rect x="{x}" y="{y}" width="{SVG_CELL}" height="{SVG_CELL}" fill="rgb(255,{shade},{shade})" stroke="black"/>"#
+            );
+            let _ = writeln!(
+                svg,
+                r#"  <text x="{}" y="{}" font-size="14">{}</text>"#,
+                x + SVG_CELL / 2,
+                y + SVG_CELL * 5 / 8,
+                count
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Cell, GameOver};
+
+    fn game(dim: usize, result: GameOver, first: Cell, moves: &[(usize, usize)]) -> GameRecord {
+        GameRecord {
+            time: 0,
+            width: dim,
+            height: dim,
+            win_length: dim,
+            wrap: false,
+            gravity: false,
+            misere: false,
+            swapped: false,
+            blocked: Vec::new(),
+            human_uses: Cell::X,
+            first,
+            result,
+            moves: moves.iter().map(|&mv| mv.into()).collect(),
+            accuracy: None,
+        }
+    }
+
+    #[test]
+    fn heatmap() {
+        let games = [
+            game(3, GameOver::HumanWon, Cell::X, &[(1, 1), (0, 0)]),
+            game(3, GameOver::ComputerWon, Cell::X, &[(1, 1)]),
+            // the computer began
+            game(3, GameOver::Tie, Cell::O, &[(1, 1), (0, 0)]),
+            game(4, GameOver::HumanWon, Cell::X, &[(2, 2)]),
+        ];
+        let first = Heatmap::first_moves(&games, 3);
+        assert_eq!((first.count(1, 1), first.count(0, 0)), (2, 1));
+        assert_eq!(first.total(), 3);
+        let won = Heatmap::winning_first_moves(&games, 3);
+        assert_eq!(won.total(), 1);
+        assert_eq!(
+            won.render(false),
+            "+---+---+---+\n| 0 | 0 | 0 |\n+---+---+---+\n| 0 | 1 | 0 |\n+---+---+---+\n| 0 | 0 | 0 |\n+---+---+---+\n"
+        );
+        assert!(won.render(true).contains("\x1b[97;48;5;196m 1 \x1b[0m"));
+        let svg = to_svg(&[("first <moves>", &first), ("won", &won)]);
+        assert_eq!(svg.matches("<rect").count(), 18);
+        assert!(svg.contains("first &lt;moves&gt;"));
+        assert!(svg.contains(r#"fill="rgb(255,0,0)""#));
+    }
+}
@@ -0,0 +1,350 @@
+//! Tic-tac-toe on a hexagonal board of hexagonal cells, three cells along each side by
+//! default.
+//!
+//! Cells have axial coordinates `q r`: `r` is the row from the top, `q` runs along the row,
+//! and the center is `0 0`. The lines run in three directions, along the rows and along the
+//! two diagonals crossing them, and the win length of them in a row wins.
+//!
+//! Unlike the square boards the moves are these `(q, r)` pairs rather than a
+//! [`Coord`](crate::board::Coord), which can't be negative.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::board::{BoardError, Cell, MoveError};
+use crate::input::{self, ParseError};
+
+/// The supported numbers of cells along each side
+pub const SIZE_RANGE: RangeInclusive<usize> = 2..=8;
+
+/// The default number of cells along each side
+pub const DEFAULT_SIZE: usize = 3;
+
+/// The steps of `q` and `r` along the three directions of the lines: along the row, down to
+/// the right and down to the left
+pub const DIRECTIONS: [(isize, isize); 3] = [(1, 0), (0, 1), (-1, 1)];
+
+/// A hex board. X always moves first.
+#[derive(Debug, Clone)]
+pub struct HexBoard {
+    size: usize,
+    win_length: usize,
+    // the cells row by row, see [`coords`]
+    cells: Vec<Cell>,
+    win_lines: Vec<Vec<usize>>,
+    history: Vec<(isize, isize)>,
+}
+
+impl HexBoard {
+    /// Create an empty board with the given number of cells along each side, won by the
+    /// given number of pieces in a row
+    ///
+    /// Returns an error if the size is unsupported or the win length is below 2 or longer
+    /// than the rows through the center.
+    pub fn build(size: usize, win_length: usize) -> Result<HexBoard, BoardError> {
+        if !SIZE_RANGE.contains(&size) {
+            return Err(BoardError::InvalidDimension {
+                got: size,
+                min: *SIZE_RANGE.start(),
+                max: *SIZE_RANGE.end(),
+            });
+        }
+        if !(2..=2 * size - 1).contains(&win_length) {
+            return Err(BoardError::InvalidWinLength {
+                got: win_length,
+                min: 2,
+                max: 2 * size - 1,
+            });
+        }
+        Ok(HexBoard {
+            size,
+            win_length,
+            cells: vec![Cell::Blank; coords(size).len()],
+            win_lines: win_lines(size, win_length),
+            history: Vec::new(),
+        })
+    }
+
+    /// The number of cells along each side
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The number of pieces in a row which wins
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// Whether the axial coordinates are those of a cell on the board
+    pub fn contains(&self, q: isize, r: isize) -> bool {
+        contains(self.size, q, r)
+    }
+
+    /// The piece at the given axial coordinates
+    pub fn get_cell(&self, q: isize, r: isize) -> Cell {
+        self.cells[index(self.size, q, r)]
+    }
+
+    /// The lines winning the game, as indexes into [`coords`]
+    pub fn win_lines(&self) -> &[Vec<usize>] {
+        &self.win_lines
+    }
+
+    /// The player to move
+    pub fn to_move(&self) -> Cell {
+        if self.history.len().is_multiple_of(2) {
+            Cell::X
+        } else {
+            Cell::O
+        }
+    }
+
+    /// The moves made so far
+    pub fn history(&self) -> &[(isize, isize)] {
+        &self.history
+    }
+
+    /// Place the piece of the player to move at the given axial coordinates
+    ///
+    /// Returns an error if the cell is taken or the game is over.
+    pub fn play(&mut self, q: isize, r: isize) -> Result<(), MoveError> {
+        let idx = index(self.size, q, r);
+        if self.cells[idx] != Cell::Blank {
+            return Err(MoveError::CellTaken);
+        }
+        if self.winner().is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        self.cells[idx] = self.to_move();
+        self.history.push((q, r));
+        Ok(())
+    }
+
+    /// Take back the last move, returning its coordinates
+    pub fn undo(&mut self) -> Option<(isize, isize)> {
+        let (q, r) = self.history.pop()?;
+        self.cells[index(self.size, q, r)] = Cell::Blank;
+        Some((q, r))
+    }
+
+    /// The player who completed a line
+    pub fn winner(&self) -> Option<Cell> {
+        self.win_lines.iter().find_map(|line| {
+            let first = self.cells[line[0]];
+            (first != Cell::Blank && line.iter().all(|&idx| self.cells[idx] == first))
+                .then_some(first)
+        })
+    }
+
+    /// Whether the game is over, by a win or because the board is full
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || !self.cells.contains(&Cell::Blank)
+    }
+
+    /// Parse a line of user input of the form `q r` into axial coordinates
+    pub fn parse_input(&self, input: &str) -> Result<(isize, isize), ParseError> {
+        input::parse_hex_move(input, self.size)
+    }
+
+    /// Choose a move for the player to move: complete a line, block the opponent's line,
+    /// or else take the cell on the most promising open lines, nearest the center first
+    ///
+    /// Returns `None` if the game is over.
+    pub fn best_move(&self) -> Option<(isize, isize)> {
+        if self.is_over() {
+            return None;
+        }
+        let player = self.to_move();
+        let coords = coords(self.size);
+        let blanks: Vec<usize> = (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == Cell::Blank)
+            .collect();
+        let completes = |p: Cell| {
+            blanks.iter().copied().find(|&idx| {
+                self.win_lines.iter().any(|line| {
+                    line.contains(&idx) && line.iter().all(|&i| i == idx || self.cells[i] == p)
+                })
+            })
+        };
+        let center = |idx: usize| {
+            let (q, r) = coords[idx];
+            distance((q, r), (0, 0))
+        };
+        let idx = completes(player)
+            .or_else(|| completes(player.opponent()))
+            .or_else(|| {
+                blanks.iter().copied().max_by_key(|&idx| {
+                    (
+                        self.score(idx, player),
+                        std::cmp::Reverse(center(idx)),
+                        std::cmp::Reverse(idx),
+                    )
+                })
+            })?;
+        Some(coords[idx])
+    }
+
+    // the value of taking a blank cell: lines the player can still complete count by their
+    // pieces, lines only the opponent can complete a bit less
+    fn score(&self, idx: usize, player: Cell) -> usize {
+        self.win_lines
+            .iter()
+            .filter(|line| line.contains(&idx))
+            .map(|line| {
+                let mine = line.iter().filter(|&&i| self.cells[i] == player).count();
+                let theirs = line
+                    .iter()
+                    .filter(|&&i| self.cells[i] == player.opponent())
+                    .count();
+                match (mine, theirs) {
+                    (n, 0) => 1 + 4 * n * n,
+                    (0, n) => 3 * n * n,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+/// Whether the axial coordinates are those of a cell on a board with `size` cells along
+/// each side
+pub fn contains(size: usize, q: isize, r: isize) -> bool {
+    let max = size as isize - 1;
+    q.abs() <= max && r.abs() <= max && (q + r).abs() <= max
+}
+
+/// The axial coordinates of the cells of a board with `size` cells along each side, row by
+/// row from the top and along each row from the left. There are `3 * size * (size - 1) + 1`
+/// of them, 19 on the default board.
+pub fn coords(size: usize) -> Vec<(isize, isize)> {
+    let max = size as isize - 1;
+    (-max..=max)
+        .flat_map(|r| (-max..=max).map(move |q| (q, r)))
+        .filter(|&(q, r)| contains(size, q, r))
+        .collect()
+}
+
+/// The number of steps between two cells
+pub fn distance((q1, r1): (isize, isize), (q2, r2): (isize, isize)) -> usize {
+    let (dq, dr) = (q1 - q2, r1 - r2);
+    (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+}
+
+/// All lines of `win_length` cells in the three [directions](DIRECTIONS) on a board with
+/// `size` cells along each side, as indexes into [`coords`]
+pub fn win_lines(size: usize, win_length: usize) -> Vec<Vec<usize>> {
+    let mut lines = Vec::new();
+    for (dq, dr) in DIRECTIONS {
+        for (q, r) in coords(size) {
+            let line: Vec<(isize, isize)> = (0..win_length as isize)
+                .map(|i| (q + i * dq, r + i * dr))
+                .collect();
+            if line.iter().all(|&(q, r)| contains(size, q, r)) {
+                lines.push(line.into_iter().map(|(q, r)| index(size, q, r)).collect());
+            }
+        }
+    }
+    lines
+}
+
+// The index of the cell into [`coords`]
+fn index(size: usize, q: isize, r: isize) -> usize {
+    assert!(contains(size, q, r), "{q} {r} is not on the board");
+    let max = size as isize - 1;
+    let row_len = |r: isize| (2 * max + 1 - r.abs()) as usize;
+    let before: usize = (-max..r).map(row_len).sum();
+    before + (q - (-max).max(-max - r)) as usize
+}
+
+/// Draws the rows shifted by half a cell each, so that every cell touches its six
+/// neighbours, with the row `r` on the left and the `q` of its first and last cell on the
+/// right. Blank cells are dots.
+impl fmt::Display for HexBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let max = self.size as isize - 1;
+        let width = 4 * (2 * self.size - 1) - 3;
+        writeln!(f, " r")?;
+        for r in -max..=max {
+            let qs: Vec<isize> = (-max..=max).filter(|&q| self.contains(q, r)).collect();
+            let cells: Vec<String> = qs
+                .iter()
+                .map(|&q| match self.get_cell(q, r) {
+                    Cell::Blank => ".".to_string(),
+                    cell => cell.to_string(),
+                })
+                .collect();
+            let row = " ".repeat(2 * r.unsigned_abs()) + &cells.join("   ");
+            writeln!(
+                f,
+                "{:>2}  {:<width$}  q {:>2} .. {:>2}",
+                r,
+                row,
+                qs[0],
+                qs[qs.len() - 1],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_and_lines() {
+        for (size, cells) in [(2, 7), (3, 19), (4, 37)] {
+            assert_eq!(coords(size).len(), cells);
+        }
+        let cells = coords(3);
+        for (idx, &(q, r)) in cells.iter().enumerate() {
+            assert_eq!(index(3, q, r), idx);
+        }
+        // 3 in a row on the default board: 9 along the rows and as many in each diagonal
+        let lines = win_lines(3, 3);
+        assert_eq!(lines.len(), 27);
+        assert!(lines.iter().all(|line| line.len() == 3));
+        // the diagonal down to the left through the center
+        let diagonal = [(2, -2), (1, -1), (0, 0)].map(|(q, r)| index(3, q, r));
+        assert!(lines.contains(&diagonal.to_vec()));
+        assert_eq!(win_lines(3, 5).len(), 3);
+        assert_eq!(distance((2, -2), (-2, 2)), 4);
+        assert!(HexBoard::build(3, 6).is_err());
+        assert!(HexBoard::build(1, 2).is_err());
+    }
+
+    #[test]
+    fn diagonal_wins() {
+        let mut board = HexBoard::build(3, 3).unwrap();
+        for (q, r) in [(0, 0), (1, 0), (0, 1), (-1, 0)] {
+            board.play(q, r).unwrap();
+        }
+        assert_eq!(board.winner(), None);
+        // X completes the line down to the right before O can block it
+        assert_eq!(board.best_move(), Some((0, -1)));
+        board.play(0, -1).unwrap();
+        assert_eq!(board.winner(), Some(Cell::X));
+        assert!(board.play(2, -2).is_err());
+        assert_eq!(board.undo(), Some((0, -1)));
+        assert_eq!(board.get_cell(0, -1), Cell::Blank);
+        board.play(2, -2).unwrap();
+        // O has no line of its own and blocks the first of X's threats
+        assert_eq!(board.to_move(), Cell::O);
+        assert_eq!(board.best_move(), Some((0, -1)));
+    }
+
+    #[test]
+    fn render() {
+        let mut board = HexBoard::build(2, 2).unwrap();
+        board.play(0, 0).unwrap();
+        board.play(1, -1).unwrap();
+        assert_eq!(
+            board.to_string(),
+            " r
+-1    .   O    q  0 ..  1
+ 0  .   X   .  q -1 ..  1
+ 1    .   .    q -1 ..  0
+"
+        );
+    }
+}
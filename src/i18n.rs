@@ -0,0 +1,229 @@
+//! Message catalogs for all user-facing text.
+//!
+//! Catalogs live in `locales/<code>.txt` and are embedded at compile time. Each line holds
+//! `key = value`; multi-line values are enclosed in `"""`. Values may contain `{name}`
+//! placeholders which are filled in by [`message`] or the [`t!`](crate::t) macro.
+//! Messages missing from a catalog fall back to English.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::LazyLock;
+
+/// Languages with a message catalog
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Lang {
+    En,
+    De,
+}
+
+const LANGS: [Lang; 2] = [Lang::En, Lang::De];
+
+static CATALOGS: LazyLock<Vec<HashMap<&'static str, &'static str>>> = LazyLock::new(|| {
+    LANGS
+        .iter()
+        .map(|lang| parse_messages(lang.source()).expect("invalid message catalog"))
+        .collect()
+});
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+impl Lang {
+    /// Look up a language by its code, e.g. `de` or `de_DE.UTF-8`
+    pub fn from_code(code: &str) -> Option<Lang> {
+        let code = code.split(['_', '.', '-']).next()?.to_ascii_lowercase();
+        LANGS.iter().copied().find(|lang| lang.code() == code)
+    }
+
+    /// Detect the language from the `LC_ALL`, `LC_MESSAGES` and `LANG` environment
+    /// variables, falling back to English.
+    pub fn detect() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::from_code(&value))
+            .unwrap_or(Lang::En)
+    }
+
+    /// The two letter language code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::De => "de",
+        }
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.txt"),
+            Lang::De => include_str!("../locales/de.txt"),
+        }
+    }
+
+    fn index(&self) -> usize {
+        LANGS.iter().position(|lang| lang == self).unwrap()
+    }
+}
+
+/// Set the language used for all messages
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang.index(), Ordering::Relaxed);
+}
+
+/// The language used for all messages
+pub fn lang() -> Lang {
+    LANGS[CURRENT.load(Ordering::Relaxed)]
+}
+
+/// Get the message for the given key in the current language.
+///
+/// Unknown keys are returned unchanged.
+pub fn tr(key: &'static str) -> &'static str {
+    let catalogs = &*CATALOGS;
+    catalogs[lang().index()]
+        .get(key)
+        .or_else(|| catalogs[Lang::En.index()].get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// Get the message for the given key in the current language with its placeholders
+/// replaced by the given values
+pub fn message(key: &'static str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    fill(tr(key), args)
+}
+
+/// Replace the `{name}` placeholders in the template by the given values
+pub fn fill(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut msg = template.to_string();
+    for (name, value) in args {
+        msg = msg.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    msg
+}
+
+/// Translate a message, optionally filling in placeholders:
+/// `t!("last-move", player = name, x = 1, y = 2)`
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::tr($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+/// Parse `key = value` lines as used by the message catalogs and the config file.
+///
+/// Empty lines and lines starting with `#` are skipped, multi-line values are enclosed in
+/// `"""` lines. Returns the number of the offending line if the source is malformed.
+pub(crate) fn parse_messages(source: &str) -> Result<HashMap<&str, &str>, usize> {
+    let mut messages = HashMap::new();
+    let mut lines = source.split_inclusive('\n').enumerate();
+    let mut offset = 0;
+    while let Some((idx, line)) = lines.next() {
+        offset += line.len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(idx + 1)?;
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() {
+            return Err(idx + 1);
+        }
+        if value != "\"\"\"" {
+            messages.insert(key, value);
+            continue;
+        }
+        let start = offset;
+        loop {
+            let (_, line) = lines.next().ok_or(idx + 1)?;
+            if line.trim() == "\"\"\"" {
+                messages.insert(key, &source[start..offset]);
+                offset += line.len();
+                break;
+            }
+            offset += line.len();
+        }
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(msg: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = msg
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn catalogs_are_complete() {
+        let en = &CATALOGS[Lang::En.index()];
+        for lang in LANGS {
+            let catalog = &CATALOGS[lang.index()];
+            assert_eq!(
+                catalog.len(),
+                en.len(),
+                "{} has a different key count",
+                lang.code()
+            );
+            for (key, msg) in en {
+                let translated = catalog
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} is missing '{}'", lang.code(), key));
+                assert_eq!(
+                    placeholders(msg),
+                    placeholders(translated),
+                    "placeholders of '{}' differ in {}",
+                    key,
+                    lang.code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multi_line_messages() {
+        let en = &CATALOGS[Lang::En.index()];
+        assert!(en["help"].starts_with("tictactoe\n"));
+        assert!(en["help"].ends_with("status 2.\n"));
+        assert_eq!(en["error"], "Error: {error}.");
+    }
+
+    #[test]
+    fn malformed_messages() {
+        assert_eq!(parse_messages("a = 1\nb\n"), Err(2));
+        assert_eq!(parse_messages("= 1"), Err(1));
+        assert_eq!(parse_messages("a = 1\nb = \"\"\"\nno end\n"), Err(2));
+    }
+
+    #[test]
+    fn language_codes() {
+        assert_eq!(Lang::from_code("de_DE.UTF-8"), Some(Lang::De));
+        assert_eq!(Lang::from_code("EN"), Some(Lang::En));
+        assert_eq!(Lang::from_code("fr_FR"), None);
+        assert_eq!(Lang::from_code("C"), None);
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        assert_eq!(
+            message("last-move", &[("player", &"You"), ("x", &1), ("y", &2)]),
+            "You: 1 2"
+        );
+        assert_eq!(tr("no-such-key"), "no-such-key");
+    }
+}
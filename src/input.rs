@@ -0,0 +1,489 @@
+use std::fmt;
+use std::io;
+
+use crate::t;
+
+/// Error returned when no valid move could be read from the input
+#[derive(Debug)]
+pub enum InputError {
+    /// The input ended before a move was entered
+    Eof,
+    /// Reading from stdin failed
+    Io(io::Error),
+    /// The input could not be parsed as coordinates
+    Parse(ParseError),
+    /// The input is not a valid move
+    Invalid(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::Eof => write!(f, "{}", t!("unexpected-eof")),
+            InputError::Io(e) => write!(f, "{}", t!("read-failed", error = e)),
+            InputError::Parse(e) => write!(f, "{}", e),
+            InputError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<ParseError> for InputError {
+    fn from(e: ParseError) -> Self {
+        InputError::Parse(e)
+    }
+}
+
+/// Reason why a line of input is not a pair of coordinates.
+///
+/// Where the intended move can be guessed, it is given as one based `(x, y)` coordinates.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// Nothing was entered
+    Empty,
+    /// Only one coordinate was entered
+    MissingCoordinate,
+    /// More than two coordinates were entered
+    TooManyCoordinates,
+    /// A coordinate is not between 1 and the size of the board along its axis
+    OutOfRange {
+        axis: char,
+        value: usize,
+        dim: usize,
+    },
+    /// A column letter beyond the board was entered
+    UnknownColumn {
+        column: char,
+        width: usize,
+        height: usize,
+    },
+    /// The input is not three numbers `x y z` on a 3D board
+    NotThreeCoordinates,
+    /// The input is not four numbers `x y x y` for the piece to move and where to
+    NotFourCoordinates,
+    /// The input is not two whole numbers `q r`, possibly negative, on a hex board
+    NotHexCoordinates,
+    /// The axial coordinates are off a hex board with `size` cells along each side
+    OffHexBoard { q: isize, r: isize, size: usize },
+    /// The input is not in the `x y` form
+    Malformed {
+        input: String,
+        suggestion: Option<(usize, usize)>,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "{}", t!("input-empty")),
+            ParseError::MissingCoordinate => write!(f, "{}", t!("input-missing-coordinate")),
+            ParseError::TooManyCoordinates => write!(f, "{}", t!("input-too-many")),
+            ParseError::NotThreeCoordinates => write!(f, "{}", t!("input-three-coordinates")),
+            ParseError::NotFourCoordinates => write!(f, "{}", t!("input-four-coordinates")),
+            ParseError::NotHexCoordinates => write!(f, "{}", t!("input-hex-coordinates")),
+            ParseError::OffHexBoard { q, r, size } => write!(
+                f,
+                "{}",
+                t!("input-off-hex-board", q = q, r = r, max = size - 1)
+            ),
+            ParseError::OutOfRange { axis, value, dim } => write!(
+                f,
+                "{}",
+                t!("input-out-of-range", axis = axis, value = value, dim = dim)
+            ),
+            ParseError::UnknownColumn {
+                column,
+                width,
+                height,
+            } => write!(
+                f,
+                "{}",
+                t!(
+                    "input-unknown-column",
+                    column = column,
+                    width = width,
+                    height = height,
+                    last = column_letter(*width)
+                )
+            ),
+            ParseError::Malformed { input, suggestion } => {
+                write!(f, "{}", t!("invalid-input", input = input))?;
+                match suggestion {
+                    Some((x, y)) => write!(f, " - {}", t!("did-you-mean", x = x, y = y)),
+                    None => write!(f, " - {}", t!("input-empty")),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Token {
+    Number(usize),
+    Letter(char),
+    // anything else: words, unparsable numbers, punctuation
+    Other,
+}
+
+/// Parse a line of input of the form `x y` into zero based coordinates on a board of the
+/// given width and height.
+///
+/// Input in other forms is analysed to tell the user what went wrong and, where possible,
+/// which move was probably meant, e.g. `b3` or `2,3` on a 3x3 board suggest `2 3`.
+pub fn parse_move(input: &str, width: usize, height: usize) -> Result<(usize, usize), ParseError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let plain = input
+        .split_whitespace()
+        .all(|word| word.chars().all(|c| c.is_ascii_digit()));
+    let tokens = tokenize(input);
+    let malformed = |suggestion| ParseError::Malformed {
+        input: input.to_string(),
+        suggestion,
+    };
+    let dim = |axis| if axis == 'x' { width } else { height };
+    let in_range = |axis, v: usize| (1..=dim(axis)).contains(&v);
+    let check = |axis, value| {
+        if in_range(axis, value) {
+            Ok(value)
+        } else {
+            Err(ParseError::OutOfRange {
+                axis,
+                value,
+                dim: dim(axis),
+            })
+        }
+    };
+    let numbers: Vec<usize> = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Number(n) => Some(*n),
+            _ => None,
+        })
+        .collect();
+
+    match tokens.as_slice() {
+        // the regular form
+        [Token::Number(x), Token::Number(y)] if plain => {
+            Ok((check('x', *x)? - 1, check('y', *y)? - 1))
+        }
+        // other separators, e.g. "2,3" or "(2, 3)"
+        [Token::Number(x), Token::Number(y)] => {
+            check('x', *x)?;
+            check('y', *y)?;
+            Err(malformed(Some((*x, *y))))
+        }
+        // chess like notation, e.g. "b3" or "3b"
+        [Token::Letter(c), Token::Number(y)] | [Token::Number(y), Token::Letter(c)] => {
+            let x = *c as usize - 'a' as usize + 1;
+            if !in_range('x', x) {
+                return Err(ParseError::UnknownColumn {
+                    column: *c,
+                    width,
+                    height,
+                });
+            }
+            check('y', *y)?;
+            Err(malformed(Some((x, *y))))
+        }
+        // both coordinates without separator, e.g. "23"
+        [Token::Number(_)] => {
+            let digits: Vec<usize> = input
+                .chars()
+                .filter_map(|c| c.to_digit(10).map(|d| d as usize))
+                .collect();
+            match digits.as_slice() {
+                [x, y] if width.max(height) < 10 && in_range('x', *x) && in_range('y', *y) => {
+                    Err(malformed(Some((*x, *y))))
+                }
+                _ => Err(ParseError::MissingCoordinate),
+            }
+        }
+        _ if numbers.len() == tokens.len() && numbers.len() > 2 => {
+            Err(ParseError::TooManyCoordinates)
+        }
+        _ => Err(malformed(None)),
+    }
+}
+
+/// Parse a line of input of the form `x y z` into zero based coordinates on a 3D board with
+/// the given number of cells along each side
+pub fn parse_move_3d(input: &str, dim: usize) -> Result<(usize, usize, usize), ParseError> {
+    let tokens = tokenize(input);
+    let [Token::Number(x), Token::Number(y), Token::Number(z)] = tokens.as_slice() else {
+        return Err(ParseError::NotThreeCoordinates);
+    };
+    let check = |axis, value: usize| {
+        if (1..=dim).contains(&value) {
+            Ok(value - 1)
+        } else {
+            Err(ParseError::OutOfRange { axis, value, dim })
+        }
+    };
+    Ok((check('x', *x)?, check('y', *y)?, check('z', *z)?))
+}
+
+/// Parse a line of input of the form `q r` into the axial coordinates of a cell on a hex
+/// board with `size` cells along each side, the center being `0 0`. Both may be negative,
+/// and a comma may separate them as well.
+pub fn parse_hex_move(input: &str, size: usize) -> Result<(isize, isize), ParseError> {
+    let numbers: Vec<Option<isize>> = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.parse().ok())
+        .collect();
+    let [Some(q), Some(r)] = numbers.as_slice() else {
+        return Err(ParseError::NotHexCoordinates);
+    };
+    let (q, r) = (*q, *r);
+    let max = size as isize - 1;
+    if q.abs() > max || r.abs() > max || (q + r).abs() > max {
+        return Err(ParseError::OffHexBoard { q, r, size });
+    }
+    Ok((q, r))
+}
+
+/// Parse a list of cells of the form `x y` separated by commas, e.g. `1 1,3 2`, into zero
+/// based coordinates on a board of the given width and height
+pub fn parse_cells(
+    input: &str,
+    width: usize,
+    height: usize,
+) -> Result<Vec<(usize, usize)>, ParseError> {
+    input
+        .split(',')
+        .map(|cell| parse_move(cell, width, height))
+        .collect()
+}
+
+/// Parse a line of input of the form `x y x y`, the cell of a piece and the cell to move it
+/// to, into zero based coordinates on a square board with `dim` cells along each side.
+/// Anything but numbers only separates them, e.g. `1 1 -> 2 2`.
+pub fn parse_slide(input: &str, dim: usize) -> Result<[(usize, usize); 2], ParseError> {
+    let tokens = tokenize(input);
+    let [Token::Number(x), Token::Number(y), Token::Number(to_x), Token::Number(to_y)] =
+        tokens.as_slice()
+    else {
+        return Err(ParseError::NotFourCoordinates);
+    };
+    let check = |axis, value: usize| {
+        if (1..=dim).contains(&value) {
+            Ok(value - 1)
+        } else {
+            Err(ParseError::OutOfRange { axis, value, dim })
+        }
+    };
+    Ok([
+        (check('x', *x)?, check('y', *y)?),
+        (check('x', *to_x)?, check('y', *to_y)?),
+    ])
+}
+
+// Splits the input into numbers, single letters and other words. Punctuation and
+// whitespace only separate tokens.
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                number.push(d);
+            }
+            tokens.push(number.parse().map_or(Token::Other, Token::Number));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(l) = chars.next_if(|l| l.is_alphabetic()) {
+                word.push(l);
+            }
+            let mut letters = word.chars();
+            tokens.push(match (letters.next(), letters.next()) {
+                (Some(l), None) if l.is_ascii_alphabetic() => Token::Letter(l.to_ascii_lowercase()),
+                _ => Token::Other,
+            });
+        } else {
+            chars.next();
+        }
+    }
+    tokens
+}
+
+/// The letter naming the given one based column
+fn column_letter(col: usize) -> char {
+    (b'a' + col.saturating_sub(1).min(25) as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn malformed(input: &str, suggestion: Option<(usize, usize)>) -> ParseError {
+        ParseError::Malformed {
+            input: input.to_string(),
+            suggestion,
+        }
+    }
+
+    #[test]
+    fn valid_input() {
+        assert_eq!(parse_move("1 1\n", 3, 3), Ok((0, 0)));
+        assert_eq!(parse_move("  3\t2 ", 3, 3), Ok((2, 1)));
+        assert_eq!(parse_move("7 6", 7, 6), Ok((6, 5)));
+        assert_eq!(
+            parse_move("6 7", 7, 6),
+            Err(ParseError::OutOfRange {
+                axis: 'y',
+                value: 7,
+                dim: 6
+            })
+        );
+    }
+
+    #[test]
+    fn hex_input() {
+        assert_eq!(parse_hex_move("0 0\n", 3), Ok((0, 0)));
+        assert_eq!(parse_hex_move(" -2, 1 ", 3), Ok((-2, 1)));
+        assert_eq!(parse_hex_move("2 -2", 3), Ok((2, -2)));
+        assert_eq!(
+            parse_hex_move("2 1", 3),
+            Err(ParseError::OffHexBoard {
+                q: 2,
+                r: 1,
+                size: 3
+            })
+        );
+        for input in ["", "1", "1 2 3", "a b", "1.5 0"] {
+            assert_eq!(parse_hex_move(input, 3), Err(ParseError::NotHexCoordinates));
+        }
+    }
+
+    #[test]
+    fn input_cells() {
+        assert_eq!(parse_cells("1 1, 3 2", 3, 3), Ok(vec![(0, 0), (2, 1)]));
+        assert_eq!(parse_cells("2 2", 3, 3), Ok(vec![(1, 1)]));
+        assert!(parse_cells("1 1,4 1", 3, 3).is_err());
+    }
+
+    #[test]
+    fn input_slide() {
+        assert_eq!(parse_slide("1 1 2 2", 3), Ok([(0, 0), (1, 1)]));
+        assert_eq!(parse_slide("3,1 -> 2,1", 3), Ok([(2, 0), (1, 0)]));
+        assert_eq!(parse_slide("1 1", 3), Err(ParseError::NotFourCoordinates));
+        assert_eq!(
+            parse_slide("1 1 2 4", 3),
+            Err(ParseError::OutOfRange {
+                axis: 'y',
+                value: 4,
+                dim: 3
+            })
+        );
+    }
+
+    #[test]
+    fn input_3d() {
+        assert_eq!(parse_move_3d("1 2 4", 4), Ok((0, 1, 3)));
+        assert_eq!(parse_move_3d("1,2,3", 4), Ok((0, 1, 2)));
+        assert_eq!(
+            parse_move_3d("1 2", 4),
+            Err(ParseError::NotThreeCoordinates)
+        );
+        assert_eq!(
+            parse_move_3d("a 2 3", 4),
+            Err(ParseError::NotThreeCoordinates)
+        );
+        assert_eq!(
+            parse_move_3d("1 2 5", 4),
+            Err(ParseError::OutOfRange {
+                axis: 'z',
+                value: 5,
+                dim: 4
+            })
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let tests = [
+            ("", ParseError::Empty),
+            ("2", ParseError::MissingCoordinate),
+            ("1 2 3", ParseError::TooManyCoordinates),
+            ("2,3", malformed("2,3", Some((2, 3)))),
+            ("(2, 3)", malformed("(2, 3)", Some((2, 3)))),
+            ("b3", malformed("b3", Some((2, 3)))),
+            ("3 B", malformed("3 B", Some((2, 3)))),
+            ("23", malformed("23", Some((2, 3)))),
+            ("45", ParseError::MissingCoordinate),
+            ("foo", malformed("foo", None)),
+            ("1 xy", malformed("1 xy", None)),
+            (
+                "d1",
+                ParseError::UnknownColumn {
+                    column: 'd',
+                    width: 3,
+                    height: 3,
+                },
+            ),
+            (
+                "4 1",
+                ParseError::OutOfRange {
+                    axis: 'x',
+                    value: 4,
+                    dim: 3,
+                },
+            ),
+            (
+                "1 0",
+                ParseError::OutOfRange {
+                    axis: 'y',
+                    value: 0,
+                    dim: 3,
+                },
+            ),
+            (
+                "99999999999999999999999 1",
+                malformed("99999999999999999999999 1", None),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(parse_move(input, 3, 3), Err(expected), "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            parse_move("d1", 3, 3).unwrap_err().to_string(),
+            "column d doesn't exist on a 3x3 board - columns are a-c"
+        );
+        assert_eq!(
+            parse_move("2,3", 3, 3).unwrap_err().to_string(),
+            "Invalid input: 2,3 - did you mean `2 3`?"
+        );
+    }
+
+    #[test]
+    fn hostile_input() {
+        let inputs = [
+            "99999999999999999999999 1",
+            "0 0",
+            "a0",
+            "ä ö",
+            "\u{0}\u{ffff}",
+            "1 -1",
+            "z",
+            "------",
+        ];
+        for dim in [0, 1, 3, 30, usize::MAX] {
+            for input in inputs {
+                if let Err(e) = parse_move(input, dim, dim) {
+                    e.to_string();
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,47 @@
+pub mod analysis;
 pub mod board;
+pub mod book;
+pub mod clipboard;
+pub mod commentary;
+pub mod config;
+pub mod cube;
+pub mod db;
+pub mod drill;
+pub mod engine;
+pub mod experience;
+pub mod external;
+pub mod fading;
+pub mod game;
+pub mod heatmap;
+pub mod hex;
+pub mod i18n;
+pub mod input;
+pub mod minimax;
+pub mod morris;
+pub mod multiplayer;
+pub mod opening;
+pub mod perft;
+pub mod player;
+pub mod pns;
+pub mod profile;
+pub mod puzzle;
+pub mod qr;
+pub mod rating;
+pub mod rng;
+pub mod rules;
+pub mod selfplay;
+pub mod simulation;
+pub mod solver;
+pub mod stats;
+pub mod tablebase;
+pub mod threats;
+pub mod ultimate;
+pub mod variant;
+pub mod variation;
 
-pub use board::{Board, Cell, GameOver};
+pub use board::{Board, BoardError, Cell, Coord, GameOver, GameResult, Move, MoveError};
+pub use engine::{Engine, EngineConfig, EngineKind};
+pub use game::Game;
+pub use input::{InputError, ParseError};
+pub use rules::Ruleset;
+pub use variation::VariationTree;
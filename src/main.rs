@@ -1,83 +1,3099 @@
 //! A text-based tic tac toe game written in Rust
 
-use tictactoe::{Board, Cell};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const HELP: &str = "\
-tictactoe
+use tictactoe::analysis::{self, ScoreMap, CSV_HEADER};
+use tictactoe::board::EvalWeights;
+use tictactoe::clipboard;
+use tictactoe::commentary;
+use tictactoe::config::{Action, Config, Keymap};
+use tictactoe::cube::{self, Cube};
+use tictactoe::db::{GameRecord, StatsDb};
+use tictactoe::drill::{self, Progress};
+use tictactoe::engine::{Blunder, Learned, Personality};
+use tictactoe::experience::Experience;
+use tictactoe::fading::{self, FadingBoard};
+use tictactoe::game::{Game, Turn};
+use tictactoe::heatmap::{self, Heatmap};
+use tictactoe::hex::{self, HexBoard};
+use tictactoe::i18n::{self, Lang};
+use tictactoe::input;
+use tictactoe::minimax;
+use tictactoe::morris::{self, MorrisBoard};
+use tictactoe::multiplayer::{MultiBoard, MultiGameOver, Seat};
+use tictactoe::opening;
+use tictactoe::perft;
+use tictactoe::player::{self, Choice, ComputerPlayer};
+use tictactoe::pns;
+use tictactoe::profile::Profile;
+use tictactoe::puzzle::{self, DailyRecord, Puzzle, WinPuzzle};
+use tictactoe::qr::QrCode;
+use tictactoe::rating::{Entrant, RoundRobin};
+use tictactoe::rng::Rng;
+use tictactoe::rules::Misere;
+use tictactoe::selfplay::SelfPlay;
+use tictactoe::simulation;
+use tictactoe::solver;
+use tictactoe::stats::{
+    estimate_skill, format_duration, GameStats, Quality, SessionScore, Skill, TimeSummary,
+    SKILL_GAMES,
+};
+use tictactoe::tablebase::{self, Tablebase, Value};
+use tictactoe::ultimate::{self, UltimateBoard};
+use tictactoe::variant;
+use tictactoe::{
+    t, Board, BoardError, Cell, Coord, Engine, EngineConfig, EngineKind, GameOver, InputError,
+    Move, VariationTree,
+};
 
-USAGE:
-  tictactoe [OPTIONS]
+/// Number of games self-play runs by default
+const SELF_PLAY_GAMES: usize = 1000;
 
-OPTIONS:
-  -h, --help     Prints help information
-  -d [n]         Board dimension (default: 3)
-  -c             Computer has first move
-  -o             Player uses O instead of X (which is the default)
-";
+/// Number of games of a simulated match by default
+const SIMULATE_GAMES: usize = 1000;
+
+/// Number of games of every pair of engines in a rating tournament by default
+const ROUND_ROBIN_GAMES: usize = 20;
+
+/// The engines of a rating tournament by default
+const ROUND_ROBIN_ENTRANTS: &str =
+    "heuristic,heuristic:aggressive,heuristic:defensive,heuristic:central,heuristic:erratic,minimax";
+
+/// Number of moves of generated puzzles by default
+const PUZZLE_MOVES: usize = 2;
+
+/// Number of players of a multiplayer game by default
+const MULTI_PLAYERS: usize = 3;
+
+/// Depth perft counts to by default, unless fewer cells are blank
+const PERFT_DEPTH: usize = 7;
+
+/// Number of moves listed with the engine's scores in very verbose mode
+const RANKED_MOVES: usize = 5;
+
+/// How much is printed during the game
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+    VeryVerbose,
+}
+
+/// How results of the analysis are printed
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Format {
+    Text,
+    Csv,
+}
+
+/// The pieces the human starts the game with, before the computer's first move
+#[derive(Debug, PartialEq, Clone)]
+enum Handicap {
+    /// The number of pieces, placed by the program
+    Pieces(usize),
+    /// A position with the pieces of one player on it
+    Position(String),
+}
+
+/// The cells blocked at the start of the game
+#[derive(Debug, PartialEq, Clone)]
+enum Blocked {
+    /// The number of cells, chosen at random
+    Random(usize),
+    /// The cells as `x y` separated by commas
+    Cells(String),
+}
+
+/// What the program should do
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Command {
+    Play,
+    Stats,
+    Heatmap,
+    Daily,
+    Puzzle,
+    Analyze,
+    Replay,
+    Drill,
+    Share,
+    Verify,
+    Solve,
+    Perft,
+    TablebaseBuild,
+    TablebaseQuery,
+    SelfPlay,
+    Simulate,
+    Rating,
+    Ultimate,
+    Cube,
+    Hex,
+    Multi,
+    Morris,
+    Fading,
+}
 
 #[derive(Debug)]
 struct AppArgs {
-    dimension: usize,
+    command: Command,
+    // the number of columns and rows
+    dimension: Option<(usize, usize)>,
+    win_length: Option<usize>,
+    wrap: bool,
+    gravity: bool,
+    misere: bool,
+    pie: bool,
+    hotseat: bool,
+    watch: bool,
     computer_begins: bool,
     player_uses_o: bool,
+    think_ms: Option<u64>,
+    engine: Option<EngineKind>,
+    personality: Option<Personality>,
+    eval_weights: Option<EvalWeights>,
+    contempt: Option<i64>,
+    no_book: bool,
+    seed: Option<u64>,
+    verbose_engine: bool,
+    pv: bool,
+    ponder: bool,
+    cache: bool,
+    resign: bool,
+    report: bool,
+    eval_map: bool,
+    learn: bool,
+    blunder_rate: Option<f64>,
+    opponent: Option<EngineKind>,
+    games: Option<usize>,
+    best_of: Option<usize>,
+    players: Option<usize>,
+    fade: Option<usize>,
+    threads: Option<usize>,
+    depth: Option<usize>,
+    moves: Option<usize>,
+    entrants: Option<Vec<Entrant>>,
+    verbosity: Option<Verbosity>,
+    config: Option<PathBuf>,
+    preset: Option<String>,
+    no_stats: bool,
+    svg: Option<PathBuf>,
+    format: Format,
+    game: Option<usize>,
+    all: bool,
+    commentary: bool,
+    teach: bool,
+    outcome: bool,
+    bell: bool,
+    position: Option<String>,
+    handicap: Option<Handicap>,
+    blocked: Option<Blocked>,
+    random_start: Option<usize>,
+    from_move: Option<usize>,
+    resume: bool,
+    file: Option<PathBuf>,
 }
 
 fn main() {
-    let args = match parse_args() {
+    let mut args = match parse_args() {
         Ok(v) => v,
         Err(e) => {
-            eprintln!("Error: {}.", e);
+            eprintln!("{}", t!("error", error = e));
             std::process::exit(1);
         }
     };
 
+    let config = Config::load(args.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    if let Err(e) = config.keymap() {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    }
+    match args.command {
+        Command::Play => {}
+        Command::Stats => return show_stats(),
+        Command::Heatmap => {
+            let dim = args.dimension.map(|_| square_dim(&new_board(&args)));
+            return show_heatmap(dim, args.svg.as_deref());
+        }
+        Command::Daily => return play_daily(),
+        Command::Puzzle => return play_puzzle(&args),
+        Command::Analyze if args.position.is_some() => return analyze_position(&args),
+        Command::Analyze => return analyze(args.game, args.all, args.format),
+        Command::Replay => {}
+        Command::Drill => return drill(),
+        Command::Ultimate => return play_ultimate(&args),
+        Command::Cube => return play_cube(&args),
+        Command::Hex => return play_hex(&args),
+        Command::Multi => return play_multi(&args),
+        Command::Morris => return play_morris(&args),
+        Command::Fading => return play_fading(&args),
+        Command::Share => return share(args.game, args.svg.as_deref()),
+        Command::Verify => return verify(args.file.as_deref()),
+        Command::Solve => return solve(&args),
+        Command::Perft => return count_sequences(&args),
+        Command::TablebaseBuild => return build_tablebase(&args),
+        Command::TablebaseQuery => return query_tablebase(&args),
+        Command::SelfPlay | Command::Simulate | Command::Rating => {}
+    }
+    if let Err(e) = apply_preset(&mut args, &config) {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    }
+    if args.hotseat && args.watch {
+        eprintln!("{}", t!("error", error = t!("hotseat-with-watch")));
+        std::process::exit(1);
+    }
+    // without a human playing the computer the players are numbered, and their games aren't
+    // recorded
+    let seats = [1, 2].map(|number| t!("player-number", number = number));
+    let (human_name, computer_name) = if args.hotseat || args.watch {
+        args.no_stats = true;
+        (seats[0].as_str(), seats[1].as_str())
+    } else {
+        (
+            config.get("name").unwrap_or(t!("you")),
+            config.get("computer-name").unwrap_or(t!("computer")),
+        )
+    };
+
+    if args.command == Command::SelfPlay {
+        return self_play(&args);
+    }
+    if args.command == Command::Simulate {
+        return simulate(&args);
+    }
+    if args.command == Command::Rating {
+        return rate_engines(&args);
+    }
+    if args.command == Command::Replay {
+        args.no_stats = true;
+        return replay(&args, &config, human_name, computer_name);
+    }
+    // the moves leading to a pasted position are unknown, and records start from the empty
+    // board, so none of these games can be recorded
+    if args.position.is_some() || args.handicap.is_some() || args.random_start.is_some() {
+        args.no_stats = true;
+    }
+
+    Match::new(&args, &config, human_name, computer_name).run();
+}
+
+/// The games played against the computer in a row: until the player doesn't want a rematch
+/// any more, or for `--best-of` until the match is decided, the players taking turns to begin
+struct Match<'a> {
+    args: &'a AppArgs,
+    config: &'a Config,
+    human_name: &'a str,
+    computer_name: &'a str,
+    score: SessionScore,
+    computer_begins: bool,
+}
+
+impl<'a> Match<'a> {
+    fn new(
+        args: &'a AppArgs,
+        config: &'a Config,
+        human_name: &'a str,
+        computer_name: &'a str,
+    ) -> Self {
+        Match {
+            args,
+            config,
+            human_name,
+            computer_name,
+            score: SessionScore::default(),
+            computer_begins: args.computer_begins,
+        }
+    }
+
+    /// Play games until the match is over, then print its result
+    fn run(mut self) {
+        loop {
+            if let Some(best_of) = self.args.best_of {
+                println!(
+                    "{}",
+                    t!(
+                        "match-game",
+                        game = self.score.games() + 1,
+                        best_of = best_of
+                    )
+                );
+            }
+            self.play_game();
+            if !self.next_game() {
+                break;
+            }
+            self.print_score();
+        }
+        match self.args.best_of {
+            Some(_) => self.print_result(),
+            None if self.score.games() > 1 => {
+                println!("{}", t!("session-summary"));
+                self.print_score();
+            }
+            None => {}
+        }
+    }
+
+    /// Play a game on a new board and count its result
+    fn play_game(&mut self) {
+        let board = new_board(self.args);
+        let result = play(
+            self.args,
+            self.config,
+            self.human_name,
+            self.computer_name,
+            board,
+            self.computer_begins,
+        );
+        self.score.record(result);
+        if self.args.best_of.is_some() {
+            self.computer_begins = !self.computer_begins;
+        }
+    }
+
+    // whether to play another game, which the player is asked unless a match length is given
+    fn next_game(&self) -> bool {
+        match self.args.best_of {
+            Some(best_of) => !self.score.decides_match(best_of),
+            None => io::stdin().is_terminal() && confirm(t!("rematch-question")),
+        }
+    }
+
+    // the score of the games played so far
+    fn print_score(&self) {
+        let score = &self.score;
+        let key = match self.args.best_of {
+            Some(_) => "match-score",
+            None => "session-score",
+        };
+        println!(
+            "{}\n",
+            t!(
+                key,
+                games = score.games(),
+                best_of = self.args.best_of.unwrap_or_default(),
+                human = self.human_name,
+                human_wins = score.human_wins,
+                computer = self.computer_name,
+                computer_wins = score.computer_wins,
+                ties = score.ties,
+            )
+        );
+    }
+
+    // the final score and the winner of a best of n match
+    fn print_result(&self) {
+        let (wins, losses) = (self.score.human_wins, self.score.computer_wins);
+        let result = match wins.cmp(&losses) {
+            Ordering::Greater => t!(
+                "match-won",
+                name = self.human_name,
+                wins = wins,
+                losses = losses
+            ),
+            Ordering::Less => t!(
+                "match-won",
+                name = self.computer_name,
+                wins = losses,
+                losses = wins
+            ),
+            Ordering::Equal => t!("match-tied", wins = wins, losses = losses),
+        };
+        println!("{}", t!("match-summary"));
+        self.print_score();
+        println!("{}", result);
+    }
+}
+
+/// Create the board for a new game
+fn new_board(args: &AppArgs) -> Board {
     let human_uses = if args.player_uses_o { Cell::O } else { Cell::X };
-    let mut board = Board::build(args.dimension, human_uses).unwrap_or_else(|e| {
-        println!("{}", e);
+    let (width, height) = args.dimension.unwrap_or((4, 4));
+    if args.position.is_some() && args.handicap.is_some() {
+        println!("{}", t!("handicap-with-position"));
+        std::process::exit(1);
+    }
+    if args.gravity && args.misere {
+        println!("{}", t!("misere-with-gravity"));
+        std::process::exit(1);
+    }
+    let from_position = |position: &str| match args.handicap {
+        Some(Handicap::Position(_)) => Board::from_handicap(position, human_uses),
+        _ => Board::from_position(position, human_uses),
+    };
+    let position = match &args.handicap {
+        Some(Handicap::Position(position)) => Some(position),
+        _ => args.position.as_ref(),
+    };
+    let board = match (position, args.win_length) {
+        (Some(position), None) => from_position(position),
+        (Some(position), Some(k)) => {
+            from_position(position).and_then(|board| board.with_win_length(k))
+        }
+        (None, k) => {
+            let k = k.unwrap_or(width.min(height));
+            Board::build_rectangle(width, height, k, human_uses)
+        }
+    };
+    board
+        .and_then(|board| board.with_wrap(args.wrap).with_gravity(args.gravity))
+        .and_then(|board| {
+            if args.misere {
+                board.with_rules(Misere)
+            } else {
+                Ok(board)
+            }
+        })
+        .and_then(|board| match &args.blocked {
+            Some(Blocked::Random(count)) => {
+                let seed = args.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64)
+                });
+                board.with_random_blocked(*count, &mut Rng::new(seed))
+            }
+            Some(Blocked::Cells(cells)) => {
+                let cells = input::parse_cells(cells, board.width(), board.height())
+                    .unwrap_or_else(|e| {
+                        println!("{}", e);
+                        std::process::exit(1);
+                    });
+                let cells: Vec<Coord> = cells.into_iter().map(Coord::from).collect();
+                board.with_blocked(&cells)
+            }
+            None => Ok(board),
+        })
+        .and_then(|board| match args.handicap {
+            Some(Handicap::Pieces(count)) => board.with_handicap(human_uses, count),
+            _ => Ok(board),
+        })
+        .and_then(|board| match args.random_start {
+            Some(plies) => {
+                let seed = args.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64)
+                });
+                board.with_random_start(plies, &mut Rng::new(seed))
+            }
+            None => Ok(board),
+        })
+        .and_then(|board| match board.winner() {
+            Some(_) => Err(BoardError::GameOver),
+            None => Ok(board),
+        })
+        .unwrap_or_else(|e| {
+            println!("{}", e);
+            std::process::exit(1);
+        })
+}
+
+/// The dimension of the board for commands which only support square boards
+fn square_dim(board: &Board) -> usize {
+    if !board.is_square() {
+        eprintln!("{}", t!("error", error = t!("board-not-square")));
         std::process::exit(1);
+    }
+    board.width()
+}
+
+/// The settings of the engines given on the command line
+fn engine_config(args: &AppArgs) -> EngineConfig {
+    EngineConfig {
+        book: !args.no_book,
+        seed: args.seed,
+        personality: args.personality.unwrap_or_default(),
+        eval_weights: args.eval_weights.unwrap_or_default(),
+        contempt: args.contempt.unwrap_or_default(),
+        think_time: args.think_ms.map(Duration::from_millis),
+        cache: args.cache,
+        tablebases: true,
+    }
+}
+
+/// Play a game from the position on the board and return its result
+///
+/// The players take the seats by the pieces they play: the first one plays the human's
+/// piece of the board, the second one the other piece. Usually the human plays the computer,
+/// with `--hotseat` two people and with `--watch` two engines play each other.
+fn play(
+    args: &AppArgs,
+    config: &Config,
+    human_name: &str,
+    computer_name: &str,
+    mut board: Board,
+    computer_begins: bool,
+) -> GameOver {
+    let human_uses = board.human_uses();
+    let kind = args.engine.clone().unwrap_or_default();
+    // a new seed for every game, unless the games should be reproduced
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
     });
+    let engine_config = EngineConfig {
+        seed: Some(seed),
+        ..engine_config(args)
+    };
+    // one engine for the whole game, so a searching engine keeps the positions it knows
+    let mut engine = kind.build(&engine_config);
+    if let Some(rate) = args.blunder_rate {
+        engine = Box::new(Blunder {
+            engine,
+            rate,
+            rng: Rng::new(seed),
+        });
+    }
+    if args.learn {
+        engine = Box::new(Learned {
+            engine,
+            experience: load_experience(&board),
+        });
+    }
+    board.set_pie_rule(args.pie);
+
+    let verbosity = args.verbosity.unwrap_or(Verbosity::Normal);
+    let quiet = verbosity == Verbosity::Quiet;
+    if verbosity >= Verbosity::Verbose {
+        println!("{}", t!("seed", seed = seed));
+    }
 
-    // loop to display the board, player and computer moves
-    let mut human_move = !args.computer_begins;
-    if args.computer_begins {
-        println!("Computer has the first move.")
+    let names = [human_name, computer_name];
+    let human = |name| {
+        Box::new(HumanPlayer {
+            name,
+            config,
+            bell: args.bell,
+            verbosity,
+            engine: (kind.clone(), engine_config),
+            hints: None,
+        })
+    };
+    let versus = !args.hotseat && !args.watch;
+    let players: [Box<dyn player::Player + '_>; 2] = if args.hotseat {
+        [human(human_name), human(computer_name)]
+    } else if args.watch {
+        let opponent = args
+            .opponent
+            .clone()
+            .unwrap_or(kind.clone())
+            .build(&EngineConfig {
+                cache: false,
+                ..engine_config
+            });
+        [
+            Box::new(ComputerPlayer::new(opponent)),
+            Box::new(ComputerPlayer::new(engine)),
+        ]
+    } else {
+        [human(human_name), Box::new(ComputerPlayer::new(engine))]
+    };
+
+    // who begins a game from the empty board; a pasted position, a handicap or a random
+    // start has the player to move of its own
+    if board.handicap().is_none() {
+        match board.history().first() {
+            Some(&first) => board.set_first_player(board.get_cell(first)),
+            None if args.position.is_none() && args.random_start.is_none() => {
+                board.set_first_player(if computer_begins {
+                    human_uses.opponent()
+                } else {
+                    human_uses
+                });
+                if computer_begins && versus && !quiet {
+                    println!("{}", t!("computer-begins"))
+                }
+            }
+            None => {}
+        }
     }
+    let mut game = Game::new(board, players);
+    let mut stats = GameStats::start();
+    // a takeback is offered once per game, and only when playing the computer interactively
+    let mut takeback_offered = !versus || !io::stdin().is_terminal();
+    // as is a draw by the computer
+    let mut draw_offered = !io::stdin().is_terminal();
+    // the outcome with best play, once the solver could determine it
+    let mut predicted = None;
+    // loop to display the board and let the players move in turn
     let won = loop {
-        if human_move {
-            println!("{}", board);
-            if let Some(won) = board.user_move() {
-                break won;
+        let (seat, side) = (game.seat(), game.board().to_move());
+        let name = names[seat];
+        let computer = game.player_mut(seat).0.engine().is_some();
+        if !computer || args.watch {
+            // the other player has just moved
+            if args.bell && !game.board().history().is_empty() {
+                print!("\x07");
+            }
+            if !quiet {
+                println!("{}", game.board());
+            }
+        }
+        if versus && computer && args.resign {
+            let (player, board) = game.player_mut(seat);
+            match player.engine().unwrap().proven_value(board, side) {
+                Some(Value::Loss) => break game.resign().expect("the game goes on"),
+                Some(Value::Draw) if !draw_offered => {
+                    draw_offered = true;
+                    if confirm(t!("draw-offer")) {
+                        break GameOver::Tie;
+                    }
+                }
+                _ => {}
+            }
+        }
+        announce_turn(
+            config,
+            &[
+                ("name", &name),
+                ("symbol", &side),
+                ("move", &(game.board().history().len() + 1)),
+            ],
+        );
+        let scores = game.board().scores_with(side, engine_config.eval_weights);
+        let (player, board) = game.player_mut(seat);
+        if let Some(engine) = player.engine() {
+            if args.eval_map {
+                println!("{}", t!("score-map", symbol = side));
+                print_score_map(engine, board, side);
+            }
+            if verbosity >= Verbosity::VeryVerbose {
+                println!("{}", t!("cell-scores"));
+                print_scores(&scores, board.width());
+                let ranked = engine.evaluate_moves(board, side);
+                let ranked: Vec<String> = ranked
+                    .iter()
+                    .take(RANKED_MOVES)
+                    .map(|&(mv, score)| format!("{} ({})", mv, format_score(score, board)))
+                    .collect();
+                println!("{}", t!("ranked-moves", moves = ranked.join(", ")));
+            }
+        } else if args.ponder {
+            game.ponder();
+        }
+        let mv = match game.play_turn() {
+            Ok(Turn::Move(mv)) => mv,
+            Ok(Turn::Undo) => {
+                println!("{}", t!("undo-done"));
+                stats.record_takeback();
+                continue;
+            }
+            Ok(Turn::Swap) => {
+                let human_uses = game.board().human_uses();
+                let message = match (versus, computer) {
+                    (true, false) => t!("pie-human-swaps", symbol = human_uses),
+                    (true, true) => t!("pie-computer-swaps", symbol = human_uses),
+                    (false, _) => t!("pie-swaps", name = name, symbol = side.opponent()),
+                };
+                println!("{}", message);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}", t!("move-rejected", name = name, error = e));
+                std::process::exit(1);
+            }
+        };
+        stats.record_move(game.board());
+        if quiet {
+            print_last_move(name, game.board());
+        } else if computer && verbosity >= Verbosity::Verbose {
+            let elapsed = game.board().move_times().last().copied().flatten();
+            let Coord { row, col } = mv.coord;
+            println!(
+                "{}",
+                t!(
+                    "computer-played",
+                    x = col + 1,
+                    y = row + 1,
+                    score = scores[col + row * game.board().width()],
+                    elapsed = format!("{:?}", elapsed.unwrap_or_default()),
+                )
+            );
+        }
+        if let Some(engine) = game.player_mut(seat).0.engine() {
+            if let Some(e) = engine.take_error() {
+                eprintln!("{}", e);
+            }
+            if args.verbose_engine {
+                match engine.last_search() {
+                    Some(search) => println!(
+                        "{}",
+                        t!(
+                            "search-stats",
+                            depth = search.depth,
+                            nodes = search.nodes,
+                            cutoffs = search.cutoffs,
+                            first = search.first_move_cutoffs,
+                            killers = search.killer_cutoffs,
+                            hits = search.table_hits,
+                            researches = search.researches,
+                        )
+                    ),
+                    None => println!("{}", t!("search-none")),
+                }
+            }
+            if args.pv {
+                // the line after the move just played
+                let line = engine
+                    .last_search()
+                    .map(|search| search.pv)
+                    .unwrap_or_default();
+                if line.len() > 1 {
+                    println!("{}", t!("expected-line", moves = format_moves(&line[1..])));
+                }
+            }
+        }
+        print_comment(args, game.board());
+        if computer && args.teach {
+            if let Some(reason) = commentary::explain(game.board()) {
+                println!("{}", t!("teach", name = name, reason = reason));
+            }
+        }
+        if computer && !takeback_offered && game.board().computer_forces_win() {
+            takeback_offered = true;
+            if !quiet {
+                println!("{}", game.board());
+            }
+            if confirm(t!("takeback-question")) {
+                game.undo();
+                game.undo();
+                stats.record_takeback();
+                continue;
             }
         }
-        human_move = true;
-        if let Some(won) = board.computer_move() {
+        if let Some(won) = game.result() {
             break won;
         }
+        if versus {
+            if let Some(won) = announce_outcome(args, game.board(), side.opponent(), &mut predicted)
+            {
+                break won;
+            }
+        }
+    };
+    for seat in 0..2 {
+        if let Some(Err(e)) = game
+            .player_mut(seat)
+            .0
+            .engine()
+            .map(|engine| engine.persist())
+        {
+            eprintln!("{}", t!("table-save-failed", error = e));
+        }
+    }
+    let mut board = game.into_board();
+    stats.finish(&board);
+    if args.learn {
+        learn_from(&board, won);
+    }
+    let human_uses = board.human_uses();
+    let names = [
+        (human_name, human_uses),
+        (computer_name, human_uses.opponent()),
+    ];
+    let (winner, symbol) = match won {
+        GameOver::HumanWon | GameOver::Resigned => names[0],
+        GameOver::ComputerWon => names[1],
+        GameOver::Tie => ("", Cell::Blank),
+    };
+    // the result names the winner unless it's the human's game against the computer
+    let result = match won {
+        GameOver::Tie => won.to_string(),
+        _ if versus => won.to_string(),
+        _ => t!("seat-won", name = winner),
+    };
+    // the cells of the winning line, e.g. `1 1, 2 2, 3 3`
+    let line = board.game_result().map_or_else(String::new, |result| {
+        let cells: Vec<String> = result.line.iter().map(Coord::to_string).collect();
+        cells.join(", ")
+    });
+    let banner = config.render(
+        "result",
+        "{result}",
+        &[
+            ("result", &result),
+            ("name", &winner),
+            ("symbol", &symbol),
+            ("move", &board.history().len()),
+            ("line", &line),
+        ],
+    );
+    println!("{}\n", banner);
+    if !quiet {
+        println!("{}", board);
+        print_stats(&stats, &board, names);
+    }
+
+    if !args.no_stats {
+        save_game(&board, won, &stats);
+        update_skill(quiet);
+    }
+
+    if args.report || (io::stdin().is_terminal() && confirm(t!("report-question"))) {
+        print_report(&board, names);
+    }
+    if versus && io::stdin().is_terminal() && confirm(t!("explore-question")) {
+        let mut engine = kind.build(&EngineConfig {
+            cache: false,
+            ..engine_config
+        });
+        explore(&mut board, config, won, engine.as_mut());
+    }
+    won
+}
+
+/// A person entering their moves in the terminal, who can ask for hints
+struct HumanPlayer<'a> {
+    name: &'a str,
+    config: &'a Config,
+    bell: bool,
+    verbosity: Verbosity,
+    // the kind and the settings of the engine giving hints, which is built for the first one
+    engine: (EngineKind, EngineConfig),
+    hints: Option<Box<dyn Engine>>,
+}
+
+impl HumanPlayer<'_> {
+    /// Ask for the move until a valid one is entered, or with `undo` the undo command. Exits
+    /// when there is no more input or, if not playing interactively, the input is invalid.
+    fn read_choice(&mut self, board: &Board, undo: bool) -> Choice {
+        let side = board.to_move();
+        let turn: [(&str, &dyn std::fmt::Display); 3] = [
+            ("name", &self.name),
+            ("symbol", &side),
+            ("move", &(board.history().len() + 1)),
+        ];
+        let default_prompt = if board.has_gravity() {
+            t!("enter-column")
+        } else {
+            t!("enter-move")
+        };
+        let prompt = self.config.render("prompt", default_prompt, &turn);
+        let verbosity = self.verbosity;
+        let (kind, engine_config) = &self.engine;
+        let hints = &mut self.hints;
+        let command = |board: &Board, input: &str| {
+            if input.eq_ignore_ascii_case(t!("hint-command")) {
+                let engine = hints.get_or_insert_with(|| {
+                    kind.build(&EngineConfig {
+                        cache: false,
+                        ..*engine_config
+                    })
+                });
+                print_hint(engine.as_mut(), board, verbosity);
+                Some(ControlFlow::Continue(()))
+            } else if undo && input.eq_ignore_ascii_case(t!("undo-command")) {
+                // the player's own move and the reply to it
+                if board.history().len() < 2 {
+                    println!("{}", t!("undo-nothing"));
+                    return Some(ControlFlow::Continue(()));
+                }
+                Some(ControlFlow::Break(()))
+            } else {
+                None
+            }
+        };
+        match read_move(board, side, &prompt, self.bell, command) {
+            Ok(Some(mv)) => Choice::Move(mv),
+            Ok(None) => Choice::Undo,
+            Err(e) => {
+                eprintln!("{}", t!("error", error = e));
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+impl player::Player for HumanPlayer<'_> {
+    fn select_move(&mut self, board: &Board) -> Move {
+        match self.read_choice(board, false) {
+            Choice::Move(mv) => mv,
+            Choice::Undo => unreachable!("undo is only entered when allowed"),
+        }
+    }
+
+    fn choose(&mut self, board: &Board) -> Choice {
+        self.read_choice(board, true)
+    }
+
+    fn swap(&mut self, _board: &Board) -> bool {
+        io::stdin().is_terminal() && confirm(t!("pie-swap-question"))
+    }
+}
+
+/// Print the moves which changed the outcome of the game with best play
+fn print_report(board: &Board, names: [(&str, Cell); 2]) {
+    let mistakes = analysis::mistakes(board, true);
+    if mistakes.is_empty() {
+        println!("{}\n", t!("report-none"));
+        return;
+    }
+    println!("{}", t!("report-header"));
+    for mistake in mistakes {
+        let name = names
+            .iter()
+            .find(|(_, cell)| *cell == mistake.player)
+            .map_or("", |(name, _)| name);
+        let key = match (mistake.before, mistake.after) {
+            (Value::Win, Value::Draw) => "report-win-to-draw",
+            (Value::Win, _) => "report-win-to-loss",
+            _ => "report-draw-to-loss",
+        };
+        println!(
+            "  {}",
+            t!(
+                key,
+                ply = mistake.ply,
+                name = name,
+                x = mistake.x + 1,
+                y = mistake.y + 1,
+                better = mistake.better
+            )
+        );
+    }
+    println!();
+}
+
+/// The experience of past games on boards of the size, empty if there is none yet, the
+/// lines wrap around the edges, the rules aren't the standard ones, cells are blocked or the
+/// game started with a handicap
+fn load_experience(board: &Board) -> Experience {
+    let path = Experience::default_path(board.width(), board.height());
+    let Some(path) = path.filter(|_| {
+        !board.wraps()
+            && board.has_standard_rules()
+            && !board.has_blocked()
+            && board.handicap().is_none()
+    }) else {
+        return Experience::default();
+    };
+    match Experience::load(&path) {
+        Ok(experience) => experience,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Experience::default(),
+        Err(e) => {
+            eprintln!("{}", t!("error", error = e));
+            Experience::default()
+        }
+    }
+}
+
+/// Add the outcome of the game to the experience of the engine
+fn learn_from(board: &Board, result: GameOver) {
+    let path = Experience::default_path(board.width(), board.height());
+    let Some(path) = path.filter(|_| {
+        !board.wraps()
+            && board.has_standard_rules()
+            && !board.has_blocked()
+            && board.handicap().is_none()
+    }) else {
+        return;
+    };
+    let winner = match result {
+        GameOver::HumanWon | GameOver::Resigned => Some(board.human_uses()),
+        GameOver::ComputerWon => Some(board.human_uses().opponent()),
+        GameOver::Tie => None,
+    };
+    let mut experience = load_experience(board);
+    experience.record(board, winner);
+    if let Err(e) = experience.save(&path) {
+        eprintln!("{}", t!("experience-save-failed", error = e));
+    }
+}
+
+/// Add the game to the statistics database
+fn save_game(board: &Board, result: GameOver, stats: &GameStats) {
+    let Some(db) = StatsDb::open_default() else {
+        return;
+    };
+    if let Err(e) = db.append(&GameRecord::new(board, result, stats.accuracy)) {
+        eprintln!(
+            "{}",
+            t!("stats-save-failed", path = db.path().display(), error = e)
+        );
+    }
+}
+
+/// Re-estimate the player's skill from the recorded games and store it in the profile.
+/// Announces the estimate when it changes.
+fn update_skill(quiet: bool) {
+    let (Some(db), Some(path)) = (StatsDb::open_default(), Profile::default_path()) else {
+        return;
+    };
+    let Some(estimate) = db.load().ok().and_then(|games| estimate_skill(&games)) else {
+        return;
+    };
+    let Ok(mut profile) = Profile::load(path) else {
+        return;
+    };
+    let previous = profile.get("skill").and_then(Skill::from_name);
+    profile.set("skill", estimate.skill.name());
+    profile.set("recommended-preset", estimate.skill.recommended_preset());
+    save_profile(&profile);
+    if previous != Some(estimate.skill) && !quiet {
+        println!(
+            "{}\n",
+            t!(
+                "skill-estimate",
+                games = SKILL_GAMES,
+                skill = skill_name(estimate.skill),
+                preset = estimate.skill.recommended_preset(),
+            )
+        );
+    }
+}
+
+fn skill_name(skill: Skill) -> &'static str {
+    match skill {
+        Skill::Beginner => t!("skill-beginner"),
+        Skill::Intermediate => t!("skill-intermediate"),
+        Skill::Advanced => t!("skill-advanced"),
+        Skill::Expert => t!("skill-expert"),
+    }
+}
+
+/// Print the statistics of all recorded games and the player's estimated skill
+fn show_stats() {
+    let Some(db) = StatsDb::open_default() else {
+        return;
+    };
+    let games = db.load().unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let count = |result| games.iter().filter(|game| game.result == result).count();
+    println!(
+        "{}",
+        t!(
+            "stats-games",
+            games = games.len(),
+            won = games.iter().filter(|game| game.result.human_won()).count(),
+            lost = count(GameOver::ComputerWon),
+            tied = count(GameOver::Tie),
+        )
+    );
+    let rated: Vec<f64> = games
+        .iter()
+        .filter_map(|game| Some(game.accuracy?.percent()))
+        .collect();
+    if !rated.is_empty() {
+        let average = rated.iter().sum::<f64>() / rated.len() as f64;
+        println!(
+            "{}",
+            t!(
+                "stats-average-accuracy",
+                percent = format!("{:.0}", average)
+            )
+        );
+    }
+    match estimate_skill(&games) {
+        Some(estimate) => println!(
+            "{}",
+            t!(
+                "stats-skill",
+                games = SKILL_GAMES,
+                skill = skill_name(estimate.skill),
+                preset = estimate.skill.recommended_preset(),
+            )
+        ),
+        None => println!(
+            "{}",
+            t!("stats-no-skill", games = SKILL_GAMES, played = games.len())
+        ),
+    }
+}
+
+/// Print heatmaps of the human's first moves on the given board size, or the one played
+/// most, and optionally write them to an SVG file
+fn show_heatmap(dimension: Option<usize>, svg: Option<&std::path::Path>) {
+    let Some(db) = StatsDb::open_default() else {
+        return;
+    };
+    let games = db.load().unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let played = |dim| games.iter().filter(|game| game.is_dim(dim)).count();
+    let Some(dim) = dimension.or_else(|| {
+        games
+            .iter()
+            .filter(|game| game.width == game.height)
+            .map(|game| game.width)
+            .max_by_key(|&dim| played(dim))
+    }) else {
+        println!("{}", t!("no-games"));
+        return;
+    };
+    let first = Heatmap::first_moves(&games, dim);
+    let won = Heatmap::winning_first_moves(&games, dim);
+    let first_title = t!("heatmap-first-moves", dim = dim, games = first.total());
+    let won_title = t!("heatmap-winning-moves", games = won.total());
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    println!("{}\n{}", first_title, first.render(color));
+    println!("{}\n{}", won_title, won.render(color));
+    if let Some(path) = svg {
+        write_svg(
+            path,
+            &heatmap::to_svg(&[(&first_title, &first), (&won_title, &won)]),
+        );
+    }
+}
+
+fn write_svg(path: &std::path::Path, image: &str) {
+    if let Err(e) = std::fs::write(path, image) {
+        eprintln!(
+            "{}",
+            t!(
+                "error",
+                error = t!("write-failed", path = path.display(), error = e)
+            )
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Show the record of the last recorded game or the given one (counting from 1) as a QR
+/// code, to scan it on another device
+fn share(game: Option<usize>, svg: Option<&std::path::Path>) {
+    let games = load_games();
+    let Some((n, record)) = select_game(&games, game) else {
+        println!("{}", t!("no-games"));
+        return;
+    };
+    let line = record.to_line();
+    let qr = QrCode::encode(&line).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    println!("{}", t!("share-title", game = n));
+    print!("{}", qr.render());
+    println!("{}", line);
+    if let Some(path) = svg {
+        write_svg(path, &qr.to_svg());
+    }
+}
+
+/// Show the engine's score of every move in the position given with `--position`
+fn analyze_position(args: &AppArgs) {
+    let board = new_board(args);
+    let mut engine = args
+        .engine
+        .clone()
+        .unwrap_or_default()
+        .build(&engine_config(args));
+    let side = board.to_move();
+    println!("{}", t!("score-map", symbol = side));
+    print_score_map(engine.as_mut(), &board, side);
+    if let Some(e) = engine.take_error() {
+        eprintln!("{}", e);
+    }
+}
+
+/// Prove the result of the position given with `--position`, or the empty board, and show
+/// the optimal line
+fn solve(args: &AppArgs) {
+    let board = new_board(args);
+    let to_move = board.to_move();
+    let Some(solution) = solver::solve(&board, to_move) else {
+        prove(&board, to_move);
+        return;
     };
-    println!("{}\n", won);
     println!("{}", board);
+    println!("{}", t!("solve-to-move", symbol = to_move));
+    match solution.winner {
+        Some(winner) => println!("{}", t!("solve-win", symbol = winner)),
+        None => println!("{}", t!("solve-draw")),
+    }
+    println!("{}", t!("solve-line", moves = format_moves(&solution.line)));
 }
 
-fn parse_args() -> Result<AppArgs, pico_args::Error> {
-    let mut pargs = pico_args::Arguments::from_env();
+/// Prove the result of a position too large to search completely with a proof-number
+/// search for each side, showing the winning move instead of the line
+fn prove(board: &Board, to_move: Cell) {
+    let undecided = || {
+        eprintln!(
+            "{}",
+            t!(
+                "error",
+                error = t!("solve-undecided", nodes = pns::MAX_NODES)
+            )
+        );
+        std::process::exit(1);
+    };
+    println!("{}", board);
+    if let Some(winner) = board.winner() {
+        println!("{}", t!("solve-win", symbol = winner));
+        return;
+    }
+    println!("{}", t!("solve-to-move", symbol = to_move));
+    let Some(own) = pns::prove(board, to_move, to_move, pns::MAX_NODES) else {
+        return undecided();
+    };
+    let mut nodes = own.nodes;
+    if let Some(mv) = own.first_move {
+        println!("{}", t!("solve-win", symbol = to_move));
+        println!("{}", t!("solve-winning-move", moves = format_moves(&[mv])));
+    } else {
+        let opponent = to_move.opponent();
+        let Some(other) = pns::prove(board, to_move, opponent, pns::MAX_NODES) else {
+            return undecided();
+        };
+        nodes += other.nodes;
+        if other.wins {
+            println!("{}", t!("solve-win", symbol = opponent));
+        } else {
+            println!("{}", t!("solve-draw"));
+        }
+    }
+    println!("{}", t!("solve-proof", nodes = nodes));
+}
 
-    if pargs.contains(["-h", "--help"]) {
-        print!("{}", HELP);
-        std::process::exit(0);
+/// Count the move sequences from the position given with `--position`, or the empty board,
+/// for every depth up to `--depth`, with the time taken
+fn count_sequences(args: &AppArgs) {
+    let board = new_board(args);
+    let to_move = board.to_move();
+    let blanks = board.legal_moves().count();
+    let depth = args.depth.unwrap_or(PERFT_DEPTH.min(blanks));
+    println!("{}", board);
+    for depth in 1..=depth {
+        let start = Instant::now();
+        let sequences = perft::perft(&board, to_move, depth);
+        let elapsed = start.elapsed();
+        println!(
+            "{}",
+            t!(
+                "perft-depth",
+                depth = depth,
+                sequences = sequences,
+                ms = elapsed.as_millis(),
+                rate = (sequences as f64 / elapsed.as_secs_f64().max(1e-9)) as u64
+            )
+        );
     }
+    if args.verbosity.unwrap_or(Verbosity::Normal) >= Verbosity::Verbose {
+        for (mv, sequences) in perft::divide(&board, to_move, depth) {
+            println!(
+                "  {}",
+                t!(
+                    "perft-move",
+                    x = mv.col + 1,
+                    y = mv.row + 1,
+                    sequences = sequences
+                )
+            );
+        }
+    }
+}
 
-    let args = AppArgs {
-        dimension: pargs.opt_value_from_str("-d")?.unwrap_or(4),
-        computer_begins: pargs.contains("-c"),
-        player_uses_o: pargs.contains("-o"),
+/// Play the engines given with `--entrants` against each other and print their ratings
+fn rate_engines(args: &AppArgs) {
+    let entrants = args.entrants.clone().unwrap_or_else(|| {
+        parse_entrants(ROUND_ROBIN_ENTRANTS).expect("the default entrants are valid")
+    });
+    let games = args.games.unwrap_or(ROUND_ROBIN_GAMES);
+    let mut tournament = RoundRobin::new(square_dim(&new_board(args)), entrants, games);
+    tournament.threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from));
+    tournament.book = !args.no_book;
+    tournament.eval_weights = args.eval_weights.unwrap_or_default();
+    tournament.contempt = args.contempt.unwrap_or_default();
+    tournament.think_time = args.think_ms.map(Duration::from_millis);
+    tournament.seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let standings = tournament.standings(&tournament.run());
+    let width = standings
+        .iter()
+        .map(|standing| standing.name.len())
+        .chain([t!("rating-entrant").len()])
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{}",
+        t!(
+            "rating-summary",
+            games = games,
+            dim = tournament.dim,
+            seed = tournament.seed
+        )
+    );
+    println!(
+        "{:>4}  {:<width$}  {:>6}  {:>6}  {}",
+        "",
+        t!("rating-entrant"),
+        t!("rating-elo"),
+        t!("rating-points"),
+        t!("rating-results")
+    );
+    for (rank, standing) in standings.iter().enumerate() {
+        let score = standing.score;
+        println!(
+            "{:>4}  {:<width$}  {:>6.0}  {:>6.1}  +{} ={} -{}",
+            rank + 1,
+            standing.name,
+            standing.rating,
+            score.points(),
+            score.wins,
+            score.draws,
+            score.losses
+        );
+    }
+}
+
+/// Let the engine play against itself, or the engine given with `--opponent`, and print
+/// the positions of the games as JSON lines, with a summary on stderr
+fn self_play(args: &AppArgs) {
+    let run = self_play_run(args, args.games.unwrap_or(SELF_PLAY_GAMES));
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let (mut x_wins, mut o_wins, mut draws) = (0, 0, 0);
+    run.run(|game| {
+        match game.winner {
+            Some(Cell::X) => x_wins += 1,
+            Some(_) => o_wins += 1,
+            None => draws += 1,
+        }
+        if let Err(e) = out.write_all(game.to_jsonl().as_bytes()) {
+            eprintln!("{}", t!("error", error = e));
+            std::process::exit(1);
+        }
+    });
+    if let Err(e) = out.flush() {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    }
+    eprintln!(
+        "{}",
+        t!(
+            "selfplay-summary",
+            games = run.games,
+            seed = run.seed,
+            x = x_wins,
+            o = o_wins,
+            draws = draws
+        )
+    );
+}
+
+/// Play `--games` games between the engine and the one given with `--opponent`, taking
+/// turns at beginning, and print the rates of the outcomes with confidence intervals
+fn simulate(args: &AppArgs) {
+    let run = self_play_run(args, args.games.unwrap_or(SIMULATE_GAMES));
+    let result = simulation::play_match(&run);
+    let name = |kind: &EngineKind| match kind {
+        EngineKind::Heuristic => "heuristic".to_string(),
+        EngineKind::Minimax => "minimax".to_string(),
+        EngineKind::External(path) => path.display().to_string(),
     };
+    let (mut a, mut b) = (name(&run.engines[0]), name(&run.engines[1]));
+    if a == b {
+        a.push_str(" #1");
+        b.push_str(" #2");
+    }
+    println!(
+        "{}",
+        t!(
+            "simulate-summary",
+            games = result.games(),
+            dim = run.dim,
+            seed = run.seed,
+            a = a,
+            b = b
+        )
+    );
+    let rows = [
+        (t!("simulate-a-won", a = a), result.a_wins),
+        (t!("simulate-b-won", b = b), result.b_wins),
+        (t!("simulate-draws").to_string(), result.draws),
+        (t!("simulate-first-won").to_string(), result.first_wins),
+        (t!("simulate-second-won").to_string(), result.second_wins),
+    ];
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (label, count) in rows {
+        let (low, high) = simulation::confidence_interval(count, result.games());
+        let rate = count as f64 / result.games().max(1) as f64;
+        println!(
+            "  {}{}  {:>5.1}%  ({:.1}% - {:.1}%)",
+            label,
+            " ".repeat(width - label.chars().count()),
+            rate * 100.0,
+            low * 100.0,
+            high * 100.0
+        );
+    }
+}
 
-    let remaining = pargs.finish();
-    if !remaining.is_empty() {
-        println!("Invalid arguments: {:?}.\n", remaining);
-        print!("{}", HELP);
+/// The self-play settings given on the command line
+fn self_play_run(args: &AppArgs, games: usize) -> SelfPlay {
+    let engine = args.engine.clone().unwrap_or_default();
+    let mut run = SelfPlay::new(square_dim(&new_board(args)), games);
+    run.threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from));
+    run.engines = [engine.clone(), args.opponent.clone().unwrap_or(engine)];
+    run.book = !args.no_book;
+    run.personality = args.personality.unwrap_or_default();
+    run.eval_weights = args.eval_weights.unwrap_or_default();
+    run.contempt = args.contempt.unwrap_or_default();
+    run.think_time = args.think_ms.map(Duration::from_millis);
+    run.seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    run
+}
+
+/// Generate the tablebase for the board size and store it where the engine finds it
+fn build_tablebase(args: &AppArgs) {
+    let dim = square_dim(&new_board(args));
+    let Some(path) = Tablebase::default_path(dim) else {
+        return;
+    };
+    if dim > tablebase::MAX_DIM {
+        eprintln!(
+            "{}",
+            t!(
+                "error",
+                error = t!("tablebase-too-large", limit = tablebase::MAX_DIM)
+            )
+        );
+        std::process::exit(1);
+    }
+    println!("{}", t!("tablebase-building", dim = dim));
+    let tablebase = Tablebase::generate(dim).expect("the board size is supported");
+    if let Err(e) = tablebase.save(&path) {
+        eprintln!(
+            "{}",
+            t!("error", error = format!("{}: {}", path.display(), e))
+        );
         std::process::exit(1);
     }
+    println!(
+        "{}",
+        t!(
+            "tablebase-built",
+            positions = tablebase.positions(),
+            path = path.display()
+        )
+    );
+}
 
-    Ok(args)
+/// Show the value of the position and the best moves from the tablebase
+fn query_tablebase(args: &AppArgs) {
+    let board = new_board(args);
+    let dim = square_dim(&board);
+    let Some(tablebase) = Tablebase::default_path(dim).and_then(|p| Tablebase::load(p).ok()) else {
+        eprintln!(
+            "{}",
+            t!("error", error = t!("tablebase-missing", dim = dim))
+        );
+        std::process::exit(1);
+    };
+    let to_move = board.to_move();
+    println!("{}", board);
+    println!("{}", t!("solve-to-move", symbol = to_move));
+    match tablebase.value(&board) {
+        Some(Value::Win) => println!("{}", t!("solve-win", symbol = to_move)),
+        Some(Value::Loss) => println!("{}", t!("solve-win", symbol = to_move.opponent())),
+        _ => println!("{}", t!("solve-draw")),
+    }
+    let moves = tablebase.best_moves(&board, to_move);
+    println!("{}", t!("tablebase-moves", moves = format_moves(&moves)));
+}
+
+/// Check every game in the given file, or the games database, and exit with an error if
+/// any of them couldn't have been played as recorded
+fn verify(file: Option<&std::path::Path>) {
+    let Some(path) = file
+        .map(std::path::Path::to_path_buf)
+        .or_else(|| Some(StatsDb::open_default()?.path().to_path_buf()))
+    else {
+        return;
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if file.is_none() && e.kind() == io::ErrorKind::NotFound => {
+            println!("{}", t!("no-games"));
+            return;
+        }
+        Err(e) => {
+            eprintln!(
+                "{}",
+                t!("error", error = format!("{}: {}", path.display(), e))
+            );
+            std::process::exit(1);
+        }
+    };
+    let mut games = 0;
+    let mut valid = 0;
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        games += 1;
+        match GameRecord::parse_verified(line) {
+            Ok(_) => valid += 1,
+            Err(e) => println!("{}", t!("verify-invalid", line = idx + 1, error = e)),
+        }
+    }
+    println!("{}", t!("verify-summary", valid = valid, games = games));
+    if valid < games {
+        std::process::exit(1);
+    }
+}
+
+/// Print the engine's evaluation of every move of the last recorded game, the given one
+/// (counting from 1) or all of them
+fn analyze(game: Option<usize>, all: bool, format: Format) {
+    let games = load_games();
+    let selected: Vec<(usize, &GameRecord)> = if all {
+        games
+            .iter()
+            .enumerate()
+            .map(|(idx, g)| (idx + 1, g))
+            .collect()
+    } else {
+        select_game(&games, game).into_iter().collect()
+    };
+    if selected.is_empty() && format == Format::Text {
+        println!("{}", t!("no-games"));
+        return;
+    }
+    if format == Format::Csv {
+        println!("{}", CSV_HEADER);
+    }
+    for (n, record) in selected {
+        // records which can't be replayed were written by hand or by another version
+        let Some(board) = record.board() else {
+            continue;
+        };
+        let moves = analysis::analyze(&board);
+        if format == Format::Csv {
+            for mv in moves {
+                println!("{}", mv.to_csv(n));
+            }
+            continue;
+        }
+        println!(
+            "{}",
+            t!(
+                "analyze-game",
+                game = n,
+                width = record.width,
+                height = record.height,
+                result = record.result
+            )
+        );
+        for mv in moves {
+            let best: Vec<String> = mv.best_moves.iter().map(Coord::to_string).collect();
+            println!(
+                "  {}",
+                t!(
+                    "analyze-move",
+                    ply = mv.ply,
+                    player = mv.player,
+                    x = mv.x + 1,
+                    y = mv.y + 1,
+                    mark = format!("{:2}", mv.quality.mark()),
+                    score = mv.score,
+                    best_score = mv.best_score,
+                    best = best.join(", "),
+                )
+            );
+            if !mv.fork_moves.is_empty() {
+                let forks: Vec<String> = mv.fork_moves.iter().map(Coord::to_string).collect();
+                println!("    {}", t!("analyze-forks", moves = forks.join(", ")));
+            }
+        }
+    }
+}
+
+/// Show the moves of a recorded game up to the given move and optionally continue playing
+/// against the computer from there
+fn replay(args: &AppArgs, config: &Config, human_name: &str, computer_name: &str) {
+    let games = load_games();
+    let Some((n, record)) = select_game(&games, args.game) else {
+        println!("{}", t!("no-games"));
+        return;
+    };
+    let fail = |error: String| -> ! {
+        eprintln!("{}", t!("error", error = error));
+        std::process::exit(1);
+    };
+    let Some(mut board) = record.board() else {
+        fail(t!("invalid-game", game = n));
+    };
+    let moves = board.history().len();
+    let keep = args.from_move.unwrap_or(moves);
+    if keep > moves {
+        fail(t!("game-too-short", game = n, moves = moves));
+    }
+    while board.history().len() > keep {
+        board.undo();
+    }
+    for ply in 1..=keep {
+        let mut position = board.clone();
+        for _ in ply..keep {
+            position.undo();
+        }
+        let mv = position.history()[ply - 1];
+        println!(
+            "{}",
+            t!(
+                "replay-move",
+                ply = ply,
+                player = position.get_cell(mv),
+                x = mv.col + 1,
+                y = mv.row + 1
+            )
+        );
+        println!("{}", position);
+    }
+    if !args.resume {
+        return;
+    }
+    if let Some(over) = board.result() {
+        println!("{}", t!("replay-game-over", result = over));
+        return;
+    }
+    println!("{}", t!("replay-continue", moves = keep));
+    play(
+        args,
+        config,
+        human_name,
+        computer_name,
+        board,
+        args.computer_begins,
+    );
+}
+
+/// All recorded games
+fn load_games() -> Vec<GameRecord> {
+    let Some(db) = StatsDb::open_default() else {
+        return Vec::new();
+    };
+    db.load().unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    })
+}
+
+/// The recorded game with the given number (counting from 1), or the last one, together
+/// with its number. Exits if there is no such game.
+fn select_game(games: &[GameRecord], game: Option<usize>) -> Option<(usize, &GameRecord)> {
+    let Some(n) = game else {
+        return games.last().map(|g| (games.len(), g));
+    };
+    match n.checked_sub(1).and_then(|idx| games.get(idx)) {
+        Some(record) => Some((n, record)),
+        None => {
+            eprintln!(
+                "{}",
+                t!(
+                    "error",
+                    error = t!("no-such-game", game = n, games = games.len())
+                )
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Let the human solve today's puzzle, once per day, and keep track of the solve streak
+/// in the profile
+fn play_daily() {
+    let day = puzzle::today();
+    let mut profile = Profile::default_path().and_then(|path| Profile::load(path).ok());
+    let mut record = profile.as_ref().map(DailyRecord::load).unwrap_or_default();
+    if record.played == Some(day) {
+        println!(
+            "{}",
+            t!("daily-already-played", streak = record.streak_on(day))
+        );
+        return;
+    }
+    let puzzle = Puzzle::daily(day);
+    let mut board = puzzle.board.clone();
+    println!("{}", t!("daily-title", date = puzzle::format_day(day)));
+    println!("{}", board);
+    if let Err(e) = user_move(&mut board, t!("enter-move"), false) {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(2);
+    }
+    let solved = puzzle.is_solution(*board.history().last().unwrap());
+    if solved {
+        println!("{}", t!("daily-solved"));
+    } else {
+        let solutions: Vec<String> = puzzle
+            .solutions()
+            .iter()
+            .map(|mv| format!("`{}`", mv))
+            .collect();
+        println!("{}", t!("daily-failed", solutions = solutions.join(", ")));
+    }
+    record.record(day, solved);
+    println!("{}", t!("daily-streak", streak = record.streak));
+    if let Some(profile) = profile.as_mut() {
+        record.store(profile);
+        save_profile(profile);
+    }
+}
+
+/// Play ultimate tic-tac-toe against the computer, X moving first
+fn play_ultimate(args: &AppArgs) {
+    let human = if args.computer_begins || args.player_uses_o {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    println!("{}", t!("ultimate-title", symbol = human));
+    play_variant(&mut UltimateBoard::new(), human, false);
+}
+
+/// Play 3D tic-tac-toe on a cube with -d [n] cells along each side against the computer
+fn play_cube(args: &AppArgs) {
+    let dim = match args.dimension {
+        Some(_) => square_dim(&new_board(args)),
+        None => cube::DEFAULT_DIM,
+    };
+    let mut board = Cube::build(dim).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let human = if args.computer_begins || args.player_uses_o {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    println!("{}", t!("cube-title", symbol = human, dim = dim));
+    play_variant(&mut board, human, false);
+}
+
+/// Play against the computer on a hex board with -d [n] cells along each side, won by -k [n]
+/// in a row (default: the side length), X moving first
+fn play_hex(args: &AppArgs) {
+    let size = match args.dimension {
+        Some(_) => square_dim(&new_board(args)),
+        None => hex::DEFAULT_SIZE,
+    };
+    let win_length = args.win_length.unwrap_or(size);
+    let mut board = HexBoard::build(size, win_length).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let human = if args.computer_begins || args.player_uses_o {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    println!(
+        "{}",
+        t!(
+            "hex-title",
+            symbol = human,
+            size = size,
+            length = win_length
+        )
+    );
+    play_variant(&mut board, human, false);
+}
+
+/// Play with --players [n] players on a board of twice that size, four in a row winning
+/// unless -d or -k say otherwise, the computer making the moves of all but the human
+fn play_multi(args: &AppArgs) {
+    let players = args.players.unwrap_or(MULTI_PLAYERS);
+    let (width, height) = args.dimension.unwrap_or((2 * players, 2 * players));
+    let win_length = args.win_length.unwrap_or(4.min(width).min(height));
+    let mut board = MultiBoard::build(width, height, win_length, players).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let human = if args.computer_begins {
+        Seat(players - 1)
+    } else {
+        Seat(0)
+    };
+    println!(
+        "{}",
+        t!(
+            "multi-title",
+            players = players,
+            symbol = human,
+            length = win_length
+        )
+    );
+    play_variant(&mut board, human, false);
+}
+
+/// Play Three Men's Morris against the computer on a board with -d [n] cells along each
+/// side, X moving first
+fn play_morris(args: &AppArgs) {
+    let dim = match args.dimension {
+        Some(_) => square_dim(&new_board(args)),
+        None => morris::DEFAULT_DIM,
+    };
+    let mut board = MorrisBoard::build(dim).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let human = if args.computer_begins || args.player_uses_o {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    println!(
+        "{}",
+        t!(
+            "morris-title",
+            symbol = human,
+            dim = dim,
+            limit = morris::MOVE_LIMIT
+        )
+    );
+    play_variant(&mut board, human, false);
+}
+
+/// Play against the computer on a board of -d [n] (default: 3x3) on which every piece
+/// disappears after --fade [n] of its owner's later moves, by default the win length
+fn play_fading(args: &AppArgs) {
+    let (width, height) = args.dimension.unwrap_or((3, 3));
+    let win_length = args.win_length.unwrap_or(width.min(height));
+    let lifetime = args.fade.unwrap_or(win_length);
+    let mut board = FadingBoard::build(width, height, win_length, lifetime).unwrap_or_else(|e| {
+        eprintln!("{}", t!("error", error = e));
+        std::process::exit(1);
+    });
+    let human = if args.computer_begins || args.player_uses_o {
+        Cell::O
+    } else {
+        Cell::X
+    };
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    println!(
+        "{}",
+        t!(
+            "fading-title",
+            symbol = human,
+            length = win_length,
+            lifetime = lifetime,
+            limit = fading::MOVE_LIMIT
+        )
+    );
+    play_variant(&mut board, human, color);
+}
+
+/// A variant played by [`play_variant`], the computer making the moves of every player but
+/// the human
+trait VariantGame {
+    /// Who makes a move: X or O, or the seat of one of several players
+    type Player: Copy + PartialEq;
+    /// How a game ended
+    type Over: fmt::Display;
+
+    /// The player to move
+    fn to_move(&self) -> Self::Player;
+
+    /// How the game ended for the human, `None` while it goes on
+    fn outcome(&self, human: Self::Player) -> Option<Self::Over>;
+
+    /// The board as shown before every move, with colors if `color` is set
+    fn show(&self, color: bool) -> String;
+
+    /// Make the move of the computer for the player to move, returning the message
+    /// announcing it
+    fn computer_move(&mut self) -> String;
+
+    /// Ask the human for a move
+    fn prompt(&self) -> String;
+
+    /// Make the move the human entered, or return why it can't be made
+    fn play_input(&mut self, input: &str) -> Result<(), String>;
+}
+
+/// Play a variant until it is over and print how it ended. A move which can't be made is
+/// asked for again, or ends the program when the moves don't come from a terminal.
+fn play_variant<G: VariantGame>(board: &mut G, human: G::Player, color: bool) {
+    let interactive = io::stdin().is_terminal();
+    let over = loop {
+        if let Some(over) = board.outcome(human) {
+            break over;
+        }
+        print!("{}", board.show(color));
+        if board.to_move() != human {
+            println!("{}", board.computer_move());
+            continue;
+        }
+        println!("{}", board.prompt());
+        let input = read_move_line();
+        if let Err(e) = board.play_input(&input) {
+            if !interactive {
+                eprintln!("{}", t!("error", error = e));
+                std::process::exit(2);
+            }
+            println!("{}", e);
+        }
+    };
+    print!("{}", board.show(color));
+    println!("{}", over);
+}
+
+// how a game of two players ended for the human playing `human`, `None` unless it is over
+fn two_player_outcome(over: bool, winner: Option<Cell>, human: Cell) -> Option<GameOver> {
+    over.then(|| match winner {
+        Some(winner) if winner == human => GameOver::HumanWon,
+        Some(_) => GameOver::ComputerWon,
+        None => GameOver::Tie,
+    })
+}
+
+impl VariantGame for UltimateBoard {
+    type Player = Cell;
+    type Over = GameOver;
+
+    fn to_move(&self) -> Cell {
+        UltimateBoard::to_move(self)
+    }
+
+    fn outcome(&self, human: Cell) -> Option<GameOver> {
+        two_player_outcome(self.is_over(), self.winner(), human)
+    }
+
+    fn show(&self, _color: bool) -> String {
+        self.to_string()
+    }
+
+    fn computer_move(&mut self) -> String {
+        let mv = variant::best_move(self, ultimate::SEARCH_DEPTH).unwrap();
+        self.play(mv).unwrap();
+        t!("ultimate-computer-move", cell = mv)
+    }
+
+    fn prompt(&self) -> String {
+        match self.target() {
+            Some(b) => t!("ultimate-enter-move", board = b + 1),
+            None => t!("ultimate-enter-move-any").to_string(),
+        }
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let mv = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(mv).map_err(|e| e.to_string())
+    }
+}
+
+impl VariantGame for Cube {
+    type Player = Cell;
+    type Over = GameOver;
+
+    fn to_move(&self) -> Cell {
+        Cube::to_move(self)
+    }
+
+    fn outcome(&self, human: Cell) -> Option<GameOver> {
+        two_player_outcome(self.is_over(), self.winner(), human)
+    }
+
+    fn show(&self, _color: bool) -> String {
+        self.to_string()
+    }
+
+    fn computer_move(&mut self) -> String {
+        let (x, y, z) = self.best_move().unwrap();
+        self.play(x, y, z).unwrap();
+        t!("cube-computer-move", x = x + 1, y = y + 1, z = z + 1)
+    }
+
+    fn prompt(&self) -> String {
+        t!("cube-enter-move").to_string()
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let (x, y, z) = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(x, y, z).map_err(|e| e.to_string())
+    }
+}
+
+impl VariantGame for HexBoard {
+    type Player = Cell;
+    type Over = GameOver;
+
+    fn to_move(&self) -> Cell {
+        HexBoard::to_move(self)
+    }
+
+    fn outcome(&self, human: Cell) -> Option<GameOver> {
+        two_player_outcome(self.is_over(), self.winner(), human)
+    }
+
+    fn show(&self, _color: bool) -> String {
+        self.to_string()
+    }
+
+    fn computer_move(&mut self) -> String {
+        let (q, r) = self.best_move().unwrap();
+        self.play(q, r).unwrap();
+        t!("hex-computer-move", q = q, r = r)
+    }
+
+    fn prompt(&self) -> String {
+        t!("hex-enter-move").to_string()
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let (q, r) = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(q, r).map_err(|e| e.to_string())
+    }
+}
+
+impl VariantGame for MultiBoard {
+    type Player = Seat;
+    type Over = MultiGameOver;
+
+    fn to_move(&self) -> Seat {
+        MultiBoard::to_move(self)
+    }
+
+    fn outcome(&self, _human: Seat) -> Option<MultiGameOver> {
+        self.result()
+    }
+
+    fn show(&self, _color: bool) -> String {
+        self.to_string()
+    }
+
+    fn computer_move(&mut self) -> String {
+        let player = MultiBoard::to_move(self);
+        let mv = self.best_move().unwrap();
+        self.play(mv).unwrap();
+        t!("multi-computer-move", mark = player, cell = mv)
+    }
+
+    fn prompt(&self) -> String {
+        t!("enter-move").to_string()
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let mv = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(mv).map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+impl VariantGame for MorrisBoard {
+    type Player = Cell;
+    type Over = GameOver;
+
+    fn to_move(&self) -> Cell {
+        MorrisBoard::to_move(self)
+    }
+
+    fn outcome(&self, human: Cell) -> Option<GameOver> {
+        two_player_outcome(self.is_over(), self.winner(), human)
+    }
+
+    fn show(&self, _color: bool) -> String {
+        self.to_string()
+    }
+
+    fn computer_move(&mut self) -> String {
+        let mv = variant::best_move(self, morris::SEARCH_DEPTH).unwrap();
+        self.play(mv).unwrap();
+        t!("morris-computer-move", move = mv)
+    }
+
+    fn prompt(&self) -> String {
+        if self.placing() {
+            let left = self.pieces_left(MorrisBoard::to_move(self));
+            t!("morris-enter-place", left = left)
+        } else {
+            t!("morris-enter-slide").to_string()
+        }
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let mv = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(mv).map_err(|e| e.to_string())
+    }
+}
+
+impl VariantGame for FadingBoard {
+    type Player = Cell;
+    type Over = GameOver;
+
+    fn to_move(&self) -> Cell {
+        FadingBoard::to_move(self)
+    }
+
+    fn outcome(&self, human: Cell) -> Option<GameOver> {
+        two_player_outcome(self.is_over(), self.winner(), human)
+    }
+
+    fn show(&self, color: bool) -> String {
+        self.render(color)
+    }
+
+    fn computer_move(&mut self) -> String {
+        let mv = variant::best_move(self, fading::SEARCH_DEPTH).unwrap();
+        self.play(mv).unwrap();
+        t!("fading-computer-move", cell = mv)
+    }
+
+    fn prompt(&self) -> String {
+        t!("enter-move").to_string()
+    }
+
+    fn play_input(&mut self, input: &str) -> Result<(), String> {
+        let mv = self.parse_input(input).map_err(|e| e.to_string())?;
+        self.play(mv).map_err(|e| e.to_string())
+    }
+}
+
+/// Ask for a move of the human with the given prompt and make it, see [`read_move`]
+fn user_move(board: &mut Board, prompt: &str, bell: bool) -> Result<Option<GameOver>, InputError> {
+    let start = Instant::now();
+    let mv = read_move(board, board.human_uses(), prompt, bell, |_, _| None)?
+        .expect("there are no commands to stop asking");
+    let over = board.apply_move(mv).expect("the move was checked");
+    board.set_last_move_time(start.elapsed());
+    Ok(over)
+}
+
+/// Ask for a move of the player using `mark` with the given prompt. Every line entered is
+/// passed to `command` first, trimmed; if the line was a command, it carries it out, e.g.
+/// showing a hint, and returns whether to ask again or to stop asking and return `None`.
+///
+/// In a terminal invalid input is rejected with a message, ringing the bell with `bell`, and
+/// the user is asked again. Otherwise, e.g. for moves piped in by a script, lines are read
+/// without prompting and the first error is returned.
+fn read_move(
+    board: &Board,
+    mark: Cell,
+    prompt: &str,
+    bell: bool,
+    mut command: impl FnMut(&Board, &str) -> Option<ControlFlow<()>>,
+) -> Result<Option<Move>, InputError> {
+    let interactive = io::stdin().is_terminal();
+    loop {
+        if interactive {
+            println!("{}", prompt);
+        }
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => return Err(InputError::Eof),
+            Ok(_) => {}
+            Err(e) if interactive => {
+                reject(t!("read-failed", error = e), bell);
+                continue;
+            }
+            Err(e) => return Err(InputError::Io(e)),
+        }
+        match command(board, input.trim()) {
+            Some(ControlFlow::Continue(())) => continue,
+            Some(ControlFlow::Break(())) => return Ok(None),
+            None => {}
+        }
+        let result = board.parse_input(&input).and_then(|coord| {
+            let mv = Move { coord, mark };
+            board
+                .check_move(mv)
+                .map(|()| mv)
+                .map_err(|e| InputError::Invalid(e.to_string()))
+        });
+        match result {
+            Ok(mv) => return Ok(Some(mv)),
+            Err(e) if interactive => reject(e, bell),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Tell the user why the input was rejected
+fn reject(msg: impl std::fmt::Display, bell: bool) {
+    if bell {
+        print!("\x07");
+    }
+    println!("{}", msg);
+}
+
+/// Read a line with a move for the variants, exiting at the end of the input
+fn read_move_line() -> String {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) => {
+            eprintln!("{}", t!("error", error = t!("unexpected-eof")));
+            std::process::exit(2);
+        }
+        Ok(_) => input,
+        Err(e) => {
+            eprintln!("{}", t!("error", error = t!("read-failed", error = e)));
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Generate a puzzle with a forced win in `--moves` moves and let the player find it
+/// against the best defence
+fn play_puzzle(args: &AppArgs) {
+    let dim = match args.dimension {
+        Some(_) => square_dim(&new_board(args)),
+        None => 3,
+    };
+    let moves = args.moves.unwrap_or(PUZZLE_MOVES).max(1);
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let Some(puzzle) = WinPuzzle::generate(dim, moves, &mut Rng::new(seed)) else {
+        eprintln!(
+            "{}",
+            t!("error", error = t!("puzzle-none", dim = dim, moves = moves))
+        );
+        std::process::exit(1);
+    };
+    let mut board = puzzle.board.clone();
+    let mut defender = EngineKind::Minimax.build(&EngineConfig {
+        book: false,
+        tablebases: true,
+        ..EngineConfig::default()
+    });
+    println!("{}", t!("puzzle-title", moves = moves, seed = seed));
+    for left in (1..=moves).rev() {
+        println!("{}", board);
+        let before = board.clone();
+        if let Err(e) = user_move(&mut board, t!("enter-move"), false) {
+            eprintln!("{}", t!("error", error = e));
+            std::process::exit(2);
+        }
+        let mv = *board.history().last().unwrap();
+        if !puzzle::wins_with(&before, Cell::X, mv, left) {
+            let solutions: Vec<Coord> = before
+                .legal_moves()
+                .filter(|&mv| puzzle::wins_with(&before, Cell::X, mv, left))
+                .collect();
+            println!(
+                "{}",
+                t!("puzzle-failed", solutions = format_moves(&solutions))
+            );
+            return;
+        }
+        if board.winner().is_some() {
+            break;
+        }
+        board.computer_move_with(defender.as_mut());
+        print_last_move(t!("computer"), &board);
+    }
+    println!("{}", board);
+    println!("{}", t!("puzzle-solved"));
+}
+
+fn save_profile(profile: &Profile) {
+    if let Err(e) = profile.save() {
+        eprintln!(
+            "{}",
+            t!(
+                "profile-save-failed",
+                path = profile.path().display(),
+                error = e
+            )
+        );
+    }
+}
+
+/// Present opening positions and check the answers, preferring the positions answered
+/// wrongly before
+fn drill() {
+    let mut profile = Profile::default_path().and_then(|path| Profile::load(path).ok());
+    let mut progress: Vec<Progress> = drill::POSITIONS
+        .iter()
+        .map(|moves| {
+            profile
+                .as_ref()
+                .map_or_else(Progress::default, |p| Progress::load(p, moves))
+        })
+        .collect();
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut rng = Rng::new(seed);
+    let mut solved = 0;
+    for round in 1..=drill::ROUNDS {
+        let idx = drill::pick(&progress, &mut rng);
+        let moves = drill::POSITIONS[idx];
+        let mut board = drill::position(moves);
+        let answers = drill::answers(&board);
+        println!(
+            "{}",
+            t!(
+                "drill-title",
+                round = round,
+                rounds = drill::ROUNDS,
+                symbol = board.human_uses()
+            )
+        );
+        println!("{}", board);
+        if let Err(e) = user_move(&mut board, t!("enter-move"), false) {
+            eprintln!("{}", t!("error", error = e));
+            std::process::exit(2);
+        }
+        let correct = answers.contains(board.history().last().unwrap());
+        if correct {
+            solved += 1;
+            println!("{}", t!("drill-correct"));
+        } else {
+            println!("{}", t!("drill-wrong", answers = format_moves(&answers)));
+        }
+        progress[idx].record(correct);
+        if let Some(profile) = profile.as_mut() {
+            progress[idx].store(profile, moves);
+            save_profile(profile);
+        }
+    }
+    println!(
+        "{}",
+        t!("drill-summary", solved = solved, rounds = drill::ROUNDS)
+    );
+    let mut weak: Vec<(f64, &[(usize, usize)])> = progress
+        .iter()
+        .zip(drill::POSITIONS)
+        .filter_map(|(p, moves)| Some((p.success_rate()?, moves)))
+        .filter(|&(rate, _)| rate < 100.0)
+        .collect();
+    weak.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if !weak.is_empty() {
+        println!("{}", t!("drill-weak"));
+    }
+    for (rate, moves) in weak.into_iter().take(3) {
+        println!(
+            "  {}",
+            t!(
+                "drill-weak-position",
+                moves = format_moves(moves),
+                rate = format!("{:.0}", rate)
+            )
+        );
+    }
+}
+
+// Formats moves like the user enters them, separated by commas
+fn format_moves<T: Copy + Into<Coord>>(moves: &[T]) -> String {
+    let moves: Vec<String> = moves.iter().map(|&mv| mv.into().to_string()).collect();
+    moves.join(", ")
+}
+
+/// Print the statistics collected during the game
+fn print_stats(stats: &GameStats, board: &Board, players: [(&str, Cell); 2]) {
+    let [(human, _), (computer, _)] = players;
+    println!("{}", t!("stats-title"));
+    println!(
+        "  {}",
+        t!(
+            "stats-moves",
+            moves = stats.moves(),
+            human = human,
+            human_moves = stats.human_moves,
+            computer = computer,
+            computer_moves = stats.computer_moves,
+        )
+    );
+    println!(
+        "  {}",
+        t!(
+            "stats-duration",
+            duration = format_duration(stats.duration())
+        )
+    );
+    if let Some(accuracy) = stats.accuracy {
+        println!(
+            "  {}",
+            t!(
+                "stats-accuracy",
+                percent = format!("{:.0}", accuracy.percent()),
+                matched = accuracy.matched,
+                moves = accuracy.moves,
+            )
+        );
+    }
+    let moves: Vec<String> = board
+        .history()
+        .iter()
+        .zip(Quality::annotate(board))
+        .enumerate()
+        .map(|(idx, (&coord, quality))| {
+            let mv = format!("{}. {} {}", idx + 1, board.get_cell(coord), coord);
+            match quality.mark() {
+                "" => mv,
+                mark => format!("{} {}", mv, mark),
+            }
+        })
+        .collect();
+    println!("  {}", t!("stats-move-list", moves = moves.join(", ")));
+    for opening in opening::recognize(board) {
+        if !opening.trap {
+            println!("  {}", t!("stats-opening", opening = opening));
+            continue;
+        }
+        let last = board.history()[opening.plies() - 1];
+        if board.get_cell(last) == board.human_uses() {
+            println!("  {}", t!("trap-allowed", trap = opening));
+        } else {
+            println!("  {}", t!("trap-set", trap = opening));
+        }
+    }
+    if stats.takebacks > 0 {
+        println!("  {}", t!("stats-takebacks", takebacks = stats.takebacks));
+    }
+    let summaries: Vec<_> = players
+        .iter()
+        .filter_map(|&(name, cell)| Some((name, TimeSummary::of(board, cell)?)))
+        .collect();
+    if summaries.is_empty() {
+        return;
+    }
+    println!("  {}", t!("time-summary"));
+    let width = summaries
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap();
+    for (name, summary) in summaries {
+        println!(
+            "    {:width$} {}",
+            format!("{}:", name),
+            t!(
+                "time-summary-line",
+                longest = format_duration(summary.longest),
+                move = summary.longest_move,
+                average = format_duration(summary.average()),
+                total = format_duration(summary.total),
+            ),
+            width = width + 1
+        );
+    }
+}
+
+/// Print the configured turn announcement, if any
+fn announce_turn(config: &Config, args: &[(&str, &dyn std::fmt::Display)]) {
+    let announcement = config.render("turn", "", args);
+    if !announcement.is_empty() {
+        println!("{}", announcement);
+    }
+}
+
+/// Print a remark on the last move if commentary is enabled
+fn print_comment(args: &AppArgs, board: &Board) {
+    if args.commentary {
+        if let Some(comment) = commentary::comment(board) {
+            println!("{}", comment);
+        }
+    }
+}
+
+/// Announce the outcome of the game with best play when the solver can tell it or it
+/// changed, and offer to end the game early. Returns the outcome if the game should end.
+fn announce_outcome(
+    args: &AppArgs,
+    board: &Board,
+    to_move: Cell,
+    predicted: &mut Option<GameOver>,
+) -> Option<GameOver> {
+    if !args.outcome {
+        return None;
+    }
+    let outcome = solver::forced_outcome(board, to_move)?;
+    if *predicted == Some(outcome) {
+        return None;
+    }
+    *predicted = Some(outcome);
+    let key = match outcome {
+        GameOver::HumanWon | GameOver::Resigned => "outcome-human",
+        GameOver::ComputerWon => "outcome-computer",
+        GameOver::Tie => "outcome-draw",
+    };
+    println!("{}", t!(key));
+    (io::stdin().is_terminal() && confirm(t!("end-early-question"))).then_some(outcome)
+}
+
+/// Print the move the engine rates best for the player to move, with its score when verbose
+fn print_hint(engine: &mut dyn Engine, board: &Board, verbosity: Verbosity) {
+    let ranked = engine.evaluate_moves(board, board.to_move());
+    let Some(&(mv, score)) = ranked.first() else {
+        return;
+    };
+    let (x, y) = (mv.col + 1, mv.row + 1);
+    if verbosity >= Verbosity::Verbose {
+        let score = format_score(score, board);
+        println!("{}", t!("hint-score", x = x, y = y, score = score));
+    } else {
+        println!("{}", t!("hint", x = x, y = y));
+    }
+}
+
+/// A score of [`Engine::evaluate_moves`], proven wins and losses with the moves until the
+/// end
+fn format_score(score: i64, board: &Board) -> String {
+    match minimax::moves_to_end(score, board) {
+        Some(moves) if score > 0 => t!("ranked-win", moves = moves),
+        Some(moves) => t!("ranked-loss", moves = moves),
+        None => score.to_string(),
+    }
+}
+
+/// Print the engine's score of every move of `side` laid out like the board
+fn print_score_map(engine: &mut dyn Engine, board: &Board, side: Cell) {
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let map = ScoreMap::new(engine, board, side);
+    println!("{}", map.render(color, |score| format_score(score, board)));
+}
+
+fn print_last_move(player: &str, board: &Board) {
+    if let Some(mv) = board.history().last() {
+        println!(
+            "{}",
+            t!("last-move", player = player, x = mv.col + 1, y = mv.row + 1)
+        );
+    }
+}
+
+/// Print the engine's cell scores laid out like the board
+fn print_scores(scores: &[usize], dim: usize) {
+    let width = scores.iter().max().map_or(1, |max| max.to_string().len());
+    for row in scores.chunks(dim) {
+        let row: Vec<String> = row.iter().map(|s| format!("{:>width$}", s)).collect();
+        println!("  {}", row.join(" "));
+    }
+}
+
+/// Ask a yes/no question, defaulting to no
+fn confirm(question: &str) -> bool {
+    println!("{}", question);
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).is_ok() && input.trim().eq_ignore_ascii_case(t!("yes"))
+}
+
+/// Let the user jump to earlier positions of the finished game and try alternative moves
+/// against the engine. All lines tried are kept in a variation tree.
+fn explore(board: &mut Board, config: &Config, result: GameOver, engine: &mut dyn Engine) {
+    let keys = config
+        .keymap()
+        .expect("key bindings are checked at startup");
+    let usage = explore_usage(&keys);
+    let record = GameRecord::new(board, result, None);
+    let mut tree = VariationTree::from_board(board);
+    print!("{}", usage);
+    loop {
+        println!("{}", tree.render_board(board));
+        match board.result() {
+            Some(over) => println!("{}", t!("explore-state", ply = tree.ply(), state = over)),
+            None => println!(
+                "{}",
+                t!("explore-state", ply = tree.ply(), state = t!("your-turn"))
+            ),
+        }
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        match keys.action(input.trim()) {
+            Some(Action::Quit) => return,
+            Some(Action::Variations) => {
+                print!("{}", tree);
+                continue;
+            }
+            Some(Action::Copy) => {
+                copy(&board.to_notation());
+                continue;
+            }
+            Some(Action::CopyGame) => {
+                copy(&record.to_line());
+                continue;
+            }
+            Some(Action::Qr) => {
+                match QrCode::encode(&board.to_notation()) {
+                    Ok(qr) => print!("{}", qr.render()),
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+            Some(Action::Help) => {
+                print!("{}", usage);
+                continue;
+            }
+            Some(Action::MainLine) => tree.goto_main_line(),
+            None => {
+                let cmd = input.trim();
+                if let Ok(ply) = cmd.parse::<usize>() {
+                    if let Err(e) = tree.goto_ply(ply) {
+                        println!("{}", e);
+                        continue;
+                    }
+                } else if board.result().is_some() {
+                    println!("{}", t!("explore-game-over"));
+                    continue;
+                } else {
+                    let coord = match board.parse_input(cmd) {
+                        Ok(coord) => coord,
+                        Err(e) => {
+                            print!("{}\n{}", e, usage);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = board.human_move(coord) {
+                        println!("{}", e);
+                        continue;
+                    }
+                    tree.record(board);
+                    if let Some(quality) = tree.quality() {
+                        println!("{}", t!("move-quality", quality = quality_name(quality)));
+                    }
+                }
+            }
+        }
+        tree.sync(board);
+        // keep the position on the human's turn
+        if board.result().is_none() && tree.to_move() != board.human_uses() {
+            board.computer_move_with(engine);
+            tree.record(board);
+        }
+    }
+}
+
+// Put the text on the clipboard and show it, for terminals which can't
+fn copy(text: &str) {
+    print!("{}", clipboard::copy_sequence(text));
+    println!("{}", t!("copied", text = text));
+}
+
+// The help of the explore mode, listing the configured keys
+fn explore_usage(keys: &Keymap) -> String {
+    let key = |action| format!("{:<4}", keys.key(action));
+    i18n::fill(
+        t!("explore-usage"),
+        &[
+            ("variations", &key(Action::Variations)),
+            ("main_line", &key(Action::MainLine)),
+            ("copy", &key(Action::Copy)),
+            ("copy_game", &key(Action::CopyGame)),
+            ("qr", &key(Action::Qr)),
+            ("help", &key(Action::Help)),
+            ("quit", &key(Action::Quit)),
+        ],
+    )
+}
+
+fn quality_name(quality: Quality) -> &'static str {
+    match quality {
+        Quality::Best => t!("quality-best"),
+        Quality::Good => t!("quality-good"),
+        Quality::Inaccuracy => t!("quality-inaccuracy"),
+        Quality::Blunder => t!("quality-blunder"),
+    }
+}
+
+fn parse_args() -> Result<AppArgs, pico_args::Error> {
+    let mut pargs = pico_args::Arguments::from_env();
+
+    let lang = pargs.opt_value_from_fn("--lang", parse_lang)?;
+    i18n::set_lang(lang.unwrap_or_else(Lang::detect));
+
+    if pargs.contains(["-h", "--help"]) {
+        print!("{}", t!("help"));
+        std::process::exit(0);
+    }
+
+    let command = match pargs.subcommand()?.as_deref() {
+        None => Command::Play,
+        Some("daily") => Command::Daily,
+        Some("puzzle") => Command::Puzzle,
+        Some("analyze") => Command::Analyze,
+        Some("replay") => Command::Replay,
+        Some("drill") => Command::Drill,
+        Some("ultimate") => Command::Ultimate,
+        Some("cube" | "3d") => Command::Cube,
+        Some("hex") => Command::Hex,
+        Some("multi") => Command::Multi,
+        Some("morris") => Command::Morris,
+        Some("fading") => Command::Fading,
+        Some("share") => Command::Share,
+        Some("verify") => Command::Verify,
+        Some("solve") => Command::Solve,
+        Some("perft" | "count") => Command::Perft,
+        Some("selfplay") => Command::SelfPlay,
+        Some("simulate") => Command::Simulate,
+        Some("rating") => Command::Rating,
+        Some("tablebase") => match pargs.subcommand()?.as_deref() {
+            Some("build") => Command::TablebaseBuild,
+            Some("query") => Command::TablebaseQuery,
+            action => {
+                return Err(pico_args::Error::ArgumentParsingFailed {
+                    cause: t!("unknown-tablebase-action", action = action.unwrap_or("")),
+                })
+            }
+        },
+        Some("stats") => match pargs.subcommand()?.as_deref() {
+            None => Command::Stats,
+            Some("heatmap") => Command::Heatmap,
+            Some(view) => {
+                return Err(pico_args::Error::ArgumentParsingFailed {
+                    cause: t!("unknown-stats-view", view = view),
+                })
+            }
+        },
+        Some(cmd) => {
+            return Err(pico_args::Error::ArgumentParsingFailed {
+                cause: t!("unknown-command", command = cmd),
+            })
+        }
+    };
+
+    let mut args = AppArgs {
+        command,
+        dimension: pargs.opt_value_from_fn("-d", parse_dimension)?,
+        win_length: pargs.opt_value_from_str("-k")?,
+        wrap: pargs.contains("--wrap"),
+        gravity: pargs.contains("--gravity"),
+        misere: pargs.contains("--misere"),
+        pie: pargs.contains("--pie"),
+        hotseat: pargs.contains("--hotseat"),
+        watch: pargs.contains("--watch"),
+        computer_begins: pargs.contains("-c"),
+        player_uses_o: pargs.contains("-o"),
+        think_ms: pargs
+            .opt_value_from_fn("--think-ms", parse_think_ms)?
+            .or(pargs.opt_value_from_fn("--time-limit", parse_think_ms)?),
+        engine: pargs.opt_value_from_fn("--engine", parse_engine)?,
+        personality: pargs.opt_value_from_fn("--personality", parse_personality)?,
+        eval_weights: pargs.opt_value_from_fn("--eval-weights", parse_eval_weights)?,
+        contempt: pargs.opt_value_from_str("--contempt")?,
+        no_book: pargs.contains("--no-book"),
+        seed: pargs.opt_value_from_str("--seed")?,
+        verbose_engine: pargs.contains("--verbose-engine"),
+        pv: pargs.contains("--pv"),
+        ponder: pargs.contains("--ponder"),
+        cache: pargs.contains("--cache"),
+        resign: pargs.contains("--resign"),
+        report: pargs.contains("--report"),
+        eval_map: pargs.contains("--eval-map"),
+        learn: pargs.contains("--learn"),
+        blunder_rate: pargs.opt_value_from_fn("--blunder-rate", parse_blunder_rate)?,
+        opponent: pargs.opt_value_from_fn("--opponent", parse_engine)?,
+        games: pargs.opt_value_from_str("--games")?,
+        best_of: pargs.opt_value_from_fn("--best-of", parse_best_of)?,
+        players: pargs.opt_value_from_str("--players")?,
+        fade: pargs.opt_value_from_str("--fade")?,
+        threads: pargs.opt_value_from_str("--threads")?,
+        depth: pargs.opt_value_from_str("--depth")?,
+        moves: pargs.opt_value_from_str("--moves")?,
+        entrants: pargs.opt_value_from_fn("--entrants", parse_entrants)?,
+        verbosity: if pargs.contains("-vv") {
+            Some(Verbosity::VeryVerbose)
+        } else if pargs.contains("-v") {
+            Some(Verbosity::Verbose)
+        } else if pargs.contains("-q") {
+            Some(Verbosity::Quiet)
+        } else {
+            None
+        },
+        config: pargs.opt_value_from_str("--config")?,
+        preset: pargs.opt_value_from_str("--preset")?,
+        no_stats: pargs.contains("--no-stats"),
+        svg: pargs.opt_value_from_str("--svg")?,
+        format: pargs
+            .opt_value_from_fn("--format", parse_format)?
+            .unwrap_or(Format::Text),
+        game: pargs.opt_value_from_str("--game")?,
+        all: pargs.contains("--all"),
+        commentary: pargs.contains("--commentary"),
+        teach: pargs.contains("--teach"),
+        outcome: pargs.contains("--outcome"),
+        bell: pargs.contains("--bell"),
+        position: pargs.opt_value_from_str("--position")?,
+        handicap: pargs.opt_value_from_fn("--handicap", parse_handicap)?,
+        blocked: pargs.opt_value_from_fn("--blocked", parse_blocked)?,
+        random_start: pargs.opt_value_from_fn("--random-start", parse_random_start)?,
+        from_move: pargs.opt_value_from_str("--from-move")?,
+        resume: pargs.contains("--continue"),
+        file: None,
+    };
+    if command == Command::Verify {
+        args.file = pargs.opt_free_from_str()?;
+    }
+
+    let remaining = pargs.finish();
+    if !remaining.is_empty() {
+        println!(
+            "{}\n",
+            t!("invalid-arguments", args = format!("{:?}", remaining))
+        );
+        print!("{}", t!("help"));
+        std::process::exit(1);
+    }
+
+    Ok(args)
+}
+
+/// Fill in the settings not given on the command line from the preset selected with
+/// `--preset` or the `preset` setting of the config file
+fn apply_preset(args: &mut AppArgs, config: &Config) -> Result<(), String> {
+    let Some(name) = args.preset.as_deref().or(config.get("preset")) else {
+        return Ok(());
+    };
+    let preset = config.preset(name).ok_or_else(|| {
+        t!(
+            "unknown-preset",
+            name = name,
+            presets = config.preset_names().join(", ")
+        )
+    })?;
+    for (key, value) in preset.iter() {
+        let invalid = || {
+            t!(
+                "invalid-preset-value",
+                name = name,
+                key = key,
+                value = value
+            )
+        };
+        match key {
+            "dimension" => {
+                let dimension = parse_dimension(value).map_err(|_| invalid())?;
+                args.dimension.get_or_insert(dimension);
+            }
+            "win-length" => {
+                let win_length = value.parse().map_err(|_| invalid())?;
+                args.win_length.get_or_insert(win_length);
+            }
+            "wrap" => {
+                args.wrap |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "gravity" => {
+                args.gravity |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "misere" => {
+                args.misere |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "pie" => {
+                args.pie |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "handicap" => {
+                let handicap = parse_handicap(value).map_err(|_| invalid())?;
+                args.handicap.get_or_insert(handicap);
+            }
+            "blocked" => {
+                let blocked = parse_blocked(value).map_err(|_| invalid())?;
+                args.blocked.get_or_insert(blocked);
+            }
+            "random-start" => {
+                let plies = parse_random_start(value).map_err(|_| invalid())?;
+                args.random_start.get_or_insert(plies);
+            }
+            "think-ms" => {
+                let think_ms = parse_think_ms(value).map_err(|_| invalid())?;
+                args.think_ms.get_or_insert(think_ms);
+            }
+            "book" => {
+                args.no_book |= !value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "blunder-rate" => {
+                let rate = parse_blunder_rate(value).map_err(|_| invalid())?;
+                args.blunder_rate.get_or_insert(rate);
+            }
+            "ponder" => {
+                args.ponder |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "cache" => {
+                args.cache |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "resign" => {
+                args.resign |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "report" => {
+                args.report |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "eval-map" => {
+                args.eval_map |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "learn" => {
+                args.learn |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "teach" => {
+                args.teach |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "engine" => {
+                let engine = parse_engine(value).map_err(|_| invalid())?;
+                args.engine.get_or_insert(engine);
+            }
+            "personality" => {
+                let personality = parse_personality(value).map_err(|_| invalid())?;
+                args.personality.get_or_insert(personality);
+            }
+            "eval-weights" => {
+                let weights = parse_eval_weights(value).map_err(|_| invalid())?;
+                args.eval_weights.get_or_insert(weights);
+            }
+            "contempt" => {
+                let contempt = value.parse().map_err(|_| invalid())?;
+                args.contempt.get_or_insert(contempt);
+            }
+            "best-of" => {
+                let best_of = parse_best_of(value).map_err(|_| invalid())?;
+                args.best_of.get_or_insert(best_of);
+            }
+            "computer-begins" => {
+                args.computer_begins |= value.parse::<bool>().map_err(|_| invalid())?;
+            }
+            "verbosity" => {
+                let verbosity = match value {
+                    "quiet" => Verbosity::Quiet,
+                    "normal" => Verbosity::Normal,
+                    "verbose" => Verbosity::Verbose,
+                    "very-verbose" => Verbosity::VeryVerbose,
+                    _ => return Err(invalid()),
+                };
+                args.verbosity.get_or_insert(verbosity);
+            }
+            _ => return Err(t!("unknown-preset-setting", name = name, key = key)),
+        }
+    }
+    Ok(())
+}
+
+// The number of columns and rows, e.g. `7x6`, or a single number for square boards
+fn parse_dimension(s: &str) -> Result<(usize, usize), &'static str> {
+    let invalid = |_| t!("invalid-dimension");
+    match s.split_once('x') {
+        Some((width, height)) => Ok((
+            width.parse().map_err(invalid)?,
+            height.parse().map_err(invalid)?,
+        )),
+        None => s.parse().map(|dim| (dim, dim)).map_err(invalid),
+    }
+}
+
+fn parse_think_ms(s: &str) -> Result<u64, &'static str> {
+    match s.parse() {
+        Ok(0) | Err(_) => Err(t!("invalid-think-ms")),
+        Ok(ms) => Ok(ms),
+    }
+}
+
+fn parse_best_of(s: &str) -> Result<usize, &'static str> {
+    match s.parse() {
+        Ok(0) | Err(_) => Err(t!("invalid-best-of")),
+        Ok(games) => Ok(games),
+    }
+}
+
+fn parse_blunder_rate(s: &str) -> Result<f64, &'static str> {
+    match s.parse() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(t!("invalid-blunder-rate")),
+    }
+}
+
+// Comma separated engines like "minimax,heuristic:aggressive@0.3", with a personality after
+// a colon and a blunder rate after an @
+fn parse_entrants(s: &str) -> Result<Vec<Entrant>, &'static str> {
+    s.split(',')
+        .map(|spec| {
+            let (rest, rate) = match spec.split_once('@') {
+                Some((rest, rate)) => (rest, Some(parse_blunder_rate(rate)?)),
+                None => (spec, None),
+            };
+            let (engine, personality) = match rest.split_once(':') {
+                Some((engine, personality)) => (engine, parse_personality(personality)?),
+                None => (rest, Personality::default()),
+            };
+            let mut entrant = Entrant::new(spec, parse_engine(engine)?);
+            entrant.personality = personality;
+            entrant.blunder_rate = rate;
+            Ok(entrant)
+        })
+        .collect()
+}
+
+fn parse_engine(s: &str) -> Result<EngineKind, &'static str> {
+    match s {
+        "heuristic" => Ok(EngineKind::Heuristic),
+        "minimax" => Ok(EngineKind::Minimax),
+        path if Path::new(path).is_file() => Ok(EngineKind::External(PathBuf::from(path))),
+        _ => Err(t!("unknown-engine")),
+    }
+}
+
+fn parse_personality(s: &str) -> Result<Personality, &'static str> {
+    match s {
+        "balanced" => Ok(Personality::Balanced),
+        "aggressive" => Ok(Personality::Aggressive),
+        "defensive" => Ok(Personality::Defensive),
+        "central" => Ok(Personality::Central),
+        "erratic" => Ok(Personality::Erratic),
+        _ => Err(t!("unknown-personality")),
+    }
+}
+
+// A number of pieces, or a position with the pieces
+fn parse_handicap(s: &str) -> Result<Handicap, &'static str> {
+    Ok(s.parse()
+        .map_or_else(|_| Handicap::Position(s.to_string()), Handicap::Pieces))
+}
+
+// A number of cells, or the cells
+fn parse_blocked(s: &str) -> Result<Blocked, &'static str> {
+    Ok(s.parse()
+        .map_or_else(|_| Blocked::Cells(s.to_string()), Blocked::Random))
+}
+
+// The number of plies played at random, one or two
+fn parse_random_start(s: &str) -> Result<usize, &'static str> {
+    match s.parse() {
+        Ok(plies @ 1..=2) => Ok(plies),
+        _ => Err(t!("invalid-random-start")),
+    }
+}
+
+// The weights of the blank cells, the lines and the progress on them, e.g. "1,1,1"
+fn parse_eval_weights(s: &str) -> Result<EvalWeights, &'static str> {
+    let weights: Vec<usize> = s
+        .split(',')
+        .map(|weight| weight.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| t!("invalid-eval-weights"))?;
+    match weights[..] {
+        [blank, line, progress] => Ok(EvalWeights {
+            blank,
+            line,
+            progress,
+        }),
+        _ => Err(t!("invalid-eval-weights")),
+    }
+}
+
+fn parse_format(s: &str) -> Result<Format, &'static str> {
+    match s {
+        "text" => Ok(Format::Text),
+        "csv" => Ok(Format::Csv),
+        _ => Err(t!("unknown-format")),
+    }
+}
+
+fn parse_lang(s: &str) -> Result<Lang, &'static str> {
+    Lang::from_code(s).ok_or(t!("unknown-language"))
 }
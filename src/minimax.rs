@@ -0,0 +1,1009 @@
+//! Minimax search with alpha-beta pruning.
+//!
+//! Positions with up to [`FULL_SEARCH`] blank cells, which includes every position on the
+//! 3x3 and 4x4 boards, are searched to the end and played perfectly. On larger boards the
+//! search stops at the depth [`NODE_BUDGET`] allows and rates the positions there by the
+//! lines each player still has open.
+//!
+//! Among winning moves the search prefers the fastest win, and when every move loses the
+//! one holding out longest, see [`moves_to_end`].
+//!
+//! Positions reached again, by moves in another order or as a rotated or mirrored version of
+//! a position already searched, are looked up in a [`TranspositionTable`]. In a symmetric
+//! position, such as the empty board, only one of the moves leading to symmetric positions
+//! is searched. A table can be [saved](TranspositionTable::save) and loaded again, so that
+//! later games start with what earlier ones found.
+//!
+//! Below the root, winning and blocking moves are tried first, then the moves which caused a
+//! cutoff at the same ply, the killer moves, then those which caused the most cutoffs
+//! anywhere, rated by the history heuristic.
+//!
+//! With a [contempt](crate::engine::EngineConfig::contempt), a draw scores that much less
+//! than 0 for the player the search is for, and as much more for their opponent. Draws are
+//! games ending with a full board and, at the end of the depth searched, positions where no
+//! line is open to either player. Where the whole game is searched every draw scores the same, so the
+//! contempt only changes the moves on larger boards. When deepening, every depth is first searched with a narrow aspiration window
+//! around the score of the previous one, and searched again with the full window only if
+//! the score falls outside.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::board::{Board, Cell, Coord, GameOver};
+use crate::db;
+use crate::rng::Rng;
+use crate::t;
+
+/// Positions with at most this many blank cells are searched to the end
+pub const FULL_SEARCH: usize = 16;
+
+/// Rough number of positions the search may visit for one move
+pub const NODE_BUDGET: usize = 1_000_000;
+
+/// Number of positions the transposition table holds before it is cleared
+pub const TABLE_LIMIT: usize = 1 << 20;
+
+// Score of a won position, reduced by the number of pieces on the board when it is won
+const WIN: i64 = i64::MAX / 2;
+
+// Half the width of the aspiration window around the score of the previous depth
+const ASPIRATION_WINDOW: i64 = 16;
+
+/// Scores of moves which win by force are higher, scores of moves which lose by force lower
+/// than the negative
+pub const WIN_SCORE: i64 = WIN - 1000;
+
+// Start of a saved transposition table
+const MAGIC: &[u8] = b"TTTT";
+
+// Bytes of a saved entry: the key, the depth, the score and the bound
+const ENTRY_SIZE: usize = 8 + 2 + 8 + 1;
+
+/// Scores of positions already searched, by their canonical Zobrist hash and the player to
+/// move. Scores don't depend on where the search started, so a table can be kept across
+/// moves and games.
+#[derive(Debug, Default)]
+pub struct TranspositionTable {
+    entries: HashMap<u64, Entry>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    // number of plies searched below the position
+    depth: usize,
+    score: i64,
+    bound: Bound,
+}
+
+// How the stored score relates to the actual one, as the search of the position may have
+// been cut off by alpha-beta pruning
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+impl TranspositionTable {
+    /// Number of positions stored
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no position is stored
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Where the table for the board size is kept between games:
+    /// `transpositions-<dim>` in the [cache directory](db::cache_dir). The keys don't
+    /// tell the board sizes apart, so every size has a table of its own.
+    pub fn default_path(dim: usize) -> Option<PathBuf> {
+        Some(db::cache_dir()?.join(format!("transpositions-{}", dim)))
+    }
+
+    /// Read a table written by [`TranspositionTable::save`]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<TranspositionTable> {
+        let content = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, t!("table-invalid"));
+        let entries = content.strip_prefix(MAGIC).ok_or_else(invalid)?;
+        if !entries.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(invalid());
+        }
+        let entries = entries
+            .chunks_exact(ENTRY_SIZE)
+            .map(|bytes| {
+                let (key, rest) = bytes.split_at(8);
+                let (depth, rest) = rest.split_at(2);
+                let (score, bound) = rest.split_at(8);
+                let bound = match bound[0] {
+                    0 => Bound::Exact,
+                    1 => Bound::Lower,
+                    2 => Bound::Upper,
+                    _ => return Err(invalid()),
+                };
+                let entry = Entry {
+                    depth: u16::from_le_bytes(depth.try_into().unwrap()).into(),
+                    score: i64::from_le_bytes(score.try_into().unwrap()),
+                    bound,
+                };
+                Ok((u64::from_le_bytes(key.try_into().unwrap()), entry))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(TranspositionTable { entries })
+    }
+
+    /// Write the table to the file, creating its directory if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = MAGIC.to_vec();
+        content.reserve(self.entries.len() * ENTRY_SIZE);
+        for (key, entry) in &self.entries {
+            content.extend_from_slice(&key.to_le_bytes());
+            // no search gets near this deep
+            content.extend_from_slice(&(entry.depth.min(u16::MAX.into()) as u16).to_le_bytes());
+            content.extend_from_slice(&entry.score.to_le_bytes());
+            content.push(entry.bound as u8);
+        }
+        fs::write(path, content)
+    }
+
+    // Searches with a contempt score draws differently, so their entries get other keys
+    fn key(board: &Board, to_move: Cell, salt: u64) -> u64 {
+        board.canonical_hash() ^ u64::from(to_move == Cell::O) ^ salt
+    }
+
+    fn get(&self, board: &Board, to_move: Cell, salt: u64) -> Option<Entry> {
+        self.entries.get(&Self::key(board, to_move, salt)).copied()
+    }
+
+    fn insert(&mut self, board: &Board, to_move: Cell, salt: u64, entry: Entry) {
+        if self.entries.len() >= TABLE_LIMIT {
+            self.entries.clear();
+        }
+        self.entries.insert(Self::key(board, to_move, salt), entry);
+    }
+}
+
+/// What a search did, e.g. to judge how well the transposition table and the move
+/// ordering work
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchStats {
+    /// Number of positions visited
+    pub nodes: usize,
+    /// Number of plies of the deepest search which finished
+    pub depth: usize,
+    /// Score of the move found for the player to move, see [`moves_to_end`]
+    pub score: i64,
+    /// Number of positions whose remaining moves were pruned by alpha-beta
+    pub cutoffs: usize,
+    /// Number of positions whose score was taken from the transposition table
+    pub table_hits: usize,
+    /// Number of cutoffs caused by the first move searched, the more the better the move
+    /// ordering
+    pub first_move_cutoffs: usize,
+    /// Number of cutoffs caused by a killer move
+    pub killer_cutoffs: usize,
+    /// Number of depths searched again because the score fell outside the aspiration window
+    pub researches: usize,
+    /// The moves of both players the deepest search expects, starting with the move found.
+    /// It ends early where the rest was taken from the transposition table.
+    pub pv: Vec<Coord>,
+}
+
+/// The best move for the given player.
+///
+/// Without a deadline the moves are searched to the [`depth`] for the position. With a
+/// deadline the search is deepened one ply at a time, trying the best move of the previous
+/// depth first, until the game is searched to the end or the time is up. The best move of
+/// the deepest search that finished is returned.
+///
+/// With a random number generator the moves are searched in random order, so that the
+/// move played is a random one of the equally good moves. Otherwise it is the one the
+/// heuristic rates highest.
+///
+/// A draw scores the contempt less than 0 for the player, see the [module](self) docs.
+///
+/// Panics if there is no blank cell.
+pub fn best_move(
+    board: &Board,
+    cell: Cell,
+    contempt: i64,
+    deadline: Option<Instant>,
+    table: &mut TranspositionTable,
+    rng: Option<&mut Rng>,
+) -> Coord {
+    best_move_with_stats(board, cell, contempt, deadline, table, rng).0
+}
+
+/// The [best move](best_move) together with the statistics of the search
+pub fn best_move_with_stats(
+    board: &Board,
+    cell: Cell,
+    contempt: i64,
+    deadline: Option<Instant>,
+    table: &mut TranspositionTable,
+    rng: Option<&mut Rng>,
+) -> (Coord, SearchStats) {
+    let mut board = board.clone();
+    let mut moves = ordered_moves(&board, cell);
+    if let Some(rng) = rng {
+        for idx in (1..moves.len()).rev() {
+            moves.swap(idx, rng.below(idx + 1));
+        }
+    }
+    let moves = distinct(&mut board, cell, moves);
+    let mut search = Search::new(&board, cell, contempt, deadline, None, table);
+    let best = search.deepen(&mut board, cell, moves, deadline.is_some());
+    (best, search.stats)
+}
+
+/// Search the position for the given player, deepening one ply at a time like
+/// [`best_move`] with a deadline, until the game is searched to the end or `stop` is set.
+///
+/// Meant to run in another thread while the player thinks: the positions searched are kept
+/// in the table, so the search for the reply to their move finds many of them there.
+pub fn ponder(
+    board: &Board,
+    cell: Cell,
+    contempt: i64,
+    table: &mut TranspositionTable,
+    stop: &AtomicBool,
+) {
+    let mut board = board.clone();
+    let moves = ordered_moves(&board, cell);
+    let moves = distinct(&mut board, cell, moves);
+    // the table is kept for the search of the reply, which is for the opponent
+    let mut search = Search::new(&board, cell.opponent(), contempt, None, Some(stop), table);
+    search.deepen(&mut board, cell, moves, true);
+}
+
+/// The score of every move for the given player, best first. Positive scores favour the
+/// player; wins score above [`WIN_SCORE`], the faster the higher, and losses below
+/// `-WIN_SCORE`. Moves with the same score are in the order the heuristic rates them.
+///
+/// Unlike [`best_move`] every move is searched with the full window, so the scores are
+/// exact for the depth searched. The depth follows the same rules as for [`best_move`].
+pub fn evaluate_moves(
+    board: &Board,
+    cell: Cell,
+    contempt: i64,
+    deadline: Option<Instant>,
+    table: &mut TranspositionTable,
+) -> Vec<(Coord, i64)> {
+    let mut board = board.clone();
+    let moves = ordered_moves(&board, cell);
+    let mut search = Search::new(&board, cell, contempt, deadline, None, table);
+    let depths = match deadline {
+        Some(_) => 1..=moves.len(),
+        None => depth(moves.len())..=depth(moves.len()),
+    };
+    let mut scores: Vec<_> = moves.iter().map(|&mv| (mv, 0)).collect();
+    for depth in depths {
+        let mut deeper = Vec::with_capacity(moves.len());
+        // moves leading to symmetric positions share their score
+        let mut searched = HashMap::new();
+        for &mv in &moves {
+            board.set_cell(mv, cell).unwrap();
+            let score = *searched.entry(board.canonical_hash()).or_insert_with(|| {
+                let mut line = Vec::new();
+                let (alpha, beta) = (-WIN - 1, WIN + 1);
+                -search.negamax(
+                    &mut board,
+                    cell.opponent(),
+                    depth - 1,
+                    alpha,
+                    beta,
+                    &mut line,
+                )
+            });
+            board.undo();
+            deeper.push((mv, score));
+        }
+        if search.aborted {
+            break;
+        }
+        scores = deeper;
+    }
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    scores
+}
+
+/// For a score of [`evaluate_moves`] which proves a win or loss, the number of moves until
+/// the game ends, counting the scored move. `None` for other scores.
+pub fn moves_to_end(score: i64, board: &Board) -> Option<usize> {
+    let end = (score.abs() > WIN_SCORE).then(|| (WIN - score.abs()) as usize)?;
+    Some(end - board.piece_count())
+}
+
+/// The depth to search with the given number of blank cells: to the end for up to
+/// [`FULL_SEARCH`] blanks, otherwise as many plies as fit into the node budget, assuming no
+/// move is pruned
+pub fn depth(blanks: usize) -> usize {
+    if blanks <= FULL_SEARCH {
+        return blanks.max(1);
+    }
+    let mut nodes = 1;
+    let mut depth = 0;
+    while depth < blanks {
+        nodes *= blanks - depth;
+        if nodes > NODE_BUDGET {
+            break;
+        }
+        depth += 1;
+    }
+    depth.max(1)
+}
+
+// Moves of both players in turn
+type Line = Vec<Coord>;
+
+struct Search<'a> {
+    deadline: Option<Instant>,
+    stop: Option<&'a AtomicBool>,
+    stats: SearchStats,
+    aborted: bool,
+    table: &'a mut TranspositionTable,
+    // The last two moves which caused a cutoff, by the number of pieces on the board
+    killers: Vec<[Option<Coord>; 2]>,
+    // How much the moves of X and O to each cell caused cutoffs, those with more plies left
+    // to search counting more
+    history: [Vec<usize>; 2],
+    // The player the search is for and how much worse than 0 a draw is for them
+    side: Cell,
+    contempt: i64,
+    // Mixed into the table keys when there is a contempt
+    salt: u64,
+}
+
+impl<'a> Search<'a> {
+    // A search for `side`, a draw scoring the contempt less than 0 for them
+    fn new(
+        board: &Board,
+        side: Cell,
+        contempt: i64,
+        deadline: Option<Instant>,
+        stop: Option<&'a AtomicBool>,
+        table: &'a mut TranspositionTable,
+    ) -> Search<'a> {
+        let cells = board.width() * board.height();
+        let salt = match contempt {
+            0 => 0,
+            _ => Rng::new(contempt as u64 ^ u64::from(side == Cell::O) << 63).next_u64() & !1,
+        };
+        Search {
+            deadline,
+            stop,
+            stats: SearchStats::default(),
+            aborted: false,
+            table,
+            killers: vec![[None; 2]; cells + 1],
+            history: [vec![0; cells], vec![0; cells]],
+            side,
+            contempt,
+            salt,
+        }
+    }
+
+    // The score of a draw for the player to move
+    fn draw(&self, to_move: Cell) -> i64 {
+        if to_move == self.side {
+            -self.contempt
+        } else {
+            self.contempt
+        }
+    }
+
+    // The best of the moves, searched to the depth for the position, or one ply deeper at a
+    // time until the search stops if `iterative` is set
+    fn deepen(
+        &mut self,
+        board: &mut Board,
+        cell: Cell,
+        mut moves: Vec<Coord>,
+        iterative: bool,
+    ) -> Coord {
+        let blanks = board.legal_moves().count();
+        let depths = match iterative {
+            true => 1..=blanks,
+            false => depth(blanks)..=depth(blanks),
+        };
+        let mut best = moves[0];
+        let mut previous = None;
+        for depth in depths {
+            let full = (-WIN - 1, WIN + 1);
+            let window = match previous {
+                Some(score) if iterative && i64::abs(score) < WIN_SCORE => {
+                    (score - ASPIRATION_WINDOW, score + ASPIRATION_WINDOW)
+                }
+                _ => full,
+            };
+            let Some(mut found) = self.root(board, cell, &moves, depth, window) else {
+                break;
+            };
+            if window != full && (found.1 <= window.0 || found.1 >= window.1) {
+                self.stats.researches += 1;
+                let Some(again) = self.root(board, cell, &moves, depth, full) else {
+                    break;
+                };
+                found = again;
+            }
+            let (idx, score, pv) = found;
+            previous = Some(score);
+            best = moves[idx];
+            self.stats.depth = depth;
+            self.stats.score = score;
+            self.stats.pv = pv;
+            moves[..=idx].rotate_right(1);
+            // a win or loss found doesn't change with more depth
+            if score.abs() >= WIN - (board.width() * board.height()) as i64 {
+                break;
+            }
+        }
+        best
+    }
+
+    // Searches the moves to the given depth within the window and returns the index of the
+    // best one with its score and the expected line, or `None` if the time ran out. A score
+    // at or outside the window is only a bound.
+    fn root(
+        &mut self,
+        board: &mut Board,
+        cell: Cell,
+        moves: &[Coord],
+        depth: usize,
+        (alpha, beta): (i64, i64),
+    ) -> Option<(usize, i64, Line)> {
+        let mut best = (0, -WIN - 1, Vec::new());
+        for (idx, &mv) in moves.iter().enumerate() {
+            board.set_cell(mv, cell).unwrap();
+            let mut line = Vec::new();
+            let score = -self.negamax(
+                board,
+                cell.opponent(),
+                depth - 1,
+                -beta,
+                -alpha.max(best.1),
+                &mut line,
+            );
+            board.undo();
+            if self.aborted {
+                return None;
+            }
+            if score > best.1 {
+                line.insert(0, mv);
+                best = (idx, score, line);
+            }
+            if best.1 >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    // The score of the position after the last move for the player to move, searching the
+    // given number of plies further. The best line found goes into `pv`.
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        to_move: Cell,
+        depth: usize,
+        mut alpha: i64,
+        mut beta: i64,
+        pv: &mut Line,
+    ) -> i64 {
+        pv.clear();
+        self.stats.nodes += 1;
+        if self.stats.nodes.is_multiple_of(1024)
+            && (self.deadline.is_some_and(|d| Instant::now() >= d)
+                || self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)))
+        {
+            self.aborted = true;
+        }
+        match board.result() {
+            Some(GameOver::Tie) => return self.draw(to_move),
+            // usually the player who just moved has won, but not by every ruleset
+            Some(_) => {
+                let score = WIN - board.piece_count() as i64;
+                return if board.winner() == Some(to_move) {
+                    score
+                } else {
+                    -score
+                };
+            }
+            None => {}
+        }
+        if depth == 0 || self.aborted {
+            return evaluate(board, to_move).unwrap_or_else(|| self.draw(to_move));
+        }
+        let (alpha_in, beta_in) = (alpha, beta);
+        if let Some(entry) = self.table.get(board, to_move, self.salt) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => {
+                        self.stats.table_hits += 1;
+                        return entry.score;
+                    }
+                    Bound::Lower => alpha = alpha.max(entry.score),
+                    Bound::Upper => beta = beta.min(entry.score),
+                }
+                if alpha >= beta {
+                    self.stats.table_hits += 1;
+                    return entry.score;
+                }
+            }
+        }
+        let mut best = -WIN - 1;
+        let moves = self.ordered_moves(board, to_move);
+        let mut line = Vec::new();
+        for (idx, mv) in distinct(board, to_move, moves).into_iter().enumerate() {
+            board.set_cell(mv, to_move).unwrap();
+            let score = -self.negamax(
+                board,
+                to_move.opponent(),
+                depth - 1,
+                -beta,
+                -alpha,
+                &mut line,
+            );
+            board.undo();
+            best = best.max(score);
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.append(&mut line);
+            }
+            if alpha >= beta {
+                self.cutoff(board, to_move, mv, depth, idx == 0);
+                break;
+            }
+        }
+        // the scores of an aborted search are unreliable
+        if !self.aborted {
+            let bound = if best <= alpha_in {
+                Bound::Upper
+            } else if best >= beta_in {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.table.insert(
+                board,
+                to_move,
+                self.salt,
+                Entry {
+                    depth,
+                    score: best,
+                    bound,
+                },
+            );
+        }
+        best
+    }
+
+    // The blank cells in the order of `ordered_moves`, but wins, blocks and the killer moves
+    // of the ply first and then those the history heuristic rates highest
+    fn ordered_moves(&self, board: &Board, to_move: Cell) -> Vec<Coord> {
+        let mut moves = ordered_moves(board, to_move);
+        let killers = self.killers[board.history().len()];
+        let history = &self.history[usize::from(to_move == Cell::O)];
+        let wins = board.winning_moves(to_move);
+        let blocks = board.winning_moves(to_move.opponent());
+        moves.sort_by_key(|&mv| {
+            let killer = killers.iter().position(|&k| k == Some(mv));
+            (
+                !wins.contains(&mv),
+                !blocks.contains(&mv),
+                killer.unwrap_or(killers.len()),
+                std::cmp::Reverse(history[mv.col + mv.row * board.width()]),
+            )
+        });
+        moves
+    }
+
+    // Counts the cutoff caused by the move and remembers it for the ordering
+    fn cutoff(&mut self, board: &Board, to_move: Cell, mv: Coord, depth: usize, first: bool) {
+        self.stats.cutoffs += 1;
+        if first {
+            self.stats.first_move_cutoffs += 1;
+        }
+        let killers = &mut self.killers[board.history().len()];
+        if killers.contains(&Some(mv)) {
+            self.stats.killer_cutoffs += 1;
+        }
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+        self.history[usize::from(to_move == Cell::O)][mv.col + mv.row * board.width()] +=
+            depth * depth;
+    }
+}
+
+// The blank cells, the ones the heuristic rates highest first, so that good moves are
+// searched early and more of the others are pruned
+fn ordered_moves(board: &Board, cell: Cell) -> Vec<Coord> {
+    let scores = board.scores(cell);
+    let mut moves: Vec<Coord> = board.legal_moves().collect();
+    moves.sort_by_key(|&mv| std::cmp::Reverse(scores[mv.col + mv.row * board.width()]));
+    moves
+}
+
+// The moves without those leading to a position symmetric to the one after an earlier move
+fn distinct(board: &mut Board, cell: Cell, mut moves: Vec<Coord>) -> Vec<Coord> {
+    if !board.is_symmetric() {
+        return moves;
+    }
+    let mut seen = Vec::new();
+    moves.retain(|&mv| {
+        board.set_cell(mv, cell).unwrap();
+        let hash = board.canonical_hash();
+        board.undo();
+        let new = !seen.contains(&hash);
+        seen.push(hash);
+        new
+    });
+    moves
+}
+
+// Rates a position which isn't searched further: every line still open to only one player
+// counts for them, the more so the more of its cells they hold. `None` if no line is open
+// to either player, so the game is drawn, or if completing lines doesn't win.
+fn evaluate(board: &Board, to_move: Cell) -> Option<i64> {
+    if !board.rules().lines_win() {
+        return None;
+    }
+    let mut score = 0;
+    let mut open = false;
+    for count in board.line_counts() {
+        let own = count.pieces(to_move) as i64;
+        let other = count.pieces(to_move.opponent()) as i64;
+        match (own, other) {
+            (n, 0) => score += n * n * n,
+            (0, n) => score -= n * n * n,
+            _ => continue,
+        }
+        open = true;
+    }
+    open.then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::EvalWeights;
+
+    fn search(board: &Board, cell: Cell) -> Coord {
+        best_move(
+            board,
+            cell,
+            0,
+            None,
+            &mut TranspositionTable::default(),
+            None,
+        )
+    }
+
+    #[test]
+    fn searches_small_boards_to_the_end() {
+        assert_eq!(depth(9), 9);
+        assert_eq!(depth(16), 16);
+        assert_eq!(depth(25), 4);
+        assert_eq!(depth(900), 2);
+        assert_eq!(depth(1), 1);
+    }
+
+    #[test]
+    fn plays_perfectly_on_3x3() {
+        // the only moves holding the draw against a corner and the center
+        assert_eq!(
+            search(&Board::from_moves(&[(0, 0)], Cell::X), Cell::O),
+            (1, 1)
+        );
+        let answer = search(&Board::from_moves(&[(1, 1)], Cell::X), Cell::O);
+        assert!([(0, 0), (2, 0), (0, 2), (2, 2)].contains(&answer.into()));
+        // blocks the diagonal
+        let board = Board::from_moves(&[(1, 1), (1, 0), (0, 0)], Cell::X);
+        assert_eq!(search(&board, Cell::O), (2, 2));
+        let board = Board::from_moves(&[(1, 1), (1, 0)], Cell::X);
+        let mut after = board.clone();
+        after.set_cell(search(&board, Cell::X), Cell::X).unwrap();
+        assert_eq!(
+            crate::solver::forced_outcome(&after, Cell::O),
+            Some(GameOver::HumanWon)
+        );
+    }
+
+    #[test]
+    fn never_loses_on_3x3() {
+        // the heuristic engine against the minimax engine, from every first move, sharing
+        // the transposition table between the games
+        let mut table = TranspositionTable::default();
+        for first in Board::build(3, Cell::X).unwrap().legal_moves() {
+            let mut board = Board::from_moves(&[first.into()], Cell::X);
+            let mut cell = Cell::O;
+            while board.result().is_none() {
+                let mv = if cell == Cell::O {
+                    best_move(&board, cell, 0, None, &mut table, None)
+                } else {
+                    board.best_move(cell, EvalWeights::default())
+                };
+                board.set_cell(mv, cell).unwrap();
+                cell = cell.opponent();
+            }
+            assert_ne!(board.winner(), Some(Cell::X), "{}", board);
+        }
+        assert!(!table.is_empty());
+    }
+
+    #[test]
+    fn table_keeps_outcomes() {
+        // moves chosen with a table filled by earlier searches keep the same outcome as
+        // moves chosen by a fresh search
+        let mut table = TranspositionTable::default();
+        let mut rng = crate::rng::Rng::new(5);
+        for plies in (0..8).cycle().take(40) {
+            let board = Board::random(3, Cell::X, plies, &mut rng).unwrap();
+            if board.result().is_some() {
+                continue;
+            }
+            let to_move = board.to_move();
+            let outcome = |mv: Coord| {
+                let mut after = board.clone();
+                after.set_cell(mv, to_move).unwrap();
+                crate::solver::forced_outcome(&after, to_move.opponent())
+            };
+            let shared = best_move(&board, to_move, 0, None, &mut table, None);
+            assert_eq!(
+                outcome(shared),
+                outcome(search(&board, to_move)),
+                "{}",
+                board
+            );
+        }
+    }
+
+    #[test]
+    fn deepens_until_the_deadline() {
+        // with time to spare the 3x3 board is searched to the end
+        let deadline = Instant::now() + std::time::Duration::from_secs(60);
+        let mut table = TranspositionTable::default();
+        assert_eq!(
+            best_move(
+                &Board::from_moves(&[(0, 0)], Cell::X),
+                Cell::O,
+                0,
+                Some(deadline),
+                &mut table,
+                None
+            ),
+            (1, 1)
+        );
+        let board = Board::from_moves(&[(1, 1), (1, 0)], Cell::X);
+        let mv = best_move(&board, Cell::X, 0, Some(deadline), &mut table, None);
+        let mut after = board.clone();
+        after.set_cell(mv, Cell::X).unwrap();
+        assert_eq!(
+            crate::solver::forced_outcome(&after, Cell::O),
+            Some(GameOver::HumanWon)
+        );
+    }
+
+    #[test]
+    fn evaluations() {
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        let scores = evaluate_moves(&board, Cell::X, 0, None, &mut TranspositionTable::default());
+        assert_eq!(scores.len(), 5);
+        assert_eq!(scores[0].0, (2, 0));
+        assert!(scores[0].1 > WIN_SCORE);
+        // every other move but the block lets O win
+        assert_eq!(scores[1].0, (2, 1));
+        assert!(scores[2..].iter().all(|&(_, score)| score < -WIN_SCORE));
+        // the answers to the center: the corners draw, the edges lose
+        let scores = evaluate_moves(
+            &Board::from_moves(&[(1, 1)], Cell::X),
+            Cell::O,
+            0,
+            None,
+            &mut TranspositionTable::default(),
+        );
+        assert!(scores[..4]
+            .iter()
+            .all(|&(mv, score)| mv.col != 1 && mv.row != 1 && score == 0));
+        assert!(scores[4..].iter().all(|&(_, score)| score < -WIN_SCORE));
+    }
+
+    #[test]
+    fn distance_to_the_end() {
+        // X wins at once rather than later
+        let board = Board::from_moves(&[(0, 0), (1, 1), (0, 2), (2, 2)], Cell::X);
+        let scores = evaluate_moves(&board, Cell::X, 0, None, &mut TranspositionTable::default());
+        assert_eq!(scores[0].0, (0, 1));
+        assert_eq!(moves_to_end(scores[0].1, &board), Some(1));
+        assert_eq!(search(&board, Cell::X), (0, 1));
+        // O is lost after answering the center with an edge, but blocking the diagonal
+        // holds out longest
+        let board = Board::from_moves(&[(1, 1), (1, 0), (0, 0)], Cell::X);
+        let scores = evaluate_moves(&board, Cell::O, 0, None, &mut TranspositionTable::default());
+        assert_eq!(scores[0].0, (2, 2));
+        assert_eq!(moves_to_end(scores[0].1, &board), Some(4));
+        assert_eq!(moves_to_end(scores[1].1, &board), Some(2));
+        assert_eq!(moves_to_end(0, &board), None);
+        // the same position without a history scores the same
+        let pasted = Board::from_notation(&board.to_notation()).unwrap();
+        assert!(pasted.history().is_empty());
+        assert_eq!(
+            evaluate_moves(
+                &pasted,
+                Cell::O,
+                0,
+                None,
+                &mut TranspositionTable::default()
+            ),
+            scores
+        );
+        assert_eq!(moves_to_end(scores[0].1, &pasted), Some(4));
+    }
+
+    #[test]
+    fn symmetric_moves() {
+        // a corner, an edge and the center
+        let mut board = Board::build(3, Cell::X).unwrap();
+        let moves = ordered_moves(&board, Cell::X);
+        assert_eq!(distinct(&mut board, Cell::X, moves).len(), 3);
+        let mut board = Board::from_moves(&[(0, 0), (1, 1)], Cell::X);
+        let moves = ordered_moves(&board, Cell::X);
+        assert_eq!(distinct(&mut board, Cell::X, moves).len(), 4);
+        let mut board = Board::from_moves(&[(0, 0), (1, 0)], Cell::X);
+        let moves = ordered_moves(&board, Cell::X);
+        assert_eq!(distinct(&mut board, Cell::X, moves).len(), 7);
+        // every move is scored, symmetric ones alike
+        let board = Board::build(3, Cell::X).unwrap();
+        let scores = evaluate_moves(&board, Cell::X, 0, None, &mut TranspositionTable::default());
+        assert_eq!(scores.len(), 9);
+        assert!(scores.iter().all(|&(_, score)| score == 0));
+    }
+
+    #[test]
+    fn stops_at_the_deadline() {
+        let board = Board::build(6, Cell::X).unwrap();
+        let start = Instant::now();
+        let mv = best_move(
+            &board,
+            Cell::O,
+            0,
+            Some(start),
+            &mut TranspositionTable::default(),
+            None,
+        );
+        assert!(mv.col < 6 && mv.row < 6);
+        assert!(start.elapsed().as_secs() < 1);
+    }
+
+    #[test]
+    fn move_ordering() {
+        let board = Board::build(4, Cell::X).unwrap();
+        let (_, stats) =
+            best_move_with_stats(&board, Cell::X, 0, None, &mut Default::default(), None);
+        // almost every cutoff is caused by the first move searched
+        assert!(stats.first_move_cutoffs * 10 > stats.cutoffs * 9);
+        assert!(stats.killer_cutoffs > stats.cutoffs / 2);
+        // without deepening there is no aspiration window
+        assert_eq!(stats.researches, 0);
+    }
+
+    #[test]
+    fn aspiration_windows() {
+        let mut board = Board::from_string("XO--/----/--X-/----", 4, Cell::X).unwrap();
+        let moves = ordered_moves(&board, Cell::O);
+        let mut table = TranspositionTable::default();
+        let mut search = Search::new(&board, Cell::O, 0, None, None, &mut table);
+        search.deepen(&mut board, Cell::O, moves, true);
+        // the score left the window of the previous depth, but the end result is that of a
+        // full-window search
+        assert!(search.stats.researches > 0);
+        let (_, stats) =
+            best_move_with_stats(&board, Cell::O, 0, None, &mut Default::default(), None);
+        assert_eq!(search.stats.score, stats.score);
+    }
+
+    #[test]
+    fn contempt() {
+        // every line is blocked, but too many cells are blank to search to the end
+        let board =
+            Board::from_string("X--O--/-OX---/---XO-/--O--X/-X---O/O---X-", 6, Cell::X).unwrap();
+        let mut table = TranspositionTable::default();
+        let mut score = |contempt| {
+            best_move_with_stats(&board, Cell::X, contempt, None, &mut table, None)
+                .1
+                .score
+        };
+        assert_eq!(score(0), 0);
+        assert_eq!(score(5), -5);
+        assert_eq!(score(-5), 5);
+        // drawing moves share the contempt, losing ones stay below them
+        let board = Board::from_moves(&[(0, 0)], Cell::X);
+        let scores = evaluate_moves(&board, Cell::O, 5, None, &mut table);
+        assert_eq!(scores[0], ((1, 1).into(), -5));
+        assert!(scores[1].1 < -WIN_SCORE);
+    }
+
+    #[test]
+    fn principal_variation() {
+        let board = Board::from_moves(&[(0, 0), (1, 1), (0, 2), (2, 2)], Cell::X);
+        let (_, stats) =
+            best_move_with_stats(&board, Cell::X, 0, None, &mut Default::default(), None);
+        assert_eq!(stats.pv, [(0, 1)]);
+        // a legal line for both players, starting with the move found
+        let board = Board::from_moves(&[(1, 1), (1, 0), (0, 0)], Cell::X);
+        let (mv, stats) =
+            best_move_with_stats(&board, Cell::O, 0, None, &mut Default::default(), None);
+        assert_eq!(stats.pv[0], mv);
+        assert!(stats.pv.len() > 1);
+        let mut after = board.clone();
+        for (ply, &mv) in stats.pv.iter().enumerate() {
+            let side = if ply % 2 == 0 { Cell::O } else { Cell::X };
+            assert!(after.result().is_none());
+            after.set_cell(mv, side).unwrap();
+        }
+    }
+
+    #[test]
+    fn pondering() {
+        // stopped before it started
+        let mut table = TranspositionTable::default();
+        let stop = AtomicBool::new(true);
+        let start = Instant::now();
+        ponder(
+            &Board::build(6, Cell::X).unwrap(),
+            Cell::X,
+            0,
+            &mut table,
+            &stop,
+        );
+        assert!(start.elapsed().as_secs() < 1);
+        // the reply to X's move is found in the table
+        let board = Board::from_moves(&[(0, 0)], Cell::X);
+        let mut fresh = TranspositionTable::default();
+        let (mv, unprepared) = best_move_with_stats(&board, Cell::O, 0, None, &mut fresh, None);
+        let stop = AtomicBool::new(false);
+        ponder(
+            &Board::build(3, Cell::X).unwrap(),
+            Cell::X,
+            0,
+            &mut table,
+            &stop,
+        );
+        let (pondered_mv, prepared) =
+            best_move_with_stats(&board, Cell::O, 0, None, &mut table, None);
+        assert_eq!(pondered_mv, mv);
+        assert!(prepared.nodes < unprepared.nodes, "{:?}", prepared);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-table-test-{}", std::process::id()));
+        let board = Board::from_moves(&[(1, 1)], Cell::X);
+        let mut table = TranspositionTable::default();
+        let (mv, searched) = best_move_with_stats(&board, Cell::O, 0, None, &mut table, None);
+        table.save(&path).unwrap();
+        let mut loaded = TranspositionTable::load(&path).unwrap();
+        assert_eq!(loaded.len(), table.len());
+        let (loaded_mv, looked_up) =
+            best_move_with_stats(&board, Cell::O, 0, None, &mut loaded, None);
+        assert_eq!(loaded_mv, mv);
+        assert!(looked_up.nodes < searched.nodes, "{:?}", looked_up);
+        fs::write(&path, b"TTTT\x01").unwrap();
+        assert!(TranspositionTable::load(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,342 @@
+//! Three Men's Morris: each player has only as many pieces as the board has rows. The
+//! players take turns placing them, and once all are on the board a turn slides one of the
+//! player's pieces to an adjacent blank cell, also diagonally. A full row, column or
+//! diagonal wins, a player who can't move loses.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::board::{Board, BoardError, Cell, Coord, MoveError};
+use crate::input::{self, ParseError};
+use crate::variant::Variant;
+
+/// The supported numbers of cells along each side
+pub const DIM_RANGE: RangeInclusive<usize> = 3..=5;
+
+/// The default number of cells along each side
+pub const DEFAULT_DIM: usize = 3;
+
+/// The search depth used by the computer player, see
+/// [`best_move`](crate::variant::best_move)
+pub const SEARCH_DEPTH: usize = 6;
+
+/// The number of moves after which a game nobody has won is a tie
+pub const MOVE_LIMIT: usize = 60;
+
+/// A move in Three Men's Morris
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MorrisMove {
+    /// Put a new piece on the blank cell
+    Place(Coord),
+    /// Move the piece on the first cell to the adjacent blank second one
+    Slide(Coord, Coord),
+}
+
+/// One based coordinates, `x y` for a placement and `x y -> x y` for a slide
+impl fmt::Display for MorrisMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MorrisMove::Place(coord) => write!(f, "{}", coord),
+            MorrisMove::Slide(from, to) => write!(f, "{} -> {}", from, to),
+        }
+    }
+}
+
+/// A Three Men's Morris position. X always moves first.
+#[derive(Debug, Clone)]
+pub struct MorrisBoard {
+    dim: usize,
+    // the cells by x + dim * y
+    cells: Vec<Cell>,
+    win_lines: Vec<Vec<usize>>,
+    history: Vec<MorrisMove>,
+}
+
+impl MorrisBoard {
+    /// Create an empty board with the given number of cells along each side, each player
+    /// having that many pieces
+    pub fn build(dim: usize) -> Result<MorrisBoard, BoardError> {
+        if !DIM_RANGE.contains(&dim) {
+            return Err(BoardError::InvalidDimension {
+                got: dim,
+                min: *DIM_RANGE.start(),
+                max: *DIM_RANGE.end(),
+            });
+        }
+        Ok(MorrisBoard {
+            dim,
+            cells: vec![Cell::Blank; dim * dim],
+            win_lines: Board::segments(dim, dim, dim, false),
+            history: Vec::new(),
+        })
+    }
+
+    /// The number of cells along each side, and of pieces of each player
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// The piece at the given coordinates
+    pub fn get_cell(&self, coord: impl Into<Coord>) -> Cell {
+        self.cells[self.index(coord.into())]
+    }
+
+    /// The player to move
+    pub fn to_move(&self) -> Cell {
+        if self.history.len().is_multiple_of(2) {
+            Cell::X
+        } else {
+            Cell::O
+        }
+    }
+
+    /// The moves made so far
+    pub fn history(&self) -> &[MorrisMove] {
+        &self.history
+    }
+
+    /// The pieces the player hasn't placed yet
+    pub fn pieces_left(&self, player: Cell) -> usize {
+        self.dim - self.cells.iter().filter(|&&c| c == player).count()
+    }
+
+    /// Whether the player to move places a new piece rather than sliding one
+    pub fn placing(&self) -> bool {
+        self.pieces_left(self.to_move()) > 0
+    }
+
+    /// The moves the player to move may make. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<MorrisMove> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.moves()
+    }
+
+    /// Make the move for the player to move
+    ///
+    /// Returns an error if the move isn't [legal](MorrisBoard::legal_moves).
+    pub fn play(&mut self, mv: MorrisMove) -> Result<(), MoveError> {
+        if !self.legal_moves().contains(&mv) {
+            return Err(match mv {
+                MorrisMove::Place(coord) if self.get_cell(coord) != Cell::Blank => {
+                    MoveError::CellTaken
+                }
+                _ if self.is_over() => MoveError::GameAlreadyOver,
+                _ => MoveError::IllegalSlide,
+            });
+        }
+        let player = self.to_move();
+        match mv {
+            MorrisMove::Place(coord) => {
+                let idx = self.index(coord);
+                self.cells[idx] = player;
+            }
+            MorrisMove::Slide(from, to) => {
+                let (from, to) = (self.index(from), self.index(to));
+                self.cells[from] = Cell::Blank;
+                self.cells[to] = player;
+            }
+        }
+        self.history.push(mv);
+        Ok(())
+    }
+
+    /// Take back the last move
+    pub fn undo(&mut self) -> Option<MorrisMove> {
+        let mv = self.history.pop()?;
+        match mv {
+            MorrisMove::Place(coord) => {
+                let idx = self.index(coord);
+                self.cells[idx] = Cell::Blank;
+            }
+            MorrisMove::Slide(from, to) => {
+                let (from, to) = (self.index(from), self.index(to));
+                self.cells[to] = Cell::Blank;
+                self.cells[from] = self.to_move();
+            }
+        }
+        Some(mv)
+    }
+
+    /// The player who completed a line, or whose opponent can't move any piece
+    pub fn winner(&self) -> Option<Cell> {
+        let line = self.win_lines.iter().find_map(|line| {
+            let first = self.cells[line[0]];
+            (first != Cell::Blank && line.iter().all(|&idx| self.cells[idx] == first))
+                .then_some(first)
+        });
+        line.or_else(|| self.moves().is_empty().then(|| self.to_move().opponent()))
+    }
+
+    /// Whether the game is over, by a win or because the move limit is reached
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || self.history.len() >= MOVE_LIMIT
+    }
+
+    /// Parse a line of user input into a move: `x y` while placing pieces, the piece to
+    /// move and the cell to move it to as `x y x y` once all are placed
+    pub fn parse_input(&self, input: &str) -> Result<MorrisMove, ParseError> {
+        if self.placing() {
+            let coord = input::parse_move(input, self.dim, self.dim)?;
+            Ok(MorrisMove::Place(coord.into()))
+        } else {
+            let [from, to] = input::parse_slide(input, self.dim)?;
+            Ok(MorrisMove::Slide(from.into(), to.into()))
+        }
+    }
+
+    // the moves of the player to move, whether or not the game is over
+    fn moves(&self) -> Vec<MorrisMove> {
+        let blanks = (0..self.cells.len()).filter(|&idx| self.cells[idx] == Cell::Blank);
+        if self.placing() {
+            return blanks
+                .map(|idx| MorrisMove::Place(self.coord(idx)))
+                .collect();
+        }
+        let player = self.to_move();
+        let mut moves = Vec::new();
+        for from in (0..self.cells.len()).filter(|&idx| self.cells[idx] == player) {
+            let from = self.coord(from);
+            for to in blanks.clone().map(|idx| self.coord(idx)) {
+                if from.col.abs_diff(to.col) <= 1 && from.row.abs_diff(to.row) <= 1 {
+                    moves.push(MorrisMove::Slide(from, to));
+                }
+            }
+        }
+        moves
+    }
+
+    // the index into the cells of the cell at the coordinates
+    fn index(&self, coord: Coord) -> usize {
+        coord.col + self.dim * coord.row
+    }
+
+    // the coordinates of the cell at the index into the cells
+    fn coord(&self, idx: usize) -> Coord {
+        Coord {
+            row: idx / self.dim,
+            col: idx % self.dim,
+        }
+    }
+}
+
+impl Variant for MorrisBoard {
+    type Move = MorrisMove;
+
+    fn legal_moves(&self) -> Vec<MorrisMove> {
+        MorrisBoard::legal_moves(self)
+    }
+
+    fn play(&mut self, mv: MorrisMove) -> Result<(), MoveError> {
+        MorrisBoard::play(self, mv)
+    }
+
+    fn undo(&mut self) -> Option<MorrisMove> {
+        MorrisBoard::undo(self)
+    }
+
+    fn winner(&self) -> Option<Cell> {
+        MorrisBoard::winner(self)
+    }
+
+    // pieces on lines the opponent has no piece on, counted by their square
+    fn evaluate(&self) -> i64 {
+        let player = self.to_move();
+        self.win_lines
+            .iter()
+            .map(|line| {
+                let count = |p| line.iter().filter(|&&idx| self.cells[idx] == p).count() as i64;
+                match (count(player), count(player.opponent())) {
+                    (n, 0) => n * n,
+                    (0, n) => -n * n,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for MorrisBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sep = "+---".repeat(self.dim) + "+";
+        writeln!(f, "{}", sep)?;
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                write!(f, "| {} ", self.get_cell((x, y)))?;
+            }
+            writeln!(f, "|")?;
+            writeln!(f, "{}", sep)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant;
+
+    #[test]
+    fn placing_then_sliding() {
+        let mut board = MorrisBoard::build(3).unwrap();
+        assert!(MorrisBoard::build(2).is_err());
+        for mv in [(1, 1), (0, 0), (2, 0), (0, 2), (1, 0)] {
+            assert!(board.placing());
+            board.play(MorrisMove::Place(mv.into())).unwrap();
+        }
+        // O blocks the column of X's pieces and places its last piece
+        board.play(MorrisMove::Place((1, 2).into())).unwrap();
+        assert_eq!(board.pieces_left(Cell::X), 0);
+        assert!(!board.placing());
+        assert!(board
+            .legal_moves()
+            .iter()
+            .all(|mv| matches!(mv, MorrisMove::Slide(..))));
+        assert_eq!(
+            board.play(MorrisMove::Place((2, 2).into())),
+            Err(MoveError::IllegalSlide)
+        );
+        // not adjacent
+        assert_eq!(
+            board.play(MorrisMove::Slide((2, 0).into(), (2, 2).into())),
+            Err(MoveError::IllegalSlide)
+        );
+        let slide = MorrisMove::Slide((2, 0).into(), (2, 1).into());
+        board.play(slide).unwrap();
+        assert_eq!(board.get_cell((2, 0)), Cell::Blank);
+        assert_eq!(board.get_cell((2, 1)), Cell::X);
+        assert_eq!(board.undo(), Some(slide));
+        assert_eq!(board.get_cell((2, 0)), Cell::X);
+        assert_eq!(board.to_move(), Cell::X);
+    }
+
+    #[test]
+    fn engine_completes_line() {
+        let mut board = MorrisBoard::build(3).unwrap();
+        for mv in [(0, 0), (2, 0), (1, 1), (0, 1), (1, 2), (2, 2)] {
+            board.play(MorrisMove::Place(mv.into())).unwrap();
+        }
+        // X slides onto the diagonal, no placement is possible any more
+        let mv = variant::best_move(&board, 2).unwrap();
+        board.play(mv).unwrap();
+        assert_eq!(board.winner(), Some(Cell::X), "{}", mv);
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn parse_input() {
+        let mut board = MorrisBoard::build(3).unwrap();
+        assert_eq!(
+            board.parse_input("2 3"),
+            Ok(MorrisMove::Place((1, 2).into()))
+        );
+        for mv in [(0, 0), (2, 0), (1, 1), (0, 1), (1, 2), (2, 2)] {
+            board.play(MorrisMove::Place(mv.into())).unwrap();
+        }
+        let slide = MorrisMove::Slide((0, 0).into(), (1, 0).into());
+        assert_eq!(board.parse_input("1 1 -> 2 1"), Ok(slide));
+        assert!(board.parse_input("2 1").is_err());
+        assert_eq!(slide.to_string(), "1 1 -> 2 1");
+    }
+}
@@ -0,0 +1,359 @@
+//! Games of three or more players, each with a mark of their own, taking turns in a fixed
+//! order on a board large enough for all of them. The first player to complete a line of
+//! the win length wins.
+//!
+//! The two player game keeps to its own types: [`Board`], [`GameOver`](crate::GameOver)
+//! and the [`Player`](crate::player::Player) trait only know X and O, so a game of several
+//! players is played on a [`MultiBoard`] with the players told apart by their [`Seat`].
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::board::{Board, BoardError, Cell, Coord, MoveError, DIM_RANGE};
+use crate::input::{self, ParseError};
+use crate::t;
+
+/// The marks of the players in turn order, the first two the same as in the two player game
+pub const MARKS: [char; 6] = ['X', 'O', 'Y', 'Z', 'W', 'V'];
+
+/// The supported numbers of players
+pub const PLAYER_RANGE: RangeInclusive<usize> = 2..=MARKS.len();
+
+/// The seat of one of the players, numbered in turn order from 0. Not to be confused with
+/// the [`Player`](crate::player::Player) trait choosing the moves of the two player game.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Seat(pub usize);
+
+impl Seat {
+    /// The mark the player puts on the board
+    pub fn mark(self) -> char {
+        MARKS[self.0]
+    }
+}
+
+impl From<Cell> for Seat {
+    /// X and O are the first and the second player. Panics for blank and blocked cells.
+    fn from(cell: Cell) -> Seat {
+        match cell {
+            Cell::X => Seat(0),
+            Cell::O => Seat(1),
+            Cell::Blank | Cell::Blocked => panic!("only X and O cells have a player"),
+        }
+    }
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mark())
+    }
+}
+
+/// How a game of several players ended
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MultiGameOver {
+    /// The player completed a line
+    Won(Seat),
+    /// The board is full
+    Tie,
+}
+
+impl fmt::Display for MultiGameOver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultiGameOver::Won(player) => write!(f, "{}", t!("multi-won", mark = player)),
+            MultiGameOver::Tie => write!(f, "{}", t!("tie")),
+        }
+    }
+}
+
+/// A board shared by several players, the first player moving first
+#[derive(Debug, Clone)]
+pub struct MultiBoard {
+    width: usize,
+    height: usize,
+    win_length: usize,
+    players: usize,
+    // the player on each cell by x + y * width
+    cells: Vec<Option<Seat>>,
+    win_lines: Vec<Vec<usize>>,
+    history: Vec<Coord>,
+}
+
+impl MultiBoard {
+    /// Create an empty board with the given number of columns and rows for the given number
+    /// of players, won by the given number of marks in a row, column or diagonal
+    ///
+    /// Returns an error if the size, the win length or the number of players is out of range.
+    pub fn build(
+        width: usize,
+        height: usize,
+        win_length: usize,
+        players: usize,
+    ) -> Result<MultiBoard, BoardError> {
+        for got in [width, height] {
+            if !DIM_RANGE.contains(&got) {
+                return Err(BoardError::InvalidDimension {
+                    got,
+                    min: *DIM_RANGE.start(),
+                    max: *DIM_RANGE.end(),
+                });
+            }
+        }
+        if !(2..=width.min(height)).contains(&win_length) {
+            return Err(BoardError::InvalidWinLength {
+                got: win_length,
+                min: 2,
+                max: width.min(height),
+            });
+        }
+        if !PLAYER_RANGE.contains(&players) {
+            return Err(BoardError::InvalidPlayers {
+                got: players,
+                min: *PLAYER_RANGE.start(),
+                max: *PLAYER_RANGE.end(),
+            });
+        }
+        Ok(MultiBoard {
+            width,
+            height,
+            win_length,
+            players,
+            cells: vec![None; width * height],
+            win_lines: Board::segments(width, height, win_length, false),
+            history: Vec::new(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The number of players taking turns
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    /// The player on the cell at the given coordinates, if any
+    pub fn get_cell(&self, coord: impl Into<Coord>) -> Option<Seat> {
+        self.cells[self.index(coord.into())]
+    }
+
+    /// The player whose turn it is
+    pub fn to_move(&self) -> Seat {
+        Seat(self.history.len() % self.players)
+    }
+
+    /// The moves made so far
+    pub fn history(&self) -> &[Coord] {
+        &self.history
+    }
+
+    /// The blank cells, row by row. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<Coord> {
+        if self.winner().is_some() {
+            return Vec::new();
+        }
+        (0..self.cells.len())
+            .filter(|&idx| self.cells[idx].is_none())
+            .map(|idx| self.coord(idx))
+            .collect()
+    }
+
+    /// Put the mark of the player to move on the cell at the given coordinates and pass the
+    /// turn to the next player
+    ///
+    /// Returns how the game ended with the move, or an error if the cell is off the board
+    /// or taken, or the game is over.
+    pub fn play(&mut self, coord: impl Into<Coord>) -> Result<Option<MultiGameOver>, MoveError> {
+        let coord = coord.into();
+        if coord.col >= self.width || coord.row >= self.height {
+            return Err(MoveError::OutOfBounds(coord));
+        }
+        if self.get_cell(coord).is_some() {
+            return Err(MoveError::CellTaken);
+        }
+        if self.winner().is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        let idx = self.index(coord);
+        self.cells[idx] = Some(self.to_move());
+        self.history.push(coord);
+        Ok(self.result())
+    }
+
+    /// Take back the last move, returning its coordinates
+    pub fn undo(&mut self) -> Option<Coord> {
+        let coord = self.history.pop()?;
+        let idx = self.index(coord);
+        self.cells[idx] = None;
+        Some(coord)
+    }
+
+    /// The player who completed a line
+    pub fn winner(&self) -> Option<Seat> {
+        self.win_lines.iter().find_map(|line| {
+            let first = self.cells[line[0]]?;
+            line.iter()
+                .all(|&idx| self.cells[idx] == Some(first))
+                .then_some(first)
+        })
+    }
+
+    /// How the game ended, `None` while it goes on
+    pub fn result(&self) -> Option<MultiGameOver> {
+        match self.winner() {
+            Some(player) => Some(MultiGameOver::Won(player)),
+            None if self.cells.iter().all(Option::is_some) => Some(MultiGameOver::Tie),
+            None => None,
+        }
+    }
+
+    /// Parse a line of user input into coordinates
+    pub fn parse_input(&self, input: &str) -> Result<Coord, ParseError> {
+        input::parse_move(input, self.width, self.height).map(Coord::from)
+    }
+
+    /// Choose a move for the player to move: complete a line, otherwise block the lines the
+    /// other players could complete, the next one first, otherwise take the cell on the most
+    /// promising lines. Returns `None` if the game is over.
+    pub fn best_move(&self) -> Option<Coord> {
+        if self.result().is_some() {
+            return None;
+        }
+        let player = self.to_move();
+        let idx = (0..self.players)
+            .map(|offset| Seat((player.0 + offset) % self.players))
+            .find_map(|p| self.completing_cell(p))
+            .or_else(|| {
+                (0..self.cells.len())
+                    .filter(|&idx| self.cells[idx].is_none())
+                    .max_by_key(|&idx| (self.score(idx, player), std::cmp::Reverse(idx)))
+            })?;
+        Some(self.coord(idx))
+    }
+
+    // the index into the cells of the cell at the coordinates
+    fn index(&self, coord: Coord) -> usize {
+        coord.col + coord.row * self.width
+    }
+
+    // the coordinates of the cell at the index into the cells
+    fn coord(&self, idx: usize) -> Coord {
+        Coord {
+            row: idx / self.width,
+            col: idx % self.width,
+        }
+    }
+
+    // A blank cell which completes a line for the player
+    fn completing_cell(&self, player: Seat) -> Option<usize> {
+        self.win_lines.iter().find_map(|line| {
+            let blanks: Vec<usize> = line
+                .iter()
+                .copied()
+                .filter(|&idx| self.cells[idx].is_none())
+                .collect();
+            let own = line
+                .iter()
+                .filter(|&&idx| self.cells[idx] == Some(player))
+                .count();
+            (blanks.len() == 1 && own == self.win_length - 1).then(|| blanks[0])
+        })
+    }
+
+    // The value of taking a blank cell for the player: the lines through it only the player
+    // has marks on count by their number, the lines of a single other player a bit less
+    fn score(&self, idx: usize, player: Seat) -> usize {
+        self.win_lines
+            .iter()
+            .filter(|line| line.contains(&idx))
+            .map(|line| {
+                let mut owners: Vec<Seat> = line.iter().filter_map(|&i| self.cells[i]).collect();
+                let count = owners.len();
+                owners.dedup();
+                match owners[..] {
+                    [] => 1,
+                    [owner] if owner == player => 1 + 4 * count * count,
+                    [_] => 3 * count * count,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for MultiBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sep = "+---".repeat(self.width) + "+";
+        writeln!(f, "{}", sep)?;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mark = self.get_cell((x, y)).map_or(' ', Seat::mark);
+                write!(f, "| {} ", mark)?;
+            }
+            writeln!(f, "|")?;
+            writeln!(f, "{}", sep)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_rotation() {
+        let mut board = MultiBoard::build(6, 6, 4, 3).unwrap();
+        assert_eq!(
+            MultiBoard::build(6, 6, 4, 7).unwrap_err(),
+            BoardError::InvalidPlayers {
+                got: 7,
+                min: 2,
+                max: 6
+            }
+        );
+        let marks: Vec<char> = (0..4)
+            .map(|i| {
+                let mark = board.to_move().mark();
+                board.play((i, 0)).unwrap();
+                mark
+            })
+            .collect();
+        assert_eq!(marks, ['X', 'O', 'Y', 'X']);
+        assert_eq!(board.get_cell((2, 0)), Some(Seat(2)));
+        assert_eq!(board.play((2, 0)), Err(MoveError::CellTaken));
+        assert_eq!(board.undo(), Some((3, 0).into()));
+        assert_eq!(board.to_move(), Seat(0));
+    }
+
+    #[test]
+    fn third_player_wins() {
+        let mut board = MultiBoard::build(5, 5, 3, 3).unwrap();
+        // Y builds the middle column while X and O play elsewhere
+        for mv in [(0, 0), (4, 0), (2, 1), (0, 4), (4, 4), (2, 2)] {
+            assert_eq!(board.play(mv), Ok(None));
+        }
+        // neither X nor O can complete a line, so X blocks Y at one end
+        assert_eq!(board.best_move(), Some((2, 0).into()));
+        board.play((0, 2)).unwrap();
+        board.play((4, 2)).unwrap();
+        assert_eq!(board.best_move(), Some((2, 0).into()));
+        assert_eq!(board.play((2, 0)), Ok(Some(MultiGameOver::Won(Seat(2)))));
+        assert_eq!(board.winner().map(Seat::mark), Some('Y'));
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn tie() {
+        let mut board = MultiBoard::build(2, 2, 2, 4).unwrap();
+        for mv in [(0, 0), (1, 0), (0, 1)] {
+            assert_eq!(board.play(mv), Ok(None));
+        }
+        assert_eq!(board.play((1, 1)), Ok(Some(MultiGameOver::Tie)));
+    }
+}
@@ -0,0 +1,155 @@
+//! A small library of well-known openings and traps on the 3x3 board, recognized in the
+//! moves of a game.
+
+use std::fmt;
+
+use crate::board::{Board, Coord};
+use crate::t;
+
+/// An opening or trap, given by its first moves
+#[derive(Debug, PartialEq)]
+pub struct Opening {
+    // message key of the name
+    key: &'static str,
+    // coordinates of the moves in one of the eight symmetric orientations
+    moves: &'static [(usize, usize)],
+    /// Whether the last move of the pattern loses by force for the player who made it
+    pub trap: bool,
+}
+
+impl Opening {
+    /// Number of moves making up the pattern
+    pub fn plies(&self) -> usize {
+        self.moves.len()
+    }
+
+    // Whether the game started with the pattern, in any orientation of the board
+    fn matches(&self, history: &[Coord]) -> bool {
+        history.len() >= self.moves.len()
+            && SYMMETRIES.iter().any(|transform| {
+                self.moves
+                    .iter()
+                    .zip(history)
+                    .all(|(&(x, y), &mv)| mv == transform(x, y))
+            })
+    }
+}
+
+impl fmt::Display for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", t!(self.key))
+    }
+}
+
+/// The known patterns. Openings name the first move, traps a reply which lets the opponent
+/// create two threats at once.
+const OPENINGS: [Opening; 7] = [
+    Opening {
+        key: "opening-center",
+        moves: &[(1, 1)],
+        trap: false,
+    },
+    Opening {
+        key: "opening-corner",
+        moves: &[(0, 0)],
+        trap: false,
+    },
+    Opening {
+        key: "opening-edge",
+        moves: &[(1, 0)],
+        trap: false,
+    },
+    // the only safe reply to the center is a corner
+    Opening {
+        key: "trap-edge-reply",
+        moves: &[(1, 1), (1, 0)],
+        trap: true,
+    },
+    // the only safe reply to a corner is the center
+    Opening {
+        key: "trap-adjacent-edge",
+        moves: &[(0, 0), (1, 0)],
+        trap: true,
+    },
+    Opening {
+        key: "trap-opposite-corner",
+        moves: &[(0, 0), (2, 2)],
+        trap: true,
+    },
+    // after opposite corners around the center the defender must take an edge
+    Opening {
+        key: "trap-double-fork",
+        moves: &[(0, 0), (1, 1), (2, 2), (2, 0)],
+        trap: true,
+    },
+];
+
+type Transform = fn(usize, usize) -> (usize, usize);
+
+const SYMMETRIES: [Transform; 8] = [
+    |x, y| (x, y),
+    |x, y| (2 - x, y),
+    |x, y| (x, 2 - y),
+    |x, y| (2 - x, 2 - y),
+    |x, y| (y, x),
+    |x, y| (2 - y, x),
+    |x, y| (y, 2 - x),
+    |x, y| (2 - y, 2 - x),
+];
+
+/// The openings and traps the game on the board started with. Only 3x3 boards are
+/// recognized, with three in a row winning, the lines ending at the edges, the standard
+/// rules, no handicap and no blocked cells.
+pub fn recognize(board: &Board) -> Vec<&'static Opening> {
+    if !board.is_square()
+        || board.width() != 3
+        || board.win_length() != 3
+        || board.wraps()
+        || !board.has_standard_rules()
+        || board.handicap().is_some()
+        || board.has_blocked()
+    {
+        return Vec::new();
+    }
+    OPENINGS
+        .iter()
+        .filter(|opening| opening.matches(board.history()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Cell;
+
+    fn keys(board: &Board) -> Vec<&'static str> {
+        recognize(board).iter().map(|opening| opening.key).collect()
+    }
+
+    #[test]
+    fn openings() {
+        assert!(keys(&Board::from_moves(&[], Cell::X)).is_empty());
+        assert_eq!(
+            keys(&Board::from_moves(&[(1, 1), (2, 2)], Cell::X)),
+            ["opening-center"]
+        );
+        assert_eq!(
+            keys(&Board::from_moves(&[(1, 1), (2, 1)], Cell::X)),
+            ["opening-center", "trap-edge-reply"]
+        );
+        // the same trap in another orientation
+        assert_eq!(
+            keys(&Board::from_moves(&[(2, 0), (2, 1), (1, 1)], Cell::X)),
+            ["opening-corner", "trap-adjacent-edge"]
+        );
+        let board = Board::from_moves(&[(2, 2), (1, 1), (0, 0), (0, 2), (2, 0)], Cell::X);
+        let found = recognize(&board);
+        assert_eq!(found.last().unwrap().key, "trap-double-fork");
+        assert_eq!(found.last().unwrap().plies(), 4);
+        assert_eq!(
+            found.last().unwrap().to_string(),
+            "double-fork trap (opposite corners answered with a corner)"
+        );
+        assert!(keys(&Board::build(4, Cell::X).unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! The players of a game on a [`Board`].
+//!
+//! A player implements [`Player`] and chooses the move for the side to move whenever it is
+//! their turn. The game itself is played by whoever drives them, e.g. the terminal game of
+//! the binary, so that people and engines can take either seat: a person against the
+//! computer, two people or two engines.
+
+use crate::board::{Board, Coord, Move};
+use crate::engine::Engine;
+
+/// Chooses the moves for one side of the game
+pub trait Player {
+    /// The move for the player to move on the board. It must be one the board
+    /// [accepts](Board::check_move), and the game isn't over yet.
+    fn select_move(&mut self, board: &Board) -> Move;
+
+    /// Whether to swap sides under the [pie rule](Board::set_pie_rule) after the opponent's
+    /// first move. Players don't swap by default.
+    fn swap(&mut self, _board: &Board) -> bool {
+        false
+    }
+
+    /// Think about the position while the opponent, who is to move, considers their move.
+    /// Players who don't think ahead ignore it.
+    fn ponder(&mut self, _board: &Board) {}
+
+    /// The engine choosing the moves, `None` for players who choose them otherwise, e.g. a
+    /// person
+    fn engine(&mut self) -> Option<&mut dyn Engine> {
+        None
+    }
+
+    /// What to do on the turn: by default make the [selected](Player::select_move) move.
+    /// Players who may take back their moves, e.g. a person, choose to undo instead.
+    fn choose(&mut self, board: &Board) -> Choice {
+        Choice::Move(self.select_move(board))
+    }
+}
+
+/// What a player does on their turn, see [`Player::choose`]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Choice {
+    /// Make the move
+    Move(Move),
+    /// Take back their last move and the opponent's reply instead of moving
+    Undo,
+}
+
+/// Plays the moves of an [`Engine`]
+pub struct ComputerPlayer {
+    engine: Box<dyn Engine>,
+}
+
+impl ComputerPlayer {
+    /// A player choosing its moves with the engine
+    pub fn new(engine: Box<dyn Engine>) -> Self {
+        ComputerPlayer { engine }
+    }
+}
+
+impl Player for ComputerPlayer {
+    // the engine's opening under the pie rule, otherwise its choice
+    fn select_move(&mut self, board: &Board) -> Move {
+        let mark = board.to_move();
+        let coord = if board.pie_rule() && board.history().is_empty() {
+            self.engine.pie_opening(board, mark)
+        } else {
+            self.engine.choose(board, mark)
+        };
+        Move { coord, mark }
+    }
+
+    fn swap(&mut self, board: &Board) -> bool {
+        self.engine.swap(board, board.to_move())
+    }
+
+    fn ponder(&mut self, board: &Board) {
+        self.engine.ponder(board, board.to_move());
+    }
+
+    fn engine(&mut self) -> Option<&mut dyn Engine> {
+        Some(self.engine.as_mut())
+    }
+}
+
+/// Plays the given moves in order, e.g. to replay a game or in tests
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedPlayer {
+    moves: Vec<Coord>,
+    played: usize,
+}
+
+impl ScriptedPlayer {
+    /// A player making the moves on the cells in order
+    pub fn new(moves: impl IntoIterator<Item = Coord>) -> Self {
+        ScriptedPlayer {
+            moves: moves.into_iter().collect(),
+            played: 0,
+        }
+    }
+
+    /// The moves not played yet
+    pub fn remaining(&self) -> &[Coord] {
+        &self.moves[self.played..]
+    }
+}
+
+impl Player for ScriptedPlayer {
+    /// # Panics
+    ///
+    /// If all moves have been played.
+    fn select_move(&mut self, board: &Board) -> Move {
+        let Some(&coord) = self.moves.get(self.played) else {
+            panic!("the script has no move left");
+        };
+        self.played += 1;
+        Move {
+            coord,
+            mark: board.to_move(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Cell, GameOver};
+    use crate::engine::{EngineConfig, EngineKind};
+
+    // Let the players take turns from the empty board until the game is over
+    fn play(players: &mut [Box<dyn Player>; 2], board: &mut Board) -> GameOver {
+        loop {
+            let seat = usize::from(board.to_move() != board.human_uses());
+            let mv = players[seat].select_move(board);
+            if let Some(over) = board.apply_move(mv).unwrap() {
+                return over;
+            }
+        }
+    }
+
+    #[test]
+    fn scripted_players() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        let mut players: [Box<dyn Player>; 2] = [
+            Box::new(ScriptedPlayer::new(
+                [(0, 0), (1, 1), (2, 2)].map(Coord::from),
+            )),
+            Box::new(ScriptedPlayer::new([(1, 0), (2, 0)].map(Coord::from))),
+        ];
+        assert_eq!(play(&mut players, &mut board), GameOver::HumanWon);
+        assert_eq!(board.history(), [(0, 0), (1, 0), (1, 1), (2, 0), (2, 2)]);
+        assert!(players[0].engine().is_none());
+    }
+
+    #[test]
+    fn engine_against_engine() {
+        let engine = || {
+            EngineKind::Minimax.build(&EngineConfig {
+                book: false,
+                ..EngineConfig::default()
+            })
+        };
+        let mut board = Board::build(3, Cell::X).unwrap();
+        let mut players: [Box<dyn Player>; 2] = [
+            Box::new(ComputerPlayer::new(engine())),
+            Box::new(ComputerPlayer::new(engine())),
+        ];
+        assert_eq!(play(&mut players, &mut board), GameOver::Tie);
+        assert!(players[1].engine().is_some());
+    }
+
+    #[test]
+    fn moves_for_the_side_to_move() {
+        let mut board = Board::build(3, Cell::O).unwrap();
+        board.set_first_player(Cell::O);
+        let mut script = ScriptedPlayer::new([(1, 1)].map(Coord::from));
+        let mv = script.select_move(&board);
+        assert_eq!(
+            mv,
+            Move {
+                coord: Coord { row: 1, col: 1 },
+                mark: Cell::O
+            }
+        );
+        assert!(script.remaining().is_empty());
+        board.apply_move(mv).unwrap();
+        let mut computer = ComputerPlayer::new(EngineKind::Heuristic.build(&EngineConfig {
+            book: false,
+            ..EngineConfig::default()
+        }));
+        assert_eq!(computer.select_move(&board).mark, Cell::X);
+    }
+}
@@ -0,0 +1,237 @@
+//! Proof-number search: decides whether a player can force a win, often visiting far
+//! fewer positions than a complete search.
+//!
+//! The search grows a tree of positions and always expands the one which is cheapest to
+//! prove or disprove the root with. Every node counts the positions still to be won (its
+//! proof number) and those still to be held (its disproof number); a forced win is proven
+//! when the root's proof number drops to 0, and disproven when its disproof number does.
+//! Unlike [`solver::solve`](crate::solver::solve) it only answers the question and finds
+//! the first winning move, not the whole line.
+
+use std::collections::HashMap;
+
+use crate::board::{Board, Cell, Coord, GameOver};
+
+/// Number of positions searched by default before giving up
+pub const MAX_NODES: usize = 1_000_000;
+
+// Proof or disproof number of a node which can't be proven or disproven
+const INFINITE: u32 = u32::MAX;
+
+/// The answer of a finished search
+#[derive(Debug, PartialEq, Clone)]
+pub struct Proof {
+    /// Whether the attacker can force a win
+    pub wins: bool,
+    /// A winning move if the attacker wins and is to move
+    pub first_move: Option<Coord>,
+    /// Number of positions in the search tree
+    pub nodes: usize,
+}
+
+/// Prove or disprove that `attacker` can force a win in the position with `to_move` to
+/// move; a draw counts as not winning. Returns `None` if the game is over or the search
+/// needs more than `max_nodes` positions.
+pub fn prove(board: &Board, to_move: Cell, attacker: Cell, max_nodes: usize) -> Option<Proof> {
+    if board.winner().is_some() || board.legal_moves().next().is_none() {
+        return None;
+    }
+    let mut search = Search {
+        board: board.clone(),
+        attacker,
+        decided: HashMap::new(),
+        nodes: vec![Node {
+            mv: Coord { row: 0, col: 0 },
+            parent: 0,
+            children: 0..0,
+            proof: 1,
+            disproof: 1,
+        }],
+    };
+    while search.nodes[0].proof != 0 && search.nodes[0].disproof != 0 {
+        if search.nodes.len() > max_nodes {
+            return None;
+        }
+        let (leaf, side) = search.select(to_move);
+        search.expand(leaf, side);
+        search.update(leaf, side);
+    }
+    let root = &search.nodes[0];
+    let wins = root.proof == 0;
+    let first_move = (wins && to_move == attacker).then(|| {
+        let mut children = root.children.clone();
+        let winning = children
+            .find(|&child| search.nodes[child].proof == 0)
+            .expect("a proven node has a proven child");
+        search.nodes[winning].mv
+    });
+    Some(Proof {
+        wins,
+        first_move,
+        nodes: search.nodes.len(),
+    })
+}
+
+struct Node {
+    // the move leading to the position
+    mv: Coord,
+    parent: usize,
+    // the children are stored next to each other
+    children: std::ops::Range<usize>,
+    proof: u32,
+    disproof: u32,
+}
+
+struct Search {
+    // the position of the node being worked on
+    board: Board,
+    attacker: Cell,
+    // positions reached by other moves before, whether the attacker wins them
+    decided: HashMap<u64, bool>,
+    nodes: Vec<Node>,
+}
+
+impl Search {
+    // Walks from the root to the most proving leaf, playing the moves on the board, and
+    // returns it with the side to move there
+    fn select(&mut self, to_move: Cell) -> (usize, Cell) {
+        let mut node = 0;
+        let mut side = to_move;
+        while !self.nodes[node].children.is_empty() {
+            let children = self.nodes[node].children.clone();
+            node = if side == self.attacker {
+                children.min_by_key(|&child| self.nodes[child].proof)
+            } else {
+                children.min_by_key(|&child| self.nodes[child].disproof)
+            }
+            .unwrap();
+            self.board.set_cell(self.nodes[node].mv, side).unwrap();
+            side = side.opponent();
+        }
+        (node, side)
+    }
+
+    // Adds the positions after every move of the leaf, rating the finished ones and those
+    // the side to move wins right away
+    fn expand(&mut self, leaf: usize, side: Cell) {
+        let start = self.nodes.len();
+        let moves: Vec<Coord> = self.board.legal_moves().collect();
+        for mv in moves {
+            self.board.set_cell(mv, side).unwrap();
+            let next = side.opponent();
+            let won = match self.board.result() {
+                Some(GameOver::Tie) => Some(false),
+                Some(_) => Some(self.board.winner() == Some(self.attacker)),
+                None if self.board.rules().lines_win()
+                    && !self.board.winning_moves(next).is_empty() =>
+                {
+                    Some(next == self.attacker)
+                }
+                None => self.decided.get(&self.board.hash()).copied(),
+            };
+            self.board.undo();
+            let (proof, disproof) = match won {
+                Some(true) => (0, INFINITE),
+                Some(false) => (INFINITE, 0),
+                None => (1, 1),
+            };
+            self.nodes.push(Node {
+                mv,
+                parent: leaf,
+                children: 0..0,
+                proof,
+                disproof,
+            });
+        }
+        self.nodes[leaf].children = start..self.nodes.len();
+    }
+
+    // Recomputes the numbers from the leaf, with `side` to move, up to the root, taking
+    // back the moves on the way
+    fn update(&mut self, leaf: usize, mut side: Cell) {
+        let mut node = leaf;
+        loop {
+            let children = self.nodes[node].children.clone();
+            let proofs = children.clone().map(|child| self.nodes[child].proof);
+            let disproofs = children.map(|child| self.nodes[child].disproof);
+            // the attacker needs one winning move, the defender has to be beaten in all
+            let (proof, disproof) = if side == self.attacker {
+                (proofs.min().unwrap(), disproofs.fold(0, sum))
+            } else {
+                (proofs.fold(0, sum), disproofs.min().unwrap())
+            };
+            self.nodes[node].proof = proof;
+            self.nodes[node].disproof = disproof;
+            if proof == 0 || disproof == 0 {
+                self.decided.insert(self.board.hash(), proof == 0);
+            }
+            if node == 0 {
+                return;
+            }
+            self.board.undo();
+            node = self.nodes[node].parent;
+            side = side.opponent();
+        }
+    }
+}
+
+fn sum(a: u32, b: u32) -> u32 {
+    a.saturating_add(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver;
+
+    #[test]
+    fn proofs() {
+        let board = Board::build(3, Cell::X).unwrap();
+        let proof = prove(&board, Cell::X, Cell::X, MAX_NODES).unwrap();
+        assert!(!proof.wins);
+        assert_eq!(proof.first_move, None);
+        // answering the center with an edge loses
+        let board = Board::from_string("-O-/-X-/---", 3, Cell::X).unwrap();
+        let proof = prove(&board, Cell::X, Cell::X, MAX_NODES).unwrap();
+        assert!(proof.wins);
+        let mv = proof.first_move.unwrap();
+        let mut after = board.clone();
+        after.set_cell(mv, Cell::X).unwrap();
+        assert_eq!(
+            solver::solve(&after, Cell::O).unwrap().winner,
+            Some(Cell::X)
+        );
+        assert!(!prove(&board, Cell::X, Cell::O, MAX_NODES).unwrap().wins);
+        // the defender to move can't hold it either
+        assert!(prove(&after, Cell::O, Cell::X, MAX_NODES).unwrap().wins);
+        let won = Board::from_string("XXX/OO-/---", 3, Cell::X).unwrap();
+        assert_eq!(prove(&won, Cell::O, Cell::X, MAX_NODES), None);
+        assert_eq!(
+            prove(&Board::build(5, Cell::X).unwrap(), Cell::X, Cell::X, 100),
+            None
+        );
+    }
+
+    #[test]
+    fn agrees_with_solver() {
+        let mut rng = Rng::new(11);
+        for plies in 0..40 {
+            let board = Board::random(4, Cell::X, 5 + plies % 6, &mut rng).unwrap();
+            if board.winner().is_some() {
+                continue;
+            }
+            let side = board.to_move();
+            let winner = solver::solve(&board, side).unwrap().winner;
+            for attacker in [Cell::X, Cell::O] {
+                let proof = prove(&board, side, attacker, MAX_NODES).unwrap();
+                assert_eq!(
+                    proof.wins,
+                    winner == Some(attacker),
+                    "{}",
+                    board.to_notation()
+                );
+            }
+        }
+    }
+}
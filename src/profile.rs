@@ -0,0 +1,101 @@
+//! The player's profile: values the game keeps about the player between sessions, such as
+//! the estimated skill.
+//!
+//! The profile is stored next to the games database in `key = value` lines like the config
+//! file, but it is written by the game rather than by the user.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+use crate::i18n;
+
+/// The player's profile
+#[derive(Debug, Clone)]
+pub struct Profile {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl Profile {
+    /// The profile file used by default: `$XDG_DATA_HOME/tictactoe/profile` (or
+    /// `~/.local/share/tictactoe/profile`)
+    pub fn default_path() -> Option<PathBuf> {
+        Some(db::data_dir()?.join("profile"))
+    }
+
+    /// Load the profile from the given file. A missing file gives an empty profile, lines
+    /// which can't be parsed are dropped.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Profile> {
+        let path = path.into();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let values = content
+            .lines()
+            .filter_map(|line| {
+                let values = i18n::parse_messages(line).ok()?;
+                let (key, value) = values.into_iter().next()?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect();
+        Ok(Profile { path, values })
+    }
+
+    /// Write the profile back to its file
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content: String = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect();
+        fs::write(&self.path, content)
+    }
+
+    /// The file the profile is stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get a value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Set a value. Values must fit on one line.
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        let value = value.to_string().replace('\n', " ");
+        self.values.insert(key.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-profile-test-{}", std::process::id()));
+        let mut profile = Profile::load(&path).unwrap();
+        assert_eq!(profile.get("skill"), None);
+        profile.set("skill", "advanced");
+        profile.set("games", 10);
+        profile.save().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "games = 10\nskill = advanced\n"
+        );
+        let profile = Profile::load(&path).unwrap();
+        assert_eq!(profile.get("skill"), Some("advanced"));
+        assert_eq!(profile.get("games"), Some("10"));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+//! The daily puzzle: a position generated from the date, the same for every player, in
+//! which the human can force a win by creating two threats at once, and [generated
+//! puzzles](WinPuzzle) with a unique forced win in a given number of moves.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::{Board, Cell, Coord};
+use crate::profile::Profile;
+use crate::rng::Rng;
+
+/// Dimension of the puzzle board
+const PUZZLE_DIM: usize = 4;
+
+/// Number of random positions [`WinPuzzle::generate`] tries before giving up
+pub const GENERATE_ATTEMPTS: usize = 10_000;
+
+/// Today as the number of days since the Unix epoch (UTC)
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400)
+}
+
+/// Format a day given as days since the Unix epoch as `YYYY-MM-DD`
+pub fn format_day(day: u64) -> String {
+    // civil date from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + u64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// A position where the human (X, to move) wins by force
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    /// The day the puzzle belongs to
+    pub day: u64,
+    /// The position to solve
+    pub board: Board,
+    solutions: Vec<Coord>,
+}
+
+impl Puzzle {
+    /// The puzzle of the given day. Every day gives the same puzzle wherever it is generated.
+    pub fn daily(day: u64) -> Puzzle {
+        let mut rng = Rng::new(day);
+        loop {
+            let pieces = 3 + rng.below(3);
+            let board = random_position(&mut rng, PUZZLE_DIM, pieces);
+            if !is_quiet(&board) {
+                continue;
+            }
+            let solutions = forks(&board);
+            if !solutions.is_empty() {
+                return Puzzle {
+                    day,
+                    board,
+                    solutions,
+                };
+            }
+        }
+    }
+
+    /// Whether the move wins by force
+    pub fn is_solution(&self, mv: Coord) -> bool {
+        self.solutions.contains(&mv)
+    }
+
+    /// All moves solving the puzzle
+    pub fn solutions(&self) -> &[Coord] {
+        &self.solutions
+    }
+}
+
+/// A position where the player to move, X, can force a win in a given number of their own
+/// moves with exactly one first move, e.g. "X to play and win in 2"
+#[derive(Debug, Clone)]
+pub struct WinPuzzle {
+    /// The position to solve, with the human playing X
+    pub board: Board,
+    /// Number of moves of X until the win, the winning one included
+    pub moves: usize,
+    /// The only first move which wins in time
+    pub solution: Coord,
+}
+
+impl WinPuzzle {
+    /// Generate a puzzle on a board of the given dimension from random positions, where X
+    /// can't win in fewer moves. Returns `None` if none of [`GENERATE_ATTEMPTS`] positions
+    /// is a puzzle.
+    pub fn generate(dim: usize, moves: usize, rng: &mut Rng) -> Option<WinPuzzle> {
+        let cells = dim * dim;
+        for _ in 0..GENERATE_ATTEMPTS {
+            let pieces = cells / 6 + rng.below(cells / 6 + 1);
+            let board = random_position(rng, dim, pieces);
+            if board.result().is_some() || !board.winning_moves(Cell::O).is_empty() {
+                continue;
+            }
+            if moves > 1 && wins_within(&board, Cell::X, moves - 1) {
+                continue;
+            }
+            let winning: Vec<Coord> = board
+                .legal_moves()
+                .filter(|&mv| wins_with(&board, Cell::X, mv, moves))
+                .collect();
+            if let [solution] = winning[..] {
+                return Some(WinPuzzle {
+                    board,
+                    moves,
+                    solution,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Whether `side`, to move, can force a win within the given number of their own moves
+pub fn wins_within(board: &Board, side: Cell, moves: usize) -> bool {
+    let mut board = board.clone();
+    let blanks: Vec<Coord> = board.legal_moves().collect();
+    blanks
+        .into_iter()
+        .any(|mv| forces_win(&mut board, side, mv, moves))
+}
+
+/// Whether the move of `side` forces a win within the given number of their own moves, this
+/// one included, whatever the opponent answers
+pub fn wins_with(board: &Board, side: Cell, mv: Coord, moves: usize) -> bool {
+    forces_win(&mut board.clone(), side, mv, moves)
+}
+
+fn forces_win(board: &mut Board, side: Cell, mv: Coord, moves: usize) -> bool {
+    if moves == 0 {
+        return false;
+    }
+    board.set_cell(mv, side).unwrap();
+    let won = match board.result() {
+        Some(_) => board.winner() == Some(side),
+        None if moves == 1 => false,
+        None => {
+            let replies: Vec<Coord> = board.legal_moves().collect();
+            replies.into_iter().all(|reply| {
+                board.set_cell(reply, side.opponent()).unwrap();
+                let won = board.result().is_none() && {
+                    let blanks: Vec<Coord> = board.legal_moves().collect();
+                    blanks
+                        .into_iter()
+                        .any(|mv| forces_win(board, side, mv, moves - 1))
+                };
+                board.undo();
+                won
+            })
+        }
+    };
+    board.undo();
+    won
+}
+
+// A position with the given number of pieces of each player and X to move
+fn random_position(rng: &mut Rng, dim: usize, pieces: usize) -> Board {
+    let mut board = Board::build(dim, Cell::X).unwrap();
+    for _ in 0..pieces {
+        for cell in [Cell::X, Cell::O] {
+            let blanks: Vec<Coord> = board.legal_moves().collect();
+            board
+                .set_cell(blanks[rng.below(blanks.len())], cell)
+                .unwrap();
+        }
+    }
+    board
+}
+
+// Whether the game goes on without an immediate win or threat for either player
+fn is_quiet(board: &Board) -> bool {
+    board.result().is_none()
+        && board.winning_moves(Cell::X).is_empty()
+        && board.winning_moves(Cell::O).is_empty()
+}
+
+// The moves for X which create two threats at once while O has none, so O can block only
+// one of them
+fn forks(board: &Board) -> Vec<Coord> {
+    let mut forks = Vec::new();
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if board.get_cell((x, y)) != Cell::Blank {
+                continue;
+            }
+            let mut next = board.clone();
+            next.set_cell((x, y), Cell::X).unwrap();
+            if next.winning_moves(Cell::X).len() >= 2 && next.winning_moves(Cell::O).is_empty() {
+                forks.push((x, y).into());
+            }
+        }
+    }
+    forks
+}
+
+/// The player's record of daily puzzles, kept in the profile
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DailyRecord {
+    /// The last day a puzzle was attempted
+    pub played: Option<u64>,
+    /// The last day a puzzle was solved
+    pub solved: Option<u64>,
+    /// Number of consecutive days up to `solved` with a solved puzzle
+    pub streak: usize,
+}
+
+impl DailyRecord {
+    /// Read the record from the profile
+    pub fn load(profile: &Profile) -> DailyRecord {
+        let get = |key| profile.get(key).and_then(|v| v.parse().ok());
+        DailyRecord {
+            played: get("daily-played"),
+            solved: get("daily-solved"),
+            streak: profile
+                .get("daily-streak")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Store the record in the profile
+    pub fn store(&self, profile: &mut Profile) {
+        if let Some(played) = self.played {
+            profile.set("daily-played", played);
+        }
+        if let Some(solved) = self.solved {
+            profile.set("daily-solved", solved);
+        }
+        profile.set("daily-streak", self.streak);
+    }
+
+    /// Record the attempt at the puzzle of the given day
+    pub fn record(&mut self, day: u64, solved: bool) {
+        self.played = Some(day);
+        if solved {
+            self.streak = self.streak_on(day.saturating_sub(1)).saturating_add(1);
+            self.solved = Some(day);
+        } else {
+            self.streak = 0;
+        }
+    }
+
+    /// The streak still alive on the given day: it ends when a day passes without solving
+    /// the puzzle
+    pub fn streak_on(&self, day: u64) -> usize {
+        match self.solved {
+            Some(solved) if solved.saturating_add(1) >= day => self.streak,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(11_016), "2000-02-29");
+        assert_eq!(format_day(20_742), "2026-10-16");
+    }
+
+    #[test]
+    fn daily_puzzle() {
+        let puzzle = Puzzle::daily(20_742);
+        let again = Puzzle::daily(20_742);
+        assert_eq!(puzzle.board.history(), again.board.history());
+        assert!(!puzzle.solutions().is_empty());
+        for &mv in puzzle.solutions() {
+            assert!(puzzle.is_solution(mv));
+            let mut board = puzzle.board.clone();
+            board.human_move(mv).unwrap();
+            // whichever threat the computer blocks, the other one wins
+            board.computer_move();
+            assert_eq!(board.winning_moves(Cell::X).len(), 1);
+        }
+        assert_ne!(
+            Puzzle::daily(20_743).board.history(),
+            puzzle.board.history()
+        );
+    }
+
+    #[test]
+    fn win_puzzles() {
+        let mut rng = Rng::new(3);
+        for (dim, moves) in [(3, 1), (3, 2), (4, 2)] {
+            let puzzle = WinPuzzle::generate(dim, moves, &mut rng).unwrap();
+            let board = &puzzle.board;
+            assert_eq!(board.to_move(), Cell::X);
+            assert!(wins_with(board, Cell::X, puzzle.solution, moves));
+            assert!(!wins_within(board, Cell::X, moves - 1));
+            let winning = board
+                .legal_moves()
+                .filter(|&mv| wins_with(board, Cell::X, mv, moves))
+                .count();
+            assert_eq!(winning, 1, "{}", board.to_notation());
+        }
+        // a fork wins in 2, but not in 1
+        let board = Board::from_string("X--/-O-/--X", 3, Cell::X).unwrap();
+        assert!(!wins_within(&board, Cell::X, 1));
+        let fork = Board::from_string("X--/-O-/O-X", 3, Cell::X).unwrap();
+        assert!(wins_with(&fork, Cell::X, (2, 0).into(), 2));
+        assert!(!wins_with(&fork, Cell::X, (1, 0).into(), 2));
+    }
+
+    #[test]
+    fn streak() {
+        let mut record = DailyRecord::default();
+        record.record(10, true);
+        record.record(11, true);
+        assert_eq!(record.streak, 2);
+        assert_eq!(record.streak_on(12), 2);
+        assert_eq!(record.streak_on(13), 0);
+        record.record(14, true);
+        assert_eq!(record.streak, 1);
+        record.record(15, false);
+        assert_eq!(
+            (record.streak, record.solved, record.played),
+            (0, Some(14), Some(15))
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-daily-test-{}", std::process::id()));
+        let mut profile = Profile::load(&path).unwrap();
+        record.store(&mut profile);
+        assert_eq!(DailyRecord::load(&profile), record);
+    }
+}
@@ -0,0 +1,602 @@
+//! QR codes for sharing positions and game records, e.g. to scan them with a phone.
+//!
+//! Text is encoded in byte mode with the lowest error correction level, which keeps the
+//! codes small enough for a terminal. The encoder follows ISO/IEC 18004 (model 2).
+
+use std::fmt::Write;
+
+use crate::t;
+
+/// Error correction codewords per block, indexed by version (level L)
+const ECC_PER_BLOCK: [usize; 41] = [
+    0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30,
+    30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+];
+
+/// Number of error correction blocks, indexed by version (level L)
+const BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14,
+    15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+];
+
+/// A QR code: a square of dark and light modules
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    // modules of the finder, timing, alignment and format patterns, which are not masked
+    function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode the text in the smallest QR code it fits in.
+    ///
+    /// Returns an error if the text is too long even for the largest version.
+    pub fn encode(text: &str) -> Result<QrCode, &'static str> {
+        let data = text.as_bytes();
+        let version = (1..=40)
+            .find(|&v| 4 + count_bits(v) + data.len() * 8 <= data_codewords(v) * 8)
+            .ok_or(t!("qr-too-long"))?;
+        let mut qr = QrCode {
+            size: version * 4 + 17,
+            modules: Vec::new(),
+            function: Vec::new(),
+        };
+        qr.modules = vec![false; qr.size * qr.size];
+        qr.function = vec![false; qr.size * qr.size];
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&add_error_correction(version, &data_bits(version, data)));
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                let mut candidate = qr.clone();
+                candidate.apply_mask(mask);
+                candidate.draw_format(mask);
+                candidate.penalty()
+            })
+            .unwrap();
+        qr.apply_mask(mask);
+        qr.draw_format(mask);
+        Ok(qr)
+    }
+
+    /// Number of modules per side
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module in column x and row y is dark
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[x + y * self.size]
+    }
+
+    /// Render the code for the terminal, two rows of modules per line, surrounded by the
+    /// quiet zone. Colors are set explicitly so that the code scans on dark and light
+    /// terminals alike.
+    pub fn render(&self) -> String {
+        const QUIET: isize = 4;
+        let dark = |x: isize, y: isize| {
+            (0..self.size as isize).contains(&x)
+                && (0..self.size as isize).contains(&y)
+                && self.get(x as usize, y as usize)
+        };
+        let mut out = String::new();
+        let end = self.size as isize + QUIET;
+        for y in (-QUIET..end).step_by(2) {
+            for x in -QUIET..end {
+                let color = |dark| if dark { 30 } else { 97 };
+                // upper half block in the color of the upper module, on the color of the lower
+                let _ = write!(
+                    out,
+                    "\x1b[{};{}m\u{2580}",
+                    color(dark(x, y)),
+                    color(dark(x, y + 1)) + 10
+                );
+            }
+            out += "\x1b[0m\n";
+        }
+        out
+    }
+
+    /// The code as an SVG image, with a module size of 8 pixels
+    pub fn to_svg(&self) -> String {
+        const MODULE: usize = 8;
+        let width = (self.size + 8) * MODULE;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n<rect width=\"{0}\" height=\"{0}\" fill=\"#fff\"/>\n",
+            width
+        );
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.get(x, y) {
+                    let _ = writeln!(
+                        svg,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\"/>",
+                        (x + 4) * MODULE,
+                        (y + 4) * MODULE,
+                        MODULE
+                    );
+                }
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[x + y * self.size] = dark;
+        self.function[x + y * self.size] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4..=4isize {
+                for dx in -4..=4isize {
+                    let (x, y) = (cx as isize + dx, cy as isize + dy);
+                    if (0..size as isize).contains(&x) && (0..size as isize).contains(&y) {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+        let positions = alignment_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &cx) in positions.iter().enumerate() {
+            for (j, &cy) in positions.iter().enumerate() {
+                // the corners are taken by the finder patterns
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2..=2isize {
+                    for dx in -2..=2isize {
+                        let dark = dx.abs().max(dy.abs()) != 1;
+                        self.set_function(
+                            (cx as isize + dx) as usize,
+                            (cy as isize + dy) as usize,
+                            dark,
+                        );
+                    }
+                }
+            }
+        }
+        // reserve the format areas, drawn after masking
+        self.draw_format(0);
+        if version >= 7 {
+            let bits = version_bits(version);
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| bits >> i & 1 == 1;
+        let size = self.size;
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // always dark
+        self.set_function(8, size - 8, true);
+    }
+
+    // Places the codewords in the zigzag pattern of two columns, from the bottom right
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.function[x + y * size] && i < codewords.len() * 8 {
+                        self.modules[x + y * size] = codewords[i / 8] >> (7 - i % 8) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let idx = x + y * self.size;
+                if invert && !self.function[idx] {
+                    self.modules[idx] = !self.modules[idx];
+                }
+            }
+        }
+    }
+
+    // The penalty score used to choose the mask: long runs and blocks of one color,
+    // patterns resembling the finders and an unbalanced share of dark modules score high
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|x| self.get(x, i)).collect::<Vec<bool>>(),
+                (0..size).map(|y| self.get(i, y)).collect::<Vec<bool>>(),
+            ]
+        });
+        for line in lines {
+            let mut run = 1;
+            for i in 1..=size {
+                if i < size && line[i] == line[i - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            penalty += finder_like(&line) * 40;
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let c = self.get(x, y);
+                if c == self.get(x + 1, y) && c == self.get(x, y + 1) && c == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|&&d| d).count();
+        let total = self.modules.len();
+        // how many 5% steps the share of dark modules is away from 50%
+        let steps = (dark * 20).abs_diff(total * 10).div_ceil(total);
+        penalty + steps.saturating_sub(1) * 10
+    }
+}
+
+// Number of patterns in the line looking like a finder, dark, light, dark, light and dark
+// runs in the ratio 1:1:3:1:1 with light on both sides, four times as wide as the narrow
+// runs on at least one of them. The quiet zone around the symbol counts as light, and a
+// pattern light on both sides counts twice.
+fn finder_like(line: &[bool]) -> usize {
+    // the lengths of the runs, alternating between light and dark, the first and the last
+    // one light and extended by the quiet zone
+    let mut runs = vec![line.len()];
+    let mut dark = false;
+    for &module in line {
+        if module != dark {
+            runs.push(0);
+            dark = module;
+        }
+        *runs.last_mut().expect("there is a run") += 1;
+    }
+    if dark {
+        runs.push(0);
+    }
+    *runs.last_mut().expect("there is a run") += line.len();
+    runs.windows(7)
+        .step_by(2)
+        .map(|w| {
+            let n = w[1];
+            if w[2] != n || w[3] != 3 * n || w[4] != n || w[5] != n {
+                return 0;
+            }
+            usize::from(w[0] >= 4 * n && w[6] >= n) + usize::from(w[6] >= 4 * n && w[0] >= n)
+        })
+        .sum()
+}
+
+// Number of bits of the character count in byte mode
+fn count_bits(version: usize) -> usize {
+    if version <= 9 {
+        8
+    } else {
+        16
+    }
+}
+
+// Number of modules available for data and error correction, in bytes
+fn raw_codewords(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let aligns = version / 7 + 2;
+        modules -= (25 * aligns - 10) * aligns - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules / 8
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_codewords(version) - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+// The centers of the alignment patterns along each axis
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let aligns = version / 7 + 2;
+    let size = version * 4 + 17;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + aligns * 2 + 1) / (aligns * 2 - 2) * 2
+    };
+    let mut positions: Vec<usize> = (0..aligns - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+// The data codewords: mode, length, the data and padding
+fn data_bits(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = data_codewords(version) * 8;
+    let mut bits: Vec<bool> = Vec::with_capacity(capacity);
+    let mut push = |value: usize, len: usize| {
+        for i in (0..len).rev() {
+            bits.push(value >> i & 1 == 1);
+        }
+    };
+    push(0b0100, 4);
+    push(data.len(), count_bits(version));
+    for &byte in data {
+        push(byte as usize, 8);
+    }
+    let terminator = (capacity - bits.len()).min(4);
+    bits.resize(bits.len() + terminator, false);
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+    let mut bytes: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | u8::from(bit)))
+        .collect();
+    for pad in [0xec, 0x11].into_iter().cycle() {
+        if bytes.len() == capacity / 8 {
+            break;
+        }
+        bytes.push(pad);
+    }
+    bytes
+}
+
+// Splits the data into blocks, adds the error correction codewords and interleaves them
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc_len = ECC_PER_BLOCK[version];
+    let short_len = data.len() / blocks;
+    let long_blocks = data.len() % blocks;
+    let divisor = rs_divisor(ecc_len);
+    let mut data_blocks = Vec::new();
+    let mut ecc_blocks = Vec::new();
+    let mut start = 0;
+    for i in 0..blocks {
+        let len = short_len + usize::from(i >= blocks - long_blocks);
+        let block = &data[start..start + len];
+        ecc_blocks.push(rs_remainder(block, &divisor));
+        data_blocks.push(block);
+        start += len;
+    }
+    let mut out = Vec::with_capacity(raw_codewords(version));
+    for i in 0..=short_len {
+        for block in &data_blocks {
+            if let Some(&byte) = block.get(i) {
+                out.push(byte);
+            }
+        }
+    }
+    for i in 0..ecc_len {
+        for block in &ecc_blocks {
+            out.push(block[i]);
+        }
+    }
+    out
+}
+
+// Multiplication in GF(256) with the polynomial x^8 + x^4 + x^3 + x^2 + 1
+fn gf_mul(a: u8, b: u8) -> u8 {
+    let mut result: u8 = 0;
+    for i in (0..8).rev() {
+        result = (result << 1) ^ if result & 0x80 != 0 { 0x1d } else { 0 };
+        if b >> i & 1 == 1 {
+            result ^= a;
+        }
+    }
+    result
+}
+
+// The generator polynomial of the Reed-Solomon code, without the leading coefficient
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_mul(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_mul(root, 2);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_mul(d, factor);
+        }
+    }
+    result
+}
+
+// The 15 bits of format information for level L and the mask
+fn format_bits(mask: u32) -> u32 {
+    let data = 0b01 << 3 | mask;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+// The 18 bits of version information
+fn version_bits(version: usize) -> u32 {
+    let mut rem = version as u32;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1f25);
+    }
+    (version as u32) << 12 | rem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_correction() {
+        // the example of version 1-M from the standard's annex
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+
+    #[test]
+    fn format_and_version() {
+        assert_eq!(format_bits(0), 0b111011111000100);
+        assert_eq!(format_bits(7), 0b110100101110110);
+        assert_eq!(version_bits(7), 0b000111110010010100);
+        assert_eq!(alignment_positions(7), [6, 22, 38]);
+        assert_eq!(alignment_positions(32), [6, 34, 60, 86, 112, 138]);
+        assert_eq!(raw_codewords(10), 346);
+        assert_eq!(data_codewords(40), 2956);
+    }
+
+    #[test]
+    fn whole_matrix() {
+        // the texts encoded in byte mode at level L by an independent encoder, Nayuki's QR
+        // Code generator; for the second one a penalty off the standard chose another mask
+        let cases = [
+            (
+                "X--/-O-/---",
+                [
+                    "#######..#.##.#######",
+                    "#.....#..###..#.....#",
+                    "#.###.#.##.##.#.###.#",
+                    "#.###.#..#.#..#.###.#",
+                    "#.###.#...#.#.#.###.#",
+                    "#.....#.....#.#.....#",
+                    "#######.#.#.#.#######",
+                    "........##.##........",
+                    "###.########.##...#..",
+                    "#.#.#....##...###.###",
+                    "..###.#.###.##..###.#",
+                    ".#...#.#.#...#...#.#.",
+                    ".##..###..#.#..#.....",
+                    "........##.#..###.#..",
+                    "#######.####.##.#####",
+                    "#.....#.#.####..##..#",
+                    "#.###.#.##.#...#.....",
+                    "#.###.#...##..##..##.",
+                    "#.###.#.###.######..#",
+                    "#.....#.###..#...#.#.",
+                    "#######.##.#...#...##",
+                ],
+            ),
+            (
+                "a",
+                [
+                    "#######..#.##.#######",
+                    "#.....#..###..#.....#",
+                    "#.###.#.##.##.#.###.#",
+                    "#.###.#..#.#..#.###.#",
+                    "#.###.#...#.#.#.###.#",
+                    "#.....#.....#.#.....#",
+                    "#######.#.#.#.#######",
+                    "........##.##........",
+                    "###.########.##...#..",
+                    "..#.##.#..#...#...##.",
+                    "....#.#####.#...#...#",
+                    "##.#.#...##...#...#..",
+                    "##..####....#.#.#.#.#",
+                    "........##.#.#.#.#.##",
+                    "#######.#..#.###.####",
+                    "#.....#.######.###...",
+                    "#.###.#.#.##.###.##.#",
+                    "#.###.#...#...#...##.",
+                    "#.###.#.##..#...#...#",
+                    "#.....#.##....#...##.",
+                    "#######.##..#.#.#.###",
+                ],
+            ),
+        ];
+        for (text, expected) in cases {
+            let qr = QrCode::encode(text).unwrap();
+            let rows: Vec<String> = (0..qr.size())
+                .map(|y| {
+                    (0..qr.size())
+                        .map(|x| if qr.get(x, y) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            assert_eq!(rows, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(QrCode::encode("X--/-O-/---").unwrap().size(), 21);
+        let record = "time=1760000000 dim=3 human=X first=X result=computer moves=1:1,2:2,1:2,1:3,3:3,3:1 accuracy=1/3";
+        assert_eq!(QrCode::encode(record).unwrap().size(), 37);
+        assert!(QrCode::encode(&"-".repeat(3000)).is_err());
+        let qr = QrCode::encode("X").unwrap();
+        // finder pattern in the top left corner
+        assert!(qr.get(0, 0) && !qr.get(1, 1) && qr.get(3, 3));
+        assert_eq!(qr.render().lines().count(), 15);
+        assert!(qr.to_svg().starts_with("<svg"));
+    }
+}
@@ -0,0 +1,340 @@
+//! Elo-like ratings of engines from a round-robin tournament, e.g. to check whether a change
+//! to the heuristic makes it stronger.
+//!
+//! Every pair of entrants plays the same number of games, taking turns at beginning. The
+//! ratings are the strengths of the Bradley-Terry model fitting the results best, with a
+//! draw counting as half a win, on the Elo scale and averaging [`AVERAGE_RATING`]. Every
+//! pairing counts one draw more than was played, so entrants winning or losing every game
+//! get finite ratings.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::board::{Board, Cell, EvalWeights};
+use crate::engine::{Blunder, Engine, EngineConfig, EngineKind, Personality};
+use crate::rng::Rng;
+
+/// The average of the ratings
+pub const AVERAGE_RATING: f64 = 1500.0;
+
+// Iterations fitting the ratings to the results
+const ITERATIONS: usize = 1000;
+
+/// A player of the tournament: an engine with its settings
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub engine: EngineKind,
+    pub personality: Personality,
+    /// Probability of a worse move, see [`Blunder`]
+    pub blunder_rate: Option<f64>,
+}
+
+impl Entrant {
+    /// An engine with the default settings
+    pub fn new(name: &str, engine: EngineKind) -> Entrant {
+        Entrant {
+            name: name.to_string(),
+            engine,
+            personality: Personality::default(),
+            blunder_rate: None,
+        }
+    }
+
+    // The engine with the settings of the tournament, its own personality and the seed
+    fn build(&self, config: &EngineConfig, seed: u64) -> Box<dyn Engine> {
+        let engine = self.engine.build(&EngineConfig {
+            seed: Some(seed),
+            personality: self.personality,
+            ..*config
+        });
+        match self.blunder_rate {
+            Some(rate) => Box::new(Blunder {
+                engine,
+                rate,
+                rng: Rng::new(seed.rotate_left(32)),
+            }),
+            None => engine,
+        }
+    }
+}
+
+/// Settings of a tournament
+#[derive(Debug, Clone)]
+pub struct RoundRobin {
+    /// Board dimension
+    pub dim: usize,
+    pub entrants: Vec<Entrant>,
+    /// Number of games of every pair of entrants
+    pub games: usize,
+    /// Number of games played at the same time
+    pub threads: usize,
+    /// Whether the engines play the opening book moves
+    pub book: bool,
+    /// Whether the minimax engine looks moves up in the tablebases, see
+    /// [`EngineConfig::tablebases`]
+    pub tablebases: bool,
+    pub eval_weights: EvalWeights,
+    /// See [`EngineConfig::contempt`]
+    pub contempt: i64,
+    /// Time the engines may think per move
+    pub think_time: Option<Duration>,
+    /// Seed of the tournament; every game has its own, derived from it
+    pub seed: u64,
+}
+
+/// The points of one entrant against another
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Score {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Score {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Wins count 1, draws 1/2
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    fn add(&mut self, other: Score) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+/// A line of the rating table
+#[derive(Debug, PartialEq, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    /// The results against all other entrants together
+    pub score: Score,
+}
+
+impl RoundRobin {
+    /// A tournament of the entrants on a single thread
+    pub fn new(dim: usize, entrants: Vec<Entrant>, games: usize) -> RoundRobin {
+        RoundRobin {
+            dim,
+            entrants,
+            games,
+            threads: 1,
+            book: true,
+            tablebases: true,
+            eval_weights: EvalWeights::default(),
+            contempt: 0,
+            think_time: None,
+            seed: 0,
+        }
+    }
+
+    /// Play all games and return the results, `scores[i][j]` being those of entrant `i`
+    /// against entrant `j`
+    pub fn run(&self) -> Vec<Vec<Score>> {
+        let n = self.entrants.len();
+        let pairs: Vec<(usize, usize)> = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect();
+        let scores = Mutex::new(vec![vec![Score::default(); n]; n]);
+        let next = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| loop {
+                    let number = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(i, j)) = pairs.get(number / self.games.max(1)) else {
+                        break;
+                    };
+                    // the entrants take turns at beginning
+                    let i_begins = number.is_multiple_of(2);
+                    let (x, o) = if i_begins { (i, j) } else { (j, i) };
+                    let winner = self.play(number, x, o);
+                    let i_uses = if i_begins { Cell::X } else { Cell::O };
+                    let result = match winner {
+                        None => Score {
+                            draws: 1,
+                            ..Score::default()
+                        },
+                        Some(cell) if cell == i_uses => Score {
+                            wins: 1,
+                            ..Score::default()
+                        },
+                        Some(_) => Score {
+                            losses: 1,
+                            ..Score::default()
+                        },
+                    };
+                    let mut scores = scores.lock().unwrap();
+                    scores[i][j].add(result);
+                    scores[j][i].add(Score {
+                        wins: result.losses,
+                        draws: result.draws,
+                        losses: result.wins,
+                    });
+                });
+            }
+        });
+        scores.into_inner().unwrap()
+    }
+
+    // Play the game with the given number between the entrants playing X and O and return
+    // the winner
+    fn play(&self, number: usize, x: usize, o: usize) -> Option<Cell> {
+        let mut board = Board::build(self.dim, Cell::X).expect("the dimension is supported");
+        let config = EngineConfig {
+            book: self.book,
+            eval_weights: self.eval_weights,
+            contempt: self.contempt,
+            think_time: self.think_time,
+            tablebases: self.tablebases,
+            ..EngineConfig::default()
+        };
+        let mut rng = Rng::new(self.seed.wrapping_add(number as u64));
+        let mut engines =
+            [x, o].map(|entrant| self.entrants[entrant].build(&config, rng.next_u64()));
+        let mut side = Cell::X;
+        while board.winner().is_none() && board.legal_moves().next().is_some() {
+            let engine = &mut engines[usize::from(side == Cell::O)];
+            let mv = engine.choose(&board, side);
+            board.set_cell(mv, side).unwrap();
+            side = side.opponent();
+        }
+        board.winner()
+    }
+
+    /// The rating table of the results of [`RoundRobin::run`], best first
+    pub fn standings(&self, scores: &[Vec<Score>]) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = ratings(scores)
+            .into_iter()
+            .zip(&self.entrants)
+            .zip(scores)
+            .map(|((rating, entrant), row)| {
+                let mut score = Score::default();
+                row.iter().for_each(|&other| score.add(other));
+                Standing {
+                    name: entrant.name.clone(),
+                    rating,
+                    score,
+                }
+            })
+            .collect();
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        standings
+    }
+}
+
+/// The Elo-like ratings of the entrants with the results `scores[i][j]` of entrant `i`
+/// against entrant `j`
+pub fn ratings(scores: &[Vec<Score>]) -> Vec<f64> {
+    let n = scores.len();
+    // the games and points of every pairing, with the extra draw
+    let games = |i: usize, j: usize| scores[i][j].games() as f64 + 1.0;
+    let points: Vec<f64> = (0..n)
+        .map(|i| {
+            (0..n)
+                .filter(|&j| j != i)
+                .map(|j| scores[i][j].points() + 0.5)
+                .sum()
+        })
+        .collect();
+    // minorization-maximization of the Bradley-Terry strengths
+    let mut strengths = vec![1.0; n];
+    for _ in 0..ITERATIONS {
+        let next: Vec<f64> = (0..n)
+            .map(|i| {
+                let expected: f64 = (0..n)
+                    .filter(|&j| j != i)
+                    .map(|j| games(i, j) / (strengths[i] + strengths[j]))
+                    .sum();
+                if expected == 0.0 {
+                    1.0
+                } else {
+                    points[i] / expected
+                }
+            })
+            .collect();
+        strengths = next;
+    }
+    let elo: Vec<f64> = strengths.iter().map(|s| 400.0 * s.log10()).collect();
+    let mean = elo.iter().sum::<f64>() / n.max(1) as f64;
+    elo.into_iter()
+        .map(|rating| rating - mean + AVERAGE_RATING)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: usize, draws: usize, losses: usize) -> Score {
+        Score {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn fitted_ratings() {
+        // an even match
+        let even = ratings(&[
+            vec![score(0, 0, 0), score(5, 0, 5)],
+            vec![score(5, 0, 5), score(0, 0, 0)],
+        ]);
+        assert!((even[0] - AVERAGE_RATING).abs() < 1e-6);
+        assert!((even[1] - AVERAGE_RATING).abs() < 1e-6);
+        // 75% of the points is a difference of about 191 points, the extra draw pulls it in
+        let ahead = ratings(&[
+            vec![score(0, 0, 0), score(29, 2, 9)],
+            vec![score(9, 2, 29), score(0, 0, 0)],
+        ]);
+        let expected = 400.0 * (30.5_f64 / 10.5).log10();
+        assert!((ahead[0] - ahead[1] - expected).abs() < 1e-3);
+        assert!((ahead[0] + ahead[1] - 2.0 * AVERAGE_RATING).abs() < 1e-6);
+        // winning everything stays finite
+        let sweep = ratings(&[
+            vec![score(0, 0, 0), score(10, 0, 0), score(10, 0, 0)],
+            vec![score(0, 0, 10), score(0, 0, 0), score(5, 0, 5)],
+            vec![score(0, 0, 10), score(5, 0, 5), score(0, 0, 0)],
+        ]);
+        assert!(sweep[0].is_finite() && sweep[0] > sweep[1] + 200.0);
+        assert!((sweep[1] - sweep[2]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tournament() {
+        let mut blunderer = Entrant::new("heuristic@0.9", EngineKind::Heuristic);
+        blunderer.blunder_rate = Some(0.9);
+        let entrants = vec![
+            Entrant::new("heuristic", EngineKind::Heuristic),
+            Entrant::new("minimax", EngineKind::Minimax),
+            blunderer,
+        ];
+        let mut tournament = RoundRobin::new(3, entrants, 10);
+        tournament.threads = 3;
+        tournament.tablebases = false;
+        let scores = tournament.run();
+        for (i, row) in scores.iter().enumerate() {
+            for (j, score) in row.iter().enumerate() {
+                assert_eq!(score.wins, scores[j][i].losses);
+                assert_eq!(score.draws, scores[j][i].draws);
+                assert_eq!(score.games(), if i == j { 0 } else { 10 });
+            }
+        }
+        // perfect play doesn't lose on the 3x3 board
+        assert_eq!(scores[1][0].losses + scores[1][2].losses, 0);
+        let standings = tournament.standings(&scores);
+        assert_eq!(standings.last().unwrap().name, "heuristic@0.9");
+        assert_eq!(standings[0].score.games(), 20);
+        // the same games on a single thread
+        tournament.threads = 1;
+        assert_eq!(tournament.run(), scores);
+    }
+}
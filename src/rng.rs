@@ -0,0 +1,53 @@
+//! A small deterministic random number generator, so that sequences can be reproduced
+//! from a seed.
+
+/// SplitMix64 pseudo random number generator. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator producing the sequence of the given seed
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// The next random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number from `0..n`. `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Whether an event with the given probability, from 0 to 1, happens
+    pub fn chance(&mut self, probability: f64) -> bool {
+        // the 53 bits a float holds exactly, as a fraction of 1
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let numbers: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(numbers, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(Rng::new(43).next_u64(), numbers[0]);
+        assert!((0..100).all(|_| a.below(3) < 3));
+        assert!((0..100).all(|_| a.chance(1.0) && !a.chance(0.0)));
+        let hits = (0..1000).filter(|_| a.chance(0.3)).count();
+        assert!((250..350).contains(&hits), "{}", hits);
+    }
+}
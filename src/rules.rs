@@ -0,0 +1,234 @@
+//! The rules of the variants played on a [`Board`].
+//!
+//! A variant implements [`Ruleset`] and is put on a board with [`Board::with_rules`]. The
+//! board keeps the cells, the line counts and the history; the ruleset decides where moves
+//! can be made, who has won and when the game is over.
+
+use std::fmt;
+
+use crate::board::{Board, BoardError, Cell, Coord};
+
+/// Where moves can be made, who has won and when the game is over
+///
+/// All methods but [`Ruleset::name`] have defaults giving the rules of the standard game,
+/// which a variant overrides where it differs.
+pub trait Ruleset: fmt::Debug + Send + Sync {
+    /// A short name of the variant, e.g. `standard`
+    fn name(&self) -> &'static str;
+
+    /// Whether a move can be made on the blank cell at the given coordinates. By default
+    /// every blank cell can be used.
+    fn is_legal(&self, _board: &Board, _coord: Coord) -> bool {
+        true
+    }
+
+    /// The player who has won the position, found by checking every line. By default it is
+    /// the one who has completed a line.
+    fn winner(&self, board: &Board) -> Option<Cell> {
+        let win_length = board.win_length();
+        board.line_counts().iter().find_map(|count| {
+            if count.x == win_length {
+                Some(Cell::X)
+            } else if count.o == win_length {
+                Some(Cell::O)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The player who has won by the move just made on the cell at the given coordinates.
+    /// Only the lines through it can have changed, so by default it is the player on the
+    /// cell if one of them is completed.
+    fn winner_after(&self, board: &Board, coord: Coord) -> Option<Cell> {
+        let cell = board.get_cell(coord);
+        board
+            .line_counts_at(coord)
+            .any(|count| count.pieces(cell) == board.win_length())
+            .then_some(cell)
+    }
+
+    /// Whether the game is over without a winner, a tie. By default it is once every cell
+    /// is taken or blocked.
+    fn is_terminal(&self, board: &Board) -> bool {
+        board.is_full()
+    }
+
+    /// Whether completing a line wins for the player completing it, which the engines
+    /// rating cells by the lines they help to complete assume. Otherwise they search the
+    /// moves ahead instead. On by default.
+    fn lines_win(&self) -> bool {
+        true
+    }
+
+    /// Whether the rotated and mirrored versions of a position play the same. Otherwise only
+    /// mirroring the columns keeps the rules, e.g. with gravity. On by default.
+    fn is_rotatable(&self) -> bool {
+        true
+    }
+
+    /// Check that the position can arise under these rules, e.g. when switching to them.
+    /// By default every position can.
+    fn check_position(&self, _board: &Board) -> Result<(), BoardError> {
+        Ok(())
+    }
+}
+
+/// The standard game: a move on any blank cell, won by completing a line
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Standard;
+
+impl Ruleset for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// Pieces fall to the lowest blank cell of the column they are dropped into, as in Connect
+/// Four
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Gravity;
+
+impl Ruleset for Gravity {
+    fn name(&self) -> &'static str {
+        "gravity"
+    }
+
+    // on the bottom row or above a piece or blocked cell
+    fn is_legal(&self, board: &Board, coord: Coord) -> bool {
+        coord.row + 1 == board.height()
+            || board.get_cell(Coord {
+                row: coord.row + 1,
+                ..coord
+            }) != Cell::Blank
+    }
+
+    fn is_rotatable(&self) -> bool {
+        false
+    }
+
+    // no piece may have a blank cell below it
+    fn check_position(&self, board: &Board) -> Result<(), BoardError> {
+        let floating = (0..board.width()).any(|x| {
+            (0..board.height() - 1).any(|y| {
+                matches!(board.get_cell((x, y)), Cell::X | Cell::O)
+                    && board.get_cell((x, y + 1)) == Cell::Blank
+            })
+        });
+        if floating {
+            Err(BoardError::Floating)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Misère: completing a line loses, so the player who completes one hands the win to the
+/// opponent
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Misere;
+
+impl Ruleset for Misere {
+    fn name(&self) -> &'static str {
+        "misere"
+    }
+
+    fn winner(&self, board: &Board) -> Option<Cell> {
+        Standard.winner(board).map(|cell| cell.opponent())
+    }
+
+    fn winner_after(&self, board: &Board, coord: Coord) -> Option<Cell> {
+        Standard
+            .winner_after(board, coord)
+            .map(|cell| cell.opponent())
+    }
+
+    fn lines_win(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Coord, Move, MoveError};
+    use crate::engine::{EngineConfig, EngineKind};
+    use crate::solver;
+    use crate::GameOver;
+
+    // A tie after four moves
+    #[derive(Debug)]
+    struct Short;
+
+    impl Ruleset for Short {
+        fn name(&self) -> &'static str {
+            "short"
+        }
+
+        fn is_terminal(&self, board: &Board) -> bool {
+            board.history().len() >= 4 || board.is_full()
+        }
+    }
+
+    #[test]
+    fn variant_without_touching_the_board() {
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        let mut board = board.with_rules(Misere).unwrap();
+        assert_eq!(board.rules().name(), "misere");
+        assert_eq!(board.human_move((2, 0)), Ok(Some(GameOver::ComputerWon)));
+        assert_eq!(board.winner(), Some(Cell::O));
+        assert!(!board.has_standard_rules());
+    }
+
+    #[test]
+    fn variant_ending_early() {
+        let mut board = Board::build(3, Cell::X).unwrap().with_rules(Short).unwrap();
+        let mut play = |col, row| {
+            let mark = board.to_move();
+            board.apply_move(Move {
+                coord: Coord { row, col },
+                mark,
+            })
+        };
+        for (col, row) in [(0, 0), (1, 1), (2, 2)] {
+            assert_eq!(play(col, row), Ok(None));
+        }
+        assert_eq!(play(0, 2), Ok(Some(GameOver::Tie)));
+        assert_eq!(play(1, 0), Err(MoveError::GameAlreadyOver));
+    }
+
+    #[test]
+    fn misere() {
+        assert!(!Misere.lines_win());
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X)
+            .unwrap()
+            .with_rules(Misere)
+            .unwrap();
+        assert!(board.has_misere());
+        // completing the top row would lose
+        for kind in [EngineKind::Heuristic, EngineKind::Minimax] {
+            let mut engine = kind.build(&EngineConfig {
+                book: false,
+                ..EngineConfig::default()
+            });
+            assert_ne!(engine.choose(&board, Cell::X), (2, 0), "{:?}", kind);
+        }
+        let empty = Board::build(3, Cell::X)
+            .unwrap()
+            .with_rules(Misere)
+            .unwrap();
+        assert_eq!(solver::forced_outcome(&empty, Cell::X), Some(GameOver::Tie));
+    }
+
+    #[test]
+    fn gravity() {
+        let board = Board::from_string_rectangle("---/-X-", 3, 2, Cell::X).unwrap();
+        assert!(Gravity.check_position(&board).is_ok());
+        assert!(Gravity.is_legal(&board, (1, 0).into()));
+        assert!(!Gravity.is_legal(&board, (0, 0).into()));
+        assert!(Standard.is_legal(&board, (0, 0).into()));
+        let floating = Board::from_string_rectangle("-X-/---", 3, 2, Cell::X).unwrap();
+        assert!(Gravity.check_position(&floating).is_err());
+        assert!(Standard.check_position(&floating).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+//! Engine-vs-engine games played on several threads, e.g. to generate training data or to
+//! study how the engines play.
+//!
+//! Every game has a seed of its own, derived from the seed of the run and the number of the
+//! game, so a run plays the same games whatever the number of threads.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::board::{Board, Cell, Coord, EvalWeights};
+use crate::engine::{EngineConfig, EngineKind, Personality};
+use crate::rng::Rng;
+
+/// Settings of a run of games
+#[derive(Debug, Clone)]
+pub struct SelfPlay {
+    /// Board dimension
+    pub dim: usize,
+    /// Number of games
+    pub games: usize,
+    /// Number of games played at the same time
+    pub threads: usize,
+    /// The engines of X, who moves first, and O
+    pub engines: [EngineKind; 2],
+    /// Whether the engines play the opening book moves
+    pub book: bool,
+    /// Whether the minimax engine looks moves up in the tablebases, see
+    /// [`EngineConfig::tablebases`]
+    pub tablebases: bool,
+    pub personality: Personality,
+    pub eval_weights: EvalWeights,
+    /// See [`EngineConfig::contempt`]
+    pub contempt: i64,
+    /// Time the engines may think per move
+    pub think_time: Option<Duration>,
+    /// Seed of the run
+    pub seed: u64,
+}
+
+impl SelfPlay {
+    /// A run of the heuristic engine against itself on a single thread
+    pub fn new(dim: usize, games: usize) -> SelfPlay {
+        SelfPlay {
+            dim,
+            games,
+            threads: 1,
+            engines: [EngineKind::Heuristic, EngineKind::Heuristic],
+            book: true,
+            tablebases: true,
+            personality: Personality::Balanced,
+            eval_weights: EvalWeights::default(),
+            contempt: 0,
+            think_time: None,
+            seed: 0,
+        }
+    }
+
+    /// Play all games and pass each one to `finished` as soon as the games before it are
+    /// finished too, so they arrive in the order of their numbers
+    pub fn run(&self, mut finished: impl FnMut(&Game)) {
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let number = next.fetch_add(1, Ordering::Relaxed);
+                    if number >= self.games || sender.send(self.play(number)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            // games finished early wait for those before them
+            let mut waiting = HashMap::new();
+            let mut expected = 0;
+            for game in receiver {
+                waiting.insert(game.number, game);
+                while let Some(game) = waiting.remove(&expected) {
+                    finished(&game);
+                    expected += 1;
+                }
+            }
+        });
+    }
+
+    /// Play the game with the given number
+    pub fn play(&self, number: usize) -> Game {
+        let mut board = Board::build(self.dim, Cell::X).expect("the dimension is supported");
+        let mut rng = Rng::new(self.seed.wrapping_add(number as u64));
+        let mut engines = self.engines.each_ref().map(|kind| {
+            kind.build(&EngineConfig {
+                book: self.book,
+                seed: Some(rng.next_u64()),
+                personality: self.personality,
+                eval_weights: self.eval_weights,
+                contempt: self.contempt,
+                think_time: self.think_time,
+                cache: false,
+                tablebases: self.tablebases,
+            })
+        });
+        let mut side = Cell::X;
+        while board.winner().is_none() && board.legal_moves().next().is_some() {
+            let engine = &mut engines[usize::from(side == Cell::O)];
+            let mv = engine.choose(&board, side);
+            board.set_cell(mv, side).unwrap();
+            side = side.opponent();
+        }
+        Game {
+            number,
+            dim: self.dim,
+            moves: board.history().to_vec(),
+            winner: board.winner(),
+        }
+    }
+}
+
+/// A finished game, which X began
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    /// Number of the game in its run, counting from 0
+    pub number: usize,
+    /// Board dimension
+    pub dim: usize,
+    pub moves: Vec<Coord>,
+    /// The player who won, `None` for a draw
+    pub winner: Option<Cell>,
+}
+
+impl Game {
+    /// The positions of the game as JSON lines, one for every move:
+    ///
+    /// ```text
+    /// {"game":0,"ply":1,"position":"X--/---/---","to_move":"O","move":[2,2],"winner":null}
+    /// ```
+    ///
+    /// `position` is the position before the move in the format of
+    /// [`Board::to_notation`], `ply` the number of moves before it. Coordinates are one
+    /// based like the user input, column first.
+    pub fn to_jsonl(&self) -> String {
+        let winner = match self.winner {
+            Some(cell) => format!("\"{}\"", cell),
+            None => "null".to_string(),
+        };
+        let mut board = Board::build(self.dim, Cell::X).expect("the dimension is supported");
+        let mut side = Cell::X;
+        let mut lines = String::new();
+        for (ply, &mv) in self.moves.iter().enumerate() {
+            let _ = writeln!(
+                lines,
+                "{{\"game\":{},\"ply\":{},\"position\":\"{}\",\"to_move\":\"{}\",\"move\":[{},{}],\"winner\":{}}}",
+                self.number,
+                ply,
+                board.to_notation(),
+                side,
+                mv.col + 1,
+                mv.row + 1,
+                winner
+            );
+            board.set_cell(mv, side).unwrap();
+            side = side.opponent();
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs() {
+        let mut run = SelfPlay::new(3, 20);
+        run.threads = 4;
+        run.seed = 7;
+        let mut games = Vec::new();
+        run.run(|game| games.push(game.clone()));
+        assert_eq!(
+            games.iter().map(|game| game.number).collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+        for game in &games {
+            let mut board = Board::build(3, Cell::X).unwrap();
+            let mut side = Cell::X;
+            for &mv in &game.moves {
+                board.set_cell(mv, side).unwrap();
+                side = side.opponent();
+            }
+            assert_eq!(board.winner(), game.winner);
+            assert!(board.winner().is_some() || board.legal_moves().next().is_none());
+        }
+        // the same games on a single thread
+        run.threads = 1;
+        assert_eq!(run.play(13), games[13]);
+        assert!(games.iter().any(|game| game.moves != games[0].moves));
+    }
+
+    #[test]
+    fn json_lines() {
+        let game = Game {
+            number: 4,
+            dim: 3,
+            moves: vec![(0, 0).into(), (1, 1).into()],
+            winner: Some(Cell::O),
+        };
+        assert_eq!(
+            game.to_jsonl(),
+            "{\"game\":4,\"ply\":0,\"position\":\"---/---/---\",\"to_move\":\"X\",\"move\":[1,1],\"winner\":\"O\"}\n\
+             {\"game\":4,\"ply\":1,\"position\":\"X--/---/---\",\"to_move\":\"O\",\"move\":[2,2],\"winner\":\"O\"}\n"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Matches between two engines taking turns at beginning, e.g. to measure the advantage of
+//! the first move on the different board sizes, with confidence intervals for the rates.
+
+use crate::board::Cell;
+use crate::selfplay::{Game, SelfPlay};
+
+/// The normal quantile for 95% confidence
+const Z_95: f64 = 1.96;
+
+/// The outcomes of a match between engine A and engine B
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct MatchResult {
+    pub a_wins: usize,
+    pub b_wins: usize,
+    pub draws: usize,
+    /// Games won by the player who began, whichever engine it was
+    pub first_wins: usize,
+    pub second_wins: usize,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> usize {
+        self.a_wins + self.b_wins + self.draws
+    }
+
+    /// Count the game, `a_began` telling whether engine A played X
+    pub fn record(&mut self, game: &Game, a_began: bool) {
+        match game.winner {
+            None => self.draws += 1,
+            Some(winner) => {
+                let first_won = winner == Cell::X;
+                if first_won {
+                    self.first_wins += 1;
+                } else {
+                    self.second_wins += 1;
+                }
+                if first_won == a_began {
+                    self.a_wins += 1;
+                } else {
+                    self.b_wins += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Play a match of `run.games` games between `run.engines[0]`, engine A, and
+/// `run.engines[1]`, engine B. A begins the first half of the games and B the other half,
+/// which repeats the seeds of the first, so every position arises with both engines on
+/// either side.
+pub fn play_match(run: &SelfPlay) -> MatchResult {
+    let mut result = MatchResult::default();
+    let mut first_half = run.clone();
+    first_half.games = run.games.div_ceil(2);
+    first_half.run(|game| result.record(game, true));
+    let mut second_half = run.clone();
+    second_half.games = run.games / 2;
+    second_half.engines.reverse();
+    second_half.run(|game| result.record(game, false));
+    result
+}
+
+/// The 95% confidence interval of a rate from `successes` out of `trials`, as the Wilson
+/// score interval, which stays within 0 to 1 and works for rates near them
+pub fn confidence_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineKind;
+
+    #[test]
+    fn intervals() {
+        let (low, high) = confidence_interval(50, 100);
+        assert!((low - 0.4038).abs() < 1e-4 && (high - 0.5962).abs() < 1e-4);
+        let (low, high) = confidence_interval(0, 10);
+        assert_eq!(low, 0.0);
+        assert!((high - 0.2775).abs() < 1e-4);
+        assert_eq!(confidence_interval(0, 0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn matches() {
+        let mut run = SelfPlay::new(4, 21);
+        run.engines = [EngineKind::Heuristic, EngineKind::Minimax];
+        run.threads = 2;
+        run.seed = 3;
+        let result = play_match(&run);
+        assert_eq!(result.games(), 21);
+        assert_eq!(result.first_wins + result.second_wins + result.draws, 21);
+        // the 4x4 board is a draw with best play
+        assert_eq!(result.a_wins, 0);
+        // the same win counts for the engine which began
+        let mut tally = MatchResult::default();
+        let game = Game {
+            number: 0,
+            dim: 3,
+            moves: Vec::new(),
+            winner: Some(Cell::X),
+        };
+        tally.record(&game, true);
+        tally.record(&game, false);
+        assert_eq!((tally.a_wins, tally.b_wins, tally.first_wins), (1, 1, 2));
+    }
+}
@@ -0,0 +1,203 @@
+//! Exact solver for positions close to the end of the game, used to tell when the outcome
+//! is decided with best play, and for proving the value of a position with [`solve`].
+
+use std::collections::HashMap;
+
+use crate::board::{Board, Cell, Coord, GameOver};
+pub use crate::minimax::FULL_SEARCH;
+use crate::minimax::{self, TranspositionTable};
+
+/// Positions with more blank cells are not solved, as the search would take too long
+pub const SOLVE_LIMIT: usize = 10;
+
+/// The outcome of the game with best play from both sides, with the given player to move.
+///
+/// Returns `None` if the position has more than [`SOLVE_LIMIT`] blank cells.
+pub fn forced_outcome(board: &Board, to_move: Cell) -> Option<GameOver> {
+    if let Some(over) = board.result() {
+        return Some(over);
+    }
+    if board.legal_moves().count() > SOLVE_LIMIT {
+        return None;
+    }
+    let mut board = board.clone();
+    let score = negamax(&mut board, to_move, &mut HashMap::new());
+    let winner = match score {
+        0 => return Some(GameOver::Tie),
+        1 => to_move,
+        _ => to_move.opponent(),
+    };
+    Some(if winner == board.human_uses() {
+        GameOver::HumanWon
+    } else {
+        GameOver::ComputerWon
+    })
+}
+
+/// The moves keeping the best outcome for the player to move, row by row.
+///
+/// Returns `None` if the position is too large to solve.
+pub fn optimal_moves(board: &Board, to_move: Cell) -> Option<Vec<Coord>> {
+    let best = forced_outcome(board, to_move)?;
+    let mut after = board.clone();
+    let moves = board
+        .legal_moves()
+        .filter(|&mv| {
+            after.set_cell(mv, to_move).unwrap();
+            let outcome = forced_outcome(&after, to_move.opponent());
+            after.undo();
+            outcome == Some(best)
+        })
+        .collect();
+    Some(moves)
+}
+
+/// The value of a position with best play from both sides and the moves leading to it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Solution {
+    /// The player who wins, `None` for a draw
+    pub winner: Option<Cell>,
+    /// The moves of both players until the game ends. Of several equally good moves the one
+    /// the heuristic rates highest is taken; a winner takes the fastest win and a loser
+    /// the longest defence.
+    pub line: Vec<Coord>,
+}
+
+/// Search the position to the end and prove whether the player to move wins, loses or
+/// draws.
+///
+/// Returns `None` if the position has more than [`FULL_SEARCH`] blank cells, which covers
+/// every position on the 3x3 and 4x4 boards.
+pub fn solve(board: &Board, to_move: Cell) -> Option<Solution> {
+    if board.legal_moves().count() > FULL_SEARCH {
+        return None;
+    }
+    let mut board = board.clone();
+    let mut table = TranspositionTable::default();
+    let mut line = Vec::new();
+    let mut cell = to_move;
+    while board.winner().is_none() && board.legal_moves().next().is_some() {
+        let mv = minimax::best_move(&board, cell, 0, None, &mut table, None);
+        board.set_cell(mv, cell).unwrap();
+        line.push(mv);
+        cell = cell.opponent();
+    }
+    Some(Solution {
+        winner: board.winner(),
+        line,
+    })
+}
+
+// 1 if the player to move wins with best play, -1 if they lose, 0 for a draw. Positions
+// already searched are looked up by their cells.
+fn negamax(board: &mut Board, to_move: Cell, known: &mut HashMap<Vec<u8>, i8>) -> i8 {
+    let width = board.width();
+    let key: Vec<u8> = (0..width * board.height())
+        .map(|idx| match board.get_cell((idx % width, idx / width)) {
+            Cell::X => 1,
+            Cell::O => 2,
+            Cell::Blank => 0,
+            Cell::Blocked => 3,
+        })
+        .collect();
+    if let Some(&score) = known.get(&key) {
+        return score;
+    }
+    let mut best = -1;
+    let moves: Vec<Coord> = board.legal_moves().collect();
+    if moves.is_empty() {
+        best = 0;
+    }
+    for mv in moves {
+        board.set_cell(mv, to_move).unwrap();
+        let score = match board.result() {
+            Some(GameOver::Tie) => 0,
+            Some(_) if board.winner() == Some(to_move) => 1,
+            Some(_) => -1,
+            None => -negamax(board, to_move.opponent(), known),
+        };
+        board.undo();
+        best = best.max(score);
+        if best == 1 {
+            break;
+        }
+    }
+    known.insert(key, best);
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcomes() {
+        // the first move can't lose
+        assert_eq!(
+            forced_outcome(&Board::from_moves(&[(1, 1)], Cell::X), Cell::O),
+            Some(GameOver::Tie)
+        );
+        // answering the center with an edge loses
+        assert_eq!(
+            forced_outcome(&Board::from_moves(&[(1, 1), (1, 0)], Cell::X), Cell::X),
+            Some(GameOver::HumanWon)
+        );
+        // as does answering a corner with the opposite corner
+        assert_eq!(
+            forced_outcome(&Board::from_moves(&[(0, 0), (2, 2)], Cell::X), Cell::X),
+            Some(GameOver::HumanWon)
+        );
+        assert_eq!(
+            forced_outcome(
+                &Board::from_moves(&[(0, 0), (1, 1), (2, 2)], Cell::X),
+                Cell::O
+            ),
+            Some(GameOver::Tie)
+        );
+        assert_eq!(
+            forced_outcome(&Board::from_moves(&[], Cell::X), Cell::X),
+            Some(GameOver::Tie)
+        );
+        assert_eq!(
+            forced_outcome(&Board::build(4, Cell::X).unwrap(), Cell::X),
+            None
+        );
+    }
+
+    #[test]
+    fn solutions() {
+        let solution = solve(&Board::from_moves(&[], Cell::X), Cell::X).unwrap();
+        assert_eq!(solution.winner, None);
+        assert_eq!(solution.line.len(), 9);
+        // the fastest of the two wins
+        let board = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        assert_eq!(
+            solve(&board, Cell::X),
+            Some(Solution {
+                winner: Some(Cell::X),
+                line: vec![(2, 0).into()]
+            })
+        );
+        // O can only delay the loss
+        let solution = solve(&Board::from_moves(&[(1, 1), (1, 0)], Cell::X), Cell::X).unwrap();
+        assert_eq!(solution.winner, Some(Cell::X));
+        assert!(solution.line.len() % 2 == 1);
+        let solution = solve(&Board::build(4, Cell::X).unwrap(), Cell::X).unwrap();
+        assert_eq!(solution.winner, None);
+        assert!(solve(&Board::build(5, Cell::X).unwrap(), Cell::X).is_none());
+    }
+
+    #[test]
+    fn optimal() {
+        // only a corner holds the draw against the center
+        assert_eq!(
+            optimal_moves(&Board::from_moves(&[(1, 1)], Cell::X), Cell::O),
+            Some([(0, 0), (2, 0), (0, 2), (2, 2)].map(Coord::from).to_vec())
+        );
+        // and only the center against a corner
+        assert_eq!(
+            optimal_moves(&Board::from_moves(&[(0, 0)], Cell::X), Cell::O),
+            Some(vec![(1, 1).into()])
+        );
+    }
+}
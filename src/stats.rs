@@ -0,0 +1,548 @@
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Cell, Coord, GameOver};
+use crate::db::GameRecord;
+
+/// Summary of the time one player took for their moves
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TimeSummary {
+    /// Number of timed moves
+    pub moves: usize,
+    /// The longest time taken for a move
+    pub longest: Duration,
+    /// Number of the move (counting both players, starting at 1) that took longest
+    pub longest_move: usize,
+    /// Time taken for all moves together
+    pub total: Duration,
+}
+
+impl TimeSummary {
+    /// Summarize the timed moves the given player made on the board.
+    ///
+    /// Returns `None` if none of their moves was timed.
+    pub fn of(board: &Board, cell: Cell) -> Option<TimeSummary> {
+        let mut summary: Option<TimeSummary> = None;
+        for (idx, (&mv, time)) in board.history().iter().zip(board.move_times()).enumerate() {
+            let Some(time) = *time else { continue };
+            if board.get_cell(mv) != cell {
+                continue;
+            }
+            let s = summary.get_or_insert(TimeSummary {
+                moves: 0,
+                longest: time,
+                longest_move: idx + 1,
+                total: Duration::ZERO,
+            });
+            s.moves += 1;
+            s.total += time;
+            if time > s.longest {
+                s.longest = time;
+                s.longest_move = idx + 1;
+            }
+        }
+        summary
+    }
+
+    /// The average time per move
+    pub fn average(&self) -> Duration {
+        self.total / self.moves as u32
+    }
+}
+
+/// How many of the human's moves agreed with the engine
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Accuracy {
+    /// Number of moves which were among the engine's best moves
+    pub matched: usize,
+    /// Number of moves made by the human
+    pub moves: usize,
+}
+
+impl Accuracy {
+    /// Compare each of the human's moves on the board with the moves the engine
+    /// considered best in that position.
+    ///
+    /// Returns `None` if the human hasn't moved yet.
+    pub fn of(board: &Board) -> Option<Accuracy> {
+        let mut replay = board.clone();
+        while replay.undo().is_some() {}
+        let mut accuracy = Accuracy {
+            matched: 0,
+            moves: 0,
+        };
+        for &mv in board.history() {
+            let cell = board.get_cell(mv);
+            if cell == board.human_uses() {
+                accuracy.moves += 1;
+                if replay.best_moves(cell).contains(&mv) {
+                    accuracy.matched += 1;
+                }
+            }
+            replay.set_cell(mv, cell).unwrap();
+        }
+        (accuracy.moves > 0).then_some(accuracy)
+    }
+
+    /// The share of matching moves in percent
+    pub fn percent(&self) -> f64 {
+        self.matched as f64 * 100.0 / self.moves as f64
+    }
+}
+
+/// Quality of a move as judged by the engine
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Quality {
+    /// One of the moves the engine considers best, unless it is a blunder
+    Best,
+    /// Scores at least three quarters of the best move
+    Good,
+    /// Scores lower, but doesn't lose
+    Inaccuracy,
+    /// Lets the opponent force a win although another move would not have
+    Blunder,
+}
+
+impl Quality {
+    /// Judge the given player's move in the position on the board
+    pub fn of(board: &Board, mv: Coord, cell: Cell) -> Quality {
+        let loses = |mv: Coord| {
+            let mut after = board.clone();
+            after.set_cell(mv, cell).unwrap();
+            after.result().is_none() && after.can_force_win(cell.opponent())
+        };
+        let mut blanks = board.legal_moves();
+        if loses(mv) && blanks.any(|other| !loses(other)) {
+            return Quality::Blunder;
+        }
+        if board.best_moves(cell).contains(&mv) {
+            return Quality::Best;
+        }
+        let scores = board.scores(cell);
+        let max = scores.iter().max().copied().unwrap_or(0);
+        if scores[mv.col + mv.row * board.width()] * 4 >= max * 3 {
+            Quality::Good
+        } else {
+            Quality::Inaccuracy
+        }
+    }
+
+    /// Judge every move played on the board, in order
+    pub fn annotate(board: &Board) -> Vec<Quality> {
+        let mut replay = board.clone();
+        while replay.undo().is_some() {}
+        board
+            .history()
+            .iter()
+            .map(|&mv| {
+                let cell = board.get_cell(mv);
+                let quality = Quality::of(&replay, mv, cell);
+                replay.set_cell(mv, cell).unwrap();
+                quality
+            })
+            .collect()
+    }
+
+    /// Judge the last move played on the board
+    pub fn of_last_move(board: &Board) -> Option<Quality> {
+        let &mv = board.history().last()?;
+        let mut before = board.clone();
+        before.undo();
+        Some(Quality::of(&before, mv, board.get_cell(mv)))
+    }
+
+    /// Identifier used in exported data
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quality::Best => "best",
+            Quality::Good => "good",
+            Quality::Inaccuracy => "inaccuracy",
+            Quality::Blunder => "blunder",
+        }
+    }
+
+    /// The annotation symbol used in move lists, as in chess notation: `!` for the best
+    /// moves, nothing for good ones, `?!` for inaccuracies and `??` for blunders
+    pub fn mark(&self) -> &'static str {
+        match self {
+            Quality::Best => "!",
+            Quality::Good => "",
+            Quality::Inaccuracy => "?!",
+            Quality::Blunder => "??",
+        }
+    }
+}
+
+/// Statistics of a game, collected while it is played
+#[derive(Debug, Clone)]
+pub struct GameStats {
+    started: Instant,
+    duration: Option<Duration>,
+    /// Agreement of the human's moves with the engine, available when the game is finished
+    pub accuracy: Option<Accuracy>,
+    /// Number of moves made by the human
+    pub human_moves: usize,
+    /// Number of moves made by the computer
+    pub computer_moves: usize,
+    /// Number of times the human took back a move
+    pub takebacks: usize,
+}
+
+impl GameStats {
+    /// Start collecting statistics for a new game
+    pub fn start() -> GameStats {
+        GameStats::start_at(Instant::now())
+    }
+
+    fn start_at(started: Instant) -> GameStats {
+        GameStats {
+            started,
+            duration: None,
+            accuracy: None,
+            human_moves: 0,
+            computer_moves: 0,
+            takebacks: 0,
+        }
+    }
+
+    /// Count the last move made on the board
+    pub fn record_move(&mut self, board: &Board) {
+        let Some(&mv) = board.history().last() else {
+            return;
+        };
+        if board.get_cell(mv) == board.human_uses() {
+            self.human_moves += 1;
+        } else {
+            self.computer_moves += 1;
+        }
+    }
+
+    /// Count a takeback of the human's last move and the computer's reply
+    pub fn record_takeback(&mut self) {
+        self.human_moves -= 1;
+        self.computer_moves -= 1;
+        self.takebacks += 1;
+    }
+
+    /// Stop the game clock and evaluate the human's moves on the final board
+    pub fn finish(&mut self, board: &Board) {
+        self.finish_at(board, Instant::now());
+    }
+
+    fn finish_at(&mut self, board: &Board, now: Instant) {
+        self.duration
+            .get_or_insert(now.saturating_duration_since(self.started));
+        self.accuracy = Accuracy::of(board);
+    }
+
+    /// Total number of moves
+    pub fn moves(&self) -> usize {
+        self.human_moves + self.computer_moves
+    }
+
+    /// Time from the start to the end of the game, or until now if it hasn't finished
+    pub fn duration(&self) -> Duration {
+        self.duration.unwrap_or_else(|| self.started.elapsed())
+    }
+}
+
+/// Results of the games played in one session
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct SessionScore {
+    pub human_wins: usize,
+    pub computer_wins: usize,
+    pub ties: usize,
+}
+
+impl SessionScore {
+    /// Count the result of a game
+    pub fn record(&mut self, result: GameOver) {
+        match result {
+            GameOver::HumanWon | GameOver::Resigned => self.human_wins += 1,
+            GameOver::ComputerWon => self.computer_wins += 1,
+            GameOver::Tie => self.ties += 1,
+        }
+    }
+
+    /// Number of games played
+    pub fn games(&self) -> usize {
+        self.human_wins + self.computer_wins + self.ties
+    }
+
+    /// Whether a match of the given number of games is decided: all have been played, or
+    /// the side ahead can't be caught in the remaining ones
+    pub fn decides_match(&self, best_of: usize) -> bool {
+        let remaining = best_of.saturating_sub(self.games());
+        remaining == 0 || self.human_wins.abs_diff(self.computer_wins) > remaining
+    }
+}
+
+/// Number of recent games the skill estimate is based on
+pub const SKILL_GAMES: usize = 10;
+
+/// Rough playing strength, on the scale of the built-in presets
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+pub enum Skill {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+const SKILLS: [Skill; 4] = [
+    Skill::Beginner,
+    Skill::Intermediate,
+    Skill::Advanced,
+    Skill::Expert,
+];
+
+impl Skill {
+    /// Identifier used in the profile and as message key
+    pub fn name(&self) -> &'static str {
+        match self {
+            Skill::Beginner => "beginner",
+            Skill::Intermediate => "intermediate",
+            Skill::Advanced => "advanced",
+            Skill::Expert => "expert",
+        }
+    }
+
+    /// Look up a skill by its identifier
+    pub fn from_name(name: &str) -> Option<Skill> {
+        SKILLS.iter().copied().find(|skill| skill.name() == name)
+    }
+
+    /// The built-in preset offering a fair challenge at this skill
+    pub fn recommended_preset(&self) -> &'static str {
+        match self {
+            Skill::Beginner => "casual",
+            Skill::Intermediate => "standard",
+            Skill::Advanced | Skill::Expert => "expert",
+        }
+    }
+
+    fn step(&self, by: isize) -> Skill {
+        let idx = SKILLS.iter().position(|skill| skill == self).unwrap() as isize + by;
+        SKILLS[idx.clamp(0, SKILLS.len() as isize - 1) as usize]
+    }
+}
+
+/// Estimated skill of the player
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SkillEstimate {
+    pub skill: Skill,
+    /// Average accuracy in the games the estimate is based on
+    pub accuracy: Option<f64>,
+}
+
+/// Estimate the player's skill from their last [`SKILL_GAMES`] games.
+///
+/// The base is the hardest level the player won a game on, mapped to the built-in presets
+/// by board size, the shorter side of rectangular boards: casual up to 3x3, standard for 4x4
+/// and expert beyond. An average accuracy
+/// below 40% moves the estimate one step down, 80% or more one step up.
+///
+/// Returns `None` if fewer games have been played.
+pub fn estimate_skill(games: &[GameRecord]) -> Option<SkillEstimate> {
+    let recent = games.get(games.len().checked_sub(SKILL_GAMES)?..)?;
+    let beaten = recent
+        .iter()
+        .filter(|game| game.result.human_won())
+        .map(|game| game.width.min(game.height).clamp(3, 5) - 2)
+        .max()
+        .unwrap_or(0);
+    let rated: Vec<f64> = recent
+        .iter()
+        .filter_map(|game| Some(game.accuracy?.percent()))
+        .collect();
+    let accuracy = (!rated.is_empty()).then(|| rated.iter().sum::<f64>() / rated.len() as f64);
+    let skill = match accuracy {
+        Some(a) if a < 40.0 => SKILLS[beaten].step(-1),
+        Some(a) if a >= 80.0 => SKILLS[beaten].step(1),
+        _ => SKILLS[beaten],
+    };
+    Some(SkillEstimate { skill, accuracy })
+}
+
+/// Format a duration for display, rounded to a precision suitable for its magnitude
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs >= 60.0 {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    } else if secs >= 1.0 {
+        format!("{:.1}s", secs)
+    } else if secs >= 0.001 {
+        format!("{:.1}ms", secs * 1e3)
+    } else {
+        format!("{}µs", d.as_micros())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_summary() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        assert_eq!(TimeSummary::of(&board, Cell::X), None);
+        board.human_move((0, 0)).unwrap();
+        board.computer_move();
+        board.human_move((2, 2)).unwrap();
+        board.computer_move();
+        // human moves are only timed when the time is recorded with them
+        assert_eq!(TimeSummary::of(&board, Cell::X), None);
+        let summary = TimeSummary::of(&board, Cell::O).unwrap();
+        assert_eq!(summary.moves, 2);
+        assert!(summary.longest_move == 2 || summary.longest_move == 4);
+        assert!(summary.total >= summary.longest);
+        assert!(summary.average() <= summary.longest);
+    }
+
+    #[test]
+    fn game_stats() {
+        let mut board = Board::build(3, Cell::O).unwrap();
+        let started = Instant::now();
+        let mut stats = GameStats::start_at(started);
+        board.computer_move();
+        stats.record_move(&board);
+        board.human_move((0, 0)).unwrap();
+        stats.record_move(&board);
+        board.computer_move();
+        stats.record_move(&board);
+        board.human_move((1, 0)).unwrap();
+        stats.record_move(&board);
+        board.computer_move();
+        stats.record_move(&board);
+        board.undo();
+        board.undo();
+        stats.record_takeback();
+        stats.finish_at(&board, started + Duration::from_secs(42));
+        assert_eq!((stats.human_moves, stats.computer_moves), (1, 2));
+        assert_eq!(stats.moves(), 3);
+        assert_eq!(stats.takebacks, 1);
+        assert_eq!(stats.duration(), Duration::from_secs(42));
+        // the clock stops at the first finish
+        stats.finish_at(&board, started + Duration::from_secs(60));
+        assert_eq!(stats.duration(), Duration::from_secs(42));
+    }
+
+    #[test]
+    fn accuracy() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        assert_eq!(Accuracy::of(&board), None);
+        // only the center is the engine's choice, the last move fails to block O
+        board.human_move((1, 1)).unwrap();
+        board.set_cell((0, 0), Cell::O).unwrap();
+        board.human_move((2, 2)).unwrap();
+        board.set_cell((0, 1), Cell::O).unwrap();
+        board.human_move((2, 1)).unwrap();
+        let accuracy = Accuracy::of(&board).unwrap();
+        assert_eq!(
+            accuracy,
+            Accuracy {
+                matched: 1,
+                moves: 3
+            }
+        );
+        assert!((accuracy.percent() - 33.3).abs() < 0.1);
+    }
+
+    #[test]
+    fn quality() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        assert_eq!(Quality::of_last_move(&board), Some(Quality::Good));
+        assert_eq!(Quality::of(&board, (1, 1).into(), Cell::O), Quality::Best);
+        assert_eq!(
+            Quality::of(&board, (1, 0).into(), Cell::O),
+            Quality::Inaccuracy
+        );
+        board.set_cell((1, 1), Cell::O).unwrap();
+        board.human_move((2, 2)).unwrap();
+        // a corner lets X fork, an edge doesn't
+        assert!(!board.best_moves(Cell::O).contains(&(2, 0).into()));
+        assert_eq!(
+            Quality::of(&board, (2, 0).into(), Cell::O),
+            Quality::Blunder
+        );
+        assert_eq!(Quality::of(&board, (1, 0).into(), Cell::O), Quality::Best);
+        assert_eq!(
+            Quality::annotate(&board),
+            [Quality::Good, Quality::Best, Quality::Good]
+        );
+        assert_eq!(Quality::Blunder.mark(), "??");
+    }
+
+    #[test]
+    fn session_score() {
+        let mut session = SessionScore::default();
+        for result in [GameOver::HumanWon, GameOver::Tie, GameOver::HumanWon] {
+            session.record(result);
+        }
+        assert_eq!(
+            session,
+            SessionScore {
+                human_wins: 2,
+                computer_wins: 0,
+                ties: 1
+            }
+        );
+        assert_eq!(session.games(), 3);
+        assert!(!session.decides_match(5));
+        // 2:0 with two games left
+        session.record(GameOver::Tie);
+        assert!(!session.decides_match(6));
+        session.record(GameOver::Resigned);
+        assert!(session.decides_match(7));
+        assert!(!session.decides_match(9));
+        assert!(SessionScore::default().decides_match(0));
+    }
+
+    fn record(dim: usize, result: GameOver, matched: usize) -> GameRecord {
+        GameRecord {
+            time: 0,
+            width: dim,
+            height: dim,
+            win_length: dim,
+            wrap: false,
+            gravity: false,
+            misere: false,
+            swapped: false,
+            blocked: Vec::new(),
+            human_uses: Cell::X,
+            first: Cell::X,
+            result,
+            moves: Vec::new(),
+            accuracy: Some(Accuracy { matched, moves: 10 }),
+        }
+    }
+
+    #[test]
+    fn skill() {
+        let mut games = vec![record(3, GameOver::ComputerWon, 5); SKILL_GAMES - 1];
+        assert_eq!(estimate_skill(&games), None);
+        games.push(record(3, GameOver::Tie, 5));
+        assert_eq!(estimate_skill(&games).unwrap().skill, Skill::Beginner);
+        games.push(record(3, GameOver::HumanWon, 5));
+        assert_eq!(estimate_skill(&games).unwrap().skill, Skill::Intermediate);
+        games.push(record(4, GameOver::HumanWon, 5));
+        let estimate = estimate_skill(&games).unwrap();
+        assert_eq!(estimate.skill, Skill::Advanced);
+        assert_eq!(estimate.accuracy, Some(50.0));
+        assert_eq!(Skill::Advanced.recommended_preset(), "expert");
+        // only the last games count, and a low accuracy lowers the estimate
+        games.extend(vec![record(6, GameOver::HumanWon, 1); SKILL_GAMES]);
+        assert_eq!(estimate_skill(&games).unwrap().skill, Skill::Advanced);
+        games.extend(vec![record(3, GameOver::HumanWon, 9); SKILL_GAMES]);
+        assert_eq!(estimate_skill(&games).unwrap().skill, Skill::Advanced);
+        assert_eq!(Skill::from_name("expert"), Some(Skill::Expert));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_micros(12)), "12µs");
+        assert_eq!(format_duration(Duration::from_micros(1250)), "1.2ms");
+        assert_eq!(format_duration(Duration::from_millis(2345)), "2.3s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+    }
+}
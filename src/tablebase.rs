@@ -0,0 +1,346 @@
+//! Tablebases: the value of every position which can arise on a small board, so that the
+//! engine plays it perfectly without searching.
+//!
+//! A table is generated for one board size by searching every position reachable from the
+//! empty board, X moving first. Each position is stored in two bits at its index as a
+//! base 3 number of its cells, which takes 5 KB for the 3x3 board and 11 MB for the 4x4
+//! board.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::board::{Board, Cell, Coord};
+use crate::db;
+use crate::t;
+
+/// Tables can be generated for boards up to this dimension
+pub const MAX_DIM: usize = 4;
+
+// Start of every table file, followed by the dimension and the packed values
+const MAGIC: &[u8; 4] = b"TTTB";
+
+// The values as stored, ordered from the worst to the best for the player to move. 0 is a
+// position which can't be reached.
+const LOSS: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 3;
+
+/// The value of a position with best play, for the player to move
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Value {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The values of every reachable position on a board of one size
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    dim: usize,
+    // four values per byte
+    values: Vec<u8>,
+}
+
+impl Tablebase {
+    /// The file the table for the given dimension is kept in by default:
+    /// `$XDG_DATA_HOME/tictactoe/tablebase-<dim>` (or `~/.local/share/tictactoe/...`)
+    pub fn default_path(dim: usize) -> Option<PathBuf> {
+        Some(db::data_dir()?.join(format!("tablebase-{}", dim)))
+    }
+
+    /// Search every position reachable on a board of the given dimension.
+    ///
+    /// Returns `None` for boards larger than [`MAX_DIM`].
+    pub fn generate(dim: usize) -> Option<Tablebase> {
+        if dim > MAX_DIM {
+            return None;
+        }
+        let cells = dim * dim;
+        let mut table = Tablebase {
+            dim,
+            values: vec![0; 3usize.pow(cells as u32).div_ceil(4)],
+        };
+        let mut generator = Generator {
+            dim,
+            lines: Board::segments(dim, dim, dim, false),
+            powers: (0..cells).map(|idx| 3usize.pow(idx as u32)).collect(),
+            cells: vec![0; cells],
+        };
+        generator.solve(&mut table, 0, 1, None);
+        Some(table)
+    }
+
+    /// Load a table written by [`Tablebase::save`]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Tablebase> {
+        let content = fs::read(path)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, t!("tablebase-invalid"));
+        let (header, values) = content
+            .split_at_checked(MAGIC.len() + 1)
+            .ok_or_else(invalid)?;
+        let dim = header[MAGIC.len()] as usize;
+        if &header[..MAGIC.len()] != MAGIC
+            || !(1..=MAX_DIM).contains(&dim)
+            || values.len() != 3usize.pow((dim * dim) as u32).div_ceil(4)
+        {
+            return Err(invalid());
+        }
+        Ok(Tablebase {
+            dim,
+            values: values.to_vec(),
+        })
+    }
+
+    /// Write the table to the given file, creating its directory if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = MAGIC.to_vec();
+        content.push(self.dim as u8);
+        content.extend_from_slice(&self.values);
+        fs::write(path, content)
+    }
+
+    /// The board dimension the table is for
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of positions in the table
+    pub fn positions(&self) -> usize {
+        self.values
+            .iter()
+            .map(|&byte| (0..4).filter(|i| byte >> (2 * i) & 3 != 0).count())
+            .sum()
+    }
+
+    /// The value of the position for the player to move, X having moved first. `None` if
+    /// the board has another size or win length, its lines wrap around the edges, it isn't
+    /// played by the standard rules, it has a handicap or blocked cells, O moved first, or
+    /// the position can't arise in a game.
+    pub fn value(&self, board: &Board) -> Option<Value> {
+        if board.first_player() != Cell::X
+            || !board.is_square()
+            || board.width() != self.dim
+            || board.win_length() != self.dim
+            || board.wraps()
+            || !board.has_standard_rules()
+            || board.handicap().is_some()
+            || board.has_blocked()
+        {
+            return None;
+        }
+        let mut index = 0;
+        for idx in (0..self.dim * self.dim).rev() {
+            let digit = match board.get_cell((idx % self.dim, idx / self.dim)) {
+                Cell::Blank => 0,
+                Cell::X => 1,
+                Cell::O => 2,
+                Cell::Blocked => unreachable!("boards with blocked cells have no value"),
+            };
+            index = 3 * index + digit;
+        }
+        match self.get(index) {
+            LOSS => Some(Value::Loss),
+            DRAW => Some(Value::Draw),
+            WIN => Some(Value::Win),
+            _ => None,
+        }
+    }
+
+    /// The moves keeping the best value for the given player, row by row, with immediate
+    /// wins only if there are any. Empty if it isn't their turn, the game is over or the
+    /// position isn't in the table.
+    pub fn best_moves(&self, board: &Board, side: Cell) -> Vec<Coord> {
+        if side != board.to_move() || board.result().is_some() || self.value(board).is_none() {
+            return Vec::new();
+        }
+        let wins = board.winning_moves(side);
+        if !wins.is_empty() {
+            return wins;
+        }
+        let mut after = board.clone();
+        let rated: Vec<_> = board
+            .legal_moves()
+            .map(|mv| {
+                after.set_cell(mv, side).unwrap();
+                // the opponent's loss is the player's win
+                let rating = match self.value(&after) {
+                    Some(Value::Loss) => 2,
+                    Some(Value::Draw) => 1,
+                    _ => 0,
+                };
+                after.undo();
+                (mv, rating)
+            })
+            .collect();
+        let best = rated.iter().map(|&(_, rating)| rating).max().unwrap_or(0);
+        let mut moves: Vec<_> = rated
+            .into_iter()
+            .filter(|&(_, rating)| rating == best)
+            .map(|(mv, _)| mv)
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        self.values[index / 4] >> (2 * (index % 4)) & 3
+    }
+
+    fn set(&mut self, index: usize, value: u8) {
+        self.values[index / 4] |= value << (2 * (index % 4));
+    }
+}
+
+// Searches the positions with the cells as plain numbers, 0 for blank, 1 for X and 2 for O
+struct Generator {
+    dim: usize,
+    lines: Vec<Vec<usize>>,
+    powers: Vec<usize>,
+    cells: Vec<u8>,
+}
+
+impl Generator {
+    // The value of the position with the given index for the player to move, after the
+    // opponent played on the given cell
+    fn solve(
+        &mut self,
+        table: &mut Tablebase,
+        index: usize,
+        to_move: u8,
+        last: Option<usize>,
+    ) -> u8 {
+        let known = table.get(index);
+        if known != 0 {
+            return known;
+        }
+        let value = if last.is_some_and(|last| self.completes_line(last)) {
+            LOSS
+        } else if !self.cells.contains(&0) {
+            DRAW
+        } else {
+            let mut best = LOSS;
+            for idx in 0..self.dim * self.dim {
+                if self.cells[idx] != 0 {
+                    continue;
+                }
+                self.cells[idx] = to_move;
+                let child = index + to_move as usize * self.powers[idx];
+                // the opponent's value turned around
+                let value = WIN + LOSS - self.solve(table, child, 3 - to_move, Some(idx));
+                self.cells[idx] = 0;
+                best = best.max(value);
+            }
+            best
+        };
+        table.set(index, value);
+        value
+    }
+
+    // Whether the piece on the cell completes one of the lines through it
+    fn completes_line(&self, idx: usize) -> bool {
+        let piece = self.cells[idx];
+        self.lines
+            .iter()
+            .filter(|line| line.contains(&idx))
+            .any(|line| line.iter().all(|&cell| self.cells[cell] == piece))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver;
+    use crate::GameOver;
+
+    #[test]
+    fn values_agree_with_the_solver() {
+        let table = Tablebase::generate(3).unwrap();
+        // every position reachable on the 3x3 board
+        assert_eq!(table.positions(), 5478);
+        let empty = Board::build(3, Cell::X).unwrap();
+        assert_eq!(table.value(&empty), Some(Value::Draw));
+        assert_eq!(table.best_moves(&empty, Cell::X).len(), 9);
+        let won = Board::from_string("XX-/OO-/---", 3, Cell::X).unwrap();
+        assert_eq!(table.value(&won), Some(Value::Win));
+        assert_eq!(table.best_moves(&won, Cell::X), [(2, 0)]);
+        assert!(table.best_moves(&won, Cell::O).is_empty());
+        // O has too many pieces
+        let illegal = Board::from_handicap("OO-/---/---", Cell::X).unwrap();
+        assert_eq!(table.value(&illegal), None);
+        assert_eq!(table.value(&Board::build(4, Cell::X).unwrap()), None);
+        let mut rng = Rng::new(1);
+        for plies in 0..50 {
+            let board = Board::random(3, Cell::X, plies % 8, &mut rng).unwrap();
+            let side = board.to_move();
+            if board.result().is_some() {
+                continue;
+            }
+            let expected = match solver::forced_outcome(&board, side).unwrap() {
+                GameOver::Tie => Value::Draw,
+                over if over == winner(&board, side) => Value::Win,
+                _ => Value::Loss,
+            };
+            assert_eq!(
+                table.value(&board),
+                Some(expected),
+                "{}",
+                board.to_notation()
+            );
+            let optimal = solver::optimal_moves(&board, side).unwrap();
+            let moves = table.best_moves(&board, side);
+            if board.winning_moves(side).is_empty() {
+                assert_eq!(moves, optimal);
+            } else {
+                assert!(moves.iter().all(|mv| optimal.contains(mv)));
+            }
+        }
+        assert!(Tablebase::generate(MAX_DIM + 1).is_none());
+    }
+
+    #[test]
+    fn positions_where_o_moved_first() {
+        let table = Tablebase::generate(3).unwrap();
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.set_first_player(Cell::O);
+        for (mv, cell) in [
+            ((1, 0), Cell::O),
+            ((0, 0), Cell::X),
+            ((2, 2), Cell::O),
+            ((0, 1), Cell::X),
+        ] {
+            board.set_cell(mv, cell).unwrap();
+        }
+        // the pieces alone look like X is to move, so the values would be X's
+        assert_eq!(board.to_move(), Cell::O);
+        assert_eq!(table.value(&board), None);
+        assert!(table.best_moves(&board, Cell::O).is_empty());
+    }
+
+    // The result of the game if the given player wins
+    fn winner(board: &Board, side: Cell) -> GameOver {
+        if side == board.human_uses() {
+            GameOver::HumanWon
+        } else {
+            GameOver::ComputerWon
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("tictactoe-tablebase-test-{}", std::process::id()));
+        let table = Tablebase::generate(2).unwrap();
+        table.save(&path).unwrap();
+        let loaded = Tablebase::load(&path).unwrap();
+        assert_eq!(loaded.dim(), 2);
+        assert_eq!(loaded.values, table.values);
+        fs::write(&path, b"TTTB\x02").unwrap();
+        assert!(Tablebase::load(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
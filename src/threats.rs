@@ -0,0 +1,160 @@
+//! Threat-space search: finds forced wins on boards too large to search completely.
+//!
+//! Instead of every move, only moves creating a threat are tried, a line which the attacker
+//! completes with their next move. The defender's reply is then forced, so the search
+//! follows a single line per attacking move until the attacker threatens two lines at
+//! once. A sequence found is a forced win; a win which needs a quiet move in between is
+//! not found.
+
+use std::collections::HashMap;
+
+use crate::board::{Board, Cell, Coord};
+
+/// Number of threats in a row searched by default
+pub const MAX_THREATS: usize = 8;
+
+/// A forced win for the attacker, who is to move, by a sequence of at most `max_threats`
+/// threats: the moves of both players until the attacker completes a line. When the
+/// defender can block every threat, or has a threat of their own, there is none.
+pub fn forced_win(board: &Board, attacker: Cell, max_threats: usize) -> Option<Vec<Coord>> {
+    if board.result().is_some() {
+        return None;
+    }
+    let mut search = ThreatSearch {
+        lines: board.lines().to_vec(),
+        refuted: HashMap::new(),
+    };
+    search.attack(&mut board.clone(), attacker, max_threats)
+}
+
+struct ThreatSearch {
+    lines: Vec<Vec<usize>>,
+    // positions already shown to hold for the defender, with the number of threats tried
+    refuted: HashMap<u64, usize>,
+}
+
+impl ThreatSearch {
+    // The winning sequence from the position with the attacker to move
+    fn attack(&mut self, board: &mut Board, attacker: Cell, threats: usize) -> Option<Vec<Coord>> {
+        if let Some(&win) = board.winning_moves(attacker).first() {
+            return Some(vec![win]);
+        }
+        let defender = attacker.opponent();
+        // a threat of the defender would have to be answered first
+        if threats == 0
+            || !board.winning_moves(defender).is_empty()
+            || self.refuted.get(&board.hash()) >= Some(&threats)
+        {
+            return None;
+        }
+        for threat in self.candidates(board, attacker) {
+            board.set_cell(threat, attacker).unwrap();
+            let blocks = board.winning_moves(attacker);
+            let line = match blocks[..] {
+                // two threats, the defender can only block one of them
+                [block, win, ..] => Some(vec![threat, block, win]),
+                [block] => {
+                    board.set_cell(block, defender).unwrap();
+                    let line = match board.result() {
+                        Some(_) => None,
+                        None => self.attack(board, attacker, threats - 1),
+                    };
+                    board.undo();
+                    line.map(|line| [vec![threat, block], line].concat())
+                }
+                [] => None,
+            };
+            board.undo();
+            if line.is_some() {
+                return line;
+            }
+        }
+        self.refuted.insert(board.hash(), threats);
+        None
+    }
+
+    // The playable cells which create a threat: those of lines the defender hasn't blocked
+    // with two cells left
+    fn candidates(&self, board: &Board, attacker: Cell) -> Vec<Coord> {
+        let width = board.width();
+        let mut cells: Vec<usize> = Vec::new();
+        for line in &self.lines {
+            let pieces: Vec<Cell> = line
+                .iter()
+                .map(|&idx| board.get_cell((idx % width, idx / width)))
+                .collect();
+            let own = pieces.iter().filter(|&&cell| cell == attacker).count();
+            let blank = pieces.iter().filter(|&&cell| cell == Cell::Blank).count();
+            if own + blank == line.len() && blank == 2 {
+                cells.extend(
+                    line.iter()
+                        .zip(&pieces)
+                        .filter_map(|(&idx, &cell)| (cell == Cell::Blank).then_some(idx)),
+                );
+            }
+        }
+        cells.sort();
+        cells.dedup();
+        cells
+            .into_iter()
+            .map(|idx| Coord {
+                row: idx / width,
+                col: idx % width,
+            })
+            .filter(|&cell| board.is_playable(cell))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver;
+
+    // Plays the sequence and returns the winner
+    fn replay(board: &Board, attacker: Cell, line: &[Coord]) -> Option<Cell> {
+        let mut board = board.clone();
+        let mut side = attacker;
+        for &mv in line {
+            board.set_cell(mv, side).unwrap();
+            side = side.opponent();
+        }
+        board.winner()
+    }
+
+    #[test]
+    fn threat_sequences() {
+        // two single threats before the double one
+        let board = Board::from_string("X-O-/O-X-/-XX-/-OO-", 4, Cell::X).unwrap();
+        let line = forced_win(&board, Cell::X, MAX_THREATS).unwrap();
+        assert_eq!(line.len(), 7);
+        assert_eq!(replay(&board, Cell::X, &line), Some(Cell::X));
+        assert_eq!(forced_win(&board, Cell::X, 2), None);
+        assert_eq!(
+            forced_win(&Board::build(5, Cell::X).unwrap(), Cell::X, 3),
+            None
+        );
+    }
+
+    #[test]
+    fn found_wins_are_forced() {
+        let mut rng = Rng::new(5);
+        let mut found = 0;
+        for plies in 0..300 {
+            let board = Board::random(4, Cell::X, 4 + plies % 8, &mut rng).unwrap();
+            if board.result().is_some() {
+                continue;
+            }
+            let side = board.to_move();
+            let Some(line) = forced_win(&board, side, MAX_THREATS) else {
+                continue;
+            };
+            found += 1;
+            assert_eq!(replay(&board, side, &line), Some(side));
+            let solution = solver::solve(&board, side).unwrap();
+            assert_eq!(solution.winner, Some(side), "{}", board.to_notation());
+        }
+        assert!(found > 10, "{}", found);
+    }
+}
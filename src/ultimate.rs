@@ -0,0 +1,377 @@
+//! Ultimate tic-tac-toe: a 3x3 grid of 3x3 boards.
+//!
+//! The cell a player takes on a small board sends the opponent to the small board at the
+//! same position in the grid. If that board is already decided or full, the opponent may
+//! play on any open board. A small board is won by three in a row on it, the game by three
+//! won small boards in a row.
+
+use std::fmt;
+
+use crate::board::{Cell, Coord, MoveError};
+use crate::input::{self, ParseError};
+use crate::variant::Variant;
+
+/// The number of cells along each side of the whole grid
+pub const SIZE: usize = 9;
+
+/// The search depth used by the computer player, see
+/// [`best_move`](crate::variant::best_move)
+pub const SEARCH_DEPTH: usize = 5;
+
+// the rows, columns and diagonals of a 3x3 board by index x + 3 * y
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+/// An ultimate tic-tac-toe position. X always moves first.
+#[derive(Debug, Clone)]
+pub struct UltimateBoard {
+    // the cells of the whole grid by x + 9 * y
+    cells: [Cell; SIZE * SIZE],
+    // the winner of each small board, blank while undecided
+    won: [Cell; 9],
+    // the small board the player to move is sent to
+    target: Option<usize>,
+    // the moves made, with the target before each move for undo
+    history: Vec<(Coord, Option<usize>)>,
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        UltimateBoard::new()
+    }
+}
+
+impl UltimateBoard {
+    /// An empty grid with X to move on any board
+    pub fn new() -> UltimateBoard {
+        UltimateBoard {
+            cells: [Cell::Blank; SIZE * SIZE],
+            won: [Cell::Blank; 9],
+            target: None,
+            history: Vec::new(),
+        }
+    }
+
+    /// The piece at the given coordinates of the whole grid
+    pub fn get_cell(&self, coord: impl Into<Coord>) -> Cell {
+        let Coord { row, col } = coord.into();
+        self.cells[col + SIZE * row]
+    }
+
+    /// The small board containing the given cell, numbered 0 to 8 row by row
+    pub fn board_of(coord: Coord) -> usize {
+        coord.col / 3 + 3 * (coord.row / 3)
+    }
+
+    /// The winner of a small board, `None` while it is undecided or if it ended in a tie
+    pub fn board_winner(&self, board: usize) -> Option<Cell> {
+        (self.won[board] != Cell::Blank).then_some(self.won[board])
+    }
+
+    /// The small board the player to move has to play on, `None` if any open board may be
+    /// chosen
+    pub fn target(&self) -> Option<usize> {
+        self.target.filter(|&b| self.is_open(b))
+    }
+
+    /// The player to move
+    pub fn to_move(&self) -> Cell {
+        if self.history.len().is_multiple_of(2) {
+            Cell::X
+        } else {
+            Cell::O
+        }
+    }
+
+    /// The moves made so far as coordinates of the whole grid
+    pub fn history(&self) -> Vec<Coord> {
+        self.history.iter().map(|&(mv, _)| mv).collect()
+    }
+
+    /// The blank cells the player to move may take, row by row. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<Coord> {
+        if self.winner().is_some() {
+            return Vec::new();
+        }
+        let target = self.target();
+        (0..SIZE * SIZE)
+            .map(|idx| Coord {
+                row: idx / SIZE,
+                col: idx % SIZE,
+            })
+            .filter(|&coord| self.get_cell(coord) == Cell::Blank)
+            .filter(|&coord| {
+                let board = UltimateBoard::board_of(coord);
+                target.map_or(self.is_open(board), |t| t == board)
+            })
+            .collect()
+    }
+
+    /// Place the piece of the player to move at the given coordinates of the whole grid
+    ///
+    /// Returns an error if the cell is off the grid, is taken or lies outside the board the
+    /// player was sent to.
+    pub fn play(&mut self, coord: impl Into<Coord>) -> Result<(), MoveError> {
+        let coord = coord.into();
+        if coord.col >= SIZE || coord.row >= SIZE {
+            return Err(MoveError::OutOfBounds(coord));
+        }
+        if self.get_cell(coord) != Cell::Blank {
+            return Err(MoveError::CellTaken);
+        }
+        if self.winner().is_some() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        if !self.legal_moves().contains(&coord) {
+            return Err(MoveError::WrongBoard);
+        }
+        let player = self.to_move();
+        self.cells[coord.col + SIZE * coord.row] = player;
+        let board = UltimateBoard::board_of(coord);
+        self.won[board] = self.small_winner(board);
+        self.history.push((coord, self.target));
+        self.target = Some(coord.col % 3 + 3 * (coord.row % 3));
+        Ok(())
+    }
+
+    /// Take back the last move, returning its coordinates
+    pub fn undo(&mut self) -> Option<Coord> {
+        let (coord, target) = self.history.pop()?;
+        self.cells[coord.col + SIZE * coord.row] = Cell::Blank;
+        let board = UltimateBoard::board_of(coord);
+        self.won[board] = self.small_winner(board);
+        self.target = target;
+        Some(coord)
+    }
+
+    /// The player who won three small boards in a row
+    pub fn winner(&self) -> Option<Cell> {
+        LINES.iter().find_map(|line| {
+            let first = self.won[line[0]];
+            (first != Cell::Blank && line.iter().all(|&b| self.won[b] == first)).then_some(first)
+        })
+    }
+
+    /// Whether the game is over, by a win or because no open board is left
+    pub fn is_over(&self) -> bool {
+        self.legal_moves().is_empty()
+    }
+
+    /// Parse a line of user input into coordinates of the whole grid
+    ///
+    /// Moves are entered as `x y` on the whole grid, from 1 to 9. When the player is sent
+    /// to a board, a single number picks a cell on it, numbered from 1 to 9 row by row.
+    pub fn parse_input(&self, input: &str) -> Result<Coord, ParseError> {
+        let trimmed = input.trim();
+        if let (Some(board), Ok(cell)) = (self.target(), trimmed.parse::<usize>()) {
+            if !(1..=9).contains(&cell) {
+                return Err(ParseError::OutOfRange {
+                    axis: '#',
+                    value: cell,
+                    dim: 9,
+                });
+            }
+            let cell = cell - 1;
+            return Ok(Coord {
+                row: 3 * (board / 3) + cell / 3,
+                col: 3 * (board % 3) + cell % 3,
+            });
+        }
+        input::parse_move(input, SIZE, SIZE).map(Coord::from)
+    }
+
+    // whether moves can still be made on a small board
+    fn is_open(&self, board: usize) -> bool {
+        self.won[board] == Cell::Blank
+            && (0..9).any(|cell| self.small_cell(board, cell) == Cell::Blank)
+    }
+
+    // the piece on a cell of a small board, both numbered 0 to 8 row by row
+    fn small_cell(&self, board: usize, cell: usize) -> Cell {
+        self.get_cell(Coord {
+            row: 3 * (board / 3) + cell / 3,
+            col: 3 * (board % 3) + cell % 3,
+        })
+    }
+
+    // the player with three in a row on a small board, blank if there is none
+    fn small_winner(&self, board: usize) -> Cell {
+        LINES
+            .iter()
+            .find_map(|line| {
+                let first = self.small_cell(board, line[0]);
+                (first != Cell::Blank && line.iter().all(|&c| self.small_cell(board, c) == first))
+                    .then_some(first)
+            })
+            .unwrap_or(Cell::Blank)
+    }
+}
+
+impl Variant for UltimateBoard {
+    type Move = Coord;
+
+    fn legal_moves(&self) -> Vec<Coord> {
+        UltimateBoard::legal_moves(self)
+    }
+
+    fn play(&mut self, mv: Coord) -> Result<(), MoveError> {
+        UltimateBoard::play(self, mv)
+    }
+
+    fn undo(&mut self) -> Option<Coord> {
+        UltimateBoard::undo(self)
+    }
+
+    fn winner(&self) -> Option<Cell> {
+        UltimateBoard::winner(self)
+    }
+
+    // won boards count most, more so when they line up, then two in a row on undecided
+    // boards
+    fn evaluate(&self) -> i64 {
+        let player = self.to_move();
+        let count = |cells: [Cell; 3], p: Cell| cells.iter().filter(|&&c| c == p).count();
+        let line_score = |cells: [Cell; 3], weights: [i64; 3]| -> i64 {
+            let mine = count(cells, player);
+            let theirs = count(cells, player.opponent());
+            match (mine, theirs) {
+                (n, 0) if n < 3 => weights[n],
+                (0, n) if n < 3 => -weights[n],
+                _ => 0,
+            }
+        };
+        let mut score = 0;
+        for board in 0..9 {
+            match self.won[board] {
+                Cell::Blank => {
+                    for line in LINES {
+                        let cells = line.map(|c| self.small_cell(board, c));
+                        score += line_score(cells, [0, 1, 5]);
+                    }
+                }
+                p if p == player => score += 50,
+                _ => score -= 50,
+            }
+        }
+        for line in LINES {
+            score += line_score(line.map(|b| self.won[b]), [0, 20, 200]);
+        }
+        score
+    }
+}
+
+/// Draws the grid with the small boards separated by double lines. Blank cells the player
+/// to move may take are shown as dots.
+impl fmt::Display for UltimateBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let legal = self.legal_moves();
+        writeln!(f, "    1 2 3   4 5 6   7 8 9")?;
+        for y in 0..SIZE {
+            if y > 0 && y % 3 == 0 {
+                writeln!(f, "   =======#=======#=======")?;
+            }
+            write!(f, "{:>2} ", y + 1)?;
+            for x in 0..SIZE {
+                if x > 0 && x % 3 == 0 {
+                    write!(f, " #")?;
+                }
+                let c = match self.get_cell((x, y)) {
+                    Cell::Blank if legal.contains(&(x, y).into()) => ".".to_string(),
+                    c => c.to_string(),
+                };
+                write!(f, " {}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant;
+
+    fn play_all(board: &mut UltimateBoard, moves: &[(usize, usize)]) {
+        for &mv in moves {
+            board.play(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn sends_to_board() {
+        let mut board = UltimateBoard::new();
+        assert_eq!(board.legal_moves().len(), 81);
+        // top right cell of the center board sends O to the top right board
+        board.play((5, 3)).unwrap();
+        assert_eq!(board.target(), Some(2));
+        assert_eq!(board.legal_moves().len(), 9);
+        assert_eq!(board.play((0, 0)), Err(MoveError::WrongBoard));
+        assert_eq!(
+            board.play((9, 0)),
+            Err(MoveError::OutOfBounds(Coord { row: 0, col: 9 }))
+        );
+        assert!(board.play((7, 1)).is_ok());
+        assert_eq!(board.target(), Some(4));
+        assert_eq!(board.undo(), Some((7, 1).into()));
+        assert_eq!(board.target(), Some(2));
+        assert_eq!(board.to_move(), Cell::O);
+    }
+
+    #[test]
+    fn won_board_frees_choice() {
+        let mut board = UltimateBoard::new();
+        // X takes the top row of the top left board, the last move sending O back there
+        play_all(&mut board, &[(1, 0), (3, 0), (2, 0), (6, 0), (0, 0)]);
+        assert_eq!(board.board_winner(0), Some(Cell::X));
+        assert_eq!(board.winner(), None);
+        // any open board may be chosen
+        assert_eq!(board.target(), None);
+        assert!(board
+            .legal_moves()
+            .iter()
+            .all(|mv| mv.col >= 3 || mv.row >= 3));
+        assert_eq!(board.legal_moves().len(), 81 - 9 - 2);
+    }
+
+    #[test]
+    fn parse_input() {
+        let mut board = UltimateBoard::new();
+        assert_eq!(board.parse_input("9 1").unwrap(), (8, 0));
+        assert!(board.parse_input("5").is_err());
+        board.play((4, 4)).unwrap();
+        // sent to the center board
+        assert_eq!(board.parse_input("1").unwrap(), (3, 3));
+        assert_eq!(board.parse_input("6").unwrap(), (5, 4));
+        assert!(board.parse_input("10").is_err());
+    }
+
+    #[test]
+    fn engine_wins_board() {
+        let mut board = UltimateBoard::new();
+        // X has two in a row on the center board and is sent there
+        play_all(&mut board, &[(3, 3), (1, 1), (4, 3), (4, 1)]);
+        assert_eq!(board.target(), Some(4));
+        assert_eq!(board.to_move(), Cell::X);
+        assert_eq!(variant::best_move(&board, 1), Some((5, 3).into()));
+    }
+
+    #[test]
+    fn render() {
+        let mut board = UltimateBoard::new();
+        board.play((4, 4)).unwrap();
+        let s = board.to_string();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[5], " 4        # . . . #      ");
+        assert_eq!(lines[6], " 5        # . X . #      ");
+    }
+}
@@ -0,0 +1,101 @@
+//! The search the computer plays the variants for two players with, ultimate tic-tac-toe,
+//! fading pieces and Three Men's Morris among them.
+//!
+//! A board of a variant implements [`Variant`], and [`best_move`] searches its moves with
+//! alpha-beta to a fixed depth, scoring the positions there with the board's own heuristic.
+//! A game which nobody won ends when no legal move is left, e.g. at a move limit, and
+//! scores 0.
+
+use crate::board::{Cell, MoveError};
+
+// the score of a won game, higher than any heuristic evaluation
+const WIN_SCORE: i64 = 1_000_000;
+
+/// A board of a variant which X and O play in turn
+pub trait Variant: Clone {
+    /// A move on the board
+    type Move: Copy;
+
+    /// The moves the player to move may make. Empty once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Make the move for the player to move
+    ///
+    /// Returns an error if the move isn't [legal](Variant::legal_moves).
+    fn play(&mut self, mv: Self::Move) -> Result<(), MoveError>;
+
+    /// Take back the last move
+    fn undo(&mut self) -> Option<Self::Move>;
+
+    /// The player who won
+    fn winner(&self) -> Option<Cell>;
+
+    /// Heuristic value of the position for the player to move, positive if it favours them
+    fn evaluate(&self) -> i64;
+}
+
+/// Choose a move for the player to move with an alpha-beta search of the given depth
+///
+/// Returns `None` if the game is over.
+pub fn best_move<V: Variant>(board: &V, depth: usize) -> Option<V::Move> {
+    let mut board = board.clone();
+    let mut best = None;
+    let mut alpha = -WIN_SCORE * 2;
+    for mv in board.legal_moves() {
+        board.play(mv).unwrap();
+        let score = -negamax(&mut board, depth.saturating_sub(1), -WIN_SCORE * 2, -alpha);
+        board.undo();
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(mv);
+        }
+    }
+    best
+}
+
+// the value of the position for the player to move, wins found earlier scoring higher
+fn negamax<V: Variant>(board: &mut V, depth: usize, mut alpha: i64, beta: i64) -> i64 {
+    if board.winner().is_some() {
+        // the player who just moved won
+        return -WIN_SCORE - depth as i64;
+    }
+    let moves = board.legal_moves();
+    if moves.is_empty() {
+        return 0;
+    }
+    if depth == 0 {
+        return board.evaluate();
+    }
+    for mv in moves {
+        board.play(mv).unwrap();
+        let score = -negamax(board, depth - 1, -beta, -alpha);
+        board.undo();
+        if score > alpha {
+            alpha = score;
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+    alpha
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fading::FadingBoard;
+    use crate::ultimate::UltimateBoard;
+
+    #[test]
+    fn searches_legal_moves() {
+        let board = UltimateBoard::new();
+        let mv = best_move(&board, 2).unwrap();
+        assert!(board.legal_moves().contains(&mv));
+        let mut board = FadingBoard::build(3, 3, 3, 3).unwrap();
+        for mv in [(1, 1), (0, 1), (0, 0), (2, 1), (2, 2)] {
+            board.play(mv).unwrap();
+        }
+        assert_eq!(board.winner(), Some(Cell::X));
+        assert_eq!(best_move(&board, 2), None);
+    }
+}
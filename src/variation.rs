@@ -0,0 +1,300 @@
+use std::fmt;
+
+use crate::board::{Board, Cell, Move, MoveError};
+use crate::stats::Quality;
+
+#[derive(Debug, Clone)]
+struct Node {
+    // the move leading to this node, None for the start position
+    mv: Option<Move>,
+    // the engine's judgement of the move, if it was recorded from a board
+    quality: Option<Quality>,
+    parent: usize,
+    children: Vec<usize>,
+    ply: usize,
+}
+
+/// Tree of the moves played in a game and the alternative continuations tried out afterwards.
+///
+/// The first child of every node continues the line it was recorded in first, so following
+/// the first children from the root yields the actual game.
+#[derive(Debug, Clone)]
+pub struct VariationTree {
+    nodes: Vec<Node>,
+    current: usize,
+}
+
+impl Default for VariationTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VariationTree {
+    /// Create a tree containing only the start position
+    pub fn new() -> VariationTree {
+        VariationTree {
+            nodes: vec![Node {
+                mv: None,
+                quality: None,
+                parent: 0,
+                children: Vec::new(),
+                ply: 0,
+            }],
+            current: 0,
+        }
+    }
+
+    /// Create a tree with the moves played on the board as its main line, annotated with
+    /// their quality
+    pub fn from_board(board: &Board) -> VariationTree {
+        let mut tree = VariationTree::new();
+        for (&coord, quality) in board.history().iter().zip(Quality::annotate(board)) {
+            let mark = board.get_cell(coord);
+            tree.add(Move { coord, mark }, Some(quality));
+        }
+        tree
+    }
+
+    /// Add a move after the current position and make it the current one.
+    ///
+    /// If the move has been recorded before, the existing branch is followed instead.
+    pub fn push(&mut self, mv: Move) {
+        self.add(mv, None);
+    }
+
+    fn add(&mut self, mv: Move, quality: Option<Quality>) {
+        let mv = Some(mv);
+        let existing = self.nodes[self.current]
+            .children
+            .iter()
+            .find(|&&child| self.nodes[child].mv == mv);
+        if let Some(&child) = existing {
+            self.current = child;
+            return;
+        }
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            mv,
+            quality,
+            parent: self.current,
+            children: Vec::new(),
+            ply: self.ply() + 1,
+        });
+        self.nodes[self.current].children.push(idx);
+        self.current = idx;
+    }
+
+    /// Record the last move played on the board, annotated with its quality
+    pub fn record(&mut self, board: &Board) {
+        if let Some(&coord) = board.history().last() {
+            let mark = board.get_cell(coord);
+            self.add(Move { coord, mark }, Quality::of_last_move(board));
+        }
+    }
+
+    /// The quality of the move leading to the current position, if known
+    pub fn quality(&self) -> Option<Quality> {
+        self.nodes[self.current].quality
+    }
+
+    /// Number of moves leading to the current position
+    pub fn ply(&self) -> usize {
+        self.nodes[self.current].ply
+    }
+
+    /// The cell type to move in the current position
+    pub fn to_move(&self) -> Cell {
+        match self.nodes[self.current].mv {
+            Some(mv) => mv.mark.opponent(),
+            None => self.nodes[0]
+                .children
+                .first()
+                .and_then(|&child| self.nodes[child].mv)
+                .map_or(Cell::X, |mv| mv.mark),
+        }
+    }
+
+    /// Jump back to the position after the given number of moves on the current line.
+    ///
+    /// Returns an error if the current line is shorter.
+    pub fn goto_ply(&mut self, ply: usize) -> Result<(), MoveError> {
+        if ply > self.ply() {
+            return Err(MoveError::NoSuchMove);
+        }
+        while self.ply() > ply {
+            self.current = self.nodes[self.current].parent;
+        }
+        Ok(())
+    }
+
+    /// Return to the last position of the actual game
+    pub fn goto_main_line(&mut self) {
+        self.current = 0;
+        while let Some(&child) = self.nodes[self.current].children.first() {
+            self.current = child;
+        }
+    }
+
+    /// Moves leading from the start to the current position
+    pub fn line(&self) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut node = self.current;
+        while let Some(mv) = self.nodes[node].mv {
+            line.push(mv);
+            node = self.nodes[node].parent;
+        }
+        line.reverse();
+        line
+    }
+
+    /// Set up the board to show the current position
+    pub fn sync(&self, board: &mut Board) {
+        while board.undo().is_some() {}
+        for mv in self.line() {
+            board.set_cell(mv.coord, mv.mark).unwrap();
+        }
+    }
+
+    /// Render the board in the current position with analysis marks: the played moves carry
+    /// their quality mark and the moves the engine recommends for the player to move are
+    /// shown as `*`
+    pub fn render_board(&self, board: &Board) -> String {
+        let (width, height) = (board.width(), board.height());
+        let mut marks = vec![""; width * height];
+        let mut node = self.current;
+        while let Some(Move { coord, .. }) = self.nodes[node].mv {
+            marks[coord.col + coord.row * width] =
+                self.nodes[node].quality.map_or("", |q| q.mark());
+            node = self.nodes[node].parent;
+        }
+        if board.result().is_none() {
+            for coord in board.best_moves(self.to_move()) {
+                marks[coord.col + coord.row * width] = "*";
+            }
+        }
+        let sep = "+---".repeat(width) + "+\n";
+        let mut out = sep.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let cell = match board.get_cell((x, y)) {
+                    Cell::Blank => marks[x + y * width].to_string(),
+                    cell => format!("{}{}", cell, marks[x + y * width]),
+                };
+                out += &format!("|{:^3}", cell);
+            }
+            out += "|\n";
+            out += &sep;
+        }
+        out
+    }
+
+    fn write_node(&self, f: &mut fmt::Formatter, node: usize, indent: usize) -> fmt::Result {
+        let n = &self.nodes[node];
+        let mv = n.mv.unwrap();
+        let mark = match n.quality.map(|q| q.mark()) {
+            Some(mark) if !mark.is_empty() => format!(" {}", mark),
+            _ => String::new(),
+        };
+        let marker = if node == self.current { "  <" } else { "" };
+        let pad = "    ".repeat(indent);
+        writeln!(f, "{}{}. {}{}{}", pad, n.ply, mv, mark, marker)
+    }
+
+    // Writes the line continuing after the given node, followed by its variations
+    fn write_line(&self, f: &mut fmt::Formatter, mut node: usize, indent: usize) -> fmt::Result {
+        while let Some(&first) = self.nodes[node].children.first() {
+            self.write_node(f, first, indent)?;
+            for &alt in &self.nodes[node].children[1..] {
+                self.write_node(f, alt, indent + 1)?;
+                self.write_line(f, alt, indent + 1)?;
+            }
+            node = first;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for VariationTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_line(f, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn main_line() -> VariationTree {
+        let mut tree = VariationTree::new();
+        for mv in ["X 1 1", "O 2 2", "X 3 3"] {
+            tree.push(mv.parse().unwrap());
+        }
+        tree
+    }
+
+    #[test]
+    fn branches_and_return() {
+        let mut tree = main_line();
+        tree.goto_ply(1).unwrap();
+        assert_eq!(tree.to_move(), Cell::O);
+        tree.push("O 3 1".parse().unwrap());
+        let line: Vec<String> = tree.line().iter().map(Move::to_string).collect();
+        assert_eq!(line, ["X 1 1", "O 3 1"]);
+        // replaying a known move follows the existing branch
+        tree.goto_ply(1).unwrap();
+        tree.push("O 2 2".parse().unwrap());
+        assert_eq!(tree.nodes[tree.current].children.len(), 1);
+        tree.goto_main_line();
+        assert_eq!(tree.ply(), 3);
+        assert_eq!(
+            tree.to_string(),
+            "1. X 1 1\n2. O 2 2\n    2. O 3 1\n3. X 3 3  <\n"
+        );
+        assert_eq!(tree.goto_ply(4), Err(MoveError::NoSuchMove));
+    }
+
+    #[test]
+    fn sync_board() {
+        let mut tree = main_line();
+        let mut board = Board::build(3, Cell::X).unwrap();
+        tree.sync(&mut board);
+        assert_eq!(board.history(), &[(0, 0), (1, 1), (2, 2)]);
+        tree.goto_ply(0).unwrap();
+        assert_eq!(tree.to_move(), Cell::X);
+        tree.sync(&mut board);
+        assert!(board.history().is_empty());
+    }
+
+    #[test]
+    fn board_marks() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        board.human_move((2, 2)).unwrap();
+        let mut tree = VariationTree::from_board(&board);
+        board.set_cell((2, 0), Cell::O).unwrap();
+        tree.record(&board);
+        assert_eq!(
+            tree.render_board(&board),
+            "+---+---+---+\n| X |   |O??|\n+---+---+---+\n|   |O! |   |\n+---+---+---+\n| * |   | X |\n+---+---+---+\n"
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let mut board = Board::build(3, Cell::X).unwrap();
+        board.human_move((0, 0)).unwrap();
+        board.set_cell((1, 1), Cell::O).unwrap();
+        board.human_move((2, 2)).unwrap();
+        let mut tree = VariationTree::from_board(&board);
+        assert_eq!(tree.quality(), Some(Quality::Good));
+        board.set_cell((2, 0), Cell::O).unwrap();
+        tree.record(&board);
+        assert_eq!(tree.quality(), Some(Quality::Blunder));
+        assert_eq!(
+            tree.to_string(),
+            "1. X 1 1\n2. O 2 2 !\n3. X 3 3\n4. O 3 1 ??  <\n"
+        );
+    }
+}